@@ -0,0 +1,219 @@
+#ifndef RAYTRACER_BASE_H
+#define RAYTRACER_BASE_H
+
+/* This file is generated by cbindgen, do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a call into the C API.
+typedef enum RtStatus {
+  // The call succeeded.
+  RT_STATUS_OK = 0,
+  // A required pointer was null.
+  RT_STATUS_NULL_POINTER = 1,
+  // An argument was invalid, for example an unknown identifier.
+  RT_STATUS_INVALID_ARGUMENT = 2,
+  // A file couldn't be loaded.
+  RT_STATUS_LOAD_FAILED = 3,
+  // The output buffer is smaller than the rendered image.
+  RT_STATUS_BUFFER_TOO_SMALL = 4,
+  // The render was cancelled by the progress callback.
+  RT_STATUS_CANCELLED = 5,
+  // The renderer panicked.
+  RT_STATUS_PANICKED = 6,
+} RtStatus;
+
+// The textures, materials and environments of a scene.
+// Identifiers handed out to C are indices into the lists of this wrapper.
+typedef struct RtResources RtResources;
+
+// The objects to render, surrounded by an environment.
+typedef struct RtScene RtScene;
+
+// A vector or color.
+typedef struct RtVec3 {
+  double x;
+  double y;
+  double z;
+} RtVec3;
+
+// The settings of the camera a scene is rendered with.
+typedef struct RtCameraSettings {
+  // The position of the camera.
+  struct RtVec3 look_from;
+  // The point the camera looks at.
+  struct RtVec3 look_at;
+  // The vertical field of view in degrees.
+  double vfov;
+  // The width of the image in pixels.
+  uint32_t image_width;
+  // The ratio of the image width to its height.
+  double aspect_ratio;
+  // The number of samples per pixel.
+  uint32_t sample_count;
+  // The maximum number of bounces of a ray.
+  uint32_t max_bounces;
+  // The seed of the random number generator, 0 continues the current sequence.
+  uint64_t seed;
+} RtCameraSettings;
+
+// Receives the progress of a render, the render is cancelled if it returns false.
+typedef bool (*RtProgressCallback)(uint64_t completed, uint64_t total, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the message of the last error on this thread. The string is valid until the next
+// call on this thread.
+const char *rt_last_error(void);
+
+// Creates empty resources, which must be freed with `rt_resources_free`.
+struct RtResources *rt_resources_new(void);
+
+// Frees resources created with `rt_resources_new`.
+//
+// # Safety
+// The resources must not be used afterwards.
+void rt_resources_free(struct RtResources *resources);
+
+// Adds a texture of a single color.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_solid_texture(struct RtResources *resources,
+                                   struct RtVec3 color,
+                                   uint32_t *out_texture);
+
+// Loads an image texture from the file at the given path.
+//
+// # Safety
+// The pointers must be valid or null, the path must be nul-terminated.
+enum RtStatus rt_add_image_texture(struct RtResources *resources,
+                                   const char *path,
+                                   uint32_t *out_texture);
+
+// Adds a 3D checker pattern alternating between two textures.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_checker_texture(struct RtResources *resources,
+                                     double scale,
+                                     uint32_t even,
+                                     uint32_t odd,
+                                     uint32_t *out_texture);
+
+// Adds a diffuse material.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_lambertian_material(struct RtResources *resources,
+                                         uint32_t albedo,
+                                         uint32_t *out_material);
+
+// Adds a reflective material, blurred by the fuzz.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_metal_material(struct RtResources *resources,
+                                    struct RtVec3 albedo,
+                                    double fuzz,
+                                    uint32_t *out_material);
+
+// Adds a transparent material with the given refraction index.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_dielectric_material(struct RtResources *resources,
+                                         double refraction_index,
+                                         uint32_t *out_material);
+
+// Adds a material that emits light.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_light_material(struct RtResources *resources,
+                                    uint32_t emission,
+                                    uint32_t *out_material);
+
+// Adds an environment of a single color.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_solid_environment(struct RtResources *resources,
+                                       struct RtVec3 color,
+                                       uint32_t *out_environment);
+
+// Adds an environment blending from the bottom to the top color.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_add_gradient_environment(struct RtResources *resources,
+                                          struct RtVec3 bottom,
+                                          struct RtVec3 top,
+                                          uint32_t *out_environment);
+
+// Creates an empty scene surrounded by the given environment, which must be freed with
+// `rt_scene_free`. Returns null if the resources are null or the environment is unknown.
+//
+// # Safety
+// The resources must be valid or null.
+struct RtScene *rt_scene_new(const struct RtResources *resources, uint32_t environment);
+
+// Frees a scene created with `rt_scene_new`.
+//
+// # Safety
+// The scene must not be used afterwards.
+void rt_scene_free(struct RtScene *scene);
+
+// Adds a sphere with a material of the resources to the scene.
+//
+// # Safety
+// The pointers must be valid or null.
+enum RtStatus rt_scene_add_sphere(struct RtScene *scene,
+                                  const struct RtResources *resources,
+                                  struct RtVec3 center,
+                                  double radius,
+                                  uint32_t material);
+
+// Loads a mesh from a Wavefront OBJ file and adds it with a material of the resources.
+//
+// # Safety
+// The pointers must be valid or null, the path must be nul-terminated.
+enum RtStatus rt_scene_add_mesh(struct RtScene *scene,
+                                const struct RtResources *resources,
+                                const char *path,
+                                uint32_t material);
+
+// Returns the size in bytes of the RGB image rendered with the settings,
+// and writes its dimensions to the outputs that aren't null.
+//
+// # Safety
+// The pointers must be valid or null.
+size_t rt_image_size(const struct RtCameraSettings *settings,
+                     uint32_t *out_width,
+                     uint32_t *out_height);
+
+// Renders the scene into the buffer as 8-bit RGB, row by row from the top left.
+// The buffer must hold at least `rt_image_size` bytes. The callback is optional and called
+// on the calling thread, returning false from it cancels the render.
+//
+// # Safety
+// The pointers must be valid or null, the buffer must be writable for `buffer_len` bytes.
+enum RtStatus rt_render(const struct RtScene *scene,
+                        const struct RtResources *resources,
+                        const struct RtCameraSettings *settings,
+                        uint8_t *buffer,
+                        size_t buffer_len,
+                        RtProgressCallback callback,
+                        void *user_data);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RAYTRACER_BASE_H */
@@ -1,52 +1,130 @@
 use std::ops::Index;
 
-use crate::{interval::Interval, intr, ray::Ray, vector::Point3};
+use crate::{
+    float::{Float, Scalar},
+    interval::{GenericInterval, Interval},
+    ray::{InverseRay, Ray},
+    vector::GenericVec3,
+};
+
+/// An axis-aligned bounding box using the floating point type used for rendering.
+pub type Aabb = GenericAabb<Float>;
 
 #[derive(Debug, Clone, Copy)]
-/// An axis-aligned bounding box.
-pub struct Aabb {
+/// An axis-aligned bounding box with bounds of any scalar type.
+pub struct GenericAabb<T> {
     /// The interval of the bounding box in the x direction.
-    pub x: Interval,
+    pub x: GenericInterval<T>,
     /// The interval of the bounding box in the y direction.
-    pub y: Interval,
+    pub y: GenericInterval<T>,
     /// The interval of the bounding box in the z direction.
-    pub z: Interval,
+    pub z: GenericInterval<T>,
 }
 
-impl Aabb {
+impl<T: Scalar> GenericAabb<T> {
     /// Empty bounding box.
     pub const EMPTY: Self = Self {
-        x: Interval::EMPTY,
-        y: Interval::EMPTY,
-        z: Interval::EMPTY,
+        x: GenericInterval::EMPTY,
+        y: GenericInterval::EMPTY,
+        z: GenericInterval::EMPTY,
+    };
+
+    /// Bounding box containing all of space, used by unbounded objects like planes.
+    pub const UNIVERSE: Self = Self {
+        x: GenericInterval::UNIVERSE,
+        y: GenericInterval::UNIVERSE,
+        z: GenericInterval::UNIVERSE,
     };
 
     /// Creates a new axis-aligned bounding box from two points.
-    pub fn new(min: Point3, max: Point3) -> Self {
+    pub fn new(min: GenericVec3<T>, max: GenericVec3<T>) -> Self {
         Self {
-            x: intr!(min.x, max.x),
-            y: intr!(min.y, max.y),
-            z: intr!(min.z, max.z),
+            x: GenericInterval {
+                start: min.x,
+                end: max.x,
+            },
+            y: GenericInterval {
+                start: min.y,
+                end: max.y,
+            },
+            z: GenericInterval {
+                start: min.z,
+                end: max.z,
+            },
         }
     }
 
-    /// Grows the bounding box to include another bounding box.
-    pub fn grow(&mut self, other: &Aabb) {
-        self.x.start = self.x.start.min(other.x.start);
-        self.x.end = self.x.end.max(other.x.end);
+    /// Creates a degenerate bounding box containing a single point.
+    pub fn from_point(point: GenericVec3<T>) -> Self {
+        Self::new(point, point)
+    }
+
+    /// Creates the smallest bounding box containing both bounding boxes.
+    pub fn union(a: &Self, b: &Self) -> Self {
+        Self {
+            x: a.x.union(&b.x),
+            y: a.y.union(&b.y),
+            z: a.z.union(&b.z),
+        }
+    }
+
+    /// Returns the bounding box with every axis grown to at least the given extent,
+    /// so flat objects like quads don't produce degenerate boxes.
+    pub fn pad(&self, min_extent: T) -> Self {
+        let pad = |axis: GenericInterval<T>| {
+            if axis.size() < min_extent {
+                axis.expand(min_extent - axis.size())
+            } else {
+                axis
+            }
+        };
+
+        Self {
+            x: pad(self.x),
+            y: pad(self.y),
+            z: pad(self.z),
+        }
+    }
+
+    /// Returns whether the bounding box has finite bounds on every axis.
+    pub fn is_bounded(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .all(|axis| axis.start > T::NEG_INFINITY && axis.end < T::INFINITY)
+    }
+
+    /// Returns the center of the bounding box.
+    pub fn center(&self) -> GenericVec3<T> {
+        let half = T::from_f64(0.5);
+
+        GenericVec3 {
+            x: (self.x.start + self.x.end) * half,
+            y: (self.y.start + self.y.end) * half,
+            z: (self.z.start + self.z.end) * half,
+        }
+    }
+
+    /// Returns the surface area of the bounding box, empty boxes have no area.
+    pub fn surface_area(&self) -> T {
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
 
-        self.y.start = self.y.start.min(other.y.start);
-        self.y.end = self.y.end.max(other.y.end);
+        if dx < T::ZERO || dy < T::ZERO || dz < T::ZERO {
+            return T::ZERO;
+        }
+
+        T::from_f64(2.0) * (dx * dy + dy * dz + dz * dx)
+    }
 
-        self.z.start = self.z.start.min(other.z.start);
-        self.z.end = self.z.end.max(other.z.end);
+    /// Grows the bounding box to include another bounding box.
+    pub fn grow(&mut self, other: &Self) {
+        *self = Self::union(self, other);
     }
 
     /// Returns the axis with the largest extent.
     pub fn largest_axis(&self) -> usize {
-        let x_extent = self.x.end - self.x.start;
-        let y_extent = self.y.end - self.y.start;
-        let z_extent = self.z.end - self.z.start;
+        let x_extent = self.x.size();
+        let y_extent = self.y.size();
+        let z_extent = self.z.size();
 
         if x_extent > y_extent {
             if x_extent > z_extent {
@@ -62,7 +140,7 @@ impl Aabb {
     }
 
     /// Returns the component of the bounding box for the given axis.
-    pub fn component(&self, axis: usize) -> Interval {
+    pub fn component(&self, axis: usize) -> GenericInterval<T> {
         match axis {
             0 => self.x,
             1 => self.y,
@@ -70,16 +148,25 @@ impl Aabb {
             _ => panic!("Invalid axis"),
         }
     }
+}
 
+impl Aabb {
     /// Checks if the bounding box intersects with a ray.
-    pub fn hit(&self, ray: &Ray, mut time: Interval) -> bool {
+    pub fn hit(&self, ray: &Ray, time: Interval) -> bool {
+        self.hit_inverse(&ray.inverse(), time)
+    }
+
+    /// Checks if the bounding box intersects with a ray prepared for traversal,
+    /// which avoids the divisions when testing the ray against many boxes.
+    pub fn hit_inverse(&self, ray: &InverseRay, mut time: Interval) -> bool {
         for idx in 0..3 {
             let axis = self[idx];
-            let inv_d = 1.0 / ray.dir[idx];
+            let inv_d = ray.inv_dir[idx];
 
             let t0 = (axis.start - ray.orig[idx]) * inv_d;
             let t1 = (axis.end - ray.orig[idx]) * inv_d;
 
+            // Ordering the bounds by comparing them is faster than by the sign of the direction.
             let (t0, t1) = if t1 < t0 { (t1, t0) } else { (t0, t1) };
 
             if t0 > time.start {
@@ -99,8 +186,8 @@ impl Aabb {
     }
 }
 
-impl Index<usize> for Aabb {
-    type Output = Interval;
+impl<T> Index<usize> for GenericAabb<T> {
+    type Output = GenericInterval<T>;
 
     fn index(&self, idx: usize) -> &Self::Output {
         match idx {
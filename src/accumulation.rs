@@ -0,0 +1,206 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+};
+
+use crate::{
+    float::{to_f32, Float},
+    framebuffer::FrameBuffer,
+    vec3,
+};
+
+/// The magic bytes at the start of an accumulation file.
+const MAGIC: &[u8; 4] = b"RACC";
+
+#[derive(Debug, Clone)]
+/// The averaged HDR samples of a render, together with the number of samples per pixel.
+pub struct Accumulation {
+    /// The average of the samples of every pixel.
+    pub buffer: FrameBuffer,
+    /// The number of samples per pixel.
+    pub samples: u32,
+}
+
+impl Accumulation {
+    /// Creates a new accumulation from a frame buffer of averaged samples.
+    pub fn new(buffer: FrameBuffer, samples: u32) -> Self {
+        Self { buffer, samples }
+    }
+
+    /// Merges the accumulations of independent renders of the same image,
+    /// weighting each by its number of samples.
+    pub fn merge(accumulations: &[Accumulation]) -> Result<Accumulation, &'static str> {
+        let first = accumulations.first().ok_or("no accumulations to merge")?;
+        let (width, height) = (first.buffer.width, first.buffer.height);
+
+        if accumulations
+            .iter()
+            .any(|acc| acc.buffer.width != width || acc.buffer.height != height)
+        {
+            return Err("accumulation sizes do not match");
+        }
+
+        let samples: u32 = accumulations.iter().map(|acc| acc.samples).sum();
+        if samples == 0 {
+            return Err("accumulations contain no samples");
+        }
+
+        let mut buffer = FrameBuffer::new(width, height);
+        for acc in accumulations {
+            let weight = acc.samples as Float / samples as Float;
+
+            for (merged, color) in buffer.data.iter_mut().zip(acc.buffer.data.iter()) {
+                *merged += *color * weight;
+            }
+        }
+
+        Ok(Accumulation::new(buffer, samples))
+    }
+
+    /// Loads an accumulation from the file at the given path.
+    pub fn load<T: ToString>(path: T) -> Result<Accumulation, &'static str> {
+        let file = File::open(path.to_string()).map_err(|_| "failed to open file")?;
+        let length = file
+            .metadata()
+            .map_err(|_| "failed to read file size")?
+            .len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| "failed to read header")?;
+        if &magic != MAGIC {
+            return Err("not an accumulation file");
+        }
+
+        let mut read_u32 = || -> Result<u32, &'static str> {
+            let mut bytes = [0; 4];
+            reader
+                .read_exact(&mut bytes)
+                .map_err(|_| "failed to read header")?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+
+        let width = read_u32()?;
+        let height = read_u32()?;
+        let samples = read_u32()?;
+
+        // Check the size in the header against the file, before allocating the pixels.
+        let expected = (width as u64)
+            .checked_mul(height as u64)
+            .and_then(|pixels| pixels.checked_mul(12))
+            .and_then(|bytes| bytes.checked_add(16));
+        if expected != Some(length) {
+            return Err("file size does not match the header");
+        }
+
+        let mut buffer = FrameBuffer::new(width, height);
+        let mut bytes = [0; 12];
+        for color in buffer.data.iter_mut() {
+            reader
+                .read_exact(&mut bytes)
+                .map_err(|_| "failed to read pixels")?;
+
+            let channel =
+                |i: usize| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) as Float;
+            *color = vec3!(channel(0), channel(4), channel(8));
+        }
+
+        Ok(Accumulation::new(buffer, samples))
+    }
+
+    /// Saves the accumulation to a file at the given path.
+    pub fn save<T: ToString>(&self, path: T) -> Result<(), &'static str> {
+        let path = path.to_string();
+        let _span = tracing::info_span!("save", path = %path).entered();
+
+        let file = File::create(&path).map_err(|_| "failed to create file")?;
+        self.save_to(BufWriter::new(file))
+    }
+
+    /// Writes the accumulation into the writer, like a socket or an in-memory buffer.
+    pub fn save_to<W: Write>(&self, mut writer: W) -> Result<(), &'static str> {
+        let mut header = Vec::with_capacity(16);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&self.buffer.width.to_le_bytes());
+        header.extend_from_slice(&self.buffer.height.to_le_bytes());
+        header.extend_from_slice(&self.samples.to_le_bytes());
+        writer
+            .write_all(&header)
+            .map_err(|_| "failed to write header")?;
+
+        for color in self.buffer.data.iter() {
+            for channel in [color.x, color.y, color.z] {
+                writer
+                    .write_all(&to_f32(channel).to_le_bytes())
+                    .map_err(|_| "failed to write pixels")?;
+            }
+        }
+
+        writer.flush().map_err(|_| "failed to write pixels")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process};
+
+    use super::*;
+
+    /// Returns an accumulation whose pixels all hold the given value.
+    fn filled(value: Float, samples: u32) -> Accumulation {
+        let mut buffer = FrameBuffer::new(3, 2);
+        buffer.data.fill(vec3!(value));
+        Accumulation::new(buffer, samples)
+    }
+
+    fn temp_path(name: &str) -> String {
+        let name = format!("rusty-ray-{}-{name}.racc", process::id());
+        std::env::temp_dir().join(name).display().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut accumulation = filled(0.5, 7);
+        accumulation.buffer.data[4] = vec3!(1.25, 2.0, 0.0);
+
+        let path = temp_path("round-trip");
+        accumulation.save(&path).unwrap();
+        let loaded = Accumulation::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.samples, 7);
+        assert_eq!(
+            (loaded.buffer.width, loaded.buffer.height),
+            (accumulation.buffer.width, accumulation.buffer.height)
+        );
+        assert_eq!(loaded.buffer.data, accumulation.buffer.data);
+    }
+
+    #[test]
+    fn load_rejects_truncated_file() {
+        let mut data = Vec::new();
+        filled(0.5, 1).save_to(&mut data).unwrap();
+
+        let path = temp_path("truncated");
+        fs::write(&path, &data[..data.len() - 4]).unwrap();
+        let loaded = Accumulation::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.err(), Some("file size does not match the header"));
+    }
+
+    #[test]
+    fn merge_weights_by_samples() {
+        let merged = Accumulation::merge(&[filled(0.0, 1), filled(4.0, 3)]).unwrap();
+        assert_eq!(merged.samples, 4);
+        assert!(merged.buffer.data.iter().all(|&color| color == vec3!(3.0)));
+
+        let mut smaller = filled(0.0, 1);
+        smaller.buffer = FrameBuffer::new(2, 2);
+        assert!(Accumulation::merge(&[filled(0.0, 1), smaller]).is_err());
+        assert!(Accumulation::merge(&[]).is_err());
+    }
+}
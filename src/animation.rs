@@ -0,0 +1,319 @@
+use std::{fmt::Debug, sync::Arc};
+
+use crate::{
+    camera::{Camera, CameraBuilder},
+    float::Float,
+    hittable::Hittable,
+    imgbuf::ImageBuffer,
+    material::Material,
+    objects::transformed::TransformedObject,
+    resources::{MaterialId, Resources},
+    scene::{ObjectId, Scene},
+    transform::Transform,
+    vector::Vec3,
+};
+
+/// A value that can be interpolated between keyframes.
+pub trait Interpolate: Clone {
+    /// Interpolates between `self` at `t = 0` and the other value at `t = 1`.
+    fn interpolate(&self, other: &Self, t: Float) -> Self;
+}
+
+impl Interpolate for Float {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Transform {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        Transform::interpolate(self, other, t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How the values between two keyframes are computed.
+pub enum Easing {
+    #[default]
+    /// The value changes at a constant rate.
+    Linear,
+    /// The value speeds up after the first and slows down towards the second keyframe.
+    Smooth,
+    /// The value jumps to the next keyframe when it is reached.
+    Step,
+}
+
+#[derive(Debug, Clone)]
+/// A value that changes over time, defined by keyframes.
+pub struct Keyframes<T> {
+    /// The keyframes ordered by their time in seconds.
+    keys: Vec<(Float, T)>,
+    /// How the values between keyframes are computed.
+    easing: Easing,
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    /// Creates a new track without keyframes.
+    pub fn new(easing: Easing) -> Self {
+        Self {
+            keys: Vec::new(),
+            easing,
+        }
+    }
+
+    /// Adds a keyframe at the given time in seconds.
+    pub fn with(mut self, time: Float, value: T) -> Self {
+        self.add(time, value);
+        self
+    }
+
+    /// Adds a keyframe at the given time in seconds, replacing a keyframe at the same time.
+    pub fn add(&mut self, time: Float, value: T) {
+        match self.keys.binary_search_by(|(key, _)| key.total_cmp(&time)) {
+            Ok(idx) => self.keys[idx].1 = value,
+            Err(idx) => self.keys.insert(idx, (time, value)),
+        }
+    }
+
+    /// Returns the time of the last keyframe.
+    pub fn end(&self) -> Float {
+        self.keys.last().map_or(0.0, |(time, _)| *time)
+    }
+
+    /// Returns the value at the given time, holding the first and last keyframes
+    /// outside of the track. Returns `None` if there are no keyframes.
+    pub fn sample(&self, time: Float) -> Option<T> {
+        let idx = self.keys.partition_point(|(key, _)| *key <= time);
+
+        if idx == 0 {
+            return self.keys.first().map(|(_, value)| value.clone());
+        }
+        if idx == self.keys.len() {
+            return self.keys.last().map(|(_, value)| value.clone());
+        }
+
+        let (start, from) = &self.keys[idx - 1];
+        let (end, to) = &self.keys[idx];
+        let t = (time - start) / (end - start);
+
+        let t = match self.easing {
+            Easing::Linear => t,
+            Easing::Smooth => t * t * (3.0 - 2.0 * t),
+            Easing::Step => 0.0,
+        };
+
+        Some(from.interpolate(to, t))
+    }
+}
+
+/// A track that rebuilds a material from an animated parameter.
+trait MaterialTrack: Send + Sync {
+    /// Replaces the material in the resources with its state at the given time.
+    fn apply(&self, time: Float, resources: &mut Resources);
+
+    /// Returns the time of the last keyframe.
+    fn end(&self) -> Float;
+}
+
+/// A material track for a parameter of type `T`.
+struct ParameterTrack<T, F> {
+    /// The material that is animated.
+    id: MaterialId,
+    /// The keyframes of the parameter.
+    keyframes: Keyframes<T>,
+    /// Creates the material from the parameter.
+    build: F,
+}
+
+impl<T, F, M> MaterialTrack for ParameterTrack<T, F>
+where
+    T: Interpolate + Send + Sync,
+    F: Fn(T) -> M + Send + Sync,
+    M: Material + 'static,
+{
+    fn apply(&self, time: Float, resources: &mut Resources) {
+        if let Some(value) = self.keyframes.sample(time) {
+            resources.replace_material(self.id, (self.build)(value));
+        }
+    }
+
+    fn end(&self) -> Float {
+        self.keyframes.end()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A track that moves an object in the scene.
+struct ObjectTrack {
+    /// The id of the object in the scene.
+    id: ObjectId,
+    /// The object in its local space.
+    object: Arc<dyn Hittable>,
+    /// The keyframes of the transformation from local to world space.
+    transform: Keyframes<Transform>,
+}
+
+#[derive(Default)]
+/// A timeline of keyframed objects, materials and camera positions.
+pub struct Animation {
+    /// The tracks moving objects.
+    objects: Vec<ObjectTrack>,
+    /// The tracks changing materials.
+    materials: Vec<Box<dyn MaterialTrack>>,
+    /// The keyframes of the camera position.
+    look_from: Option<Keyframes<Vec3>>,
+    /// The keyframes of the point the camera looks at.
+    look_at: Option<Keyframes<Vec3>>,
+}
+
+impl Animation {
+    /// Creates a new empty animation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Animates the transformation of an object in the scene, the object with the given
+    /// id is replaced by the local object placed with the transformation of each frame.
+    pub fn add_object<H: Hittable + 'static>(
+        &mut self,
+        id: ObjectId,
+        object: H,
+        transform: Keyframes<Transform>,
+    ) -> &mut Self {
+        self.objects.push(ObjectTrack {
+            id,
+            object: Arc::new(object),
+            transform,
+        });
+        self
+    }
+
+    /// Animates a parameter of a material, which is rebuilt from the parameter for each frame.
+    pub fn add_material<T, F, M>(
+        &mut self,
+        id: MaterialId,
+        keyframes: Keyframes<T>,
+        build: F,
+    ) -> &mut Self
+    where
+        T: Interpolate + Send + Sync + 'static,
+        F: Fn(T) -> M + Send + Sync + 'static,
+        M: Material + 'static,
+    {
+        self.materials.push(Box::new(ParameterTrack {
+            id,
+            keyframes,
+            build,
+        }));
+        self
+    }
+
+    /// Animates the position of the camera.
+    pub fn with_look_from(&mut self, keyframes: Keyframes<Vec3>) -> &mut Self {
+        self.look_from = Some(keyframes);
+        self
+    }
+
+    /// Animates the point the camera looks at.
+    pub fn with_look_at(&mut self, keyframes: Keyframes<Vec3>) -> &mut Self {
+        self.look_at = Some(keyframes);
+        self
+    }
+
+    /// Returns the time of the last keyframe of any track.
+    pub fn duration(&self) -> Float {
+        let objects = self.objects.iter().map(|track| track.transform.end());
+        let materials = self.materials.iter().map(|track| track.end());
+        let camera = [&self.look_from, &self.look_at]
+            .into_iter()
+            .flatten()
+            .map(|keyframes| keyframes.end());
+
+        objects.chain(materials).chain(camera).fold(0.0, Float::max)
+    }
+
+    /// Updates the scene and resources to the given time. Objects are moved along their
+    /// motion until the end of the frame, so they are blurred over the shutter interval.
+    pub fn apply(
+        &self,
+        time: Float,
+        frame_duration: Float,
+        scene: &mut Scene,
+        resources: &mut Resources,
+    ) {
+        for track in self.objects.iter() {
+            let (Some(start), Some(end)) = (
+                track.transform.sample(time),
+                track.transform.sample(time + frame_duration),
+            ) else {
+                continue;
+            };
+
+            let object = if start == end {
+                TransformedObject::from_shared(track.object.clone(), start)
+            } else {
+                TransformedObject::from_shared_with_motion(track.object.clone(), start, end)
+            };
+
+            scene.replace(track.id, object);
+        }
+
+        for track in self.materials.iter() {
+            track.apply(time, resources);
+        }
+    }
+
+    /// Builds the camera at the given time, starting from the settings of the builder.
+    pub fn camera(&self, builder: &CameraBuilder, time: Float) -> Camera {
+        let mut builder = builder.clone();
+
+        if let Some(look_from) = self.look_from.as_ref().and_then(|k| k.sample(time)) {
+            builder.with_look_from(look_from);
+        }
+        if let Some(look_at) = self.look_at.as_ref().and_then(|k| k.sample(time)) {
+            builder.with_look_at(look_at);
+        }
+
+        builder.build()
+    }
+
+    /// Renders every frame of the animation at the given frame rate, updating the scene,
+    /// resources and camera before each frame and passing the finished images to the callback.
+    pub fn render<F: FnMut(u32, ImageBuffer)>(
+        &self,
+        scene: &mut Scene,
+        resources: &mut Resources,
+        builder: &CameraBuilder,
+        fps: Float,
+        mut on_frame: F,
+    ) {
+        let frame_duration = 1.0 / fps;
+        let frame_count = (self.duration() * fps).floor() as u32 + 1;
+
+        for frame in 0..frame_count {
+            let time = frame as Float * frame_duration;
+
+            self.apply(time, frame_duration, scene, resources);
+            let camera = self.camera(builder, time);
+
+            on_frame(frame, camera.render(scene, resources, |_| {}));
+        }
+    }
+}
+
+impl Debug for Animation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Animation")
+            .field("objects", &self.objects)
+            .field("materials", &self.materials.len())
+            .field("look_from", &self.look_from)
+            .field("look_at", &self.look_at)
+            .finish()
+    }
+}
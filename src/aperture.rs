@@ -0,0 +1,93 @@
+use crate::float::consts::PI;
+use crate::float::Float;
+use std::sync::Arc;
+
+use crate::{imgbuf::ImageBuffer, random::random_float};
+
+/// The number of attempts to sample a mask before falling back to the center.
+const MAX_MASK_ATTEMPTS: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+/// The shape of the camera's lens aperture, which shapes out of focus highlights.
+pub enum Aperture {
+    #[default]
+    /// A perfectly round aperture.
+    Disk,
+    /// A regular polygon formed by the given number of blades, rotated by an angle in degrees.
+    Polygon { blades: u32, rotation: Float },
+    /// An aperture shaped by the brightness of a mask image, stretched over the square around
+    /// the unit disk. The parts of the mask outside of the disk are cut off.
+    Mask(Arc<ImageBuffer>),
+}
+
+impl Aperture {
+    /// Samples a random point on the aperture, inside the unit disk.
+    pub fn sample(&self) -> (Float, Float) {
+        match self {
+            Aperture::Disk => {
+                // Map the square to the disk concentrically, which keeps the stratification
+                // of the numbers of a sampler, unlike rejecting points outside the disk.
+                let x = random_float() * 2.0 - 1.0;
+                let y = random_float() * 2.0 - 1.0;
+                if x == 0.0 && y == 0.0 {
+                    return (0.0, 0.0);
+                }
+
+                let (radius, angle) = if x.abs() > y.abs() {
+                    (x, PI / 4.0 * (y / x))
+                } else {
+                    (y, PI / 2.0 - PI / 4.0 * (x / y))
+                };
+                (radius * angle.cos(), radius * angle.sin())
+            }
+            Aperture::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3);
+
+                // Pick one of the triangles between the center and two neighbouring corners.
+                let blade = ((random_float() * blades as Float) as u32).min(blades - 1);
+                let angle = |corner: u32| {
+                    rotation.to_radians() + 2.0 * PI * corner as Float / blades as Float
+                };
+                let (a, b) = (angle(blade), angle(blade + 1));
+
+                // Sample the triangle uniformly by folding the unit square.
+                let mut s = random_float();
+                let mut t = random_float();
+                if s + t > 1.0 {
+                    s = 1.0 - s;
+                    t = 1.0 - t;
+                }
+
+                (s * a.cos() + t * b.cos(), s * a.sin() + t * b.sin())
+            }
+            Aperture::Mask(mask) => {
+                if mask.width == 0 || mask.height == 0 {
+                    return (0.0, 0.0);
+                }
+
+                // Rejection sample the mask, using its brightness as the acceptance probability.
+                for _ in 0..MAX_MASK_ATTEMPTS {
+                    let x = random_float();
+                    let y = random_float();
+
+                    let (u, v) = (x * 2.0 - 1.0, 1.0 - y * 2.0);
+                    if u * u + v * v > 1.0 {
+                        continue;
+                    }
+
+                    let px = ((x * mask.width as Float) as u32).min(mask.width - 1);
+                    let py = ((y * mask.height as Float) as u32).min(mask.height - 1);
+                    let pixel = &mask[(px, py)];
+                    let brightness =
+                        (pixel[0] as Float + pixel[1] as Float + pixel[2] as Float) / 765.0;
+
+                    if random_float() < brightness {
+                        return (u, v);
+                    }
+                }
+
+                (0.0, 0.0)
+            }
+        }
+    }
+}
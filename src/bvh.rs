@@ -1,19 +1,98 @@
-use std::ops::Index;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::Hasher,
+    io::{BufReader, BufWriter, Read, Write},
+    mem,
+    ops::Index,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
 
-use crate::{aabb::Aabb, interval::Interval, ray::Ray, scene::ObjectId};
+use crate::{
+    aabb::Aabb,
+    float::Float,
+    interval::Interval,
+    intr,
+    profiler::{self, Counter},
+    ray::Ray,
+    scene::ObjectId,
+};
+
+/// The magic bytes at the start of a cached BVH file.
+const MAGIC: &[u8; 4] = b"RBVH";
+
+/// The version of the cached BVH files, which changes whenever the format or the way
+/// the tree is built changes, so outdated files are ignored.
+const CACHE_VERSION: u32 = 1;
+
+/// The directory built BVHs are cached in, or `None` if caching is disabled.
+static CACHE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Caches the BVHs built from now on in the directory, keyed by a hash of the bounding boxes
+/// they are built over, so renders of the same scene load them instead of building them again.
+/// This includes the BVHs over the triangles of meshes. `None` disables caching.
+pub fn set_cache_dir(dir: Option<PathBuf>) {
+    *CACHE_DIR.lock().unwrap() = dir;
+}
+
+/// An object of a BVH, which is stored in cached BVH files by its index.
+pub trait BvhObject: Copy {
+    /// Returns the index of the object.
+    fn index(self) -> u64;
+
+    /// Returns the object with the given index.
+    fn from_index(index: u64) -> Self;
+}
+
+impl BvhObject for usize {
+    fn index(self) -> u64 {
+        self as u64
+    }
+
+    fn from_index(index: u64) -> Self {
+        index as usize
+    }
+}
 
 #[derive(Debug)]
-/// A bounding volume hierarchy for a scene.
-pub struct Bvh {
+/// A bounding volume hierarchy over objects identified by `T`, which are scene objects by default.
+pub struct Bvh<T = ObjectId> {
     /// The nodes in the BVH.
-    nodes: Vec<BvhNode>,
+    nodes: Vec<BvhNode<T>>,
     /// The root node of the BVH.
     root: Option<NodeId>,
+    /// The objects without finite bounds, which are kept out of the tree and always tested.
+    unbounded: Vec<T>,
 }
 
-impl Bvh {
+impl<T: BvhObject> Bvh<T> {
     /// Creates a new bounding volume hierarchy for the given bounding boxes.
-    pub fn new(mut objects: Vec<(ObjectId, Aabb)>) -> Self {
+    /// Objects with unbounded boxes, like planes, are returned by every hit instead.
+    /// With a cache directory set, a BVH cached for the same boxes is loaded instead.
+    pub fn new(objects: Vec<(T, Aabb)>) -> Self {
+        let Some(dir) = CACHE_DIR.lock().unwrap().clone() else {
+            return Self::build(objects);
+        };
+
+        let path = dir.join(format!("{:016x}.bvh", Self::content_hash(&objects)));
+        if let Ok(bvh) = Self::load(&path, &objects) {
+            return bvh;
+        }
+
+        let bvh = Self::build(objects);
+        // A cache that can't be written only costs the time of building again.
+        let _ = fs::create_dir_all(&dir).and_then(|_| bvh.save(&path));
+        bvh
+    }
+
+    /// Builds a new bounding volume hierarchy for the given bounding boxes.
+    fn build(objects: Vec<(T, Aabb)>) -> Self {
+        let (mut objects, unbounded): (Vec<_>, Vec<_>) =
+            objects.into_iter().partition(|(_, bb)| bb.is_bounded());
+        let unbounded = unbounded.into_iter().map(|(object, _)| object).collect();
+
         let mut nodes = Vec::new();
 
         let mut root = None;
@@ -29,14 +108,18 @@ impl Bvh {
             ));
         }
 
-        Self { nodes, root }
+        Self {
+            nodes,
+            root,
+            unbounded,
+        }
     }
 
     /// Builds the BVH by splitting the objects into two groups based on the axis with the largest extent.
     /// Returns the id of the created node.
     fn build_tree(
-        nodes: &mut Vec<BvhNode>,
-        objects: &mut [(ObjectId, Aabb)],
+        nodes: &mut Vec<BvhNode<T>>,
+        objects: &mut [(T, Aabb)],
         start: usize,
         end: usize,
     ) -> NodeId {
@@ -86,13 +169,185 @@ impl Bvh {
         }
     }
 
+    /// Hashes the objects and their bounding boxes, which determine the built tree.
+    fn content_hash(objects: &[(T, Aabb)]) -> u64 {
+        // The hash names the cache files, so it has to be the same for every build and run.
+        let mut hasher = Fnv1a::default();
+        hasher.write_u32(CACHE_VERSION);
+        hasher.write_usize(mem::size_of::<Float>());
+
+        for (object, bb) in objects {
+            hasher.write_u64(object.index());
+            for axis in [bb.x, bb.y, bb.z] {
+                hasher.write(&axis.start.to_le_bytes());
+                hasher.write(&axis.end.to_le_bytes());
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Saves the BVH to a temporary file next to the given path and then renames it,
+    /// so concurrent renders never load a partially written file. The temporary file is
+    /// named after the process, so concurrent renders don't write into the same one.
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(format!(".{}.tmp", process::id()));
+
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&CACHE_VERSION.to_le_bytes())?;
+
+        let root = self.root.map_or(u64::MAX, |root| root.0 as u64);
+        writer.write_all(&root.to_le_bytes())?;
+
+        writer.write_all(&(self.unbounded.len() as u64).to_le_bytes())?;
+        for object in self.unbounded.iter() {
+            writer.write_all(&object.index().to_le_bytes())?;
+        }
+
+        writer.write_all(&(self.nodes.len() as u64).to_le_bytes())?;
+        for node in self.nodes.iter() {
+            match node {
+                BvhNode::Leaf(object) => {
+                    writer.write_all(&0u64.to_le_bytes())?;
+                    writer.write_all(&object.index().to_le_bytes())?;
+                }
+                BvhNode::Branch {
+                    left,
+                    right,
+                    bounding_box,
+                } => {
+                    writer.write_all(&1u64.to_le_bytes())?;
+                    writer.write_all(&(left.0 as u64).to_le_bytes())?;
+                    writer.write_all(&(right.0 as u64).to_le_bytes())?;
+                    for axis in [bounding_box.x, bounding_box.y, bounding_box.z] {
+                        writer.write_all(&axis.start.to_le_bytes())?;
+                        writer.write_all(&axis.end.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&temporary, path)
+    }
+
+    /// Loads a BVH over the given objects from the file at the given path. The file is checked
+    /// to hold a single tree with every object in exactly one leaf, so a corrupted file is
+    /// rejected instead of causing out of bounds objects or endless traversals.
+    fn load(path: &Path, objects: &[(T, Aabb)]) -> Result<Self, &'static str> {
+        let file = File::open(path).map_err(|_| "failed to open file")?;
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 4];
+        read_bytes(&mut reader, &mut magic)?;
+        let mut version = [0; 4];
+        read_bytes(&mut reader, &mut version)?;
+        if &magic != MAGIC || u32::from_le_bytes(version) != CACHE_VERSION {
+            return Err("not a cached BVH file");
+        }
+
+        // Whether each object is bounded, which is removed once the object is found in the file.
+        let mut remaining: HashMap<u64, bool> = objects
+            .iter()
+            .map(|(object, bb)| (object.index(), bb.is_bounded()))
+            .collect();
+        let mut take_object = |index: u64, bounded: bool| match remaining.remove(&index) {
+            Some(is_bounded) if is_bounded == bounded => Ok(T::from_index(index)),
+            _ => Err("cached BVH doesn't match the objects"),
+        };
+
+        let root = read_u64(&mut reader)?;
+        let unbounded_count = read_u64(&mut reader)? as usize;
+        if unbounded_count > objects.len() {
+            return Err("cached BVH doesn't match the objects");
+        }
+        let unbounded = (0..unbounded_count)
+            .map(|_| take_object(read_u64(&mut reader)?, false))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Every object is in exactly one leaf, and every branch joins two subtrees.
+        let node_count = read_u64(&mut reader)? as usize;
+        let leaf_count = objects.len() - unbounded_count;
+        if node_count != (2 * leaf_count).saturating_sub(1) {
+            return Err("cached BVH doesn't match the objects");
+        }
+
+        let mut nodes = Vec::with_capacity(node_count);
+        let mut has_parent = vec![false; node_count];
+        for _ in 0..node_count {
+            let node = match read_u64(&mut reader)? {
+                0 => BvhNode::Leaf(take_object(read_u64(&mut reader)?, true)?),
+                1 => {
+                    let left = read_u64(&mut reader)? as usize;
+                    let right = read_u64(&mut reader)? as usize;
+                    // Children are always stored before their parents, and belong to one parent.
+                    if left >= nodes.len() || right >= nodes.len() {
+                        return Err("invalid cached BVH node");
+                    }
+                    for child in [left, right] {
+                        if mem::replace(&mut has_parent[child], true) {
+                            return Err("invalid cached BVH node");
+                        }
+                    }
+
+                    let mut bounds = [0.0; 6];
+                    for bound in bounds.iter_mut() {
+                        let mut bytes = [0; mem::size_of::<Float>()];
+                        read_bytes(&mut reader, &mut bytes)?;
+                        *bound = Float::from_le_bytes(bytes);
+                    }
+
+                    BvhNode::Branch {
+                        left: NodeId(left),
+                        right: NodeId(right),
+                        bounding_box: Aabb {
+                            x: intr!(bounds[0], bounds[1]),
+                            y: intr!(bounds[2], bounds[3]),
+                            z: intr!(bounds[4], bounds[5]),
+                        },
+                    }
+                }
+                _ => return Err("invalid cached BVH node"),
+            };
+            nodes.push(node);
+        }
+
+        let root = match root {
+            u64::MAX => None,
+            root if (root as usize) < node_count => Some(NodeId(root as usize)),
+            _ => return Err("invalid cached BVH root"),
+        };
+        if root.is_none() != nodes.is_empty() {
+            return Err("invalid cached BVH root");
+        }
+
+        // As parents come after their children, only the root lacking a parent makes one tree.
+        let single_tree = has_parent
+            .iter()
+            .enumerate()
+            .all(|(idx, &has_parent)| has_parent != root.is_some_and(|root| root.0 == idx));
+        if !single_tree {
+            return Err("invalid cached BVH root");
+        }
+
+        Ok(Self {
+            nodes,
+            root,
+            unbounded,
+        })
+    }
+
     /// Checks for intersections between the ray and the objects in the scene.
     /// Returns a list of object IDs that were hit by the ray.
-    pub fn hit(&self, ray: &Ray, time: Interval) -> Option<Vec<ObjectId>> {
-        let mut hit_objects = Vec::new();
+    pub fn hit(&self, ray: &Ray, time: Interval) -> Option<Vec<T>> {
+        let mut hit_objects = self.unbounded.clone();
+        let inverse = ray.inverse();
 
-        // Start at the root node or return early if there is no root node
-        let mut stack = vec![self.root?];
+        // Start at the root node, without one only the unbounded objects can be hit
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
 
         while let Some(node_id) = stack.pop() {
             let node = &self[node_id];
@@ -106,7 +361,9 @@ impl Bvh {
                     right,
                     bounding_box,
                 } => {
-                    if bounding_box.hit(ray, time) {
+                    profiler::count(Counter::BvhNodeTests);
+
+                    if bounding_box.hit_inverse(&inverse, time) {
                         stack.push(*left);
                         stack.push(*right);
                     }
@@ -120,10 +377,54 @@ impl Bvh {
             Some(hit_objects)
         }
     }
+
+    /// Checks whether the ray hits any of the objects, testing the objects whose bounds the ray
+    /// passes through as they are found and stopping at the first one the test reports as hit.
+    pub fn any_hit<F: FnMut(T) -> bool>(&self, ray: &Ray, time: Interval, mut test: F) -> bool {
+        if self.unbounded.iter().any(|&object_id| test(object_id)) {
+            return true;
+        }
+
+        let inverse = ray.inverse();
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+
+        while let Some(node_id) = stack.pop() {
+            match &self[node_id] {
+                BvhNode::Leaf(object_id) => {
+                    if test(*object_id) {
+                        return true;
+                    }
+                }
+                BvhNode::Branch {
+                    left,
+                    right,
+                    bounding_box,
+                } => {
+                    profiler::count(Counter::BvhNodeTests);
+
+                    if bounding_box.hit_inverse(&inverse, time) {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        }
+
+        false
+    }
+}
+
+impl<T> Bvh<T> {
+    /// Returns an estimate of the bytes of memory used by the BVH, excluding the objects.
+    pub fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.nodes.capacity() * size_of::<BvhNode<T>>()
+            + self.unbounded.capacity() * size_of::<T>()
+    }
 }
 
-impl Index<NodeId> for Bvh {
-    type Output = BvhNode;
+impl<T> Index<NodeId> for Bvh<T> {
+    type Output = BvhNode<T>;
 
     fn index(&self, idx: NodeId) -> &Self::Output {
         &self.nodes[idx.0]
@@ -136,9 +437,9 @@ struct NodeId(usize);
 
 #[derive(Debug)]
 /// A node in the BVH.
-enum BvhNode {
+enum BvhNode<T> {
     /// A leaf node containing an object ID.
-    Leaf(ObjectId),
+    Leaf(T),
     /// A branch node containing two child nodes and a bounding box.
     Branch {
         left: NodeId,
@@ -146,3 +447,118 @@ enum BvhNode {
         bounding_box: Aabb,
     },
 }
+
+#[derive(Debug)]
+/// The 64 bit FNV-1a hash, which unlike the hasher of the standard library
+/// is the same for every Rust version.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Fills the buffer with the next bytes of a cached BVH file.
+fn read_bytes<R: Read>(reader: &mut R, bytes: &mut [u8]) -> Result<(), &'static str> {
+    reader
+        .read_exact(bytes)
+        .map_err(|_| "failed to read cached BVH")
+}
+
+/// Reads the next number of a cached BVH file.
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, &'static str> {
+    let mut bytes = [0; 8];
+    read_bytes(reader, &mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3;
+
+    /// Returns a row of unit boxes along the x axis, followed by an unbounded object.
+    fn objects() -> Vec<(usize, Aabb)> {
+        let mut objects: Vec<_> = (0..10)
+            .map(|i| {
+                let min = vec3!(i as Float * 2.0, 0, 0);
+                (i, Aabb::new(min, min + vec3!(1)))
+            })
+            .collect();
+        objects.push((10, Aabb::UNIVERSE));
+        objects
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rusty-ray-{}-{name}.bvh", process::id()))
+    }
+
+    /// Returns the sorted objects of the BVH whose bounds a ray along the z axis passes through.
+    fn hits(bvh: &Bvh<usize>, x: Float) -> Vec<usize> {
+        let ray = Ray::new(vec3!(x, 0.5, -5), vec3!(0, 0, 1));
+        let mut hits = bvh
+            .hit(&ray, intr!(0.0, Float::INFINITY))
+            .unwrap_or_default();
+        hits.sort();
+        hits
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let bvh = Bvh::build(objects());
+        let path = temp_path("round-trip");
+        bvh.save(&path).unwrap();
+        let loaded = Bvh::load(&path, &objects());
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        for x in [0.5, 4.5, 7.0, 18.5, 30.0] {
+            assert_eq!(hits(&bvh, x), hits(&loaded, x));
+        }
+        assert_eq!(hits(&loaded, 4.5), [2, 10]);
+    }
+
+    #[test]
+    fn cache_rejects_other_objects() {
+        let path = temp_path("other-objects");
+        Bvh::build(objects()).save(&path).unwrap();
+
+        let mut fewer = objects();
+        fewer.remove(3);
+        let mut unbounded = objects();
+        unbounded[3].1 = Aabb::UNIVERSE;
+
+        let data = fs::read(&path).unwrap();
+        let results = [
+            Bvh::load(&path, &fewer).is_err(),
+            Bvh::load(&path, &unbounded).is_err(),
+            fs::write(&path, &data[..data.len() / 2]).is_ok()
+                && Bvh::load(&path, &objects()).is_err(),
+        ];
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(results, [true; 3]);
+    }
+
+    #[test]
+    fn content_hash_depends_on_bounds() {
+        let mut moved = objects();
+        moved[5].1 = Aabb::new(vec3!(0), vec3!(1));
+
+        assert_eq!(Bvh::content_hash(&objects()), Bvh::content_hash(&objects()));
+        assert_ne!(Bvh::content_hash(&objects()), Bvh::content_hash(&moved));
+    }
+}
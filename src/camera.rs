@@ -1,34 +1,182 @@
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::aabb::Aabb;
+use crate::accumulation::Accumulation;
+use crate::aperture::Aperture;
+use crate::cancel::CancelToken;
+use crate::filter::{FilterSampler, PixelFilter};
+use crate::framebuffer::FrameBuffer;
+use crate::guiding::{self, PathGuide};
+use crate::heatmap::{SampleVariance, VarianceHeatmap};
+use crate::hittable::Hittable;
+use crate::interval::Interval;
 use crate::intr;
-use crate::random::THREAD_RNG;
+use crate::light_group::{LayeredColor, LightGroupId, LightGroupLayers, Radiance};
+use crate::lightmap::{self, BakeMode};
+use crate::material::{Material, Scatter, ScatterRecord};
+use crate::mesh::Mesh;
+use crate::nan_check::{self, InvalidBounce};
+use crate::onb::Onb;
+use crate::pdf::{ConePdf, CosinePdf, LightPdf, MixturePdf, Pdf, PortalPdf};
+use crate::postprocess::PostProcessPipeline;
+use crate::priority::PriorityMap;
+use crate::profiler::{self, Counter};
+use crate::progress::{ProgressEvent, RenderStage, Tile};
+use crate::random::{self, random_float, THREAD_RNG};
+use crate::render_handle::{RenderEvent, RenderHandle, RenderedTile};
+use crate::sampler::Sampler;
+use crate::stereo::{StereoCamera, StereoLayout};
 use crate::vector::Color;
 use crate::{
+    float::{consts::PI, Float},
     imgbuf::ImageBuffer,
-    ray::Ray,
-    resources::Resources,
+    ray::{Intersection, Ray},
+    resources::{Resources, TextureId},
     scene::Scene,
     vec3,
     vector::{Point3, Vec3},
 };
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// The projection used to map pixels to rays.
+pub enum Projection {
+    #[default]
+    /// A pinhole perspective projection with the vertical fov of the camera.
+    Perspective,
+    /// A 360 degree equirectangular panorama around the camera.
+    Equirectangular,
+    /// A 360 degree omni-directional stereo panorama with the given interpupillary
+    /// distance, the left eye is placed above the right eye. Odd image heights are
+    /// rounded up, so both eyes get the same number of rows.
+    OmniDirectionalStereo { ipd: Float },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The point the camera automatically focuses on.
+pub enum FocusTarget {
+    /// The first surface hit by a ray through the center of the image.
+    Center,
+    /// The look at point of the camera.
+    LookAt,
+    /// The surface of the object with the given name in the scene.
+    Named(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Named render settings, which set the sample count, the bounces, the resolution and the
+/// roughness clamp together, from quick iterations on a scene to the final image.
+pub enum Preset {
+    /// A quarter of the resolution with a few samples and bounces, for arranging the scene.
+    Draft,
+    /// Half of the resolution with enough samples to judge the lighting and materials.
+    Preview,
+    /// The full resolution with many samples and bounces and no bias.
+    Final,
+}
+
+impl Preset {
+    /// Returns the preset with the given name, `draft`, `preview` or `final`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "draft" => Some(Self::Draft),
+            "preview" => Some(Self::Preview),
+            "final" => Some(Self::Final),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+/// The buffers rendered next to the colors of the image, and the pixels that are rendered.
+struct ExtraBuffers<'a> {
+    /// The alpha of the pixels, with the background rendered transparent.
+    alpha: Option<&'a mut FrameBuffer>,
+    /// The light of every light group.
+    layers: Option<&'a mut LightGroupLayers>,
+    /// The variance of every pixel, which is only tracked if requested.
+    heatmap: Option<&'a mut VarianceHeatmap>,
+    /// Whether every pixel is rendered, the other pixels are left black.
+    active: Option<&'a [bool]>,
+}
+
+/// What a ray from the camera hit.
+enum PrimarySample {
+    /// The ray left the scene, with the color of the background.
+    Background(Color),
+    /// The ray hit a surface, with the light leaving it towards the camera.
+    Surface(Color),
+    /// The ray hit a shadow catcher, with the light it receives from the scene and from
+    /// the background alone, and the color of the background behind it.
+    Catcher {
+        received: Color,
+        unshadowed: Color,
+        background: Color,
+    },
+}
+
+/// The number of samples per pixel of reference renders.
+const REFERENCE_SAMPLES: u32 = 16;
+
+/// The seed of the random number generator for reference renders.
+const REFERENCE_SEED: u64 = 0x5eed;
+
 #[derive(Debug, Clone)]
 /// A camera, which can render a scene.
 pub struct Camera {
     /// The sample count of the camera.
     sample_count: u32,
+    /// The index of the first sample of every pixel, later passes of a render continue
+    /// the samples of the earlier ones.
+    first_sample: u32,
     /// The maximum number of bounces for rays in the scene.
     max_bounces: u32,
+    /// The smallest roughness of glossy and specular surfaces after a diffuse bounce.
+    roughness_clamp: Float,
     /// The width of the image to render.
     image_width: u32,
     /// The height of the image to render.
     image_height: u32,
+    /// The edge length of the tiles the image is rendered in.
+    tile_size: u32,
     /// The position of the camera.
     look_from: Point3,
+    /// The basis of the camera, u points right, v up, and w backwards.
+    basis: Onb,
+    /// The projection used to map pixels to rays.
+    projection: Projection,
+    /// The shape of the lens aperture.
+    aperture: Aperture,
+    /// The horizontal radius vector of the lens aperture.
+    defocus_u: Vec3,
+    /// The vertical radius vector of the lens aperture.
+    defocus_v: Vec3,
+    /// The normal of the plane in focus, scaled so its component along w is one.
+    /// Tilting the lens tilts the plane away from facing the camera.
+    focal_plane: Vec3,
+    /// The radial distortion coefficients of the lens.
+    distortion: (Float, Float),
+    /// The times at which the shutter opens and closes.
+    shutter: (Float, Float),
+    /// The distances of the near and far clipping planes from the camera.
+    clip: (Float, Float),
+    /// Samples the offsets of the rays from the pixel centers.
+    filter: FilterSampler,
+    /// Generates the random numbers of the samples, or `None` for independent random numbers.
+    sampler: Option<Arc<dyn Sampler>>,
+    /// The seed of the random number generator, or `None` to continue the current sequence.
+    seed: Option<u64>,
     /// The position of the top left pixel of the camera's view.
     pixel_origin: Point3,
     /// The offset between pixels in the horizontal direction.
     pixel_offset_u: Vec3,
     /// The offset between pixels in the vertical direction.
     pixel_offset_v: Vec3,
+    /// The effects applied to the HDR frame buffer before quantization.
+    post_process: PostProcessPipeline,
 }
 
 impl Camera {
@@ -47,111 +195,1447 @@ impl Camera {
         self.image_height
     }
 
+    /// Returns the tiles the image is rendered in.
+    pub fn tiles(&self) -> Vec<Tile> {
+        Tile::grid(self.image_width, self.image_height, self.tile_size)
+    }
+
     /// Renders the scene from the camera's perspective.
-    pub fn render<F: Fn(u32)>(
+    /// The callback receives events reporting the progress of the render.
+    pub fn render<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        callback: F,
+    ) -> ImageBuffer {
+        self.render_cancellable(scene, resources, &CancelToken::new(), callback)
+    }
+
+    /// Renders the scene from the camera's perspective, until the token is cancelled.
+    /// A cancelled render returns the image with all pixels rendered so far,
+    /// the remaining pixels are left black.
+    pub fn render_cancellable<F: Fn(ProgressEvent)>(
         &self,
         scene: &Scene,
         resources: &Resources,
+        cancel: &CancelToken,
         callback: F,
     ) -> ImageBuffer {
-        let mut image = ImageBuffer::new(self.image_width, self.image_height);
+        self.render_frame(scene, resources, cancel, callback, |_, _| {})
+    }
+
+    /// Renders the scene in front of a transparent background, for compositing it onto a
+    /// backplate. Returns the colors and the alpha of the pixels as a grayscale image,
+    /// shadow catchers only show the shadows they receive.
+    pub fn render_with_alpha<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        callback: F,
+    ) -> (ImageBuffer, ImageBuffer) {
+        let start = Instant::now();
+        let mut alpha = FrameBuffer::new(self.image_width, self.image_height);
+        let mut fb = self.render_samples(
+            scene,
+            resources,
+            &CancelToken::new(),
+            &callback,
+            ExtraBuffers {
+                alpha: Some(&mut alpha),
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        callback(ProgressEvent::StageStarted(RenderStage::PostProcessing));
+
+        profiler::time_stage("post process", || self.post_process.apply(&mut fb));
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        (fb.quantize(), alpha.quantize())
+    }
+
+    /// Renders the scene, also returning the variance of every pixel,
+    /// which shows where the image is still noisy.
+    pub fn render_with_heatmap<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        callback: F,
+    ) -> (ImageBuffer, VarianceHeatmap) {
+        let start = Instant::now();
+        let mut heatmap = VarianceHeatmap::new(self.image_width, self.image_height);
+        let mut fb = self.render_samples(
+            scene,
+            resources,
+            &CancelToken::new(),
+            &callback,
+            ExtraBuffers {
+                heatmap: Some(&mut heatmap),
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        callback(ProgressEvent::StageStarted(RenderStage::PostProcessing));
+
+        profiler::time_stage("post process", || self.post_process.apply(&mut fb));
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        (fb.quantize(), heatmap)
+    }
+
+    /// Starts rendering the scene on a background thread.
+    /// The returned handle streams the completed tiles and joins the final image.
+    pub fn render_async(&self, scene: Arc<Scene>, resources: Arc<Resources>) -> RenderHandle {
+        let camera = self.clone();
+        let cancel = CancelToken::new();
+        let (sender, receiver) = mpsc::channel();
+
+        let thread_cancel = cancel.clone();
+        let thread = thread::spawn(move || {
+            camera.render_frame(
+                &scene,
+                &resources,
+                &thread_cancel,
+                |_| {},
+                |tile, fb| {
+                    let pixels = tile.pixels().map(|pixel| fb[pixel]).collect();
+
+                    // Nobody may be listening for tiles, which is fine.
+                    let _ = sender.send(RenderedTile { tile, pixels });
+                },
+            )
+        });
+
+        RenderHandle::new(receiver, thread, cancel)
+    }
+
+    /// Renders the scene until the token is cancelled, sending the progress events and the
+    /// completed tiles over the channel instead of calling a closure on the render thread.
+    /// The render continues if the receiver is dropped, cancel it to stop rendering.
+    pub fn render_to_channel(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        cancel: &CancelToken,
+        sender: &mpsc::Sender<RenderEvent>,
+    ) -> ImageBuffer {
+        self.render_frame(
+            scene,
+            resources,
+            cancel,
+            |event| {
+                let _ = sender.send(RenderEvent::Progress(event));
+            },
+            |tile, fb| {
+                let pixels = tile.pixels().map(|pixel| fb[pixel]).collect();
+                let _ = sender.send(RenderEvent::Tile(RenderedTile { tile, pixels }));
+            },
+        )
+    }
 
-        let sample_scale = 1.0 / self.sample_count as f64;
+    /// Renders the scene into an accumulation of HDR samples without post processing,
+    /// which can be saved and merged with the accumulations of other renders.
+    pub fn render_accumulation<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        callback: F,
+    ) -> Accumulation {
+        let start = Instant::now();
+        let buffer = self.render_samples(
+            scene,
+            resources,
+            &CancelToken::new(),
+            &callback,
+            ExtraBuffers::default(),
+            |_, _| {},
+        );
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        Accumulation::new(buffer, self.sample_count)
+    }
+
+    /// Renders passes of the sample count of the camera until the time budget is spent, without
+    /// post processing. A pass is only started if it is expected to finish within the budget,
+    /// but at least one pass is rendered. The samples of the result count all passes.
+    pub fn render_for<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        budget: Duration,
+        callback: F,
+    ) -> Accumulation {
+        let start = Instant::now();
+        let pass_samples =
+            self.image_width as u64 * self.image_height as u64 * self.sample_count as u64;
+
+        let mut accumulation: Option<Accumulation> = None;
+        for pass_index in 0.. {
+            let previous = pass_index as u64 * pass_samples;
+            let buffer = self.pass(pass_index).render_samples(
+                scene,
+                resources,
+                &CancelToken::new(),
+                &|event| match event {
+                    ProgressEvent::SamplesCompleted {
+                        completed, total, ..
+                    } => callback(ProgressEvent::SamplesCompleted {
+                        completed: previous + completed,
+                        total: previous + total,
+                        elapsed: start.elapsed(),
+                        remaining: budget.saturating_sub(start.elapsed()),
+                    }),
+                    event => callback(event),
+                },
+                ExtraBuffers::default(),
+                |_, _| {},
+            );
+
+            let pass = Accumulation::new(buffer, self.sample_count);
+            let merged = match accumulation.take() {
+                Some(accumulation) => Accumulation::merge(&[accumulation, pass]),
+                None => Ok(pass),
+            };
+            accumulation = Some(merged.expect("passes have the same size"));
+
+            // Stop if the next pass would likely exceed the budget.
+            let elapsed = start.elapsed();
+            if elapsed + elapsed / (pass_index + 1) > budget {
+                break;
+            }
+        }
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        accumulation.expect("at least one pass is rendered")
+    }
+
+    /// Renders the given number of passes of the sample count of the camera with path guiding,
+    /// without post processing. Every pass learns from which directions the light arrives at
+    /// the diffuse surfaces, and the later passes send more of their rays there, which finds
+    /// the light that only reaches the surfaces through small openings or other surfaces.
+    /// The samples of the result count all passes.
+    pub fn render_guided<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        passes: u32,
+        callback: F,
+    ) -> Accumulation {
+        let start = Instant::now();
+        let pass_samples =
+            self.image_width as u64 * self.image_height as u64 * self.sample_count as u64;
+        let total = passes.max(1) as u64 * pass_samples;
+
+        let mut guide = PathGuide::new(scene.stats(resources).bounds);
+        let mut accumulation: Option<Accumulation> = None;
+        for pass_index in 0..passes.max(1) {
+            let previous = pass_index as u64 * pass_samples;
+
+            guiding::start(guide);
+            let buffer = self.pass(pass_index).render_samples(
+                scene,
+                resources,
+                &CancelToken::new(),
+                &|event| match event {
+                    ProgressEvent::SamplesCompleted {
+                        completed, elapsed, ..
+                    } => {
+                        let completed = previous + completed;
+                        let elapsed = start.elapsed().max(elapsed);
+                        callback(ProgressEvent::SamplesCompleted {
+                            completed,
+                            total,
+                            elapsed,
+                            remaining: elapsed
+                                .mul_f64((total - completed) as f64 / completed.max(1) as f64),
+                        })
+                    }
+                    event => callback(event),
+                },
+                ExtraBuffers::default(),
+                |_, _| {},
+            );
+            guide = guiding::finish().expect("the guide is set during the pass");
+            guide.update();
+
+            let pass = Accumulation::new(buffer, self.sample_count);
+            let merged = match accumulation.take() {
+                Some(accumulation) => Accumulation::merge(&[accumulation, pass]),
+                None => Ok(pass),
+            };
+            accumulation = Some(merged.expect("passes have the same size"));
+        }
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        accumulation.expect("at least one pass is rendered")
+    }
+
+    /// Renders the scene progressively in passes of the sample count of the camera, without
+    /// post processing. After every pass, `on_pass` is called with the average of the samples
+    /// of every pixel so far. Pixels of higher priority get their passes earlier, but every
+    /// pixel gets the given number of passes, so the result doesn't depend on the priorities.
+    pub fn render_progressive<F: Fn(ProgressEvent), P: FnMut(&FrameBuffer)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        priority: &PriorityMap,
+        passes: u32,
+        callback: F,
+        mut on_pass: P,
+    ) -> Accumulation {
+        assert!(
+            priority.width == self.image_width && priority.height == self.image_height,
+            "priority map size does not match the image"
+        );
+
+        let start = Instant::now();
+        let passes = passes.max(1);
+        let total_passes = priority.total_passes(passes);
+        let pass_samples =
+            self.image_width as u64 * self.image_height as u64 * self.sample_count as u64;
+        let total = total_passes as u64 * pass_samples;
+
+        let start_passes: Vec<u32> = (0..self.image_height)
+            .flat_map(|y| (0..self.image_width).map(move |x| (x, y)))
+            .map(|(x, y)| priority.start_pass(x, y, passes))
+            .collect();
+
+        let mut sum = FrameBuffer::new(self.image_width, self.image_height);
+        let mut counts = vec![0u32; sum.data.len()];
+        let mut average = sum.clone();
+
+        for pass_index in 0..total_passes {
+            let active: Vec<bool> = start_passes
+                .iter()
+                .map(|&first| (first..first + passes).contains(&pass_index))
+                .collect();
+
+            let previous = pass_index as u64 * pass_samples;
+            let buffer = self.pass(pass_index).render_samples(
+                scene,
+                resources,
+                &CancelToken::new(),
+                &|event| match event {
+                    ProgressEvent::SamplesCompleted {
+                        completed, elapsed, ..
+                    } => {
+                        let completed = previous + completed;
+                        let elapsed = start.elapsed().max(elapsed);
+                        callback(ProgressEvent::SamplesCompleted {
+                            completed,
+                            total,
+                            elapsed,
+                            remaining: elapsed
+                                .mul_f64((total - completed) as f64 / completed.max(1) as f64),
+                        })
+                    }
+                    event => callback(event),
+                },
+                ExtraBuffers {
+                    active: Some(&active),
+                    ..Default::default()
+                },
+                |_, _| {},
+            );
+
+            for (idx, &active) in active.iter().enumerate() {
+                if active {
+                    sum.data[idx] += buffer.data[idx];
+                    counts[idx] += 1;
+                    average.data[idx] = sum.data[idx] / counts[idx] as Float;
+                }
+            }
+
+            on_pass(&average);
+        }
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        Accumulation::new(average, passes * self.sample_count)
+    }
+
+    /// Returns the camera rendering a pass of a render in passes, which continues the samples
+    /// of the sampler after the earlier passes, with independent random numbers.
+    pub fn pass(&self, index: u32) -> Camera {
+        let mut pass = self.clone();
+        pass.first_sample = index * self.sample_count;
+        pass.seed = self
+            .seed
+            .map(|seed| seed ^ (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+
+        pass
+    }
+
+    /// Renders the scene with the light of every light group in its own layer, without
+    /// post processing. Mixing the layers adjusts the brightness of the light groups
+    /// without rendering again. Shadow catchers are rendered like other surfaces.
+    /// A cancelled render returns the layers with all pixels rendered so far.
+    pub fn render_light_groups<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        cancel: &CancelToken,
+        callback: F,
+    ) -> LightGroupLayers {
+        let start = Instant::now();
+        let mut layers = LightGroupLayers::new(
+            scene,
+            self.image_width,
+            self.image_height,
+            self.sample_count,
+        );
+        self.render_samples(
+            scene,
+            resources,
+            cancel,
+            &callback,
+            ExtraBuffers {
+                layers: Some(&mut layers),
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        layers
+    }
+
+    /// Bakes the light arriving at a mesh of the scene into a lightmap of the given dimensions,
+    /// instead of rendering the view of the camera. The texels covered by the texture
+    /// coordinates of the mesh take the sample count of the camera, other texels stay black
+    /// apart from the edges of the islands of texture coordinates, which are dilated.
+    /// The light is gathered at the positions of the mesh as they are, so the mesh must be
+    /// in the scene without a transformation, or the lightmap shows the light elsewhere.
+    pub fn bake<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        mesh: &Mesh,
+        (width, height): (u32, u32),
+        mode: BakeMode,
+        callback: F,
+    ) -> Result<FrameBuffer, &'static str> {
+        let start = Instant::now();
+        let texels = lightmap::texels(mesh, width, height)?;
+
+        let mut fb = FrameBuffer::new(width, height);
+        let mut covered = vec![false; (width * height) as usize];
+
+        if let Some(seed) = self.seed {
+            random::seed(seed);
+        }
+
+        let total = texels.len() as u64 * self.sample_count as u64;
+        callback(ProgressEvent::StageStarted(RenderStage::Rendering));
+
+        // Report the progress after every row of texels.
+        for (chunk_idx, chunk) in texels.chunks(width.max(1) as usize).enumerate() {
+            for texel in chunk {
+                let mut color = Color::ZERO;
+
+                for index in 0..self.sample_count {
+                    if let Some(sampler) = &self.sampler {
+                        random::start_sample(sampler, (texel.x, texel.y), index, self.sample_count);
+                    }
+
+                    // The cosine weighted light is the light a white diffuse surface reflects.
+                    let cosine = CosinePdf::new(texel.normal);
+                    let (direction, pdf_value) = match mode {
+                        BakeMode::Irradiance => {
+                            Self::guided_direction(scene, resources, texel.point, 0.0, &cosine)
+                        }
+                        BakeMode::AmbientOcclusion(_) => {
+                            let direction = cosine.generate();
+                            (direction, cosine.value(direction))
+                        }
+                    };
+                    if pdf_value <= 0.0 || direction.dot(texel.face_normal) <= 0.0 {
+                        continue;
+                    }
+
+                    // The rays leave the mesh like after a diffuse bounce.
+                    let mut ray = Ray::leaving(texel.point, texel.face_normal, direction);
+                    ray.min_roughness = self.roughness_clamp;
+                    ray.roughness_clamp = self.roughness_clamp;
+                    let weight = cosine.value(direction) / pdf_value;
+
+                    // Ambient occlusion only casts shadow rays, which the scene counts.
+                    color += match mode {
+                        BakeMode::Irradiance => {
+                            profiler::count(Counter::PrimaryRays);
+                            let light: Color = Self::ray_color(
+                                scene,
+                                resources,
+                                ray,
+                                intr!(0.0, Float::INFINITY),
+                                self.max_bounces,
+                            );
+                            light * weight
+                        }
+                        BakeMode::AmbientOcclusion(distance) => {
+                            if scene.occluded(&ray, intr!(0.0, distance)) {
+                                Color::ZERO
+                            } else {
+                                vec3!(weight)
+                            }
+                        }
+                    };
+                }
+
+                random::finish_sample();
+
+                fb[(texel.x, texel.y)] = color / self.sample_count.max(1) as Float;
+                covered[(texel.y * width + texel.x) as usize] = true;
+            }
+
+            let completed =
+                ((chunk_idx * width as usize + chunk.len()) * self.sample_count as usize) as u64;
+            let elapsed = start.elapsed();
+            callback(ProgressEvent::SamplesCompleted {
+                completed,
+                total,
+                elapsed,
+                remaining: elapsed.mul_f64((total - completed) as f64 / completed.max(1) as f64),
+            });
+        }
+
+        lightmap::dilate(&mut fb, &mut covered);
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        Ok(fb)
+    }
+
+    /// Renders the motion of the surfaces seen through the pixels until the next frame, as seen
+    /// by the camera of the next frame. The x and y channels hold the movement in pixels to the
+    /// right and down. Objects move as far as their motion over the shutter interval, which
+    /// animations stretch over a frame. Stereo panoramas have no motion vectors.
+    pub fn render_motion_vectors(&self, scene: &Scene, next: &Camera) -> FrameBuffer {
+        self.motion_vectors(scene, next, 1.0)
+    }
+
+    /// Renders where the surfaces seen through the pixels were in the previous frame, as seen by
+    /// the camera of the previous frame, to reproject the previous frame onto this one. Objects
+    /// are assumed to have moved as far over the previous frame as they move over this one.
+    pub fn render_previous_motion_vectors(&self, scene: &Scene, previous: &Camera) -> FrameBuffer {
+        self.motion_vectors(scene, previous, -1.0)
+    }
+
+    /// Renders the motion of the surfaces seen through the pixels, as seen by the other camera,
+    /// with the motion of the objects scaled by the direction.
+    fn motion_vectors(&self, scene: &Scene, other: &Camera, direction: Float) -> FrameBuffer {
+        let mut fb = FrameBuffer::new(self.image_width, self.image_height);
 
         for y in 0..self.image_height {
             for x in 0..self.image_width {
-                let mut color = vec3!(0);
+                let ray = self.ray_at(x as Float + 0.5, y as Float + 0.5);
+
+                // The background is infinitely far away, so only the direction towards it matters.
+                let (point, moved) = match scene.hit_object(&ray, self.primary_interval(&ray)) {
+                    Some((id, hit)) => {
+                        let motion = scene[id].motion(hit.point, ray.time);
+                        (hit.point, hit.point + motion * direction)
+                    }
+                    None => (self.look_from + ray.dir, other.look_from + ray.dir),
+                };
 
-                for _ in 0..self.sample_count {
-                    let ray = self.ray(x, y);
+                if let (Some((x0, y0)), Some((x1, y1))) =
+                    (self.project(point), other.project(moved))
+                {
+                    // Panoramas wrap around, so points crossing the seam move the short way.
+                    let width = self.image_width as Float;
+                    let mut dx = x1 - x0;
+                    if self.projection == Projection::Equirectangular {
+                        dx -= width * (dx / width).round();
+                    }
 
-                    color += Self::ray_color(scene, resources, ray, self.max_bounces);
+                    fb[(x, y)] = vec3!(dx, y1 - y0, 0);
                 }
+            }
+        }
+
+        fb
+    }
+
+    /// Renders a preview of the shapes of the surfaces with one ray through the center of every
+    /// pixel, shading the surfaces with the color of the matcap texture where it shows a sphere
+    /// with the normal as seen from the ray. The background is shown as in renders.
+    pub fn render_matcap(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        matcap: TextureId,
+    ) -> FrameBuffer {
+        let mut fb = FrameBuffer::new(self.image_width, self.image_height);
+        let background = &resources[scene.background()];
 
-                color *= sample_scale;
+        for y in 0..self.image_height {
+            for x in 0..self.image_width {
+                let ray = self.ray_at(x as Float + 0.5, y as Float + 0.5);
+
+                fb[(x, y)] = match scene.hit(&ray, self.primary_interval(&ray)) {
+                    Some(hit) => {
+                        // Look at the normal from the ray, with up towards the top of the image.
+                        let w = -ray.dir.unit();
+                        let side = self.basis.v.cross(w);
+                        let u = if side.near_zero() {
+                            self.basis.u
+                        } else {
+                            side.unit()
+                        };
+                        let v = w.cross(u);
 
-                let pixel = &mut image[(x, y)];
-                pixel[0] = (color.x * 255.0).clamp(0.0, 255.0) as u8;
-                pixel[1] = (color.y * 255.0).clamp(0.0, 255.0) as u8;
-                pixel[2] = (color.z * 255.0).clamp(0.0, 255.0) as u8;
+                        // The matcap shows the half of the sphere facing the viewer.
+                        let (nu, nv) = (hit.normal.dot(u), hit.normal.dot(v));
+                        resources[matcap].color(
+                            resources,
+                            0.5 + nu * 0.5,
+                            0.5 - nv * 0.5,
+                            hit.point,
+                            0.0,
+                        )
+                    }
+                    None => background.color(resources, ray.dir),
+                };
             }
+        }
 
-            callback(y);
+        fb
+    }
+
+    /// Returns the continuous position on the image in pixels at which a point appears,
+    /// ignoring lens distortion. Returns `None` for points behind a perspective camera
+    /// and for stereo panoramas.
+    pub fn project(&self, point: Point3) -> Option<(Float, Float)> {
+        let offset = point - self.look_from;
+
+        match self.projection {
+            Projection::Perspective => {
+                // Intersect the line of sight with the plane of the pixels.
+                let depth = (self.pixel_origin - self.look_from).dot(self.basis.w);
+                let along = offset.dot(self.basis.w);
+                if along * depth <= 0.0 {
+                    return None;
+                }
+
+                let on_plane = self.look_from + offset * (depth / along) - self.pixel_origin;
+                Some((
+                    on_plane.dot(self.pixel_offset_u) / self.pixel_offset_u.len_sq() + 0.5,
+                    on_plane.dot(self.pixel_offset_v) / self.pixel_offset_v.len_sq() + 0.5,
+                ))
+            }
+            Projection::Equirectangular => {
+                let dir = offset.unit();
+                let phi = dir.dot(self.basis.u).atan2(-dir.dot(self.basis.w));
+                let theta = dir.dot(self.basis.v).clamp(-1.0, 1.0).acos();
+
+                Some((
+                    (phi + PI) / (2.0 * PI) * self.image_width as Float,
+                    theta / PI * self.image_height as Float,
+                ))
+            }
+            Projection::OmniDirectionalStereo { .. } => None,
+        }
+    }
+
+    /// Returns about how many pixels of the image the bounding box covers, from the size of
+    /// the sphere around it. A box around the camera covers the whole image.
+    pub fn screen_area(&self, bounding_box: &Aabb) -> Float {
+        let image_area = self.image_width as Float * self.image_height as Float;
+
+        let extent = vec3!(
+            bounding_box.x.size(),
+            bounding_box.y.size(),
+            bounding_box.z.size()
+        );
+        let radius = extent.len() / 2.0;
+        let distance = (bounding_box.center() - self.look_from).len();
+        if distance <= radius {
+            return image_area;
+        }
+
+        let angle = (radius / distance).asin();
+        let pixel_radius = match self.projection {
+            Projection::Perspective => {
+                // Measure the sphere on the plane of the pixels, where pixels have the same size.
+                let depth = (self.look_from - self.pixel_origin).dot(self.basis.w);
+                angle.tan() * depth / self.pixel_offset_u.len()
+            }
+            Projection::Equirectangular | Projection::OmniDirectionalStereo { .. } => {
+                angle * self.image_width as Float / (2.0 * PI)
+            }
+        };
+
+        (PI * pixel_radius * pixel_radius).min(image_area)
+    }
+
+    /// Renders a deterministic low sample image of the scene, to compare against stored references.
+    /// The result only depends on the scene and the camera, not on earlier renders or the sample count.
+    pub fn render_reference(&self, scene: &Scene, resources: &Resources) -> ImageBuffer {
+        let mut camera = self.clone();
+        camera.sample_count = REFERENCE_SAMPLES;
+        camera.seed = Some(REFERENCE_SEED);
+
+        camera.render(scene, resources, |_| {})
+    }
+
+    /// Applies the post processing of the camera to an accumulation and quantizes it into an image.
+    pub fn develop(&self, accumulation: &Accumulation) -> ImageBuffer {
+        let mut fb = accumulation.buffer.clone();
+
+        profiler::time_stage("post process", || self.post_process.apply(&mut fb));
+
+        fb.quantize()
+    }
+
+    /// Renders the scene, calling `on_tile` with the frame buffer after each completed tile.
+    fn render_frame<F: Fn(ProgressEvent), T: FnMut(Tile, &FrameBuffer)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        cancel: &CancelToken,
+        callback: F,
+        on_tile: T,
+    ) -> ImageBuffer {
+        let start = Instant::now();
+        let mut fb = self.render_samples(
+            scene,
+            resources,
+            cancel,
+            &callback,
+            ExtraBuffers::default(),
+            on_tile,
+        );
+
+        callback(ProgressEvent::StageStarted(RenderStage::PostProcessing));
+
+        profiler::time_stage("post process", || self.post_process.apply(&mut fb));
+
+        callback(ProgressEvent::Finished {
+            elapsed: start.elapsed(),
+        });
+
+        fb.quantize()
+    }
+
+    /// Renders all tiles of the image into an HDR frame buffer, and into the extra buffers.
+    fn render_samples<F: Fn(ProgressEvent), T: FnMut(Tile, &FrameBuffer)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        cancel: &CancelToken,
+        callback: &F,
+        mut extra: ExtraBuffers<'_>,
+        mut on_tile: T,
+    ) -> FrameBuffer {
+        let _span = tracing::info_span!(
+            "render",
+            width = self.image_width,
+            height = self.image_height,
+            samples = self.sample_count
+        )
+        .entered();
+
+        let start = Instant::now();
+        let mut fb = FrameBuffer::new(self.image_width, self.image_height);
+
+        if let Some(seed) = self.seed {
+            random::seed(seed);
+        }
+
+        let total = self.image_width as u64 * self.image_height as u64 * self.sample_count as u64;
+        let mut completed = 0;
+
+        callback(ProgressEvent::StageStarted(RenderStage::Rendering));
+
+        for tile in self.tiles() {
+            callback(ProgressEvent::TileStarted(tile));
+
+            let finished = self.render_tile(scene, resources, &mut fb, &mut extra, tile, cancel);
+
+            // The random numbers after the last sample of the tile are independent again.
+            random::finish_sample();
+
+            if !finished {
+                callback(ProgressEvent::Cancelled);
+                break;
+            }
+
+            callback(ProgressEvent::TileFinished(tile));
+            on_tile(tile, &fb);
+
+            // Estimate the remaining time from the average time per sample so far.
+            completed += tile.pixel_count() * self.sample_count as u64;
+            let elapsed = start.elapsed();
+            let remaining = elapsed.mul_f64((total - completed) as f64 / completed.max(1) as f64);
+
+            callback(ProgressEvent::SamplesCompleted {
+                completed,
+                total,
+                elapsed,
+                remaining,
+            });
         }
 
-        image
+        fb
     }
 
-    /// Calculates the color of a ray in the scene.
-    fn ray_color(scene: &Scene, resources: &Resources, ray: Ray, depth: u32) -> Color {
+    /// Renders all samples of the pixels in a tile into the frame buffer, and the extra buffers.
+    /// Returns false if the render was cancelled before the tile was completed.
+    fn render_tile(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        fb: &mut FrameBuffer,
+        extra: &mut ExtraBuffers<'_>,
+        tile: Tile,
+        cancel: &CancelToken,
+    ) -> bool {
+        let _span = tracing::debug_span!("tile", x = tile.x, y = tile.y).entered();
+
+        let sample_scale = 1.0 / self.sample_count as Float;
+        let mut alpha = extra.alpha.as_deref_mut();
+
+        for (x, y) in tile.pixels() {
+            if cancel.is_cancelled() {
+                return false;
+            }
+
+            let idx = (y * self.image_width + x) as usize;
+            if extra.active.is_some_and(|active| !active[idx]) {
+                continue;
+            }
+
+            // The variance of the samples is only tracked for a heatmap.
+            let mut samples = extra.heatmap.is_some().then(SampleVariance::default);
+            let mut invalid = false;
+
+            // Light groups trace the light of every group separately through the same paths.
+            if let Some(layers) = extra.layers.as_deref_mut() {
+                let mut light = LayeredColor::black();
+                for index in 0..self.sample_count {
+                    let ray = self.ray(x, y, self.first_sample + index);
+                    profiler::count(Counter::PrimaryRays);
+                    nan_check::start_path();
+
+                    let interval = self.primary_interval(&ray);
+                    let sample: LayeredColor =
+                        Self::ray_color(scene, resources, ray, interval, self.max_bounces);
+                    invalid |= Self::check_sample(x, y, index, sample.total());
+                    if let Some(samples) = samples.as_mut() {
+                        samples.add(sample.total().luminance());
+                    }
+                    light = light.add(sample);
+                }
+
+                let light = light.scale(vec3!(sample_scale));
+                fb[(x, y)] = light.total();
+                layers.set(x, y, light);
+
+                if invalid && nan_check::highlights() {
+                    fb[(x, y)] = nan_check::HIGHLIGHT_COLOR;
+                }
+
+                if let (Some(heatmap), Some(samples)) = (extra.heatmap.as_deref_mut(), &samples) {
+                    heatmap.set(x, y, samples);
+                }
+                continue;
+            }
+
+            let mut color = vec3!(0);
+            let mut coverage = 0.0;
+
+            // The light shadow catchers receive from the scene and from the background alone,
+            // and the background behind them.
+            let mut received = Color::ZERO;
+            let mut unshadowed = Color::ZERO;
+            let mut behind = Color::ZERO;
+            let mut caught = 0;
+
+            for index in 0..self.sample_count {
+                let ray = self.ray(x, y, self.first_sample + index);
+                profiler::count(Counter::PrimaryRays);
+                nan_check::start_path();
+
+                let sample = self.primary_sample(scene, resources, ray);
+                if nan_check::is_enabled() {
+                    let light = match sample {
+                        PrimarySample::Background(light) | PrimarySample::Surface(light) => light,
+                        PrimarySample::Catcher {
+                            received,
+                            unshadowed,
+                            background,
+                        } => received + unshadowed + background,
+                    };
+                    invalid |= Self::check_sample(x, y, index, light);
+                }
+
+                match sample {
+                    PrimarySample::Background(background) => {
+                        let visible = if alpha.is_none() {
+                            color += background;
+                            background
+                        } else {
+                            Color::ZERO
+                        };
+                        if let Some(samples) = samples.as_mut() {
+                            samples.add(visible.luminance());
+                        }
+                    }
+                    PrimarySample::Surface(surface) => {
+                        color += surface;
+                        coverage += 1.0;
+                        if let Some(samples) = samples.as_mut() {
+                            samples.add(surface.luminance());
+                        }
+                    }
+                    PrimarySample::Catcher {
+                        received: light,
+                        unshadowed: free,
+                        background,
+                    } => {
+                        received += light;
+                        unshadowed += free;
+                        behind += background;
+                        caught += 1;
+
+                        // A single sample scales the background by the light it receives alone.
+                        if let Some(samples) = samples.as_mut() {
+                            let shadow = if free.luminance() > 0.0 {
+                                light.luminance() / free.luminance()
+                            } else {
+                                1.0
+                            };
+                            samples.add(background.luminance() * shadow);
+                        }
+                    }
+                }
+            }
+
+            if let (Some(heatmap), Some(samples)) = (extra.heatmap.as_deref_mut(), &samples) {
+                heatmap.set(x, y, samples);
+            }
+
+            // Shadow catchers scale the background behind them by the fraction of light
+            // they receive, over a transparent background they turn into black shadows.
+            if caught > 0 {
+                let ratio = |received: Float, unshadowed: Float| {
+                    if unshadowed > 0.0 {
+                        received / unshadowed
+                    } else {
+                        1.0
+                    }
+                };
+
+                if alpha.is_none() {
+                    color += vec3!(
+                        behind.x * ratio(received.x, unshadowed.x),
+                        behind.y * ratio(received.y, unshadowed.y),
+                        behind.z * ratio(received.z, unshadowed.z)
+                    );
+                } else {
+                    let shadow =
+                        1.0 - ratio(received.luminance(), unshadowed.luminance()).clamp(0.0, 1.0);
+                    coverage += caught as Float * shadow;
+                }
+            }
+
+            match alpha.as_deref_mut() {
+                None => fb[(x, y)] = color * sample_scale,
+                Some(alpha) => {
+                    // The colors are stored without the alpha multiplied in.
+                    let opacity = coverage * sample_scale;
+                    alpha[(x, y)] = vec3!(opacity);
+                    fb[(x, y)] = if opacity > 0.0 {
+                        color * sample_scale / opacity
+                    } else {
+                        Color::ZERO
+                    };
+                }
+            }
+
+            if invalid && nan_check::highlights() {
+                fb[(x, y)] = nan_check::HIGHLIGHT_COLOR;
+            }
+        }
+
+        true
+    }
+
+    /// Checks the light of a sample of a pixel for NaN and infinite values, if enabled.
+    /// Returns true if it is invalid.
+    fn check_sample(x: u32, y: u32, index: u32, light: Color) -> bool {
+        if !nan_check::is_enabled() || light.luminance().is_finite() {
+            return false;
+        }
+
+        nan_check::record_sample(x, y, index, light);
+        true
+    }
+
+    /// Traces a ray from the camera, telling apart the background, shadow catchers and
+    /// other surfaces.
+    fn primary_sample(&self, scene: &Scene, resources: &Resources, ray: Ray) -> PrimarySample {
+        if self.max_bounces == 0 {
+            return PrimarySample::Surface(Color::ZERO);
+        }
+
+        let background = &resources[scene.background()];
+        let Some(hit) = scene.hit(&ray, self.primary_interval(&ray)) else {
+            return PrimarySample::Background(background.color(resources, ray.dir));
+        };
+
+        let material = &resources[hit.material];
+        if !material.is_shadow_catcher() {
+            return PrimarySample::Surface(Self::shade(
+                scene,
+                resources,
+                &ray,
+                &hit,
+                None,
+                self.max_bounces,
+            ));
+        }
+
+        // Sample the light arriving at the catcher, which is invisible without other objects.
+        let Some(ScatterRecord {
+            attenuation,
+            scatter: Scatter::Pdf(pdf),
+        }) = material.scatter(resources, &ray, &hit)
+        else {
+            return PrimarySample::Background(background.color(resources, ray.dir));
+        };
+
+        let direction = pdf.generate();
+        let pdf_value = pdf.value(direction);
+        let scatter_ray = ray.bounce(&hit, direction);
+        let weight = if pdf_value > 0.0 {
+            attenuation * (material.scattering_pdf(resources, &ray, &hit, &scatter_ray) / pdf_value)
+        } else {
+            Color::ZERO
+        };
+
+        profiler::count(Counter::SecondaryRays);
+        let next = scene.hit(&scatter_ray, intr!(0.0, Float::INFINITY));
+        let scattered_background = background.color(resources, scatter_ray.dir);
+
+        // Without the other objects, the light would come from the background or other catchers.
+        let (received, unshadowed) = match next {
+            None => (scattered_background, scattered_background),
+            Some(next) => {
+                let light = Self::shade(
+                    scene,
+                    resources,
+                    &scatter_ray,
+                    &next,
+                    None,
+                    self.max_bounces - 1,
+                );
+                if resources[next.material].is_shadow_catcher() {
+                    (light, light)
+                } else {
+                    (light, scattered_background)
+                }
+            }
+        };
+
+        PrimarySample::Catcher {
+            received: received * weight,
+            unshadowed: unshadowed * weight,
+            background: background.color(resources, ray.dir),
+        }
+    }
+
+    /// Returns the interval of a primary ray between the clipping planes. Panoramas have
+    /// no view direction, so they clip at the distance from the camera instead.
+    fn primary_interval(&self, ray: &Ray) -> Interval {
+        let (near, far) = self.clip;
+        let depth = match self.projection {
+            Projection::Perspective => -ray.dir.dot(self.basis.w),
+            _ => ray.dir.len(),
+        };
+
+        intr!(near / depth, far / depth)
+    }
+
+    /// Calculates the color of a ray in the scene, which hits the surfaces in the interval.
+    fn ray_color<R: Radiance>(
+        scene: &Scene,
+        resources: &Resources,
+        ray: Ray,
+        interval: Interval,
+        depth: u32,
+    ) -> R {
         if depth == 0 {
-            return Color::ZERO;
+            return R::black();
         }
 
         // calculate intersection if there is no hit return scene background
-        let Some(hit) = scene.hit(&ray, intr!(0.001, f64::INFINITY)) else {
-            return scene.background(ray.dir);
+        let Some((id, hit)) = scene.hit_object(&ray, interval) else {
+            let background = resources[scene.background()].color(resources, ray.dir);
+            return R::emitted(background, scene.background_light_group());
         };
 
+        Self::shade(scene, resources, &ray, &hit, scene.light_group(id), depth)
+    }
+
+    /// Calculates the color of the light leaving a hit towards the origin of the ray,
+    /// the light emitted at the hit belongs to the given light group.
+    fn shade<R: Radiance>(
+        scene: &Scene,
+        resources: &Resources,
+        ray: &Ray,
+        hit: &Intersection,
+        group: Option<LightGroupId>,
+        depth: u32,
+    ) -> R {
+        if depth == 0 {
+            return R::black();
+        }
+
         // calculate the color of the hit object
         let material = &resources[hit.material];
 
-        let emitted = material.emit(resources, &hit);
+        let emitted = R::emitted(material.emit(resources, hit), group);
 
         // check if the material scatters the ray if not return the emitted color
-        let Some((scatter_ray, scattered)) = material.scatter(resources, &ray, &hit) else {
+        let Some(record) = material.scatter(resources, ray, hit) else {
             return emitted;
         };
 
         // calculate the color of the scattered ray
-        let scattered = Self::ray_color(scene, resources, scatter_ray, depth - 1) * scattered;
+        let scattered = match record.scatter {
+            Scatter::Specular(scatter_ray) if ray.min_roughness > 0.0 => {
+                // Blur the scattered ray into a cone like a rough surface, which is sampled
+                // together with the lights, so paths over the surface find small lights.
+                let lobe = ConePdf::new(scatter_ray.dir, ray.min_roughness.min(1.0).asin());
+                let (direction, pdf_value) =
+                    Self::guided_direction(scene, resources, hit.point, ray.time, &lobe);
+
+                if pdf_value <= 0.0 {
+                    return emitted;
+                }
+
+                profiler::count(Counter::SecondaryRays);
+
+                Self::ray_color::<R>(
+                    scene,
+                    resources,
+                    ray.bounce(hit, direction),
+                    intr!(0.0, Float::INFINITY),
+                    depth - 1,
+                )
+                .scale(record.attenuation * (lobe.value(direction) / pdf_value))
+            }
+            Scatter::Specular(scatter_ray) => {
+                profiler::count(Counter::SecondaryRays);
+                Self::ray_color::<R>(
+                    scene,
+                    resources,
+                    scatter_ray,
+                    intr!(0.0, Float::INFINITY),
+                    depth - 1,
+                )
+                .scale(record.attenuation)
+            }
+            Scatter::Pdf(pdf) => {
+                // Diffuse bounces also reflect the ambient light arriving from every direction.
+                let ambient = R::emitted(scene.ambient(), None).scale(record.attenuation);
+
+                // Follow the light learned by the path guide of the render, if there is one.
+                let learned = guiding::pdf(hit.point);
+                let mixture;
+                let pdf = match &learned {
+                    Some(learned) => {
+                        mixture = MixturePdf::new(learned, pdf.as_ref());
+                        &mixture as &dyn Pdf
+                    }
+                    None => pdf.as_ref(),
+                };
+
+                let (direction, pdf_value) =
+                    Self::guided_direction(scene, resources, hit.point, ray.time, pdf);
+
+                // The paths continuing from diffuse surfaces see the specular ones as rough.
+                let mut scatter_ray = ray.bounce(hit, direction);
+                scatter_ray.min_roughness = scatter_ray.min_roughness.max(ray.roughness_clamp);
+
+                if pdf_value <= 0.0 {
+                    return emitted.add(ambient);
+                }
+
+                profiler::count(Counter::SecondaryRays);
+
+                let scattering_pdf = material.scattering_pdf(resources, ray, hit, &scatter_ray);
+
+                let incoming = Self::ray_color::<R>(
+                    scene,
+                    resources,
+                    scatter_ray,
+                    intr!(0.0, Float::INFINITY),
+                    depth - 1,
+                );
+                guiding::record(hit.point, direction, incoming.luminance() / pdf_value);
+
+                incoming
+                    .scale(record.attenuation * (scattering_pdf / pdf_value))
+                    .add(ambient)
+            }
+        };
+
+        if nan_check::is_enabled() {
+            let (emitted_luminance, scattered_luminance) =
+                (emitted.luminance(), scattered.luminance());
+            if !(emitted_luminance + scattered_luminance).is_finite() {
+                nan_check::record_bounce(InvalidBounce {
+                    depth,
+                    point: hit.point,
+                    normal: hit.normal,
+                    direction: ray.dir,
+                    material: hit.material,
+                    emitted: emitted_luminance,
+                    scattered: scattered_luminance,
+                });
+            }
+        }
+
+        emitted.add(scattered)
+    }
+
+    /// Samples a direction for the light arriving at a point from the density of the material,
+    /// sending half of the rays towards the lights and through the light portals instead.
+    /// Returns the direction and its density. Moving lights are sampled at the given time.
+    fn guided_direction(
+        scene: &Scene,
+        resources: &Resources,
+        point: Point3,
+        time: Float,
+        pdf: &dyn Pdf,
+    ) -> (Vec3, Float) {
+        let lights = scene.lights(resources);
+        let portal_pdf = PortalPdf::new(scene.portals(), point);
+        let light_pdf = LightPdf::new(lights, point, time);
+        let both = MixturePdf::new(&portal_pdf, &light_pdf);
+
+        let guide: Option<&dyn Pdf> = match (scene.portals().is_empty(), lights.is_empty()) {
+            (true, true) => None,
+            (false, true) => Some(&portal_pdf),
+            (true, false) => Some(&light_pdf),
+            (false, false) => Some(&both),
+        };
+
+        match guide {
+            None => {
+                let direction = pdf.generate();
+                (direction, pdf.value(direction))
+            }
+            Some(guide) => {
+                let mixture = MixturePdf::new(guide, pdf);
+                let direction = mixture.generate();
+                (direction, mixture.value(direction))
+            }
+        }
+    }
+
+    /// Returns the point on the plane in focus seen through a point on the plane of the pixels,
+    /// from which rays from the whole lens converge. Where the line of sight doesn't reach the
+    /// tilted plane, the point is so far away that rays leave the lens in parallel.
+    fn focus(&self, pixel_sample: Point3) -> Point3 {
+        let sight = pixel_sample - self.look_from;
+        let towards_plane = sight.dot(self.focal_plane);
+        if towards_plane < 0.0 {
+            self.look_from + sight * (sight.dot(self.basis.w) / towards_plane)
+        } else {
+            self.look_from + sight * 1e6
+        }
+    }
+
+    /// Applies the radial lens distortion to a position on the image in pixels.
+    fn distort(&self, x: Float, y: Float) -> (Float, Float) {
+        let (k1, k2) = self.distortion;
+        if k1 == 0.0 && k2 == 0.0 {
+            return (x, y);
+        }
+
+        // Normalize the position so that the top and bottom edges are at a radius of one.
+        let half_width = self.image_width as Float / 2.0;
+        let half_height = self.image_height as Float / 2.0;
+        let nx = (x - half_width) / half_height;
+        let ny = (y - half_height) / half_height;
 
-        emitted + scattered
+        let r2 = nx * nx + ny * ny;
+        let scale = 1.0 + k1 * r2 + k2 * r2 * r2;
+
+        (
+            half_width + nx * scale * half_height,
+            half_height + ny * scale * half_height,
+        )
     }
 
-    /// Creates a ray from the camera through a pixel.
-    fn ray(&self, x: u32, y: u32) -> Ray {
-        let (offset_x, offset_y) = THREAD_RNG.with(|rng| {
+    /// Creates a ray from the camera through a pixel for the sample with the given index.
+    /// With a sampler, the random numbers of the sample come from it from here on.
+    fn ray(&self, x: u32, y: u32, index: u32) -> Ray {
+        if let Some(sampler) = &self.sampler {
+            random::start_sample(sampler, (x, y), index, self.sample_count);
+        }
+
+        let (u, v) = THREAD_RNG.with(|rng| {
             let mut rng = rng.borrow_mut();
-            (rng.random_f64() - 0.5, rng.random_f64() - 0.5)
+            (rng.random_float(), rng.random_float())
         });
 
-        let pixel_sample = self.pixel_origin
-            + self.pixel_offset_u * (x as f64 + offset_x)
-            + self.pixel_offset_v * (y as f64 + offset_y);
+        // Distribute the rays around the pixel center like the reconstruction filter.
+        let (offset_x, offset_y) = (self.filter.sample(u), self.filter.sample(v));
+        let mut ray = self.ray_at(x as Float + offset_x + 0.5, y as Float + offset_y + 0.5);
 
-        let direction = pixel_sample - self.look_from;
+        // Cast the ray at a random time while the shutter is open.
+        let (open, close) = self.shutter;
+        ray.time = open + random_float() * (close - open);
+        ray.roughness_clamp = self.roughness_clamp;
 
-        Ray::new(self.look_from, direction)
+        ray
+    }
+
+    /// Creates a ray from the camera through a continuous position on the image in pixels,
+    /// at the time the shutter opens.
+    fn ray_at(&self, x: Float, y: Float) -> Ray {
+        let mut ray = match self.projection {
+            Projection::Perspective => {
+                let (px, py) = self.distort(x, y);
+
+                let pixel_sample = self.pixel_origin
+                    + self.pixel_offset_u * (px - 0.5)
+                    + self.pixel_offset_v * (py - 0.5);
+
+                // Start the ray from a random point on the lens aperture.
+                let origin = if self.defocus_u.near_zero() && self.defocus_v.near_zero() {
+                    self.look_from
+                } else {
+                    let (lens_x, lens_y) = self.aperture.sample();
+                    self.look_from + self.defocus_u * lens_x + self.defocus_v * lens_y
+                };
+
+                let direction = self.focus(pixel_sample) - origin;
+
+                Ray::new(origin, direction)
+            }
+            Projection::Equirectangular => {
+                let (_, direction) = self.panorama_direction(x, y, self.image_height);
+
+                Ray::new(self.look_from, direction)
+            }
+            Projection::OmniDirectionalStereo { ipd } => {
+                // The top half of the image holds the left eye, the bottom half the right eye.
+                let eye_height = self.image_height / 2;
+                let (y, side) = if y < eye_height as Float {
+                    (y, -1.0)
+                } else {
+                    (y - eye_height as Float, 1.0)
+                };
+
+                let (phi, direction) = self.panorama_direction(x, y, eye_height);
+
+                // Offset the eye perpendicular to the horizontal view direction.
+                let right = self.basis.u * phi.cos() + self.basis.w * phi.sin();
+                let origin = self.look_from + right * (side * ipd / 2.0);
+
+                Ray::new(origin, direction)
+            }
+        };
+
+        // Widen the cone around the ray by the angle a pixel covers, to filter textures.
+        ray.cone_spread = match self.projection {
+            Projection::Perspective => self.pixel_offset_u.len() / ray.dir.len(),
+            _ => 2.0 * PI / self.image_width as Float,
+        };
+        ray.time = self.shutter.0;
+
+        ray
+    }
+
+    /// Maps a continuous pixel position of an equirectangular panorama to its
+    /// longitude and view direction, the center of the image looks forward.
+    fn panorama_direction(&self, x: Float, y: Float, height: u32) -> (Float, Vec3) {
+        let phi = 2.0 * PI * (x / self.image_width as Float) - PI;
+        let theta = PI * (y / height as Float);
+
+        let horizontal = self.basis.u * phi.sin() - self.basis.w * phi.cos();
+        let direction = horizontal * theta.sin() + self.basis.v * theta.cos();
+
+        (phi, direction)
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 /// A builder for a camera, to allow for easy construction.
 pub struct CameraBuilder {
-    vfov: Option<f64>,
-    aspect_ratio: Option<f64>,
+    vfov: Option<Float>,
+    aspect_ratio: Option<Float>,
     sample_count: Option<u32>,
     max_bounces: Option<u32>,
+    roughness_clamp: Option<Float>,
     image_width: Option<u32>,
-    look_from: Option<Point3>,
-    look_at: Option<Point3>,
+    resolution_scale: Option<Float>,
+    tile_size: Option<u32>,
+    pub(crate) look_from: Option<Point3>,
+    pub(crate) look_at: Option<Point3>,
+    post_process: Option<PostProcessPipeline>,
+    projection: Option<Projection>,
+    defocus_angle: Option<Float>,
+    focus_dist: Option<Float>,
+    aperture: Option<Aperture>,
+    focal_length: Option<Float>,
+    sensor_size: Option<(Float, Float)>,
+    f_stop: Option<Float>,
+    distortion: Option<(Float, Float)>,
+    tilt: Option<(Float, Float)>,
+    shift: Option<(Float, Float)>,
+    shutter: Option<(Float, Float)>,
+    near: Option<Float>,
+    far: Option<Float>,
+    filter: Option<PixelFilter>,
+    sampler: Option<Arc<dyn Sampler>>,
+    seed: Option<u64>,
 }
 
 impl CameraBuilder {
     /// Sets the vertical fov of the camera.
-    pub fn with_vfov(&mut self, fov: f64) -> &mut Self {
+    pub fn with_vfov(&mut self, fov: Float) -> &mut Self {
         self.vfov = Some(fov);
         self
     }
 
     /// Sets the aspect ratio of the camera.
-    pub fn with_aspect_ratio(&mut self, aspect_ratio: f64) -> &mut Self {
+    pub fn with_aspect_ratio(&mut self, aspect_ratio: Float) -> &mut Self {
         self.aspect_ratio = Some(aspect_ratio);
         self
     }
 
-    /// Sets the sample count of the camera.
+    /// Sets the sample count of the camera, at least one sample is taken per pixel.
     pub fn with_sample_count(&mut self, sample_count: u32) -> &mut Self {
         self.sample_count = Some(sample_count);
         self
@@ -169,6 +1653,34 @@ impl CameraBuilder {
         self
     }
 
+    /// Scales the width and height of the image, like for quick renders at a lower resolution
+    /// with the same framing. Defaults to one.
+    pub fn with_resolution_scale(&mut self, scale: Float) -> &mut Self {
+        self.resolution_scale = Some(scale);
+        self
+    }
+
+    /// Sets the sample count, the max bounces, the resolution scale and the roughness clamp
+    /// of the preset. Settings made afterwards override the ones of the preset.
+    pub fn with_preset(&mut self, preset: Preset) -> &mut Self {
+        let (samples, bounces, scale, roughness) = match preset {
+            Preset::Draft => (4, 3, 0.25, 0.5),
+            Preset::Preview => (32, 8, 0.5, 0.2),
+            Preset::Final => (512, 50, 1.0, 0.0),
+        };
+
+        self.with_sample_count(samples)
+            .with_max_bounces(bounces)
+            .with_resolution_scale(scale)
+            .with_roughness_clamp(roughness)
+    }
+
+    /// Sets the edge length of the tiles the image is rendered in.
+    pub fn with_tile_size(&mut self, tile_size: u32) -> &mut Self {
+        self.tile_size = Some(tile_size);
+        self
+    }
+
     /// Sets the look from position of the camera.
     pub fn with_look_from(&mut self, position: Vec3) -> &mut Self {
         self.look_from = Some(position);
@@ -181,19 +1693,204 @@ impl CameraBuilder {
         self
     }
 
+    /// Sets the post processing pipeline applied to the rendered image.
+    pub fn with_post_process(&mut self, pipeline: PostProcessPipeline) -> &mut Self {
+        self.post_process = Some(pipeline);
+        self
+    }
+
+    /// Sets the projection of the camera.
+    pub fn with_projection(&mut self, projection: Projection) -> &mut Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Sets the defocus angle of the camera in degrees, which is the angle of the
+    /// cone from each pixel to the lens aperture. Zero disables depth of field.
+    pub fn with_defocus_angle(&mut self, defocus_angle: Float) -> &mut Self {
+        self.defocus_angle = Some(defocus_angle);
+        self
+    }
+
+    /// Sets the distance from the camera to the plane that is in perfect focus.
+    pub fn with_focus_dist(&mut self, focus_dist: Float) -> &mut Self {
+        self.focus_dist = Some(focus_dist);
+        self
+    }
+
+    /// Sets the focus distance by measuring the distance to the target in the scene.
+    /// The look from and look at points must be set before calling this.
+    /// Leaves the focus distance unchanged if the target can not be found.
+    pub fn with_autofocus(&mut self, scene: &Scene, target: FocusTarget) -> &mut Self {
+        let look_from = self.look_from.unwrap();
+        let look_at = self.look_at.unwrap();
+        let forward = (look_at - look_from).unit();
+
+        let point = match target {
+            FocusTarget::Center => scene
+                .hit(&Ray::new(look_from, forward), intr!(0.001, Float::INFINITY))
+                .map(|hit| hit.point),
+            FocusTarget::LookAt => Some(look_at),
+            FocusTarget::Named(name) => scene.get_named(&name).map(|object| {
+                // Focus on the surface facing the camera, or the center if it is not visible.
+                let center = object.bounding_box().center();
+                let ray = Ray::new(look_from, center - look_from);
+
+                object
+                    .hit(&ray, intr!(0.001, Float::INFINITY))
+                    .map_or(center, |hit| hit.point)
+            }),
+        };
+
+        // The focus plane is perpendicular to the view direction.
+        if let Some(point) = point {
+            self.focus_dist = Some((point - look_from).dot(forward).max(0.001));
+        }
+
+        self
+    }
+
+    /// Sets the shape of the lens aperture.
+    pub fn with_aperture(&mut self, aperture: Aperture) -> &mut Self {
+        self.aperture = Some(aperture);
+        self
+    }
+
+    /// Sets the focal length of the lens in millimeters, which overrides the vertical fov.
+    pub fn with_focal_length(&mut self, focal_length: Float) -> &mut Self {
+        self.focal_length = Some(focal_length);
+        self
+    }
+
+    /// Sets the width and height of the sensor in millimeters, defaults to a 36x24 full frame sensor.
+    pub fn with_sensor_size(&mut self, width: Float, height: Float) -> &mut Self {
+        self.sensor_size = Some((width, height));
+        self
+    }
+
+    /// Sets the f-number of the lens, which overrides the defocus angle.
+    /// Requires a focal length and assumes that the scene is modelled in meters.
+    pub fn with_f_stop(&mut self, f_stop: Float) -> &mut Self {
+        self.f_stop = Some(f_stop);
+        self
+    }
+
+    /// Sets the radial distortion coefficients of the lens. Positive values produce
+    /// barrel distortion and negative values produce pincushion distortion.
+    pub fn with_distortion(&mut self, k1: Float, k2: Float) -> &mut Self {
+        self.distortion = Some((k1, k2));
+        self
+    }
+
+    /// Sets the tilt and swing of the lens in degrees, which rotate the plane in focus around
+    /// the horizontal and vertical axis of the image instead of facing the camera. A positive
+    /// tilt moves the top of the plane away from the camera, a positive swing its right side.
+    /// Tilting against the ground creates the shallow focus of the miniature effect.
+    pub fn with_tilt(&mut self, tilt: Float, swing: Float) -> &mut Self {
+        self.tilt = Some((tilt, swing));
+        self
+    }
+
+    /// Sets the shift of the lens as fractions of the image width and height, which moves
+    /// the image right and up without turning the camera. Shifting instead of pointing the
+    /// camera upwards keeps vertical lines parallel.
+    pub fn with_shift(&mut self, x: Float, y: Float) -> &mut Self {
+        self.shift = Some((x, y));
+        self
+    }
+
+    /// Sets the times in the range [0, 1] at which the shutter opens and closes,
+    /// moving objects are blurred over this interval. Defaults to the whole range.
+    pub fn with_shutter(&mut self, open: Float, close: Float) -> &mut Self {
+        self.shutter = Some((open, close));
+        self
+    }
+
+    /// Sets the distance of the near clipping plane, surfaces closer to the camera are
+    /// cut away from the primary rays, revealing what is behind them. Defaults to zero.
+    pub fn with_near(&mut self, near: Float) -> &mut Self {
+        self.near = Some(near);
+        self
+    }
+
+    /// Sets the distance of the far clipping plane, surfaces beyond it are not seen by the
+    /// primary rays, which see the background instead. Defaults to infinity.
+    pub fn with_far(&mut self, far: Float) -> &mut Self {
+        self.far = Some(far);
+        self
+    }
+
+    /// Sets the reconstruction filter of the pixels. Defaults to a box filter covering
+    /// only the pixel itself.
+    pub fn with_pixel_filter(&mut self, filter: PixelFilter) -> &mut Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Sets the sampler generating the random numbers of the samples, which can spread them
+    /// more evenly than independent random numbers. Defaults to independent random numbers.
+    pub fn with_sampler<S: Sampler + 'static>(&mut self, sampler: S) -> &mut Self {
+        self.sampler = Some(Arc::new(sampler));
+        self
+    }
+
+    /// Sets the smallest roughness of glossy and specular surfaces after a diffuse bounce,
+    /// which trades a little bias for removing the noise of caustics and other paths from
+    /// diffuse surfaces over specular ones to lights, which sampling rarely finds.
+    /// Defaults to zero, which keeps the materials as they are.
+    pub fn with_roughness_clamp(&mut self, roughness: Float) -> &mut Self {
+        self.roughness_clamp = Some(roughness.max(0.0));
+        self
+    }
+
+    /// Sets the seed of the random number generator at the start of each render,
+    /// renders with different seeds produce independent noise that can be merged.
+    pub fn with_seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Builds a stereo camera with the eyes the interpupillary distance apart.
+    pub fn build_stereo(&self, ipd: Float, layout: StereoLayout) -> StereoCamera {
+        StereoCamera::new(self, ipd, layout)
+    }
+
+    /// Returns the aspect ratio set so far, which is the one of the sensor if only that is set.
+    pub(crate) fn aspect_ratio(&self) -> Option<Float> {
+        self.aspect_ratio
+            .or(self.sensor_size.map(|(width, height)| width / height))
+    }
+
     /// Builds the camera.
     pub fn build(&self) -> Camera {
         // Determine viewport size based on aspect ratio and image width.
-        let aspect_ratio = self.aspect_ratio.unwrap();
-        let image_width = self.image_width.unwrap();
-        let image_height = (image_width as f64 / aspect_ratio) as u32;
+        let sensor_size = self.sensor_size.unwrap_or((36.0, 24.0));
+        let aspect_ratio = self.aspect_ratio().unwrap();
+        let scale = self.resolution_scale.unwrap_or(1.0);
+        let image_width = ((self.image_width.unwrap() as Float * scale).round() as u32).max(1);
+        let mut image_height = (image_width as Float / aspect_ratio) as u32;
+
+        // Stereo panoramas split the rows evenly between the eyes.
+        let projection = self.projection.unwrap_or_default();
+        if let Projection::OmniDirectionalStereo { .. } = projection {
+            image_height += image_height % 2;
+        }
 
         let look_from = self.look_from.unwrap();
         let look_at = self.look_at.unwrap();
 
-        let theta = self.vfov.unwrap().to_radians();
+        let focus_dist = self.focus_dist.unwrap_or(1.0);
+        let defocus_angle = self.defocus_angle.unwrap_or(0.0);
+
+        // Derive the fov from the focal length by fitting the sensor width to the image.
+        let theta = match self.focal_length {
+            Some(focal_length) => {
+                2.0 * (sensor_size.0 / aspect_ratio / (2.0 * focal_length)).atan()
+            }
+            None => self.vfov.unwrap().to_radians(),
+        };
         let h = (theta / 2.0).tan();
-        let viewport_height = 2.0 * h; // * focus_dist;
+        let viewport_height = 2.0 * h * focus_dist;
         let viewport_width = viewport_height * aspect_ratio;
 
         // Calculate the camera's u, v, w basis vectors.
@@ -206,27 +1903,65 @@ impl CameraBuilder {
         let viewport_v = -v * viewport_height;
 
         // Calculate the horizontal and vertical pixel offsets.
-        let pixel_offset_u = viewport_u / image_width as f64;
-        let pixel_offset_v = viewport_v / image_height as f64;
+        let pixel_offset_u = viewport_u / image_width as Float;
+        let pixel_offset_v = viewport_v / image_height as Float;
 
-        // Calculate the pixel origin.
-        let viewport_upper_left = look_from - viewport_u / 2.0 - viewport_v / 2.0 - w; // * focus_dist;
+        // Calculate the pixel origin, the shift moves the viewport within the plane.
+        let (shift_x, shift_y) = self.shift.unwrap_or((0.0, 0.0));
+        let viewport_upper_left = look_from
+            - viewport_u * (0.5 - shift_x)
+            - viewport_v * (0.5 + shift_y)
+            - w * focus_dist;
 
         let pixel_origin = viewport_upper_left + (pixel_offset_u + pixel_offset_v) * 0.5;
 
-        let sample_count = self.sample_count.unwrap_or(10);
+        // Calculate the radius vectors of the lens aperture.
+        let defocus_radius = match (self.f_stop, self.focal_length) {
+            // The aperture diameter is the focal length divided by the f-number.
+            (Some(f_stop), Some(focal_length)) => focal_length / f_stop / 2.0 / 1000.0,
+            _ => focus_dist * (defocus_angle / 2.0).to_radians().tan(),
+        };
+        let defocus_u = u * defocus_radius;
+        let defocus_v = v * defocus_radius;
+
+        // Tilt the plane in focus around the point in focus straight ahead of the camera.
+        let (tilt, swing) = self.tilt.unwrap_or((0.0, 0.0));
+        let focal_plane = w + v * tilt.to_radians().tan() + u * swing.to_radians().tan();
+
+        let sample_count = self.sample_count.unwrap_or(10).max(1);
         let max_bounces = self.max_bounces.unwrap_or(50);
+        let tile_size = self.tile_size.unwrap_or(32).max(1);
+        let post_process = self.post_process.clone().unwrap_or_default();
 
         // Create the camera.
         Camera {
             sample_count,
+            first_sample: 0,
             max_bounces,
+            roughness_clamp: self.roughness_clamp.unwrap_or(0.0),
             image_width,
             image_height,
+            tile_size,
             look_from,
+            basis: Onb { u, v, w },
+            projection,
+            aperture: self.aperture.clone().unwrap_or_default(),
+            defocus_u,
+            defocus_v,
+            focal_plane,
+            distortion: self.distortion.unwrap_or((0.0, 0.0)),
+            shutter: self.shutter.unwrap_or((0.0, 1.0)),
+            clip: (
+                self.near.unwrap_or(0.0),
+                self.far.unwrap_or(Float::INFINITY),
+            ),
+            filter: FilterSampler::new(self.filter.unwrap_or_default()),
+            sampler: self.sampler.clone(),
+            seed: self.seed,
             pixel_origin,
             pixel_offset_u,
             pixel_offset_v,
+            post_process,
         }
     }
 }
@@ -0,0 +1,565 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_void, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::{
+    camera::Camera,
+    cancel::CancelToken,
+    environments::{gradient::GradientEnvironment, solid::SolidEnvironment},
+    float::Float,
+    materials::{
+        dielectric::DielectricMaterial, diffuse_light::DiffuseLightMaterial,
+        lambertian::LambertianMaterial, metal::MetalMaterial,
+    },
+    mesh::Mesh,
+    objects::{mesh::MeshObject, sphere::SphereObject},
+    progress::ProgressEvent,
+    resources::{EnvironmentId, MaterialId, Resources, TextureId},
+    scene::Scene,
+    textures::{checker::CheckerTexture, solid::SolidTexture},
+    vector::Vec3,
+};
+
+thread_local! {
+    /// The message of the last error on this thread.
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The result of a call into the C API.
+pub enum RtStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// An argument was invalid, for example an unknown identifier.
+    InvalidArgument = 2,
+    /// A file couldn't be loaded.
+    LoadFailed = 3,
+    /// The output buffer is smaller than the rendered image.
+    BufferTooSmall = 4,
+    /// The render was cancelled by the progress callback.
+    Cancelled = 5,
+    /// The renderer panicked.
+    Panicked = 6,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+/// A vector or color.
+pub struct RtVec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl From<RtVec3> for Vec3 {
+    fn from(v: RtVec3) -> Self {
+        Vec3 {
+            x: v.x as Float,
+            y: v.y as Float,
+            z: v.z as Float,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+/// The settings of the camera a scene is rendered with.
+pub struct RtCameraSettings {
+    /// The position of the camera.
+    pub look_from: RtVec3,
+    /// The point the camera looks at.
+    pub look_at: RtVec3,
+    /// The vertical field of view in degrees.
+    pub vfov: f64,
+    /// The width of the image in pixels.
+    pub image_width: u32,
+    /// The ratio of the image width to its height.
+    pub aspect_ratio: f64,
+    /// The number of samples per pixel.
+    pub sample_count: u32,
+    /// The maximum number of bounces of a ray.
+    pub max_bounces: u32,
+    /// The seed of the random number generator, 0 continues the current sequence.
+    pub seed: u64,
+}
+
+impl RtCameraSettings {
+    /// Converts the settings into a camera.
+    fn build(&self) -> Camera {
+        let mut builder = Camera::builder();
+        builder
+            .with_look_from(self.look_from.into())
+            .with_look_at(self.look_at.into())
+            .with_vfov(self.vfov as Float)
+            .with_image_width(self.image_width)
+            .with_aspect_ratio(self.aspect_ratio as Float)
+            .with_sample_count(self.sample_count)
+            .with_max_bounces(self.max_bounces);
+
+        if self.seed != 0 {
+            builder.with_seed(self.seed);
+        }
+
+        builder.build()
+    }
+}
+
+/// Receives the progress of a render, the render is cancelled if it returns false.
+pub type RtProgressCallback =
+    Option<extern "C" fn(completed: u64, total: u64, user_data: *mut c_void) -> bool>;
+
+/// The textures, materials and environments of a scene.
+/// Identifiers handed out to C are indices into the lists of this wrapper.
+pub struct RtResources {
+    resources: Resources,
+    textures: Vec<TextureId>,
+    materials: Vec<MaterialId>,
+    environments: Vec<EnvironmentId>,
+}
+
+/// The objects to render, surrounded by an environment.
+pub struct RtScene {
+    scene: Scene,
+}
+
+/// Remembers the message of an error and returns its status.
+fn fail(status: RtStatus, message: &'static str) -> RtStatus {
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = CString::new(message).unwrap_or_default();
+    });
+    status
+}
+
+/// Looks up the identifier with the given index.
+fn lookup<T: Copy>(ids: &[T], index: u32) -> Result<T, RtStatus> {
+    ids.get(index as usize)
+        .copied()
+        .ok_or_else(|| fail(RtStatus::InvalidArgument, "unknown identifier"))
+}
+
+/// Converts a C string into a string.
+unsafe fn string<'a>(ptr: *const c_char) -> Result<&'a str, RtStatus> {
+    if ptr.is_null() {
+        return Err(fail(RtStatus::NullPointer, "string is null"));
+    }
+
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| fail(RtStatus::InvalidArgument, "string is not valid UTF-8"))
+}
+
+/// Pushes an identifier and writes its index to the output, unless the output is null.
+unsafe fn push<T>(ids: &mut Vec<T>, id: T, out: *mut u32) -> RtStatus {
+    if let Some(out) = out.as_mut() {
+        *out = ids.len() as u32;
+    }
+
+    ids.push(id);
+    RtStatus::Ok
+}
+
+/// Runs a call, turning errors and panics into a status.
+fn guard<F: FnOnce() -> Result<RtStatus, RtStatus>>(call: F) -> RtStatus {
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(status) | Err(status)) => status,
+        Err(_) => fail(RtStatus::Panicked, "the renderer panicked"),
+    }
+}
+
+/// Returns the message of the last error on this thread. The string is valid until the next
+/// call on this thread.
+#[no_mangle]
+pub extern "C" fn rt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Creates empty resources, which must be freed with `rt_resources_free`.
+#[no_mangle]
+pub extern "C" fn rt_resources_new() -> *mut RtResources {
+    Box::into_raw(Box::new(RtResources {
+        resources: Resources::default(),
+        textures: Vec::new(),
+        materials: Vec::new(),
+        environments: Vec::new(),
+    }))
+}
+
+/// Frees resources created with `rt_resources_new`.
+///
+/// # Safety
+/// The resources must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rt_resources_free(resources: *mut RtResources) {
+    if !resources.is_null() {
+        drop(Box::from_raw(resources));
+    }
+}
+
+/// Adds a texture of a single color.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_solid_texture(
+    resources: *mut RtResources,
+    color: RtVec3,
+    out_texture: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    let id = res.resources.add_texture(SolidTexture::new(color.into()));
+    push(&mut res.textures, id, out_texture)
+}
+
+/// Loads an image texture from the file at the given path.
+///
+/// # Safety
+/// The pointers must be valid or null, the path must be nul-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_image_texture(
+    resources: *mut RtResources,
+    path: *const c_char,
+    out_texture: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    guard(|| {
+        let path = string(path)?;
+        let id = res
+            .resources
+            .add_image_texture(path)
+            .map_err(|err| fail(RtStatus::LoadFailed, err))?;
+        Ok(push(&mut res.textures, id, out_texture))
+    })
+}
+
+/// Adds a 3D checker pattern alternating between two textures.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_checker_texture(
+    resources: *mut RtResources,
+    scale: f64,
+    even: u32,
+    odd: u32,
+    out_texture: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    guard(|| {
+        let even = lookup(&res.textures, even)?;
+        let odd = lookup(&res.textures, odd)?;
+        let id = res
+            .resources
+            .add_texture(CheckerTexture::new(scale as Float, even, odd));
+        Ok(push(&mut res.textures, id, out_texture))
+    })
+}
+
+/// Adds a diffuse material.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_lambertian_material(
+    resources: *mut RtResources,
+    albedo: u32,
+    out_material: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    guard(|| {
+        let albedo = lookup(&res.textures, albedo)?;
+        let id = res.resources.add_material(LambertianMaterial::new(albedo));
+        Ok(push(&mut res.materials, id, out_material))
+    })
+}
+
+/// Adds a reflective material, blurred by the fuzz.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_metal_material(
+    resources: *mut RtResources,
+    albedo: RtVec3,
+    fuzz: f64,
+    out_material: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    let id = res
+        .resources
+        .add_material(MetalMaterial::new(albedo.into(), fuzz as Float));
+    push(&mut res.materials, id, out_material)
+}
+
+/// Adds a transparent material with the given refraction index.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_dielectric_material(
+    resources: *mut RtResources,
+    refraction_index: f64,
+    out_material: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    let id = res
+        .resources
+        .add_material(DielectricMaterial::new(refraction_index as Float));
+    push(&mut res.materials, id, out_material)
+}
+
+/// Adds a material that emits light.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_light_material(
+    resources: *mut RtResources,
+    emission: u32,
+    out_material: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    guard(|| {
+        let emission = lookup(&res.textures, emission)?;
+        let id = res
+            .resources
+            .add_material(DiffuseLightMaterial::new(emission));
+        Ok(push(&mut res.materials, id, out_material))
+    })
+}
+
+/// Adds an environment of a single color.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_solid_environment(
+    resources: *mut RtResources,
+    color: RtVec3,
+    out_environment: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    let id = res
+        .resources
+        .add_environment(SolidEnvironment::new(color.into()));
+    push(&mut res.environments, id, out_environment)
+}
+
+/// Adds an environment blending from the bottom to the top color.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_add_gradient_environment(
+    resources: *mut RtResources,
+    bottom: RtVec3,
+    top: RtVec3,
+    out_environment: *mut u32,
+) -> RtStatus {
+    let Some(res) = resources.as_mut() else {
+        return fail(RtStatus::NullPointer, "resources are null");
+    };
+
+    let id = res
+        .resources
+        .add_environment(GradientEnvironment::new(bottom.into(), top.into()));
+    push(&mut res.environments, id, out_environment)
+}
+
+/// Creates an empty scene surrounded by the given environment, which must be freed with
+/// `rt_scene_free`. Returns null if the resources are null or the environment is unknown.
+///
+/// # Safety
+/// The resources must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_new(
+    resources: *const RtResources,
+    environment: u32,
+) -> *mut RtScene {
+    let Some(res) = resources.as_ref() else {
+        fail(RtStatus::NullPointer, "resources are null");
+        return ptr::null_mut();
+    };
+
+    match lookup(&res.environments, environment) {
+        Ok(background) => Box::into_raw(Box::new(RtScene {
+            scene: Scene::new(background),
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Frees a scene created with `rt_scene_new`.
+///
+/// # Safety
+/// The scene must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_free(scene: *mut RtScene) {
+    if !scene.is_null() {
+        drop(Box::from_raw(scene));
+    }
+}
+
+/// Adds a sphere with a material of the resources to the scene.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_sphere(
+    scene: *mut RtScene,
+    resources: *const RtResources,
+    center: RtVec3,
+    radius: f64,
+    material: u32,
+) -> RtStatus {
+    let (Some(scene), Some(res)) = (scene.as_mut(), resources.as_ref()) else {
+        return fail(RtStatus::NullPointer, "scene or resources are null");
+    };
+
+    guard(|| {
+        let material = lookup(&res.materials, material)?;
+        scene
+            .scene
+            .add(SphereObject::new(center.into(), radius as Float, material));
+        Ok(RtStatus::Ok)
+    })
+}
+
+/// Loads a mesh from a Wavefront OBJ file and adds it with a material of the resources.
+///
+/// # Safety
+/// The pointers must be valid or null, the path must be nul-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_mesh(
+    scene: *mut RtScene,
+    resources: *const RtResources,
+    path: *const c_char,
+    material: u32,
+) -> RtStatus {
+    let (Some(scene), Some(res)) = (scene.as_mut(), resources.as_ref()) else {
+        return fail(RtStatus::NullPointer, "scene or resources are null");
+    };
+
+    guard(|| {
+        let material = lookup(&res.materials, material)?;
+        let mesh = Mesh::load_obj(string(path)?).map_err(|err| fail(RtStatus::LoadFailed, err))?;
+        scene.scene.add(MeshObject::new(&mesh, material));
+        Ok(RtStatus::Ok)
+    })
+}
+
+/// Returns the size in bytes of the RGB image rendered with the settings,
+/// and writes its dimensions to the outputs that aren't null.
+///
+/// # Safety
+/// The pointers must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_image_size(
+    settings: *const RtCameraSettings,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> usize {
+    let Some(settings) = settings.as_ref() else {
+        fail(RtStatus::NullPointer, "settings are null");
+        return 0;
+    };
+
+    let camera = settings.build();
+    if let Some(width) = out_width.as_mut() {
+        *width = camera.image_width();
+    }
+    if let Some(height) = out_height.as_mut() {
+        *height = camera.image_height();
+    }
+
+    camera.image_width() as usize * camera.image_height() as usize * 3
+}
+
+/// Renders the scene into the buffer as 8-bit RGB, row by row from the top left.
+/// The buffer must hold at least `rt_image_size` bytes. The callback is optional and called
+/// on the calling thread, returning false from it cancels the render.
+///
+/// # Safety
+/// The pointers must be valid or null, the buffer must be writable for `buffer_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(
+    scene: *const RtScene,
+    resources: *const RtResources,
+    settings: *const RtCameraSettings,
+    buffer: *mut u8,
+    buffer_len: usize,
+    callback: RtProgressCallback,
+    user_data: *mut c_void,
+) -> RtStatus {
+    let (Some(scene), Some(res), Some(settings)) =
+        (scene.as_ref(), resources.as_ref(), settings.as_ref())
+    else {
+        return fail(
+            RtStatus::NullPointer,
+            "scene, resources or settings are null",
+        );
+    };
+    if buffer.is_null() {
+        return fail(RtStatus::NullPointer, "buffer is null");
+    }
+
+    guard(|| {
+        let camera = settings.build();
+        let size = camera.image_width() as usize * camera.image_height() as usize * 3;
+        if buffer_len < size {
+            return Err(fail(RtStatus::BufferTooSmall, "buffer is too small"));
+        }
+
+        let cancel = CancelToken::new();
+        let image = camera.render_cancellable(&scene.scene, &res.resources, &cancel, |event| {
+            if let (
+                Some(callback),
+                ProgressEvent::SamplesCompleted {
+                    completed, total, ..
+                },
+            ) = (callback, event)
+            {
+                if !callback(completed, total, user_data) {
+                    cancel.cancel();
+                }
+            }
+        });
+
+        slice::from_raw_parts_mut(buffer, size).copy_from_slice(&image.data);
+
+        if cancel.is_cancelled() {
+            Err(fail(RtStatus::Cancelled, "the render was cancelled"))
+        } else {
+            Ok(RtStatus::Ok)
+        }
+    })
+}
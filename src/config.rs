@@ -0,0 +1,326 @@
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    camera::{CameraBuilder, Preset},
+    float::Float,
+};
+
+/// The prefix of the environment variables that override the settings of the config file.
+const ENV_PREFIX: &str = "RAYTRACER_";
+#[derive(Debug, Clone)]
+/// The render settings of a project, kept apart from the scene so they can be versioned.
+///
+/// Config files hold one `key = value` setting per line in the syntax of TOML, strings are
+/// quoted and `#` starts a comment:
+///
+/// ```text
+/// output = "renders/shot.png"
+/// width = 1920
+/// height = 1080
+/// samples = 256
+/// seed = 42
+/// preset = "final"
+/// ```
+///
+/// Every setting can be overridden with an environment variable of its key in upper case,
+/// prefixed with `RAYTRACER_`, like `RAYTRACER_SAMPLES=16`. Other variables with the prefix are
+/// ignored, while unknown keys in the file are errors. Relative output paths in the file are
+/// relative to the file. Together with the width, the height sets the aspect ratio, alone it
+/// sets the width that keeps the aspect ratio of the scene. Settings made after the preset
+/// override the ones of the preset. There is no setting for threads, as rendering runs on one.
+pub struct RenderConfig {
+    /// The path of the rendered image.
+    pub output: PathBuf,
+    /// The width of the image in pixels.
+    pub width: Option<u32>,
+    /// The height of the image in pixels.
+    pub height: Option<u32>,
+    /// The number of samples per pixel.
+    pub samples: Option<u32>,
+    /// The seed of the random number generator.
+    pub seed: Option<u64>,
+    /// The preset the other settings start from.
+    pub preset: Option<Preset>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            output: PathBuf::from("output.png"),
+            width: None,
+            height: None,
+            samples: None,
+            seed: None,
+            preset: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    /// The keys of the settings.
+    pub const KEYS: [&'static str; 6] = ["output", "width", "height", "samples", "seed", "preset"];
+
+    /// Loads a config file from the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<RenderConfig, ConfigError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|_| ConfigError {
+            line: None,
+            message: "failed to read file",
+        })?;
+
+        Self::parse(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a config file, resolving relative paths against the given directory.
+    pub fn parse(source: &str, dir: &Path) -> Result<RenderConfig, ConfigError> {
+        let mut config = RenderConfig::default();
+
+        for (idx, line) in source.lines().enumerate() {
+            let error = |message| ConfigError {
+                line: Some(idx + 1),
+                message,
+            };
+
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(error("tables are not supported"));
+            }
+
+            let (key, value) = line.split_once('=').ok_or(error("expected key = value"))?;
+            let value = unquote(value.trim()).map_err(error)?;
+            config.set(key.trim(), value).map_err(error)?;
+
+            if key.trim() == "output" {
+                config.output = dir.join(&config.output);
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Overrides the settings with the environment variables prefixed with `RAYTRACER_`,
+    /// from the given pairs of names and values, like `std::env::vars()`.
+    pub fn apply_env<I: IntoIterator<Item = (String, String)>>(
+        &mut self,
+        vars: I,
+    ) -> Result<(), ConfigError> {
+        for (name, value) in vars {
+            // Other programs may use the prefix too, so unknown variables are left alone.
+            let Some(key) = name.strip_prefix(ENV_PREFIX).map(str::to_lowercase) else {
+                continue;
+            };
+            if !Self::KEYS.contains(&key.as_str()) {
+                continue;
+            }
+
+            self.set(&key, &value).map_err(|message| ConfigError {
+                line: None,
+                message,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the setting with the given key to the unquoted value.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), &'static str> {
+        match key {
+            "output" => self.output = PathBuf::from(value),
+            "width" => self.width = Some(nonzero(value, "width must not be zero")?),
+            "height" => self.height = Some(nonzero(value, "height must not be zero")?),
+            "samples" => self.samples = Some(nonzero(value, "samples must not be zero")?),
+            "seed" => self.seed = Some(number(value)?),
+            "preset" => self.preset = Some(Preset::from_name(value).ok_or("unknown preset")?),
+            "threads" => return Err("threads are not supported, rendering is single threaded"),
+            _ => return Err("unknown setting"),
+        }
+
+        Ok(())
+    }
+
+    /// Applies the settings to the camera settings of the scene.
+    pub fn configure(&self, builder: &mut CameraBuilder) {
+        if let Some(preset) = self.preset {
+            builder.with_preset(preset);
+        }
+        match (self.width, self.height) {
+            (Some(width), height) => {
+                builder.with_image_width(width);
+                if let Some(height) = height {
+                    builder.with_aspect_ratio(width as Float / height.max(1) as Float);
+                }
+            }
+            (None, Some(height)) => {
+                if let Some(aspect_ratio) = builder.aspect_ratio() {
+                    let width = (height as Float * aspect_ratio).round() as u32;
+                    builder.with_image_width(width.max(1));
+                }
+            }
+            (None, None) => {}
+        }
+        if let Some(samples) = self.samples {
+            builder.with_sample_count(samples);
+        }
+        if let Some(seed) = self.seed {
+            builder.with_seed(seed);
+        }
+    }
+}
+
+/// Removes the comment at the end of a line, ignoring `#` characters inside strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..idx],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+/// Removes the quotes around a string value, other values are returned as they are.
+fn unquote(value: &str) -> Result<&str, &'static str> {
+    match value.strip_prefix('"') {
+        Some(rest) => rest.strip_suffix('"').ok_or("unterminated string"),
+        None => Ok(value),
+    }
+}
+
+/// Parses a value as a number.
+fn number<T: FromStr>(value: &str) -> Result<T, &'static str> {
+    value.parse().map_err(|_| "invalid number")
+}
+
+/// Parses a value as a number, failing with the message if it is zero.
+fn nonzero(value: &str, message: &'static str) -> Result<u32, &'static str> {
+    match number(value)? {
+        0 => Err(message),
+        number => Ok(number),
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An error in a config file or in the environment variables overriding it.
+pub struct ConfigError {
+    /// The line of the error, or `None` if it doesn't belong to a line of the file.
+    pub line: Option<usize>,
+    /// A description of the error.
+    pub message: &'static str,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{camera::Camera, vec3};
+
+    #[test]
+    fn parses_settings() {
+        let config = RenderConfig::parse(
+            "# the final shot\n\
+             output = \"renders/shot#1.png\" # relative to the file\n\
+             width = 320\n\
+             height = 160\n\
+             samples = 16\n\
+             preset = \"draft\"\n",
+            Path::new("project"),
+        )
+        .unwrap();
+
+        assert_eq!(config.output, Path::new("project/renders/shot#1.png"));
+        assert_eq!(config.width, Some(320));
+        assert_eq!(config.height, Some(160));
+        assert_eq!(config.samples, Some(16));
+        assert_eq!(config.seed, None);
+        assert!(config.preset.is_some());
+    }
+
+    #[test]
+    fn reports_line_of_error() {
+        let err = RenderConfig::parse("width = 320\nthreads = 8\n", Path::new(""))
+            .err()
+            .unwrap();
+        assert_eq!(err.line, Some(2));
+
+        for source in [
+            "[render]",
+            "width",
+            "width = wide",
+            "output = \"open",
+            "size = 1",
+        ] {
+            assert!(RenderConfig::parse(source, Path::new("")).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_zero_sizes() {
+        let mut config = RenderConfig::default();
+        assert_eq!(config.set("samples", "0"), Err("samples must not be zero"));
+        assert_eq!(config.set("width", "0"), Err("width must not be zero"));
+        assert_eq!(config.set("height", "0"), Err("height must not be zero"));
+        assert_eq!(config.set("samples", "-1"), Err("invalid number"));
+        assert_eq!(
+            (config.width, config.height, config.samples),
+            (None, None, None)
+        );
+    }
+
+    #[test]
+    fn environment_overrides_known_keys() {
+        let mut config = RenderConfig::default();
+        config
+            .apply_env([
+                ("RAYTRACER_SAMPLES".to_string(), "4".to_string()),
+                ("RAYTRACER_LOG".to_string(), "debug".to_string()),
+                ("SAMPLES".to_string(), "8".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(config.samples, Some(4));
+
+        let err = config
+            .apply_env([("RAYTRACER_WIDTH".to_string(), "wide".to_string())])
+            .err()
+            .unwrap();
+        assert_eq!(err.line, None);
+    }
+
+    #[test]
+    fn lone_height_keeps_aspect_ratio() {
+        let mut builder = Camera::builder();
+        builder
+            .with_look_from(vec3!(0))
+            .with_look_at(vec3!(0, 0, -1))
+            .with_vfov(90.0)
+            .with_image_width(400)
+            .with_aspect_ratio(2.0);
+
+        let config = RenderConfig {
+            height: Some(100),
+            ..RenderConfig::default()
+        };
+        config.configure(&mut builder);
+
+        let camera = builder.build();
+        assert_eq!((camera.image_width(), camera.image_height()), (200, 100));
+    }
+}
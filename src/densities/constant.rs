@@ -0,0 +1,21 @@
+use crate::{density::Density, float::Float, vector::Point3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A medium with the same density everywhere, like fog.
+pub struct ConstantDensity {
+    /// The density of the medium.
+    density: Float,
+}
+
+impl ConstantDensity {
+    /// Creates a new constant density.
+    pub const fn new(density: Float) -> Self {
+        Self { density }
+    }
+}
+
+impl Density for ConstantDensity {
+    fn density(&self, _point: Point3) -> Float {
+        self.density
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{density::Density, float::Float, vector::Point3};
+
+#[derive(Debug, Clone)]
+/// A medium whose density is stored in a regular grid, like a simulated smoke volume.
+/// The density is interpolated trilinearly between the grid points and is zero outside.
+pub struct GridDensity {
+    /// The position of the first grid point.
+    origin: Point3,
+    /// The distance between neighbouring grid points.
+    spacing: Float,
+    /// The number of grid points along each axis.
+    size: (usize, usize, usize),
+    /// The densities at the grid points, ordered by x, then y, then z.
+    data: Box<[Float]>,
+}
+
+impl GridDensity {
+    /// Creates a new grid density from the densities of the grid points.
+    pub fn new(
+        origin: Point3,
+        spacing: Float,
+        size: (usize, usize, usize),
+        data: Vec<Float>,
+    ) -> Result<Self, &'static str> {
+        if data.len() != size.0 * size.1 * size.2 {
+            return Err("grid data does not match the grid size");
+        }
+
+        Ok(Self {
+            origin,
+            spacing,
+            size,
+            data: data.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the density at the grid point, or zero outside of the grid.
+    fn at(&self, x: i64, y: i64, z: i64) -> Float {
+        let (w, h, d) = self.size;
+        if x < 0 || y < 0 || z < 0 || x >= w as i64 || y >= h as i64 || z >= d as i64 {
+            return 0.0;
+        }
+
+        self.data[(z as usize * h + y as usize) * w + x as usize]
+    }
+}
+
+impl Density for GridDensity {
+    fn density(&self, point: Point3) -> Float {
+        let local = (point - self.origin) / self.spacing;
+        let (fx, fy, fz) = (local.x.floor(), local.y.floor(), local.z.floor());
+        let (tx, ty, tz) = (local.x - fx, local.y - fy, local.z - fz);
+        let (x, y, z) = (fx as i64, fy as i64, fz as i64);
+
+        let lerp = |a: Float, b: Float, t: Float| a + (b - a) * t;
+
+        let c00 = lerp(self.at(x, y, z), self.at(x + 1, y, z), tx);
+        let c10 = lerp(self.at(x, y + 1, z), self.at(x + 1, y + 1, z), tx);
+        let c01 = lerp(self.at(x, y, z + 1), self.at(x + 1, y, z + 1), tx);
+        let c11 = lerp(self.at(x, y + 1, z + 1), self.at(x + 1, y + 1, z + 1), tx);
+
+        lerp(lerp(c00, c10, ty), lerp(c01, c11, ty), tz)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{density::Density, float::Float, noise::Perlin, vector::Point3};
+
+#[derive(Debug, Clone)]
+/// A wispy medium whose density follows fractal noise, like smoke or clouds.
+pub struct NoiseDensity {
+    /// The noise generator.
+    perlin: Perlin,
+    /// The frequency of the noise.
+    scale: Float,
+    /// The number of noise octaves.
+    octaves: u32,
+    /// The density where the noise is at its maximum.
+    density: Float,
+}
+
+impl NoiseDensity {
+    /// Creates a new noise density with the given seed, frequency, octaves and maximum density.
+    pub fn new(seed: u64, scale: Float, octaves: u32, density: Float) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            octaves,
+            density,
+        }
+    }
+}
+
+impl Density for NoiseDensity {
+    fn density(&self, point: Point3) -> Float {
+        let noise = self.perlin.fbm(point * self.scale, self.octaves);
+        self.density * noise.clamp(0.0, 1.0)
+    }
+}
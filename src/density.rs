@@ -0,0 +1,10 @@
+use std::fmt::Debug;
+
+use crate::float::Float;
+use crate::vector::Point3;
+
+/// A spatially varying density of a participating medium.
+pub trait Density: Debug + Send + Sync {
+    /// Returns the density of the medium at the given point.
+    fn density(&self, point: Point3) -> Float;
+}
@@ -0,0 +1,238 @@
+use std::{any::Any, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    float::Float,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, ScatterRecord},
+    materials::{
+        conductor::ConductorMaterial, dielectric::DielectricMaterial,
+        diffuse_light::DiffuseLightMaterial, isotropic::IsotropicMaterial,
+        lambertian::LambertianMaterial, metal::MetalMaterial,
+    },
+    objects::{
+        mesh::MeshObject, plane::PlaneObject, sphere::SphereObject, transformed::TransformedObject,
+        volume::VolumeObject, voxel_grid::VoxelGridObject,
+    },
+    ray::{Intersection, Ray},
+    resources::{MaterialId, Resources},
+    vector::{Color, Point3, Vec3},
+};
+
+/// Moves the value out of the slot if it has the type `T`.
+fn take<T: 'static, V: 'static>(slot: &mut Option<V>) -> Option<T> {
+    (slot as &mut dyn Any).downcast_mut::<Option<T>>()?.take()
+}
+
+#[derive(Debug)]
+/// An object in a scene, which calls the built in objects without dynamic dispatch.
+/// With the `dynamic-dispatch` feature, all objects are stored as trait objects instead.
+pub enum Primitive {
+    Sphere(SphereObject),
+    Mesh(MeshObject),
+    Plane(PlaneObject),
+    Transformed(TransformedObject),
+    Volume(VolumeObject),
+    VoxelGrid(VoxelGridObject),
+    /// Any other object, which is called through dynamic dispatch.
+    Dynamic(Box<dyn Hittable>),
+}
+
+impl Primitive {
+    /// Wraps an object into the matching variant.
+    pub fn new<H: Hittable + 'static>(object: H) -> Self {
+        let mut slot = Some(object);
+
+        if !cfg!(feature = "dynamic-dispatch") {
+            if let Some(object) = take(&mut slot) {
+                return Self::Sphere(object);
+            }
+            if let Some(object) = take(&mut slot) {
+                return Self::Mesh(object);
+            }
+            if let Some(object) = take(&mut slot) {
+                return Self::Plane(object);
+            }
+            if let Some(object) = take(&mut slot) {
+                return Self::Transformed(object);
+            }
+            if let Some(object) = take(&mut slot) {
+                return Self::Volume(object);
+            }
+            if let Some(object) = take(&mut slot) {
+                return Self::VoxelGrid(object);
+            }
+            if let Some(object) = take(&mut slot) {
+                return object;
+            }
+        }
+
+        Self::Dynamic(Box::new(slot.expect("object was taken")))
+    }
+
+    /// Converts the primitive into a trait object.
+    pub fn into_boxed(self) -> Box<dyn Hittable> {
+        match self {
+            Self::Sphere(object) => Box::new(object),
+            Self::Mesh(object) => Box::new(object),
+            Self::Plane(object) => Box::new(object),
+            Self::Transformed(object) => Box::new(object),
+            Self::Volume(object) => Box::new(object),
+            Self::VoxelGrid(object) => Box::new(object),
+            Self::Dynamic(object) => object,
+        }
+    }
+}
+
+/// Forwards a call to the object of every variant of a primitive.
+macro_rules! dispatch_primitive {
+    ($primitive:expr, $object:ident => $call:expr) => {
+        match $primitive {
+            Primitive::Sphere($object) => $call,
+            Primitive::Mesh($object) => $call,
+            Primitive::Plane($object) => $call,
+            Primitive::Transformed($object) => $call,
+            Primitive::Volume($object) => $call,
+            Primitive::VoxelGrid($object) => $call,
+            Primitive::Dynamic($object) => $call,
+        }
+    };
+}
+
+impl Hittable for Primitive {
+    fn hit(&self, r: &Ray, time: Interval) -> Option<Intersection> {
+        dispatch_primitive!(self, object => object.hit(r, time))
+    }
+
+    fn occluded(&self, r: &Ray, time: Interval) -> bool {
+        dispatch_primitive!(self, object => object.occluded(r, time))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        dispatch_primitive!(self, object => object.bounding_box())
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3, time: Float) -> Float {
+        dispatch_primitive!(self, object => object.pdf_value(origin, dir, time))
+    }
+
+    fn random(&self, origin: Point3, time: Float) -> Vec3 {
+        dispatch_primitive!(self, object => object.random(origin, time))
+    }
+
+    fn light_material(&self) -> Option<MaterialId> {
+        dispatch_primitive!(self, object => object.light_material())
+    }
+
+    fn motion(&self, point: Point3, time: Float) -> Vec3 {
+        dispatch_primitive!(self, object => object.motion(point, time))
+    }
+
+    fn kind(&self) -> &'static str {
+        dispatch_primitive!(self, object => object.kind())
+    }
+
+    fn triangle_count(&self) -> usize {
+        dispatch_primitive!(self, object => object.triangle_count())
+    }
+
+    fn memory_size(&self) -> usize {
+        dispatch_primitive!(self, object => object.memory_size())
+    }
+}
+
+#[derive(Debug)]
+/// A material in the resources, which calls the built in materials without dynamic dispatch.
+/// With the `dynamic-dispatch` feature, all materials are stored as trait objects instead.
+pub enum MaterialKind {
+    Lambertian(LambertianMaterial),
+    Metal(MetalMaterial),
+    Dielectric(DielectricMaterial),
+    Conductor(ConductorMaterial),
+    DiffuseLight(DiffuseLightMaterial),
+    Isotropic(IsotropicMaterial),
+    /// Any other material, which is called through dynamic dispatch.
+    Dynamic(Arc<dyn Material>),
+}
+
+impl MaterialKind {
+    /// Wraps a material into the matching variant.
+    pub fn new<M: Material + 'static>(material: M) -> Self {
+        let mut slot = Some(material);
+
+        if !cfg!(feature = "dynamic-dispatch") {
+            if let Some(material) = take(&mut slot) {
+                return Self::Lambertian(material);
+            }
+            if let Some(material) = take(&mut slot) {
+                return Self::Metal(material);
+            }
+            if let Some(material) = take(&mut slot) {
+                return Self::Dielectric(material);
+            }
+            if let Some(material) = take(&mut slot) {
+                return Self::Conductor(material);
+            }
+            if let Some(material) = take(&mut slot) {
+                return Self::DiffuseLight(material);
+            }
+            if let Some(material) = take(&mut slot) {
+                return Self::Isotropic(material);
+            }
+            if let Some(material) = take(&mut slot) {
+                return material;
+            }
+        }
+
+        Self::Dynamic(Arc::new(slot.expect("material was taken")))
+    }
+}
+
+/// Forwards a call to the material of every variant of a material kind.
+macro_rules! dispatch_material {
+    ($kind:expr, $material:ident => $call:expr) => {
+        match $kind {
+            MaterialKind::Lambertian($material) => $call,
+            MaterialKind::Metal($material) => $call,
+            MaterialKind::Dielectric($material) => $call,
+            MaterialKind::Conductor($material) => $call,
+            MaterialKind::DiffuseLight($material) => $call,
+            MaterialKind::Isotropic($material) => $call,
+            MaterialKind::Dynamic($material) => $call,
+        }
+    };
+}
+
+impl Material for MaterialKind {
+    fn scatter(
+        &self,
+        resources: &Resources,
+        ray: &Ray,
+        hit: &Intersection,
+    ) -> Option<ScatterRecord> {
+        dispatch_material!(self, material => material.scatter(resources, ray, hit))
+    }
+
+    fn scattering_pdf(
+        &self,
+        resources: &Resources,
+        ray: &Ray,
+        hit: &Intersection,
+        scattered: &Ray,
+    ) -> Float {
+        dispatch_material!(self, material => material.scattering_pdf(resources, ray, hit, scattered))
+    }
+
+    fn emit(&self, resources: &Resources, hit: &Intersection) -> Color {
+        dispatch_material!(self, material => material.emit(resources, hit))
+    }
+
+    fn is_emissive(&self) -> bool {
+        dispatch_material!(self, material => material.is_emissive())
+    }
+
+    fn is_shadow_catcher(&self) -> bool {
+        dispatch_material!(self, material => material.is_shadow_catcher())
+    }
+}
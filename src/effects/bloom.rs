@@ -0,0 +1,95 @@
+use crate::{float::Float, framebuffer::FrameBuffer, postprocess::PostProcess, vector::Color};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A bloom filter, which spreads the light of bright pixels into their surroundings.
+pub struct BloomEffect {
+    /// The luminance above which pixels start to glow.
+    pub threshold: Float,
+    /// The standard deviation of the blur in pixels.
+    pub radius: Float,
+    /// The strength with which the glow is added back to the image.
+    pub intensity: Float,
+}
+
+impl BloomEffect {
+    /// Creates a new bloom filter with the given threshold, radius, and intensity.
+    pub const fn new(threshold: Float, radius: Float, intensity: Float) -> Self {
+        Self {
+            threshold,
+            radius,
+            intensity,
+        }
+    }
+}
+
+impl PostProcess for BloomEffect {
+    fn apply(&self, fb: &mut FrameBuffer) {
+        // Extract the part of every pixel that is brighter than the threshold.
+        let mut bright = fb.clone();
+        for color in bright.data.iter_mut() {
+            let luminance = color.luminance();
+
+            *color = if luminance > self.threshold {
+                *color * ((luminance - self.threshold) / luminance)
+            } else {
+                Color::ZERO
+            };
+        }
+
+        // Blur the bright parts with a separable gaussian kernel.
+        let kernel = gaussian_kernel(self.radius);
+        let blurred = blur(&blur(&bright, &kernel, true), &kernel, false);
+
+        // Add the glow back on top of the image.
+        for (color, glow) in fb.data.iter_mut().zip(blurred.data.iter()) {
+            *color += *glow * self.intensity;
+        }
+    }
+}
+
+/// Creates a normalized gaussian kernel with the given standard deviation.
+/// The kernel is symmetric and only stores the weights from the center outwards.
+pub(crate) fn gaussian_kernel(sigma: Float) -> Vec<Float> {
+    let sigma = sigma.max(0.5);
+    let size = (sigma * 3.0).ceil() as usize;
+
+    let mut kernel: Vec<Float> = (0..=size)
+        .map(|i| (-((i * i) as Float) / (2.0 * sigma * sigma)).exp())
+        .collect();
+
+    let sum = kernel[0] + 2.0 * kernel[1..].iter().sum::<Float>();
+    kernel.iter_mut().for_each(|weight| *weight /= sum);
+
+    kernel
+}
+
+/// Blurs the frame buffer with the kernel in horizontal or vertical direction.
+pub(crate) fn blur(fb: &FrameBuffer, kernel: &[Float], horizontal: bool) -> FrameBuffer {
+    let mut result = FrameBuffer::new(fb.width, fb.height);
+
+    let (width, height) = (fb.width as i64, fb.height as i64);
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut color = fb[(x as u32, y as u32)] * kernel[0];
+
+            for (offset, weight) in kernel.iter().enumerate().skip(1) {
+                let offset = offset as i64;
+
+                // Clamp the samples to the edge of the image.
+                let (a, b) = if horizontal {
+                    (((x - offset).max(0), y), ((x + offset).min(width - 1), y))
+                } else {
+                    ((x, (y - offset).max(0)), (x, (y + offset).min(height - 1)))
+                };
+
+                color += fb[(a.0 as u32, a.1 as u32)] * *weight;
+                color += fb[(b.0 as u32, b.1 as u32)] * *weight;
+            }
+
+            result[(x as u32, y as u32)] = color;
+        }
+    }
+
+    result
+}
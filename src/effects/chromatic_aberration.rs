@@ -0,0 +1,80 @@
+use crate::{
+    effects::bloom::{blur, gaussian_kernel},
+    float::Float,
+    framebuffer::FrameBuffer,
+    postprocess::PostProcess,
+    vector::Color,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A lens effect, which separates the color channels like a simple uncorrected lens.
+pub struct ChromaticAberrationEffect {
+    /// How far the red and blue channels are scaled apart towards the image edges,
+    /// as a fraction of the distance from the center.
+    pub lateral: Float,
+    /// The standard deviation in pixels with which the red and blue channels are defocused.
+    pub longitudinal: Float,
+}
+
+impl ChromaticAberrationEffect {
+    /// Creates a new chromatic aberration effect with the given lateral and longitudinal strength.
+    pub const fn new(lateral: Float, longitudinal: Float) -> Self {
+        Self {
+            lateral,
+            longitudinal,
+        }
+    }
+}
+
+impl PostProcess for ChromaticAberrationEffect {
+    fn apply(&self, fb: &mut FrameBuffer) {
+        // Defocus the red and blue channels, while green stays in focus.
+        let source = if self.longitudinal > 0.0 {
+            let kernel = gaussian_kernel(self.longitudinal);
+            blur(&blur(fb, &kernel, true), &kernel, false)
+        } else {
+            fb.clone()
+        };
+
+        let center_x = fb.width as Float / 2.0;
+        let center_y = fb.height as Float / 2.0;
+
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let dx = x as Float + 0.5 - center_x;
+                let dy = y as Float + 0.5 - center_y;
+
+                // Red is magnified and blue is shrunk around the image center.
+                let red = sample(
+                    &source,
+                    center_x + dx * (1.0 + self.lateral),
+                    center_y + dy * (1.0 + self.lateral),
+                );
+                let blue = sample(
+                    &source,
+                    center_x + dx * (1.0 - self.lateral),
+                    center_y + dy * (1.0 - self.lateral),
+                );
+
+                let color = &mut fb[(x, y)];
+                color.x = red.x;
+                color.z = blue.z;
+            }
+        }
+    }
+}
+
+/// Samples the frame buffer bilinearly at the given pixel coordinates, clamped to the edges.
+fn sample(fb: &FrameBuffer, x: Float, y: Float) -> Color {
+    let x = (x - 0.5).clamp(0.0, (fb.width - 1) as Float);
+    let y = (y - 0.5).clamp(0.0, (fb.height - 1) as Float);
+
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(fb.width - 1), (y0 + 1).min(fb.height - 1));
+    let (tx, ty) = (x - x0 as Float, y - y0 as Float);
+
+    let top = fb[(x0, y0)].lerp(fb[(x1, y0)], tx);
+    let bottom = fb[(x0, y1)].lerp(fb[(x1, y1)], tx);
+
+    top.lerp(bottom, ty)
+}
@@ -0,0 +1,25 @@
+use crate::{float::Float, framebuffer::FrameBuffer, postprocess::PostProcess};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An effect that encodes linear colors with a gamma curve.
+pub struct GammaEffect {
+    /// The gamma of the encoding.
+    gamma: Float,
+}
+
+impl GammaEffect {
+    /// Creates a new gamma effect with the given gamma.
+    pub const fn new(gamma: Float) -> Self {
+        Self { gamma }
+    }
+}
+
+impl PostProcess for GammaEffect {
+    fn apply(&self, fb: &mut FrameBuffer) {
+        let exponent = 1.0 / self.gamma;
+
+        for color in fb.data.iter_mut() {
+            *color = color.map(|value| value.max(0.0).powf(exponent));
+        }
+    }
+}
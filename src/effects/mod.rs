@@ -0,0 +1,6 @@
+pub mod bloom;
+pub mod chromatic_aberration;
+pub mod gamma;
+pub mod tonemap;
+pub mod vignette;
+pub mod white_balance;
@@ -0,0 +1,92 @@
+use crate::{float::Float, framebuffer::FrameBuffer, postprocess::PostProcess, vector::Color};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// An operator that maps HDR radiance into the displayable [0, 1] range.
+pub enum ToneMapper {
+    #[default]
+    /// Clamps every channel to [0, 1], this is the behaviour without tone mapping.
+    Clamp,
+    /// Reinhard's operator applied to the luminance, which preserves hue.
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2.
+    Filmic,
+}
+
+impl ToneMapper {
+    /// Maps a color with the operator, the exposure is given in stops.
+    pub fn apply(&self, color: Color, exposure: Float) -> Color {
+        let color = color * exposure.exp2();
+
+        let mapped = match self {
+            Self::Clamp => color,
+            Self::Reinhard => {
+                let luminance = color.luminance();
+                if luminance <= 0.0 {
+                    return Color::ZERO;
+                }
+
+                color * (1.0 / (1.0 + luminance))
+            }
+            Self::Aces => color.map(|x| {
+                const A: Float = 2.51;
+                const B: Float = 0.03;
+                const C: Float = 2.43;
+                const D: Float = 0.59;
+                const E: Float = 0.14;
+
+                (x * (A * x + B)) / (x * (C * x + D) + E)
+            }),
+            Self::Filmic => {
+                const EXPOSURE_BIAS: Float = 2.0;
+                const WHITE_POINT: Float = 11.2;
+
+                let white_scale = 1.0 / hable(WHITE_POINT);
+                color.map(|x| hable(x * EXPOSURE_BIAS) * white_scale)
+            }
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An effect that tone maps the frame buffer with an exposure compensation.
+pub struct ToneMapEffect {
+    /// The operator used to map the colors.
+    tone_mapper: ToneMapper,
+    /// The exposure compensation in stops.
+    exposure: Float,
+}
+
+impl ToneMapEffect {
+    /// Creates a new tone mapping effect with the given operator and exposure in stops.
+    pub const fn new(tone_mapper: ToneMapper, exposure: Float) -> Self {
+        Self {
+            tone_mapper,
+            exposure,
+        }
+    }
+}
+
+impl PostProcess for ToneMapEffect {
+    fn apply(&self, fb: &mut FrameBuffer) {
+        for color in fb.data.iter_mut() {
+            *color = self.tone_mapper.apply(*color, self.exposure);
+        }
+    }
+}
+
+/// The filmic curve by John Hable.
+/// See http://filmicworlds.com/blog/filmic-tonemapping-operators/.
+fn hable(x: Float) -> Float {
+    const A: Float = 0.15;
+    const B: Float = 0.50;
+    const C: Float = 0.10;
+    const D: Float = 0.20;
+    const E: Float = 0.02;
+    const F: Float = 0.30;
+
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
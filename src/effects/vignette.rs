@@ -0,0 +1,38 @@
+use crate::{float::Float, framebuffer::FrameBuffer, postprocess::PostProcess};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An effect that darkens the image towards its corners.
+pub struct VignetteEffect {
+    /// How much the corners are darkened, in the range [0, 1].
+    strength: Float,
+    /// The exponent of the falloff from the center.
+    falloff: Float,
+}
+
+impl VignetteEffect {
+    /// Creates a new vignette effect with the given strength and falloff exponent.
+    pub const fn new(strength: Float, falloff: Float) -> Self {
+        Self { strength, falloff }
+    }
+}
+
+impl PostProcess for VignetteEffect {
+    fn apply(&self, fb: &mut FrameBuffer) {
+        let center_x = fb.width as Float / 2.0;
+        let center_y = fb.height as Float / 2.0;
+        let max_distance = (center_x * center_x + center_y * center_y).sqrt();
+
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let dx = x as Float + 0.5 - center_x;
+                let dy = y as Float + 0.5 - center_y;
+
+                // The distance from the center, where 1 is a corner of the image.
+                let distance = (dx * dx + dy * dy).sqrt() / max_distance;
+                let factor = 1.0 - self.strength * distance.powf(self.falloff);
+
+                fb[(x, y)] *= factor.max(0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    float::Float, framebuffer::FrameBuffer, postprocess::PostProcess, vec3, vector::Color,
+};
+
+/// The color temperature of a neutral white in kelvin.
+const NEUTRAL_TEMPERATURE: Float = 6500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A white balance adjustment, which scales the channels of a color to
+/// compensate for the color temperature and tint of the lighting.
+pub struct WhiteBalanceEffect {
+    /// The per channel gains of the adjustment.
+    gains: Color,
+}
+
+impl WhiteBalanceEffect {
+    /// The neutral white balance, which leaves colors untouched.
+    pub const NEUTRAL: Self = Self {
+        gains: Color::WHITE,
+    };
+
+    /// Creates a white balance for lighting with the given color temperature in kelvin.
+    /// The tint is in the range [-1, 1], positive values shift towards magenta
+    /// and negative values towards green.
+    pub fn new(temperature: Float, tint: Float) -> Self {
+        let illuminant = kelvin_to_rgb(temperature);
+        let neutral = kelvin_to_rgb(NEUTRAL_TEMPERATURE);
+
+        let mut gains = vec3!(
+            neutral.x / illuminant.x,
+            neutral.y / illuminant.y,
+            neutral.z / illuminant.z
+        );
+        gains.y *= 1.0 - tint.clamp(-1.0, 1.0) * 0.5;
+
+        // Normalize the gains so the adjustment keeps the luminance of white.
+        let luminance = gains.luminance();
+
+        Self {
+            gains: gains / luminance,
+        }
+    }
+}
+
+impl PostProcess for WhiteBalanceEffect {
+    fn apply(&self, fb: &mut FrameBuffer) {
+        for color in fb.data.iter_mut() {
+            *color *= self.gains;
+        }
+    }
+}
+
+/// Approximates the color of a black body radiator at the given temperature.
+/// See https://tannerhelland.com/2012/09/18/convert-temperature-rgb-algorithm-code.html.
+fn kelvin_to_rgb(temperature: Float) -> Color {
+    let t = temperature.clamp(1000.0, 40000.0) / 100.0;
+
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_17 * (t - 60.0).powf(-0.075_514_85)
+    };
+
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    // Keep every channel above zero so the gains stay finite.
+    vec3!(
+        r.clamp(1.0, 255.0) / 255.0,
+        g.clamp(1.0, 255.0) / 255.0,
+        b.clamp(1.0, 255.0) / 255.0
+    )
+}
@@ -0,0 +1,12 @@
+use std::fmt::Debug;
+
+use crate::{
+    resources::Resources,
+    vector::{Color, Vec3},
+};
+
+/// The environment surrounding a scene, which is seen by rays that don't hit any object.
+pub trait Environment: Debug + Send + Sync {
+    /// Get the color of the environment in the given direction.
+    fn color(&self, resources: &Resources, dir: Vec3) -> Color;
+}
@@ -0,0 +1,29 @@
+use crate::{
+    environment::Environment,
+    resources::Resources,
+    vector::{Color, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An environment that blends vertically from a color below to a color above.
+pub struct GradientEnvironment {
+    /// The color straight down.
+    bottom: Color,
+    /// The color straight up.
+    top: Color,
+}
+
+impl GradientEnvironment {
+    /// Create a new gradient environment from the bottom to the top color.
+    pub const fn new(bottom: Color, top: Color) -> Self {
+        Self { bottom, top }
+    }
+}
+
+impl Environment for GradientEnvironment {
+    fn color(&self, _resources: &Resources, dir: Vec3) -> Color {
+        let a = 0.5 * (dir.unit().y + 1.0);
+
+        self.bottom * (1.0 - a) + self.top * a
+    }
+}
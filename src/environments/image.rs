@@ -0,0 +1,50 @@
+use crate::float::consts::PI;
+
+use crate::{
+    environment::Environment,
+    float::Float,
+    framebuffer::FrameBuffer,
+    resources::Resources,
+    vector::{Color, Vec3},
+};
+
+#[derive(Debug, Clone)]
+/// An environment from an equirectangular HDR panorama, like a light probe.
+pub struct ImageEnvironment {
+    /// The panorama, with the zenith at the top and the -z direction in the center.
+    image: FrameBuffer,
+    /// The factor the colors of the panorama are scaled by.
+    intensity: Float,
+}
+
+impl ImageEnvironment {
+    /// Create a new image environment from an equirectangular panorama.
+    pub fn new(image: FrameBuffer, intensity: Float) -> Self {
+        Self { image, intensity }
+    }
+}
+
+impl Environment for ImageEnvironment {
+    fn color(&self, _resources: &Resources, dir: Vec3) -> Color {
+        // An empty panorama has no pixel in any direction.
+        if self.image.width == 0 || self.image.height == 0 {
+            return Color::ZERO;
+        }
+
+        let (x, y) = panorama_pixel(dir, self.image.width, self.image.height);
+        self.image[(x, y)] * self.intensity
+    }
+}
+
+/// Returns the pixel of an equirectangular panorama with the given size in a direction.
+pub(crate) fn panorama_pixel(dir: Vec3, width: u32, height: u32) -> (u32, u32) {
+    let dir = dir.unit();
+
+    let u = 0.5 + dir.x.atan2(-dir.z) / (2.0 * PI);
+    let v = dir.y.clamp(-1.0, 1.0).acos() / PI;
+
+    let x = ((u * width as Float) as u32).min(width - 1);
+    let y = ((v * height as Float) as u32).min(height - 1);
+
+    (x, y)
+}
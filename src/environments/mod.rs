@@ -0,0 +1,5 @@
+pub mod gradient;
+pub mod image;
+pub mod sky;
+pub mod solid;
+pub mod tiled;
@@ -0,0 +1,79 @@
+use crate::{
+    environment::Environment,
+    float::Float,
+    resources::Resources,
+    vec3,
+    vector::{Color, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An outdoor sky with a vertical gradient and an analytic sun disk.
+pub struct SkyEnvironment {
+    /// The color of the sky at the horizon.
+    horizon: Color,
+    /// The color of the sky straight up.
+    zenith: Color,
+    /// The color below the horizon.
+    ground: Color,
+    /// The unit direction towards the sun.
+    sun_direction: Vec3,
+    /// The cosine of the angular radius of the sun.
+    sun_cos_radius: Float,
+    /// The color of the sun, including its intensity.
+    sun_color: Color,
+}
+
+impl SkyEnvironment {
+    /// Creates a new sky with the given colors, and a sun in the given direction with
+    /// an angular diameter in degrees and a color that includes its intensity.
+    pub fn new(
+        horizon: Color,
+        zenith: Color,
+        ground: Color,
+        sun_direction: Vec3,
+        sun_size: Float,
+        sun_color: Color,
+    ) -> Self {
+        Self {
+            horizon,
+            zenith,
+            ground,
+            sun_direction: sun_direction.unit(),
+            sun_cos_radius: (sun_size / 2.0).to_radians().cos(),
+            sun_color,
+        }
+    }
+
+    /// Creates a clear daylight sky with a bright sun in the given direction.
+    pub fn daylight(sun_direction: Vec3) -> Self {
+        Self::new(
+            vec3!(0.9, 0.95, 1.0),
+            vec3!(0.35, 0.55, 0.95),
+            vec3!(0.35, 0.33, 0.3),
+            sun_direction,
+            2.0,
+            vec3!(1.0, 0.95, 0.85) * 50.0,
+        )
+    }
+}
+
+impl Environment for SkyEnvironment {
+    fn color(&self, _resources: &Resources, dir: Vec3) -> Color {
+        let dir = dir.unit();
+
+        // Blend from the horizon towards the zenith, or fade into the ground below.
+        let sky = if dir.y >= 0.0 {
+            let a = dir.y.sqrt();
+            self.horizon * (1.0 - a) + self.zenith * a
+        } else {
+            let a = (-dir.y * 4.0).min(1.0);
+            self.horizon * (1.0 - a) + self.ground * a
+        };
+
+        if dir.dot(self.sun_direction) >= self.sun_cos_radius {
+            sky + self.sun_color
+        } else {
+            sky
+        }
+    }
+}
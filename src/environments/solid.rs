@@ -0,0 +1,25 @@
+use crate::{
+    environment::Environment,
+    resources::Resources,
+    vector::{Color, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An environment with the same color in every direction.
+pub struct SolidEnvironment {
+    /// The color of the environment.
+    color: Color,
+}
+
+impl SolidEnvironment {
+    /// Create a new solid color environment.
+    pub const fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Environment for SolidEnvironment {
+    fn color(&self, _resources: &Resources, _dir: Vec3) -> Color {
+        self.color
+    }
+}
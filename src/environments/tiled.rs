@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use crate::{
+    environment::Environment,
+    float::Float,
+    imgbuf::{
+        hdr::rgbe_to_color,
+        tiled::{TiledImage, DEFAULT_CACHE_TILES},
+    },
+    resources::Resources,
+    vector::{Color, Vec3},
+};
+
+use super::image::panorama_pixel;
+
+#[derive(Debug)]
+/// An environment from an equirectangular HDR panorama too large to keep in memory,
+/// whose tiles are loaded when sampled.
+pub struct TiledImageEnvironment {
+    /// The panorama as RGBE pixels, with the zenith at the top and the -z direction in the center.
+    image: TiledImage,
+    /// The factor the colors of the panorama are scaled by.
+    intensity: Float,
+}
+
+impl TiledImageEnvironment {
+    /// Loads a Radiance HDR panorama, keeping the default number of tiles in memory.
+    pub fn open<P: AsRef<Path>>(path: P, intensity: Float) -> Result<Self, &'static str> {
+        Self::open_with_cache(path, intensity, DEFAULT_CACHE_TILES)
+    }
+
+    /// Loads a Radiance HDR panorama, keeping at most the given number of tiles in memory.
+    pub fn open_with_cache<P: AsRef<Path>>(
+        path: P,
+        intensity: Float,
+        cache_tiles: usize,
+    ) -> Result<Self, &'static str> {
+        Ok(Self {
+            image: TiledImage::load_hdr(path, cache_tiles)?,
+            intensity,
+        })
+    }
+}
+
+impl Environment for TiledImageEnvironment {
+    fn color(&self, _resources: &Resources, dir: Vec3) -> Color {
+        // An empty panorama has no pixel in any direction.
+        if self.image.width == 0 || self.image.height == 0 {
+            return Color::ZERO;
+        }
+
+        let (x, y) = panorama_pixel(dir, self.image.width, self.image.height);
+        rgbe_to_color(&self.image.pixel(x, y)) * self.intensity
+    }
+}
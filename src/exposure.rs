@@ -0,0 +1,208 @@
+use std::fmt::Write;
+
+use crate::{float::Float, framebuffer::FrameBuffer, imgbuf::ImageBuffer};
+
+/// The exposure values of the lower and upper edge of the histogram, relative to a luminance of one.
+const HISTOGRAM_RANGE: (Float, Float) = (-16.0, 16.0);
+
+/// The number of bins of the histogram, two per stop.
+const HISTOGRAM_BINS: usize = 64;
+
+/// The percentiles of the luminance that are reported.
+const PERCENTILES: [Float; 7] = [1.0, 5.0, 25.0, 50.0, 75.0, 95.0, 99.0];
+
+/// The luminance of middle grey, which the suggested exposure brings the average luminance to.
+const MIDDLE_GREY: Float = 0.18;
+
+/// The width and height in pixels of the histogram drawn onto images.
+const OVERLAY_SIZE: (u32, u32) = (192, 64);
+
+#[derive(Debug, Clone)]
+/// Statistics of the luminance of an HDR frame buffer, to guide the choice of exposure
+/// and tone mapping. Exposure values are the base two logarithm of the luminance.
+pub struct ExposureStats {
+    /// The number of pixels with a luminance in each bin, evenly spaced in exposure values
+    /// over the range of the histogram. Pixels outside the range fall into the outer bins.
+    pub histogram: [usize; HISTOGRAM_BINS],
+    /// The number of pixels in the frame buffer.
+    pub pixels: usize,
+    /// The number of pixels without any light, which have no exposure value.
+    pub black: usize,
+    /// The number of pixels with a channel that is clipped without tone mapping.
+    pub clipped: usize,
+    /// The mean luminance of all pixels.
+    pub average: Float,
+    /// The mean exposure value of the pixels with light.
+    pub average_ev: Float,
+    /// The exposure values at the reported percentiles of the pixels with light.
+    pub percentiles: [(Float, Float); PERCENTILES.len()],
+}
+
+impl ExposureStats {
+    /// Analyzes the luminance of the pixels of a frame buffer.
+    pub fn new(fb: &FrameBuffer) -> Self {
+        let (low, high) = HISTOGRAM_RANGE;
+        let bin_size = (high - low) / HISTOGRAM_BINS as Float;
+
+        let mut histogram = [0; HISTOGRAM_BINS];
+        let mut evs = Vec::with_capacity(fb.data.len());
+        let mut clipped = 0;
+        let mut total = 0.0;
+
+        for color in fb.data.iter() {
+            if color.x >= 1.0 || color.y >= 1.0 || color.z >= 1.0 {
+                clipped += 1;
+            }
+
+            let luminance = color.luminance();
+            total += luminance.max(0.0);
+            if luminance <= 0.0 || !luminance.is_finite() {
+                continue;
+            }
+
+            let ev = luminance.log2();
+            let bin = ((ev - low) / bin_size).floor().max(0.0) as usize;
+            histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+            evs.push(ev);
+        }
+
+        evs.sort_by(Float::total_cmp);
+        let percentiles = PERCENTILES.map(|percentile| {
+            let rank = (percentile / 100.0 * evs.len() as Float) as usize;
+            (
+                percentile,
+                evs.get(rank.min(evs.len().saturating_sub(1)))
+                    .copied()
+                    .unwrap_or(Float::NEG_INFINITY),
+            )
+        });
+
+        let pixels = fb.data.len();
+        Self {
+            histogram,
+            pixels,
+            black: pixels - evs.len(),
+            clipped,
+            average: total / pixels.max(1) as Float,
+            average_ev: if evs.is_empty() {
+                Float::NEG_INFINITY
+            } else {
+                evs.iter().sum::<Float>() / evs.len() as Float
+            },
+            percentiles,
+        }
+    }
+
+    /// Returns the exposure compensation in stops that brings the mean exposure value
+    /// to middle grey, zero if the frame buffer has no light.
+    pub fn suggested_exposure(&self) -> Float {
+        if self.average_ev.is_finite() {
+            MIDDLE_GREY.log2() - self.average_ev
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the exposure values of the lower and upper edge of a bin of the histogram.
+    pub fn bin_range(bin: usize) -> (Float, Float) {
+        let (low, high) = HISTOGRAM_RANGE;
+        let bin_size = (high - low) / HISTOGRAM_BINS as Float;
+
+        (
+            low + bin as Float * bin_size,
+            low + (bin + 1) as Float * bin_size,
+        )
+    }
+
+    /// Formats the statistics as a JSON object.
+    pub fn to_json(&self) -> String {
+        // Infinite exposure values of frame buffers without light aren't valid JSON numbers.
+        let number = |value: Float| {
+            if value.is_finite() {
+                format!("{value}")
+            } else {
+                "null".to_string()
+            }
+        };
+
+        let mut json = String::new();
+        let _ = write!(
+            json,
+            "{{\"pixels\":{},\"black\":{},\"clipped\":{},\"average\":{},\"average_ev\":{},\
+             \"suggested_exposure\":{},\"percentiles\":{{",
+            self.pixels,
+            self.black,
+            self.clipped,
+            number(self.average),
+            number(self.average_ev),
+            number(self.suggested_exposure()),
+        );
+
+        for (idx, (percentile, ev)) in self.percentiles.iter().enumerate() {
+            let separator = if idx == 0 { "" } else { "," };
+            let _ = write!(json, "{separator}\"{percentile}\":{}", number(*ev));
+        }
+
+        let (low, high) = HISTOGRAM_RANGE;
+        let _ = write!(
+            json,
+            "}},\"histogram\":{{\"min_ev\":{low},\"max_ev\":{high},\"counts\":{:?}}}}}",
+            self.histogram
+        );
+
+        json
+    }
+
+    /// Draws the histogram into the lower left corner of an image, with the bins holding
+    /// the median in red and the pixels that clip without tone mapping as a bar on the right.
+    pub fn draw_overlay(&self, image: &mut ImageBuffer) {
+        let (width, height) = (
+            OVERLAY_SIZE.0.min(image.width),
+            OVERLAY_SIZE.1.min(image.height),
+        );
+        let top = image.height - height;
+        let bar_width = (width / (HISTOGRAM_BINS as u32 + 2)).max(1);
+
+        let median = self.percentiles[PERCENTILES.len() / 2].1;
+        let largest = self.histogram.iter().copied().max().unwrap_or(0).max(1);
+
+        // Darken the background so the histogram is readable on bright images.
+        for y in top..image.height {
+            for x in 0..width {
+                for channel in image[(x, y)].iter_mut() {
+                    *channel /= 4;
+                }
+            }
+        }
+
+        let columns = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(bin, &count)| {
+                let (low, high) = Self::bin_range(bin);
+                let color = if (low..high).contains(&median) {
+                    [255, 64, 64]
+                } else {
+                    [224, 224, 224]
+                };
+                (count as Float / largest as Float, color)
+            })
+            .chain(std::iter::once((0.0, [0; 3])))
+            .chain(std::iter::once((
+                self.clipped as Float / self.pixels.max(1) as Float,
+                [255, 192, 0],
+            )));
+
+        for (column, (fraction, color)) in columns.enumerate() {
+            let bar_height = (fraction * height as Float).ceil() as u32;
+            let x0 = column as u32 * bar_width;
+
+            for y in image.height - bar_height.min(height)..image.height {
+                for x in x0..(x0 + bar_width).min(width) {
+                    image[(x, y)].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
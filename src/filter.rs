@@ -0,0 +1,145 @@
+use crate::float::{consts::PI, Float};
+
+/// The number of steps of the tabulated distribution of filters without a closed form inverse.
+const TABLE_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The reconstruction filter of the pixels, which weights the samples of a pixel by their
+/// offset from its center. The filters are separable, and samples are distributed like the
+/// filter, so every sample keeps the same weight.
+pub enum PixelFilter {
+    /// Weights the samples within the radius equally. A radius of half a pixel
+    /// only covers the pixel itself.
+    Box { radius: Float },
+    /// Weights the samples falling off linearly until the radius.
+    Tent { radius: Float },
+    /// Weights the samples with a Gaussian of the given standard deviation,
+    /// cut off after three standard deviations.
+    Gaussian { sigma: Float },
+    /// Weights the samples with a Blackman-Harris window reaching zero at the radius,
+    /// which is about as sharp as a Gaussian with less blur at the same radius.
+    BlackmanHarris { radius: Float },
+}
+
+impl Default for PixelFilter {
+    fn default() -> Self {
+        PixelFilter::Box { radius: 0.5 }
+    }
+}
+
+impl PixelFilter {
+    /// Returns the distance from the pixel center beyond which the filter is zero.
+    pub fn radius(&self) -> Float {
+        match *self {
+            PixelFilter::Box { radius }
+            | PixelFilter::Tent { radius }
+            | PixelFilter::BlackmanHarris { radius } => radius,
+            PixelFilter::Gaussian { sigma } => 3.0 * sigma,
+        }
+    }
+
+    /// Evaluates the filter along one axis at the given offset from the pixel center.
+    pub fn evaluate(&self, x: Float) -> Float {
+        let radius = self.radius();
+        if x.abs() > radius {
+            return 0.0;
+        }
+
+        match *self {
+            PixelFilter::Box { .. } => 1.0,
+            PixelFilter::Tent { .. } => 1.0 - x.abs() / radius,
+            PixelFilter::Gaussian { sigma } => {
+                // Subtract the value at the radius, so the filter falls off to zero there.
+                let gaussian = |x: Float| (-x * x / (2.0 * sigma * sigma)).exp();
+                gaussian(x) - gaussian(radius)
+            }
+            PixelFilter::BlackmanHarris { .. } => {
+                let t = 2.0 * PI * (x / (2.0 * radius) + 0.5);
+                0.35875 - 0.48829 * t.cos() + 0.14128 * (2.0 * t).cos() - 0.01168 * (3.0 * t).cos()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Samples offsets from the pixel center distributed like a pixel filter.
+pub struct FilterSampler {
+    /// The filter the offsets are distributed like.
+    filter: PixelFilter,
+    /// The cumulative distribution over equal steps across the filter, for filters
+    /// that are sampled from a table.
+    cdf: Vec<Float>,
+}
+
+impl FilterSampler {
+    /// Creates a sampler for the filter, tabulating its distribution if needed.
+    pub fn new(filter: PixelFilter) -> Self {
+        let cdf = match filter {
+            PixelFilter::Box { .. } | PixelFilter::Tent { .. } => Vec::new(),
+            PixelFilter::Gaussian { .. } | PixelFilter::BlackmanHarris { .. } => {
+                let radius = filter.radius();
+                let step = 2.0 * radius / TABLE_SIZE as Float;
+
+                // Integrate the filter over each step with the midpoint rule.
+                let mut cdf = Vec::with_capacity(TABLE_SIZE + 1);
+                let mut total = 0.0;
+                cdf.push(0.0);
+                for idx in 0..TABLE_SIZE {
+                    let x = -radius + (idx as Float + 0.5) * step;
+                    total += filter.evaluate(x).max(0.0);
+                    cdf.push(total);
+                }
+
+                for value in cdf.iter_mut() {
+                    *value /= total;
+                }
+                cdf
+            }
+        };
+
+        Self { filter, cdf }
+    }
+
+    /// Returns the filter the offsets are distributed like.
+    pub fn filter(&self) -> PixelFilter {
+        self.filter
+    }
+
+    /// Maps a uniform random number in the range [0, 1) to an offset from the pixel center.
+    pub fn sample(&self, u: Float) -> Float {
+        match self.filter {
+            PixelFilter::Box { radius } => (2.0 * u - 1.0) * radius,
+            PixelFilter::Tent { radius } => {
+                // Invert the cumulative distribution of each half of the tent.
+                if u < 0.5 {
+                    radius * ((2.0 * u).sqrt() - 1.0)
+                } else {
+                    radius * (1.0 - (2.0 - 2.0 * u).sqrt())
+                }
+            }
+            PixelFilter::Gaussian { .. } | PixelFilter::BlackmanHarris { .. } => {
+                // Find the step containing the sample and interpolate linearly within it.
+                let idx = self
+                    .cdf
+                    .partition_point(|&value| value <= u)
+                    .clamp(1, TABLE_SIZE)
+                    - 1;
+                let (start, end) = (self.cdf[idx], self.cdf[idx + 1]);
+                let within = if end > start {
+                    (u - start) / (end - start)
+                } else {
+                    0.5
+                };
+
+                let radius = self.filter.radius();
+                -radius + (idx as Float + within) * 2.0 * radius / TABLE_SIZE as Float
+            }
+        }
+    }
+}
+
+impl Default for FilterSampler {
+    fn default() -> Self {
+        Self::new(PixelFilter::default())
+    }
+}
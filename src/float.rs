@@ -0,0 +1,125 @@
+use std::{
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// The floating point type used for rendering, which is `f32` with the `f32` feature.
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+
+/// The floating point type used for rendering, which is `f32` with the `f32` feature.
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+/// Mathematical constants of the floating point type used for rendering.
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+/// Mathematical constants of the floating point type used for rendering.
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+
+/// A conversion of numbers into the floating point type used for rendering.
+pub trait IntoFloat {
+    /// Converts the number into the floating point type used for rendering.
+    fn into_float(self) -> Float;
+}
+
+macro_rules! impl_into_float {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoFloat for $ty {
+                #[inline]
+                fn into_float(self) -> Float {
+                    self as Float
+                }
+            }
+        )*
+    };
+}
+
+impl_into_float!(f32, f64, i8, i16, i32, u8, u16, u32);
+
+#[inline]
+#[allow(clippy::unnecessary_cast)]
+/// Converts a number of the floating point type used for rendering into `f32`,
+/// for the file formats storing single precision.
+pub fn to_f32(value: Float) -> f32 {
+    value as f32
+}
+
+/// A number type the vector math can be performed with.
+pub trait Scalar:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// The additive identity.
+    const ZERO: Self;
+    /// The multiplicative identity.
+    const ONE: Self;
+    /// The largest value, which is larger than any finite value.
+    const INFINITY: Self;
+    /// The smallest value, which is smaller than any finite value.
+    const NEG_INFINITY: Self;
+
+    /// Converts a constant into the scalar type.
+    fn from_f64(value: f64) -> Self;
+
+    /// Returns the square root of the value.
+    fn sqrt(self) -> Self;
+
+    /// Returns the absolute value.
+    fn abs(self) -> Self;
+
+    /// Returns the smaller of the two values.
+    fn min(self, other: Self) -> Self;
+
+    /// Returns the larger of the two values.
+    fn max(self, other: Self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($($ty:ident),*) => {
+        $(
+            impl Scalar for $ty {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+                const INFINITY: Self = $ty::INFINITY;
+                const NEG_INFINITY: Self = $ty::NEG_INFINITY;
+
+                #[inline]
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+
+                #[inline]
+                fn sqrt(self) -> Self {
+                    $ty::sqrt(self)
+                }
+
+                #[inline]
+                fn abs(self) -> Self {
+                    $ty::abs(self)
+                }
+
+                #[inline]
+                fn min(self, other: Self) -> Self {
+                    $ty::min(self, other)
+                }
+
+                #[inline]
+                fn max(self, other: Self) -> Self {
+                    $ty::max(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar!(f32, f64);
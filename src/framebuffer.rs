@@ -0,0 +1,120 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    ops::{Index, IndexMut},
+};
+
+use crate::{
+    float::to_f32,
+    imgbuf::{colorspace::ColorSpace, hdr, ImageBuffer},
+    vector::Color,
+};
+
+#[derive(Debug, Clone)]
+/// A buffer of HDR colors, which holds the result of rendering before it is quantized.
+pub struct FrameBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub data: Box<[Color]>,
+}
+
+impl FrameBuffer {
+    /// Creates a new black frame buffer with the given dimensions.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![Color::ZERO; (width * height) as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Loads a frame buffer from a Radiance HDR file at the given path.
+    pub fn load_hdr<T: ToString>(path: T) -> Result<FrameBuffer, &'static str> {
+        let file = File::open(path.to_string()).map_err(|_| "failed to open file")?;
+
+        hdr::decode(BufReader::new(file))
+    }
+
+    /// Decodes a frame buffer from the bytes of a Radiance HDR file.
+    pub fn hdr_from_bytes(bytes: &[u8]) -> Result<FrameBuffer, &'static str> {
+        hdr::decode(bytes)
+    }
+
+    /// Saves the frame buffer to a PFM file at the given path, which keeps the exact values
+    /// including negative ones, like the offsets of motion vectors.
+    pub fn save_pfm<T: ToString>(&self, path: T) -> Result<(), &'static str> {
+        let path = path.to_string();
+        let _span = tracing::info_span!("save", path = %path).entered();
+
+        let file = File::create(&path).map_err(|_| "failed to create file")?;
+        self.save_pfm_to(BufWriter::new(file))
+    }
+
+    /// Encodes the frame buffer as a PFM into the writer, like stdout or an in-memory buffer.
+    pub fn save_pfm_to<W: Write>(&self, mut writer: W) -> Result<(), &'static str> {
+        // A negative scale marks the values as little endian.
+        write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)
+            .map_err(|_| "failed to write header")?;
+
+        // The rows are stored from the bottom up.
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let color = self[(x, y)];
+                for channel in [color.x, color.y, color.z] {
+                    writer
+                        .write_all(&to_f32(channel).to_le_bytes())
+                        .map_err(|_| "failed to write pixels")?;
+                }
+            }
+        }
+
+        writer.flush().map_err(|_| "failed to write pixels")
+    }
+
+    /// Converts the HDR frame buffer into an 8-bit image.
+    pub fn quantize(&self) -> ImageBuffer {
+        let mut image = ImageBuffer::new(self.width, self.height);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = self[(x, y)];
+
+                let pixel = &mut image[(x, y)];
+                pixel[0] = (color.x * 255.0).clamp(0.0, 255.0) as u8;
+                pixel[1] = (color.y * 255.0).clamp(0.0, 255.0) as u8;
+                pixel[2] = (color.z * 255.0).clamp(0.0, 255.0) as u8;
+            }
+        }
+
+        image
+    }
+
+    /// Converts the linear frame buffer into the given color space and quantizes it into an 8-bit image.
+    pub fn quantize_to(&self, color_space: &ColorSpace) -> ImageBuffer {
+        let convert = color_space.converter();
+        let transfer = color_space.transfer;
+
+        let mut converted = self.clone();
+        for color in converted.data.iter_mut() {
+            *color = convert(*color)
+                .clamp(0.0, 1.0)
+                .map(|value| transfer.encode(value));
+        }
+
+        converted.quantize()
+    }
+}
+
+impl Index<(u32, u32)> for FrameBuffer {
+    type Output = Color;
+
+    fn index(&self, (x, y): (u32, u32)) -> &Self::Output {
+        &self.data[(y * self.width + x) as usize]
+    }
+}
+
+impl IndexMut<(u32, u32)> for FrameBuffer {
+    fn index_mut(&mut self, (x, y): (u32, u32)) -> &mut Self::Output {
+        &mut self.data[(y * self.width + x) as usize]
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crate::{hittable::Hittable, objects::transformed::TransformedObject, transform::Transform};
+
+#[derive(Debug, Clone)]
+/// A node in the scene graph.
+pub enum Node {
+    /// An object placed in the space of its parent group.
+    Object(Arc<dyn Hittable>),
+    /// A nested group with its own transformation.
+    Group(Group),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// An ID for a child of a group, which stays valid when other children are removed.
+pub struct ChildId(usize);
+
+#[derive(Debug, Clone, Default)]
+/// A group of objects and nested groups that are transformed as a unit.
+pub struct Group {
+    /// The transformation from the group's space to its parent's space.
+    transform: Transform,
+    /// The children of the group with their ids.
+    children: Vec<(ChildId, Node)>,
+    /// The id of the next child added to the group.
+    next_id: usize,
+}
+
+impl Group {
+    /// Creates a new empty group with the given transformation.
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            transform,
+            children: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Adds an object to the group and returns its id.
+    pub fn add<H: Hittable + 'static>(&mut self, object: H) -> ChildId {
+        self.add_node(Node::Object(Arc::new(object)))
+    }
+
+    /// Adds a nested group to the group and returns its id.
+    pub fn add_group(&mut self, group: Group) -> ChildId {
+        self.add_node(Node::Group(group))
+    }
+
+    /// Adds a node to the group and returns its id.
+    pub fn add_node(&mut self, node: Node) -> ChildId {
+        let id = ChildId(self.next_id);
+        self.next_id += 1;
+        self.children.push((id, node));
+        id
+    }
+
+    /// Removes the child with the given id from the group.
+    pub fn remove(&mut self, id: ChildId) -> Option<Node> {
+        let idx = self.children.iter().position(|(child, _)| *child == id)?;
+        Some(self.children.remove(idx).1)
+    }
+
+    /// Returns the transformation of the group.
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// Sets the transformation of the group.
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    /// Returns the children of the group with their ids.
+    pub fn children(&self) -> &[(ChildId, Node)] {
+        &self.children
+    }
+
+    /// Returns the child with the given id mutably.
+    pub fn child_mut(&mut self, id: ChildId) -> Option<&mut Node> {
+        self.children
+            .iter_mut()
+            .find_map(|(child, node)| (*child == id).then_some(node))
+    }
+
+    /// Flattens the group into a list of objects placed in world space, each with
+    /// the ids of the children leading to it, which identify it across changes.
+    pub fn flatten(&self) -> Vec<(Vec<ChildId>, TransformedObject)> {
+        let mut objects = Vec::new();
+        self.flatten_into(&Transform::IDENTITY, &mut Vec::new(), &mut objects);
+        objects
+    }
+
+    /// Flattens the group below a parent transformation into the list of objects.
+    fn flatten_into(
+        &self,
+        parent: &Transform,
+        path: &mut Vec<ChildId>,
+        objects: &mut Vec<(Vec<ChildId>, TransformedObject)>,
+    ) {
+        let transform = *parent * self.transform;
+
+        for (id, child) in self.children.iter() {
+            path.push(*id);
+            match child {
+                Node::Object(object) => objects.push((
+                    path.clone(),
+                    TransformedObject::from_shared(object.clone(), transform),
+                )),
+                Node::Group(group) => group.flatten_into(&transform, path, objects),
+            }
+            path.pop();
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{cell::RefCell, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    float::{consts::PI, Float},
+    pdf::Pdf,
+    random::random_float,
+    vec3,
+    vector::{Point3, Vec3},
+};
+
+/// The number of cells of the grid over the scene along every axis.
+const GRID_RESOLUTION: usize = 12;
+
+/// The number of bins of the directional histograms along the cosine of the angle to the
+/// z axis and along the angle around it, so every bin covers the same solid angle.
+const COS_BINS: usize = 8;
+const PHI_BINS: usize = 16;
+const BINS: usize = COS_BINS * PHI_BINS;
+
+/// The fraction of the learned distributions that is spread over all directions,
+/// so the directions the earlier passes missed can still be sampled.
+const UNIFORM_FRACTION: Float = 0.1;
+
+/// The smallest number of records in a cell before its distribution is learned.
+const MIN_RECORDS: u32 = 16;
+
+thread_local! {
+    /// The guide of the render on this thread, which samples and records the light.
+    static GUIDE: RefCell<Option<PathGuide>> = const { RefCell::new(None) };
+}
+
+/// Guides the directions sampled at diffuse bounces on this thread with the guide,
+/// and records the light arriving there into it, until the guide is finished.
+pub fn start(guide: PathGuide) {
+    GUIDE.with(|current| *current.borrow_mut() = Some(guide));
+}
+
+/// Stops guiding on this thread and returns the guide with the recorded light.
+pub fn finish() -> Option<PathGuide> {
+    GUIDE.with(|current| current.borrow_mut().take())
+}
+
+/// Returns the learned distribution of the light arriving at a point, if there is a guide
+/// on this thread that learned it.
+pub(crate) fn pdf(point: Point3) -> Option<GuidePdf> {
+    GUIDE.with(|current| current.borrow().as_ref().and_then(|guide| guide.pdf(point)))
+}
+
+/// Records the light arriving at a point from a direction into the guide on this thread.
+/// The light is divided by the density it was sampled with.
+pub(crate) fn record(point: Point3, direction: Vec3, light: Float) {
+    GUIDE.with(|current| {
+        if let Some(guide) = current.borrow_mut().as_mut() {
+            guide.record(point, direction, light);
+        }
+    });
+}
+
+#[derive(Debug, Clone)]
+/// Learns from which directions light arrives in the cells of a grid over the scene, so
+/// later passes send more rays towards the light that reaches the surfaces indirectly.
+/// The light is recorded during a pass and learned at its end.
+pub struct PathGuide {
+    /// The bounds of the grid, points outside belong to the closest cell.
+    bounds: Aabb,
+    /// The cumulative probabilities of the bins of the learned distribution of every cell,
+    /// `None` for cells without enough records so far.
+    distributions: Vec<Option<Arc<[Float]>>>,
+    /// The light recorded in the bins of every cell during the current pass.
+    sums: Vec<[Float; BINS]>,
+    /// The number of records of every cell during the current pass.
+    counts: Vec<u32>,
+}
+
+impl PathGuide {
+    /// Creates a guide without any learned light over the bounds of the scene.
+    pub fn new(bounds: Aabb) -> Self {
+        let cells = GRID_RESOLUTION * GRID_RESOLUTION * GRID_RESOLUTION;
+
+        Self {
+            bounds: bounds.pad(0.0001),
+            distributions: vec![None; cells],
+            sums: vec![[0.0; BINS]; cells],
+            counts: vec![0; cells],
+        }
+    }
+
+    /// Records the light arriving at a point from a direction, divided by the density
+    /// the direction was sampled with.
+    pub fn record(&mut self, point: Point3, direction: Vec3, light: Float) {
+        if !light.is_finite() || light < 0.0 {
+            return;
+        }
+
+        let cell = self.cell(point);
+        self.sums[cell][bin(direction)] += light;
+        self.counts[cell] += 1;
+    }
+
+    /// Learns the light recorded during the pass, replacing the distributions of the cells
+    /// with enough records, and clears the records for the next pass.
+    pub fn update(&mut self) {
+        for (cell, sums) in self.sums.iter_mut().enumerate() {
+            let total: Float = sums.iter().sum();
+
+            if self.counts[cell] >= MIN_RECORDS && total > 0.0 {
+                let mut cumulative = 0.0;
+                let cdf = sums
+                    .iter()
+                    .map(|&sum| {
+                        cumulative += (1.0 - UNIFORM_FRACTION) * sum / total
+                            + UNIFORM_FRACTION / BINS as Float;
+                        cumulative
+                    })
+                    .collect();
+                self.distributions[cell] = Some(cdf);
+            }
+
+            *sums = [0.0; BINS];
+            self.counts[cell] = 0;
+        }
+    }
+
+    /// Returns the learned distribution of the light arriving at a point, if there is one.
+    pub fn pdf(&self, point: Point3) -> Option<GuidePdf> {
+        self.distributions[self.cell(point)]
+            .clone()
+            .map(|cdf| GuidePdf { cdf })
+    }
+
+    /// Returns the index of the cell containing a point.
+    fn cell(&self, point: Point3) -> usize {
+        let index = |axis: usize, value: Float| {
+            let interval = self.bounds.component(axis);
+            let t = (value - interval.start) / interval.size();
+            ((t * GRID_RESOLUTION as Float).max(0.0) as usize).min(GRID_RESOLUTION - 1)
+        };
+
+        let (x, y, z) = (index(0, point.x), index(1, point.y), index(2, point.z));
+        (z * GRID_RESOLUTION + y) * GRID_RESOLUTION + x
+    }
+}
+
+#[derive(Debug)]
+/// A density following the light learned by a path guide in a cell.
+pub struct GuidePdf {
+    /// The cumulative probabilities of the bins.
+    cdf: Arc<[Float]>,
+}
+
+impl Pdf for GuidePdf {
+    fn value(&self, dir: Vec3) -> Float {
+        let bin = bin(dir);
+        let previous = if bin == 0 { 0.0 } else { self.cdf[bin - 1] };
+
+        // Every bin covers the same solid angle.
+        (self.cdf[bin] - previous) * BINS as Float / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vec3 {
+        let u = random_float() * self.cdf[BINS - 1];
+        let bin = self.cdf.partition_point(|&c| c <= u).min(BINS - 1);
+
+        let cos_theta =
+            -1.0 + 2.0 * ((bin / PHI_BINS) as Float + random_float()) / COS_BINS as Float;
+        let phi = 2.0 * PI * ((bin % PHI_BINS) as Float + random_float()) / PHI_BINS as Float - PI;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+        vec3!(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta)
+    }
+}
+
+/// Returns the bin of the directional histograms a direction falls into.
+fn bin(dir: Vec3) -> usize {
+    let dir = dir.unit();
+    let cos_bin = ((dir.z + 1.0) / 2.0 * COS_BINS as Float) as usize;
+    let phi = dir.y.atan2(dir.x) + PI;
+    let phi_bin = (phi / (2.0 * PI) * PHI_BINS as Float) as usize;
+
+    cos_bin.min(COS_BINS - 1) * PHI_BINS + phi_bin.min(PHI_BINS - 1)
+}
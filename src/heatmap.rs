@@ -0,0 +1,115 @@
+use crate::{float::Float, imgbuf::ImageBuffer};
+
+/// The percentile of the variances that is shown at the top of the color ramp,
+/// so a few fireflies don't squash all other pixels to black.
+const VARIANCE_PERCENTILE: Float = 99.0;
+
+/// The orders of magnitude of variances below the top of the color ramp that are shown,
+/// smaller variances are black.
+const VARIANCE_DECADES: Float = 4.0;
+
+#[derive(Debug, Clone)]
+/// The variance of every pixel of a render, which shows where the image is still noisy.
+pub struct VarianceHeatmap {
+    pub width: u32,
+    pub height: u32,
+    /// The variance of the mean luminance of every pixel, which falls with more samples.
+    pub variances: Vec<Float>,
+}
+
+impl VarianceHeatmap {
+    /// Creates a heatmap of the given dimensions without any samples.
+    /// Panics if the heatmap has more pixels than fit into memory.
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .expect("Heatmap is too large");
+
+        Self {
+            width,
+            height,
+            variances: vec![0.0; len],
+        }
+    }
+
+    /// Stores the variance of the mean luminance of the samples of a pixel.
+    pub fn set(&mut self, x: u32, y: u32, samples: &SampleVariance) {
+        let idx = y as usize * self.width as usize + x as usize;
+        self.variances[idx] = samples.variance() / samples.count.max(1) as Float;
+    }
+
+    /// Returns a false color image of the variances on a logarithmic scale, from black for
+    /// small variances to white for the largest ones.
+    pub fn variance_image(&self) -> ImageBuffer {
+        let mut sorted: Vec<Float> = self
+            .variances
+            .iter()
+            .copied()
+            .filter(|&variance| variance > 0.0)
+            .collect();
+        sorted.sort_by(Float::total_cmp);
+
+        if sorted.is_empty() {
+            return self.image(|_| 0.0);
+        }
+        let rank = (VARIANCE_PERCENTILE / 100.0 * sorted.len() as Float) as usize;
+        let high = sorted[rank.min(sorted.len() - 1)].log10();
+
+        self.image(|idx| {
+            let variance = self.variances[idx];
+            if variance <= 0.0 {
+                0.0
+            } else {
+                1.0 - (high - variance.log10()) / VARIANCE_DECADES
+            }
+        })
+    }
+
+    /// Creates an image with the false color of the value of every pixel.
+    fn image<F: Fn(usize) -> Float>(&self, value: F) -> ImageBuffer {
+        let mut image = ImageBuffer::new(self.width, self.height);
+        for (idx, pixel) in image.data.chunks_mut(3).enumerate() {
+            pixel.copy_from_slice(&heat_color(value(idx)));
+        }
+
+        image
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// The running mean and variance of the luminance of the samples of a pixel, after Welford.
+pub struct SampleVariance {
+    /// The number of samples.
+    pub count: u32,
+    /// The mean luminance of the samples.
+    mean: Float,
+    /// The sum of the squared differences of the samples from the mean.
+    squared_deviations: Float,
+}
+
+impl SampleVariance {
+    /// Adds the luminance of a sample.
+    pub fn add(&mut self, luminance: Float) {
+        self.count += 1;
+        let delta = luminance - self.mean;
+        self.mean += delta / self.count as Float;
+        self.squared_deviations += delta * (luminance - self.mean);
+    }
+
+    /// Returns the unbiased variance of the luminance of the samples.
+    pub fn variance(&self) -> Float {
+        if self.count < 2 {
+            return 0.0;
+        }
+
+        self.squared_deviations / (self.count - 1) as Float
+    }
+}
+
+/// Maps a value in the range [0, 1] to a color ramping from black over red and yellow to white.
+pub fn heat_color(value: Float) -> [u8; 3] {
+    let value = value.clamp(0.0, 1.0) * 3.0;
+    let channel = |start: Float| ((value - start).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    [channel(0.0), channel(1.0), channel(2.0)]
+}
@@ -2,13 +2,61 @@ use std::fmt::Debug;
 
 use crate::{
     aabb::Aabb,
+    float::Float,
     interval::Interval,
     ray::{Intersection, Ray},
+    resources::MaterialId,
+    vec3,
+    vector::{Point3, Vec3},
 };
 
 /// A trait for objects that can be hit by a ray.
 pub trait Hittable: Debug + Send + Sync {
     fn hit(&self, r: &Ray, time: Interval) -> Option<Intersection>;
 
+    /// Returns whether the ray hits the object anywhere in the interval, like for shadow rays,
+    /// which don't need the closest intersection or its normal and texture coordinates.
+    fn occluded(&self, r: &Ray, time: Interval) -> bool {
+        self.hit(r, time).is_some()
+    }
+
     fn bounding_box(&self) -> Aabb;
+
+    /// Returns the probability density, with respect to solid angle, of sampling
+    /// the given direction from the origin at the given time with [`Hittable::random`].
+    fn pdf_value(&self, _origin: Point3, _dir: Vec3, _time: Float) -> Float {
+        0.0
+    }
+
+    /// Samples a random direction from the origin towards the object at the given time.
+    fn random(&self, _origin: Point3, _time: Float) -> Vec3 {
+        vec3!(1, 0, 0)
+    }
+
+    /// Returns the material of the object if directions towards it can be sampled,
+    /// so the scene registers the object as a light when the material is emissive.
+    fn light_material(&self) -> Option<MaterialId> {
+        None
+    }
+
+    /// Returns how far a point on the surface at the given time moves until the shutter
+    /// closes, which animations stretch over a frame.
+    fn motion(&self, _point: Point3, _time: Float) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    /// Returns a short name of the kind of object, which scene statistics count objects by.
+    fn kind(&self) -> &'static str {
+        "custom"
+    }
+
+    /// Returns the number of triangles the object is made of.
+    fn triangle_count(&self) -> usize {
+        0
+    }
+
+    /// Returns an estimate of the bytes of memory used by the object and the data it owns.
+    fn memory_size(&self) -> usize {
+        size_of_val(self)
+    }
 }
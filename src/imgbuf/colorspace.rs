@@ -0,0 +1,339 @@
+use crate::{
+    float::Float,
+    vector::{linear_to_srgb, srgb_to_linear, Color},
+};
+
+/// A 3x3 matrix, stored row by row.
+type Matrix3 = [[Float; 3]; 3];
+
+/// The white point of the ICC profile connection space.
+const D50: (Float, Float) = (0.34567, 0.35850);
+
+/// The Bradford cone response matrix used for chromatic adaptation.
+const BRADFORD: Matrix3 = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The chromaticity coordinates of the primaries and the white point of an RGB color space.
+pub struct Chromaticities {
+    /// The white point.
+    pub white: (Float, Float),
+    /// The red primary.
+    pub red: (Float, Float),
+    /// The green primary.
+    pub green: (Float, Float),
+    /// The blue primary.
+    pub blue: (Float, Float),
+}
+
+impl Chromaticities {
+    /// The primaries of sRGB and Rec. 709, which the renderer works in.
+    pub const REC709: Self = Self {
+        white: (0.31270, 0.32900),
+        red: (0.64000, 0.33000),
+        green: (0.30000, 0.60000),
+        blue: (0.15000, 0.06000),
+    };
+
+    /// The primaries of Display P3, with a D65 white point.
+    pub const DISPLAY_P3: Self = Self {
+        white: (0.31270, 0.32900),
+        red: (0.68000, 0.32000),
+        green: (0.26500, 0.69000),
+        blue: (0.15000, 0.06000),
+    };
+
+    /// The primaries of Rec. 2020.
+    pub const REC2020: Self = Self {
+        white: (0.31270, 0.32900),
+        red: (0.70800, 0.29200),
+        green: (0.17000, 0.79700),
+        blue: (0.13100, 0.04600),
+    };
+
+    /// Returns the matrix converting linear RGB in these primaries to CIE XYZ.
+    fn to_xyz(self) -> Matrix3 {
+        let xyz = |(x, y): (Float, Float)| [x / y, 1.0, (1.0 - x - y) / y];
+
+        let [r, g, b] = [xyz(self.red), xyz(self.green), xyz(self.blue)];
+        let primaries = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+
+        // Scale the primaries, so they add up to the white point.
+        let scale = mul_vec(&invert(&primaries), xyz(self.white));
+        let mut matrix = primaries;
+        for row in matrix.iter_mut() {
+            for (value, scale) in row.iter_mut().zip(scale) {
+                *value *= scale;
+            }
+        }
+
+        matrix
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The function encoding linear values for storage.
+pub enum TransferFunction {
+    /// The values are stored linearly.
+    Linear,
+    /// The values are raised to the inverse of the gamma.
+    Gamma(Float),
+    /// The piecewise sRGB curve.
+    Srgb,
+}
+
+impl TransferFunction {
+    /// Encodes a linear value in [0, 1].
+    pub fn encode(&self, value: Float) -> Float {
+        match self {
+            Self::Linear => value,
+            Self::Gamma(gamma) => value.max(0.0).powf(1.0 / gamma),
+            Self::Srgb => linear_to_srgb(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An RGB color space images are stored in.
+pub struct ColorSpace {
+    /// The primaries and white point.
+    pub chromaticities: Chromaticities,
+    /// The encoding of the stored values.
+    pub transfer: TransferFunction,
+}
+
+impl ColorSpace {
+    /// The sRGB color space.
+    pub const SRGB: Self = Self {
+        chromaticities: Chromaticities::REC709,
+        transfer: TransferFunction::Srgb,
+    };
+
+    /// The sRGB primaries without any encoding, which is what the renderer produces.
+    pub const LINEAR_SRGB: Self = Self {
+        chromaticities: Chromaticities::REC709,
+        transfer: TransferFunction::Linear,
+    };
+
+    /// The Display P3 color space, with the sRGB curve.
+    pub const DISPLAY_P3: Self = Self {
+        chromaticities: Chromaticities::DISPLAY_P3,
+        transfer: TransferFunction::Srgb,
+    };
+
+    /// The sRGB primaries with a pure 2.2 gamma, which images were tagged with before.
+    pub const GAMMA_22: Self = Self {
+        chromaticities: Chromaticities::REC709,
+        transfer: TransferFunction::Gamma(2.2),
+    };
+
+    /// Returns true if this is exactly the sRGB color space.
+    pub fn is_srgb(&self) -> bool {
+        *self == Self::SRGB
+    }
+
+    /// Returns the gamma stored in the PNG gAMA chunk, which approximates the transfer function.
+    pub fn png_gamma(&self) -> Float {
+        match self.transfer {
+            TransferFunction::Linear => 1.0,
+            TransferFunction::Gamma(gamma) => 1.0 / gamma,
+            TransferFunction::Srgb => 1.0 / 2.2,
+        }
+    }
+
+    /// Returns a function converting linear Rec. 709 colors, as rendered, into this color space.
+    /// The transfer function is not applied.
+    pub fn converter(&self) -> impl Fn(Color) -> Color {
+        let source = Chromaticities::REC709;
+        let target = self.chromaticities;
+
+        let adapt = adaptation(source.white, target.white);
+        let matrix = mul(&invert(&target.to_xyz()), &mul(&adapt, &source.to_xyz()));
+
+        move |color| {
+            let [r, g, b] = mul_vec(&matrix, [color.x, color.y, color.z]);
+            Color { x: r, y: g, z: b }
+        }
+    }
+
+    /// Creates an ICC version 2 display profile describing this color space.
+    pub fn icc_profile(&self, description: &str) -> Vec<u8> {
+        // The colorants are adapted to the D50 white of the profile connection space.
+        let colorants = mul(
+            &adaptation(self.chromaticities.white, D50),
+            &self.chromaticities.to_xyz(),
+        );
+        let column = |idx: usize| [colorants[0][idx], colorants[1][idx], colorants[2][idx]];
+        let d50 = [D50.0 / D50.1, 1.0, (1.0 - D50.0 - D50.1) / D50.1];
+
+        let trc = match self.transfer {
+            TransferFunction::Linear => curve(&[]),
+            TransferFunction::Gamma(gamma) => curve(&[(gamma * 256.0).round() as u16]),
+            TransferFunction::Srgb => curve(
+                &(0..1024)
+                    .map(|i| {
+                        let value = srgb_to_linear(i as Float / 1023.0);
+                        (value * 65535.0).round() as u16
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        };
+
+        let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+            (b"desc", text_description(description)),
+            (b"cprt", text("No copyright, use freely")),
+            (b"wtpt", xyz(d50)),
+            (b"rXYZ", xyz(column(0))),
+            (b"gXYZ", xyz(column(1))),
+            (b"bXYZ", xyz(column(2))),
+            (b"rTRC", trc.clone()),
+            (b"gTRC", trc.clone()),
+            (b"bTRC", trc),
+        ];
+
+        // Lay out the tag data after the header and the tag table, aligned to four bytes.
+        let mut offset = 128 + 4 + tags.len() * 12;
+        let mut table = Vec::new();
+        let mut data = Vec::new();
+        for (signature, tag) in tags.iter() {
+            table.extend_from_slice(*signature);
+            table.extend_from_slice(&(offset as u32).to_be_bytes());
+            table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+
+            data.extend_from_slice(tag);
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            offset = 128 + 4 + tags.len() * 12 + data.len();
+        }
+
+        let size = 128 + 4 + table.len() + data.len();
+
+        let mut profile = Vec::with_capacity(size);
+        profile.extend_from_slice(&(size as u32).to_be_bytes());
+        profile.extend_from_slice(&[0; 4]); // preferred CMM
+        profile.extend_from_slice(&0x02100000u32.to_be_bytes()); // version 2.1
+        profile.extend_from_slice(b"mntrRGB XYZ ");
+        profile.extend_from_slice(&[0; 12]); // creation date
+        profile.extend_from_slice(b"acsp");
+        profile.extend_from_slice(&[0; 24]); // platform, flags, manufacturer, model, attributes
+        profile.extend_from_slice(&0u32.to_be_bytes()); // perceptual rendering intent
+        profile.extend_from_slice(&xyz(d50)[8..]); // illuminant of the connection space
+        profile.extend_from_slice(&[0; 48]); // creator and reserved
+        profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        profile.extend_from_slice(&table);
+        profile.extend_from_slice(&data);
+
+        profile
+    }
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        Self::GAMMA_22
+    }
+}
+
+/// Encodes a number as an ICC s15Fixed16 number.
+fn s15_fixed16(value: Float) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+/// Creates an ICC XYZ tag.
+fn xyz(values: [Float; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for value in values {
+        tag.extend_from_slice(&s15_fixed16(value));
+    }
+    tag
+}
+
+/// Creates an ICC curve tag, which is linear without entries, a gamma with one entry and a table otherwise.
+fn curve(entries: &[u16]) -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for entry in entries {
+        tag.extend_from_slice(&entry.to_be_bytes());
+    }
+    tag
+}
+
+/// Creates an ICC text tag.
+fn text(text: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend(text.bytes().filter(u8::is_ascii));
+    tag.push(0);
+    tag
+}
+
+/// Creates an ICC version 2 text description tag.
+fn text_description(text: &str) -> Vec<u8> {
+    let ascii: Vec<u8> = text.bytes().filter(u8::is_ascii).collect();
+
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(ascii.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(&ascii);
+    tag.push(0);
+    tag.extend_from_slice(&[0; 8]); // no unicode description
+    tag.extend_from_slice(&[0; 3 + 67]); // no script code description
+    tag
+}
+
+/// Returns the Bradford matrix adapting XYZ colors from one white point to another.
+fn adaptation(from: (Float, Float), to: (Float, Float)) -> Matrix3 {
+    let xyz = |(x, y): (Float, Float)| [x / y, 1.0, (1.0 - x - y) / y];
+
+    let from = mul_vec(&BRADFORD, xyz(from));
+    let to = mul_vec(&BRADFORD, xyz(to));
+
+    let scale = [
+        [to[0] / from[0], 0.0, 0.0],
+        [0.0, to[1] / from[1], 0.0],
+        [0.0, 0.0, to[2] / from[2]],
+    ];
+
+    mul(&invert(&BRADFORD), &mul(&scale, &BRADFORD))
+}
+
+/// Multiplies two matrices.
+fn mul(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|row| {
+        std::array::from_fn(|col| (0..3).map(|k| a[row][k] * b[k][col]).sum())
+    })
+}
+
+/// Multiplies a matrix with a column vector.
+fn mul_vec(m: &Matrix3, v: [Float; 3]) -> [Float; 3] {
+    std::array::from_fn(|row| m[row][0] * v[0] + m[row][1] * v[1] + m[row][2] * v[2])
+}
+
+/// Inverts a matrix, which must not be singular.
+fn invert(m: &Matrix3) -> Matrix3 {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2),
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2),
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+
+    let det = m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+
+    adjugate.map(|row| row.map(|value| value / det))
+}
@@ -0,0 +1,220 @@
+use super::ImageBuffer;
+use crate::{float::Float, heatmap::heat_color};
+
+/// The standard deviation in pixels of the Gaussian window SSIM compares the images in.
+const SSIM_SIGMA: Float = 1.5;
+
+/// The radius in pixels of the Gaussian window, beyond which its weights are negligible.
+const SSIM_RADIUS: usize = 5;
+
+/// The constants stabilizing the divisions of SSIM in dark and flat areas,
+/// for luma in the range [0, 1].
+const SSIM_C1: Float = 0.01 * 0.01;
+const SSIM_C2: Float = 0.03 * 0.03;
+
+#[derive(Debug)]
+/// Measures of how closely an image matches a reference image.
+pub struct Metrics {
+    /// The peak signal to noise ratio over all channels in decibels,
+    /// infinite if the images are identical.
+    pub psnr: Float,
+    /// The mean structural similarity of the luma of the images, one if they are identical.
+    pub ssim: Float,
+    /// An image of the dissimilarity of every pixel, from black where the images match
+    /// over red and yellow to white where their structure differs completely.
+    pub diff: ImageBuffer,
+}
+
+#[derive(Debug)]
+/// The result of comparing an image against a reference image.
+pub struct Comparison {
+    /// The largest difference of any channel of any pixel.
+    pub max_difference: u8,
+    /// The root mean square error over all channels, in [0, 1].
+    pub rmse: Float,
+    /// The number of pixels with a channel differing by more than the tolerance.
+    pub mismatched_pixels: usize,
+    /// An image of the differences, with mismatched pixels marked red, if it was requested.
+    pub diff: Option<ImageBuffer>,
+}
+
+impl Comparison {
+    /// Returns true if no pixel differs by more than the tolerance.
+    pub fn passed(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares an image against a reference image, allowing every channel to differ by the tolerance.
+/// If `diff` is true, an image of the differences is created, where matching pixels show their
+/// difference amplified in gray and mismatched pixels are red.
+pub fn compare(
+    reference: &ImageBuffer,
+    image: &ImageBuffer,
+    tolerance: u8,
+    diff: bool,
+) -> Result<Comparison, &'static str> {
+    check_dimensions(reference, image)?;
+
+    let mut max_difference = 0;
+    let mut squared_error = 0.0;
+    let mut mismatched_pixels = 0;
+    let mut diff_image = diff.then(|| ImageBuffer::new(image.width, image.height));
+
+    let pixels = reference.data.chunks(3).zip(image.data.chunks(3));
+    for (idx, (expected, actual)) in pixels.enumerate() {
+        let mut pixel_difference = 0;
+
+        for (&expected, &actual) in expected.iter().zip(actual) {
+            let difference = expected.abs_diff(actual);
+            pixel_difference = pixel_difference.max(difference);
+
+            let error = difference as Float / 255.0;
+            squared_error += error * error;
+        }
+
+        max_difference = max_difference.max(pixel_difference);
+
+        let mismatched = pixel_difference > tolerance;
+        if mismatched {
+            mismatched_pixels += 1;
+        }
+
+        if let Some(diff_image) = diff_image.as_mut() {
+            let pixel = &mut diff_image.data[idx * 3..idx * 3 + 3];
+            if mismatched {
+                pixel.copy_from_slice(&[255, 0, 0]);
+            } else {
+                pixel.fill(pixel_difference.saturating_mul(16));
+            }
+        }
+    }
+
+    let rmse = (squared_error / reference.data.len().max(1) as Float).sqrt();
+
+    Ok(Comparison {
+        max_difference,
+        rmse,
+        mismatched_pixels,
+        diff: diff_image,
+    })
+}
+
+/// Compares an image against a reference image of the same dimensions.
+pub fn metrics(reference: &ImageBuffer, image: &ImageBuffer) -> Result<Metrics, &'static str> {
+    let ssim_map = ssim_map(reference, image)?;
+
+    let mut diff = ImageBuffer::new(image.width, image.height);
+    for (pixel, &similarity) in diff.data.chunks_mut(3).zip(ssim_map.iter()) {
+        pixel.copy_from_slice(&heat_color(1.0 - similarity));
+    }
+
+    Ok(Metrics {
+        psnr: psnr(reference, image)?,
+        ssim: ssim_map.iter().sum::<Float>() / ssim_map.len().max(1) as Float,
+        diff,
+    })
+}
+
+/// Returns the peak signal to noise ratio of the image against the reference in decibels,
+/// infinite if the images are identical.
+pub fn psnr(reference: &ImageBuffer, image: &ImageBuffer) -> Result<Float, &'static str> {
+    check_dimensions(reference, image)?;
+
+    let squared_error: Float = reference
+        .data
+        .iter()
+        .zip(image.data.iter())
+        .map(|(&expected, &actual)| (expected.abs_diff(actual) as Float).powi(2))
+        .sum();
+    let mse = squared_error / reference.data.len().max(1) as Float;
+
+    Ok(10.0 * (255.0 * 255.0 / mse).log10())
+}
+
+/// Returns the mean structural similarity of the luma of the image and the reference,
+/// after Wang et al., "Image Quality Assessment: From Error Visibility to Structural Similarity".
+pub fn ssim(reference: &ImageBuffer, image: &ImageBuffer) -> Result<Float, &'static str> {
+    let map = ssim_map(reference, image)?;
+    Ok(map.iter().sum::<Float>() / map.len().max(1) as Float)
+}
+
+/// Returns the structural similarity of every pixel, comparing the means, variances and
+/// covariance of the luma of both images in a Gaussian window around the pixel.
+pub fn ssim_map(reference: &ImageBuffer, image: &ImageBuffer) -> Result<Vec<Float>, &'static str> {
+    check_dimensions(reference, image)?;
+
+    let (width, height) = (image.width as usize, image.height as usize);
+    let x = luma(reference);
+    let y = luma(image);
+
+    let blur = |values: Vec<Float>| gaussian_blur(&values, width, height);
+    let mean_x = blur(x.clone());
+    let mean_y = blur(y.clone());
+    let mean_xx = blur(x.iter().map(|x| x * x).collect());
+    let mean_yy = blur(y.iter().map(|y| y * y).collect());
+    let mean_xy = blur(x.iter().zip(y.iter()).map(|(x, y)| x * y).collect());
+
+    Ok((0..width * height)
+        .map(|idx| {
+            let (mx, my) = (mean_x[idx], mean_y[idx]);
+            let var_x = mean_xx[idx] - mx * mx;
+            let var_y = mean_yy[idx] - my * my;
+            let cov = mean_xy[idx] - mx * my;
+
+            ((2.0 * mx * my + SSIM_C1) * (2.0 * cov + SSIM_C2))
+                / ((mx * mx + my * my + SSIM_C1) * (var_x + var_y + SSIM_C2))
+        })
+        .collect())
+}
+
+/// Returns an error if the images have different dimensions.
+fn check_dimensions(reference: &ImageBuffer, image: &ImageBuffer) -> Result<(), &'static str> {
+    if reference.width != image.width || reference.height != image.height {
+        return Err("images have different dimensions");
+    }
+
+    Ok(())
+}
+
+/// Returns the Rec. 709 luma of every pixel in the range [0, 1].
+fn luma(image: &ImageBuffer) -> Vec<Float> {
+    image
+        .data
+        .chunks(3)
+        .map(|pixel| {
+            (0.2126 * pixel[0] as Float + 0.7152 * pixel[1] as Float + 0.0722 * pixel[2] as Float)
+                / 255.0
+        })
+        .collect()
+}
+
+/// Blurs the values of an image with the Gaussian window of SSIM, first along the rows and
+/// then along the columns. The window is cut off at the edges and its weights renormalized.
+fn gaussian_blur(values: &[Float], width: usize, height: usize) -> Vec<Float> {
+    let radius = SSIM_RADIUS as isize;
+    let weights: Vec<Float> = (-radius..=radius)
+        .map(|d| (-((d * d) as Float) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp())
+        .collect();
+
+    let pass = |values: &[Float], stride: usize, len: usize, lines: usize, step: usize| {
+        let mut result = vec![0.0; values.len()];
+        for line in 0..lines {
+            for i in 0..len {
+                let (mut sum, mut total) = (0.0, 0.0);
+                for (offset, weight) in (-radius..=radius).zip(weights.iter()) {
+                    let j = i as isize + offset;
+                    if j >= 0 && (j as usize) < len {
+                        sum += weight * values[line * step + j as usize * stride];
+                        total += weight;
+                    }
+                }
+                result[line * step + i * stride] = sum / total;
+            }
+        }
+        result
+    };
+
+    let rows = pass(values, 1, width, height, width);
+    pass(&rows, width, height, width, 1)
+}
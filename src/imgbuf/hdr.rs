@@ -0,0 +1,186 @@
+use std::io::BufRead;
+
+use crate::{float::Float, framebuffer::FrameBuffer, vec3, vector::Color};
+
+/// Decodes a Radiance RGBE image into a frame buffer of linear colors.
+/// See https://www.graphics.cornell.edu/~bjw/rgbe.html.
+pub fn decode<R: BufRead>(reader: R) -> Result<FrameBuffer, &'static str> {
+    let mut scanlines = Scanlines::new(reader)?;
+
+    let mut fb = FrameBuffer::new(scanlines.width, scanlines.height);
+    let mut scanline = vec![0u8; scanlines.width as usize * 4];
+
+    for y in 0..scanlines.height {
+        scanlines.next(&mut scanline)?;
+
+        for x in 0..scanlines.width {
+            let idx = x as usize * 4;
+            fb[(x, y)] = rgbe_to_color(&scanline[idx..idx + 4]);
+        }
+    }
+
+    Ok(fb)
+}
+
+/// The scanlines of a Radiance RGBE image, decoded one after another from the top.
+pub(crate) struct Scanlines {
+    /// The width of the image in pixels.
+    pub width: u32,
+    /// The height of the image in pixels.
+    pub height: u32,
+    /// The encoded scanlines.
+    data: Vec<u8>,
+    /// The offset of the next scanline in the data.
+    offset: usize,
+}
+
+impl Scanlines {
+    /// Reads the header of the image.
+    pub fn new<R: BufRead>(mut reader: R) -> Result<Self, &'static str> {
+        // Read the header, which ends with an empty line.
+        let mut line = String::new();
+        let mut first = true;
+        loop {
+            line.clear();
+            reader
+                .read_line(&mut line)
+                .map_err(|_| "failed to read image info")?;
+
+            let trimmed = line.trim_end();
+            if first {
+                if !trimmed.starts_with("#?") {
+                    return Err("not a Radiance HDR image");
+                }
+                first = false;
+            } else if trimmed.is_empty() {
+                break;
+            } else if let Some(format) = trimmed.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err("unsupported HDR pixel format");
+                }
+            }
+        }
+
+        // Read the resolution line, only the standard orientation is supported.
+        line.clear();
+        reader
+            .read_line(&mut line)
+            .map_err(|_| "failed to read image info")?;
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (height, width) = match parts.as_slice() {
+            ["-Y", height, "+X", width] => (
+                height
+                    .parse::<u32>()
+                    .map_err(|_| "invalid HDR resolution")?,
+                width.parse::<u32>().map_err(|_| "invalid HDR resolution")?,
+            ),
+            _ => return Err("unsupported HDR orientation"),
+        };
+
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .map_err(|_| "failed to read image data")?;
+
+        // Frame buffers count their pixels in 32 bits, and even run-length encoded scanlines
+        // take a few bytes for every 127 pixels, so the data must be able to hold the image
+        // before its pixels are allocated.
+        let pixels = (width as usize).checked_mul(height as usize);
+        if pixels.is_none_or(|pixels| pixels > u32::MAX as usize) {
+            return Err("HDR image is too large");
+        }
+        let min_scanline = (4 * width as usize).min(4 + 8 * (width as usize).div_ceil(127));
+        if min_scanline.saturating_mul(height as usize) > data.len() {
+            return Err("failed to read image data");
+        }
+
+        Ok(Self {
+            width,
+            height,
+            data,
+            offset: 0,
+        })
+    }
+
+    /// Decodes the next scanline into RGBE pixels.
+    pub fn next(&mut self, scanline: &mut [u8]) -> Result<(), &'static str> {
+        let src = &self.data[self.offset..];
+        let rest = read_scanline(src, scanline)?;
+        self.offset += src.len() - rest.len();
+        Ok(())
+    }
+}
+
+/// Reads a single scanline of RGBE pixels into `scanline`, returning the remaining input.
+fn read_scanline<'a>(src: &'a [u8], scanline: &mut [u8]) -> Result<&'a [u8], &'static str> {
+    let width = scanline.len() / 4;
+
+    // Scanlines that don't start with the marker of the adaptive
+    // run-length encoding are stored as flat RGBE pixels.
+    let is_rle = (8..0x8000).contains(&width)
+        && src.len() >= 4
+        && src[0] == 2
+        && src[1] == 2
+        && ((src[2] as usize) << 8 | src[3] as usize) == width;
+
+    if !is_rle {
+        let data = src
+            .get(..scanline.len())
+            .ok_or("failed to read image data")?;
+        scanline.copy_from_slice(data);
+        return Ok(&src[scanline.len()..]);
+    }
+
+    let mut src = &src[4..];
+
+    // Every channel is encoded separately with runs and literal spans.
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let (&count, rest) = src.split_first().ok_or("failed to read image data")?;
+
+            if count > 128 {
+                let count = count as usize - 128;
+                let &value = rest.first().ok_or("failed to read image data")?;
+                if x + count > width {
+                    return Err("invalid HDR run length");
+                }
+
+                for _ in 0..count {
+                    scanline[x * 4 + channel] = value;
+                    x += 1;
+                }
+                src = &rest[1..];
+            } else {
+                let count = count as usize;
+                let values = rest.get(..count).ok_or("failed to read image data")?;
+                if count == 0 || x + count > width {
+                    return Err("invalid HDR run length");
+                }
+
+                for &value in values {
+                    scanline[x * 4 + channel] = value;
+                    x += 1;
+                }
+                src = &rest[count..];
+            }
+        }
+    }
+
+    Ok(src)
+}
+
+/// Converts a pixel with a shared exponent into a linear color.
+pub(crate) fn rgbe_to_color(rgbe: &[u8]) -> Color {
+    if rgbe[3] == 0 {
+        return vec3!(0);
+    }
+
+    let scale = (2.0 as Float).powi(rgbe[3] as i32 - (128 + 8));
+    vec3!(
+        (rgbe[0] as Float + 0.5) * scale,
+        (rgbe[1] as Float + 0.5) * scale,
+        (rgbe[2] as Float + 0.5) * scale
+    )
+}
@@ -0,0 +1,327 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, Read, Write},
+    ops::{Index, IndexMut},
+    path::Path,
+};
+
+use jpeg_decoder::PixelFormat;
+use png::{
+    chunk::ChunkType, BitDepth, ColorType, Encoder, ScaledFloat, SourceChromaticities,
+    SrgbRenderingIntent,
+};
+
+use crate::float::{to_f32, Float};
+use colorspace::ColorSpace;
+
+pub mod colorspace;
+pub mod compare;
+pub mod hdr;
+pub mod ops;
+pub mod terminal;
+pub mod tga;
+pub mod tiled;
+
+#[derive(Debug)]
+/// A image buffer that can be used to store the result of rendering.
+pub struct ImageBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub data: Box<[u8]>,
+}
+
+impl ImageBuffer {
+    /// Creates a new image buffer with the given dimensions.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; (width * height * 3) as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Creates a new image buffer with the given dimensions and data.
+    pub fn with_data<D: Into<Box<[u8]>>>(width: u32, height: u32, data: D) -> Self {
+        let data = data.into();
+
+        assert!(
+            data.len() == (width * height * 3) as usize,
+            "Data length does not match dimensions"
+        );
+
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Loads an image buffer from a file at the given path.
+    /// The format is chosen by the file extension, supported are PNG, JPEG, and TGA.
+    /// Radiance HDR images hold float data and are loaded with [`FrameBuffer::load_hdr`].
+    ///
+    /// [`FrameBuffer::load_hdr`]: crate::framebuffer::FrameBuffer::load_hdr
+    pub fn load<T: ToString>(path: T) -> Result<ImageBuffer, &'static str> {
+        let path = path.to_string();
+
+        let extension = Path::new(&path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        let file = File::open(&path).map_err(|_| "failed to open file")?;
+        let reader = BufReader::new(file);
+
+        match extension.as_deref() {
+            Some("jpg" | "jpeg") => Self::decode_jpeg(reader),
+            Some("tga") => tga::decode(reader),
+            Some("hdr") => Err("HDR images must be loaded as a frame buffer"),
+            _ => Self::decode_png(reader),
+        }
+    }
+
+    /// Decodes an image from the bytes of an encoded file, for example from `include_bytes!`.
+    /// The format is detected from the signature of the data, data without a known
+    /// signature is decoded as TGA, which has none.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ImageBuffer, &'static str> {
+        if bytes.starts_with(b"\x89PNG") {
+            Self::decode_png(bytes)
+        } else if bytes.starts_with(&[0xff, 0xd8]) {
+            Self::decode_jpeg(bytes)
+        } else if bytes.starts_with(b"#?") {
+            Err("HDR images must be loaded as a frame buffer")
+        } else {
+            tga::decode(bytes)
+        }
+    }
+
+    /// Decodes a PNG image from the reader.
+    fn decode_png<R: Read>(reader: R) -> Result<ImageBuffer, &'static str> {
+        let decoder = png::Decoder::new(reader);
+        let mut reader = decoder
+            .read_info()
+            .map_err(|_| "failed to read image info")?;
+
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut data)
+            .map_err(|_| "failed to read image data")?;
+
+        let bytes = &data[..info.buffer_size()];
+
+        if info.color_type != ColorType::Rgb {
+            return Err("image must be in RGB color type");
+        }
+
+        Ok(ImageBuffer {
+            width: info.width,
+            height: info.height,
+            data: bytes.into(),
+        })
+    }
+
+    /// Decodes a JPEG image from the reader.
+    fn decode_jpeg<R: Read>(reader: R) -> Result<ImageBuffer, &'static str> {
+        let mut decoder = jpeg_decoder::Decoder::new(reader);
+
+        let pixels = decoder.decode().map_err(|_| "failed to read image data")?;
+        let info = decoder.info().ok_or("failed to read image info")?;
+
+        let data: Vec<u8> = match info.pixel_format {
+            PixelFormat::RGB24 => pixels,
+            PixelFormat::L8 => pixels.iter().flat_map(|&l| [l, l, l]).collect(),
+            _ => return Err("image must be in RGB or grayscale color type"),
+        };
+
+        Ok(ImageBuffer {
+            width: info.width as u32,
+            height: info.height as u32,
+            data: data.into(),
+        })
+    }
+
+    /// Saves the image buffer to a file at the given path.
+    /// The image is tagged as having the sRGB primaries and a gamma of 2.2.
+    pub fn save<T: ToString>(self, path: T) -> Result<(), &'static str> {
+        self.save_with_color_space(path, &ColorSpace::default(), false)
+    }
+
+    /// Encodes the image buffer as a PNG into the writer, like stdout or an in-memory buffer.
+    /// The image is tagged as having the sRGB primaries and a gamma of 2.2.
+    pub fn save_to<W: Write>(self, writer: W) -> Result<(), &'static str> {
+        self.save_with_color_space_to(writer, &ColorSpace::default(), false)
+    }
+
+    /// Saves the image buffer to a temporary file next to the given path and then renames it,
+    /// so programs watching the path never see a partially written image.
+    pub fn save_atomic<P: AsRef<Path>>(self, path: P) -> Result<(), &'static str> {
+        let path = path.as_ref();
+
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        self.save(Path::new(&temporary).display())?;
+        fs::rename(&temporary, path).map_err(|_| "failed to replace file")
+    }
+
+    /// Saves the image buffer to an RGBA PNG file at the given path, taking the opacity
+    /// of every pixel from the first channel of the alpha image.
+    pub fn save_with_alpha<T: ToString>(
+        self,
+        alpha: &ImageBuffer,
+        path: T,
+    ) -> Result<(), &'static str> {
+        let path = path.to_string();
+        let _span = tracing::info_span!("save", path = %path).entered();
+
+        let file = File::create(&path).map_err(|_| "failed to create file")?;
+        self.save_with_alpha_to(alpha, file)
+    }
+
+    /// Encodes the image buffer as an RGBA PNG into the writer, taking the opacity
+    /// of every pixel from the first channel of the alpha image.
+    pub fn save_with_alpha_to<W: Write>(
+        self,
+        alpha: &ImageBuffer,
+        writer: W,
+    ) -> Result<(), &'static str> {
+        if alpha.width != self.width || alpha.height != self.height {
+            return Err("alpha must have the size of the image");
+        }
+
+        let data: Vec<u8> = self
+            .data
+            .chunks(3)
+            .zip(alpha.data.chunks(3))
+            .flat_map(|(color, alpha)| [color[0], color[1], color[2], alpha[0]])
+            .collect();
+
+        let mut encoder = Encoder::new(writer, self.width, self.height);
+
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_srgb(SrgbRenderingIntent::Perceptual);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|_| "failed to write image header")?;
+
+        writer
+            .write_image_data(&data)
+            .map_err(|_| "failed to write image data")?;
+
+        writer.finish().map_err(|_| "failed to finish image")
+    }
+
+    /// Saves the image buffer to a PNG file at the given path, tagged with the color space
+    /// its data is encoded in. Optionally an ICC profile of the color space is embedded,
+    /// which viewers prefer over the other tags.
+    pub fn save_with_color_space<T: ToString>(
+        self,
+        path: T,
+        color_space: &ColorSpace,
+        embed_icc: bool,
+    ) -> Result<(), &'static str> {
+        let path = path.to_string();
+        let _span = tracing::info_span!("save", path = %path).entered();
+
+        let file = File::create(&path).map_err(|_| "failed to create file")?;
+        self.save_with_color_space_to(file, color_space, embed_icc)
+    }
+
+    /// Encodes the image buffer as a PNG into the writer, tagged with the color space
+    /// its data is encoded in, optionally with an embedded ICC profile.
+    pub fn save_with_color_space_to<W: Write>(
+        self,
+        writer: W,
+        color_space: &ColorSpace,
+        embed_icc: bool,
+    ) -> Result<(), &'static str> {
+        let mut encoder = Encoder::new(writer, self.width, self.height);
+
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(BitDepth::Eight);
+
+        // The sRGB chunk must not be combined with an ICC profile.
+        if color_space.is_srgb() && !embed_icc {
+            encoder.set_srgb(SrgbRenderingIntent::Perceptual);
+        } else {
+            encoder.set_source_gamma(ScaledFloat::new(to_f32(color_space.png_gamma())));
+
+            let chromaticities = color_space.chromaticities;
+            let point =
+                |(x, y): (Float, Float)| (ScaledFloat::new(to_f32(x)), ScaledFloat::new(to_f32(y)));
+            encoder.set_source_chromaticities(SourceChromaticities {
+                white: point(chromaticities.white),
+                red: point(chromaticities.red),
+                green: point(chromaticities.green),
+                blue: point(chromaticities.blue),
+            });
+        }
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|_| "failed to write image header")?;
+
+        if embed_icc {
+            // The chunk holds the profile name, the compression method and the compressed profile.
+            let mut chunk = b"rusty-ray\0\0".to_vec();
+            chunk.extend(zlib_stored(&color_space.icc_profile("rusty-ray output")));
+
+            writer
+                .write_chunk(ChunkType(*b"iCCP"), &chunk)
+                .map_err(|_| "failed to write color profile")?;
+        }
+
+        writer
+            .write_image_data(&self.data)
+            .map_err(|_| "failed to write image data")?;
+
+        writer.finish().map_err(|_| "failed to finish image")
+    }
+}
+
+/// Wraps the data into a zlib stream without compressing it.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+
+    let mut chunks = data.chunks(u16::MAX as usize).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let len = chunk.len() as u16;
+        stream.push(chunks.peek().is_none() as u8);
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+
+    // The Adler-32 checksum of the uncompressed data.
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    stream.extend_from_slice(&((b << 16) | a).to_be_bytes());
+
+    stream
+}
+
+impl Index<(u32, u32)> for ImageBuffer {
+    type Output = [u8];
+
+    fn index(&self, (x, y): (u32, u32)) -> &Self::Output {
+        let idx = ((y * self.width + x) * 3) as usize;
+
+        &self.data[idx..idx + 3]
+    }
+}
+
+impl IndexMut<(u32, u32)> for ImageBuffer {
+    fn index_mut(&mut self, (x, y): (u32, u32)) -> &mut Self::Output {
+        let idx = ((y * self.width + x) * 3) as usize;
+
+        &mut self.data[idx..idx + 3]
+    }
+}
@@ -0,0 +1,196 @@
+use super::ImageBuffer;
+use crate::float::Float;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The filter used when resizing an image.
+pub enum ResizeFilter {
+    /// Picks the closest source pixel.
+    Nearest,
+    #[default]
+    /// Interpolates linearly between the four closest source pixels.
+    Bilinear,
+    /// Averages all source pixels covered by the pixel, which avoids aliasing when downscaling.
+    Box,
+}
+
+impl ImageBuffer {
+    /// Returns a copy of the image resized to the given dimensions.
+    pub fn resize(&self, width: u32, height: u32, filter: ResizeFilter) -> ImageBuffer {
+        let mut result = ImageBuffer::new(width, height);
+
+        // An empty image has no pixels to sample, so it resizes into a black image.
+        if self.width == 0 || self.height == 0 {
+            return result;
+        }
+
+        let scale_x = self.width as Float / width as Float;
+        let scale_y = self.height as Float / height as Float;
+
+        for y in 0..height {
+            for x in 0..width {
+                // The position of the pixel center in the source image.
+                let src_x = (x as Float + 0.5) * scale_x;
+                let src_y = (y as Float + 0.5) * scale_y;
+
+                let pixel = match filter {
+                    ResizeFilter::Nearest => {
+                        let sx = (src_x as u32).min(self.width - 1);
+                        let sy = (src_y as u32).min(self.height - 1);
+
+                        let mut pixel = [0; 3];
+                        pixel.copy_from_slice(&self[(sx, sy)]);
+                        pixel
+                    }
+                    ResizeFilter::Bilinear => self.sample_bilinear(src_x - 0.5, src_y - 0.5),
+                    ResizeFilter::Box => self.sample_box(
+                        x as Float * scale_x,
+                        y as Float * scale_y,
+                        scale_x,
+                        scale_y,
+                    ),
+                };
+
+                result[(x, y)].copy_from_slice(&pixel);
+            }
+        }
+
+        result
+    }
+
+    /// Averages the pixels overlapping the area with the given corner and size.
+    fn sample_box(&self, x: Float, y: Float, width: Float, height: Float) -> [u8; 3] {
+        if self.width == 0 || self.height == 0 {
+            return [0; 3];
+        }
+
+        let x0 = (x as u32).min(self.width - 1);
+        let y0 = (y as u32).min(self.height - 1);
+        let x1 = ((x + width).ceil() as u32).clamp(x0 + 1, self.width);
+        let y1 = ((y + height).ceil() as u32).clamp(y0 + 1, self.height);
+
+        let mut sum = [0u32; 3];
+        for sy in y0..y1 {
+            for sx in x0..x1 {
+                for (total, &value) in sum.iter_mut().zip(&self[(sx, sy)]) {
+                    *total += value as u32;
+                }
+            }
+        }
+
+        let count = (x1 - x0) * (y1 - y0);
+        sum.map(|total| ((total + count / 2) / count) as u8)
+    }
+
+    /// Samples the image at a continuous pixel position, clamping at the edges.
+    fn sample_bilinear(&self, x: Float, y: Float) -> [u8; 3] {
+        if self.width == 0 || self.height == 0 {
+            return [0; 3];
+        }
+
+        let x = x.clamp(0.0, (self.width - 1) as Float);
+        let y = y.clamp(0.0, (self.height - 1) as Float);
+
+        let x0 = x.floor() as u32;
+        let y0 = y.floor() as u32;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+
+        let tx = x - x0 as Float;
+        let ty = y - y0 as Float;
+
+        let mut pixel = [0; 3];
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            let top = self[(x0, y0)][channel] as Float * (1.0 - tx)
+                + self[(x1, y0)][channel] as Float * tx;
+            let bottom = self[(x0, y1)][channel] as Float * (1.0 - tx)
+                + self[(x1, y1)][channel] as Float * tx;
+
+            *value = (top * (1.0 - ty) + bottom * ty).round() as u8;
+        }
+
+        pixel
+    }
+
+    /// Returns the region of the image with the given top left corner and dimensions.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> ImageBuffer {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "Crop region exceeds image dimensions"
+        );
+
+        let mut result = ImageBuffer::new(width, height);
+
+        for row in 0..height {
+            let src = ((y + row) * self.width + x) as usize * 3;
+            let dst = (row * width) as usize * 3;
+            let len = width as usize * 3;
+
+            result.data[dst..dst + len].copy_from_slice(&self.data[src..src + len]);
+        }
+
+        result
+    }
+
+    /// Copies another image into this image with its top left corner at the given position.
+    pub fn paste(&mut self, other: &ImageBuffer, x: u32, y: u32) {
+        assert!(
+            x + other.width <= self.width && y + other.height <= self.height,
+            "Pasted image exceeds image dimensions"
+        );
+
+        for row in 0..other.height {
+            let src = (row * other.width) as usize * 3;
+            let dst = ((y + row) * self.width + x) as usize * 3;
+            let len = other.width as usize * 3;
+
+            self.data[dst..dst + len].copy_from_slice(&other.data[src..src + len]);
+        }
+    }
+
+    /// Mirrors the image along its vertical axis.
+    pub fn flip_horizontal(&mut self) {
+        let row_len = self.width as usize * 3;
+        if row_len == 0 {
+            return;
+        }
+
+        for row in self.data.chunks_exact_mut(row_len) {
+            let width = self.width as usize;
+
+            for x in 0..width / 2 {
+                let (left, right) = row.split_at_mut((width - 1 - x) * 3);
+                left[x * 3..x * 3 + 3].swap_with_slice(&mut right[..3]);
+            }
+        }
+    }
+
+    /// Mirrors the image along its horizontal axis.
+    pub fn flip_vertical(&mut self) {
+        let row_len = self.width as usize * 3;
+        let height = self.height as usize;
+
+        for y in 0..height / 2 {
+            let (top, bottom) = self.data.split_at_mut((height - 1 - y) * row_len);
+            top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+        }
+    }
+
+    /// Returns a grayscale image containing only the given channel.
+    pub fn extract_channel(&self, channel: usize) -> ImageBuffer {
+        self.swizzle([channel; 3])
+    }
+
+    /// Returns a copy of the image with its channels rearranged,
+    /// the n-th output channel is taken from the n-th given source channel.
+    pub fn swizzle(&self, channels: [usize; 3]) -> ImageBuffer {
+        assert!(channels.iter().all(|&c| c < 3), "Invalid channel");
+
+        let data: Vec<u8> = self
+            .data
+            .chunks_exact(3)
+            .flat_map(|pixel| channels.map(|c| pixel[c]))
+            .collect();
+
+        ImageBuffer::with_data(self.width, self.height, data)
+    }
+}
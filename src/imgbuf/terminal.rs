@@ -0,0 +1,150 @@
+use std::{fmt::Write, iter};
+
+use super::{ops::ResizeFilter, ImageBuffer};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The way an image is drawn in a terminal.
+pub enum TerminalFormat {
+    #[default]
+    /// Half block characters colored with ANSI 24-bit escapes, two pixels per character.
+    Ansi,
+    /// Sixel graphics, drawn at the pixel resolution of terminals supporting them.
+    Sixel,
+}
+
+/// The number of levels per channel of the colors used for sixel graphics.
+const SIXEL_LEVELS: u32 = 6;
+
+impl ImageBuffer {
+    /// Encodes a downscaled preview of the image that can be printed to a terminal.
+    /// The width is the number of characters for ANSI and the number of pixels for sixel,
+    /// the image is never scaled up.
+    pub fn terminal_preview(&self, format: TerminalFormat, width: u32) -> String {
+        let width = width.clamp(1, self.width.max(1));
+        let height = ((self.height as u64 * width as u64 / self.width.max(1) as u64) as u32).max(1);
+
+        let preview = self.resize(width, height, ResizeFilter::Box);
+
+        match format {
+            TerminalFormat::Ansi => preview.encode_ansi(),
+            TerminalFormat::Sixel => preview.encode_sixel(),
+        }
+    }
+
+    /// Encodes the image as lines of upper half blocks, with the top pixel in the foreground
+    /// and the bottom pixel in the background.
+    fn encode_ansi(&self) -> String {
+        let mut output = String::new();
+
+        for y in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let top = &self[(x, y)];
+                write!(output, "\x1b[38;2;{};{};{}m", top[0], top[1], top[2]).unwrap();
+
+                // An odd last row leaves the bottom half in the terminal's background.
+                if y + 1 < self.height {
+                    let bottom = &self[(x, y + 1)];
+                    write!(
+                        output,
+                        "\x1b[48;2;{};{};{}m",
+                        bottom[0], bottom[1], bottom[2]
+                    )
+                    .unwrap();
+                }
+
+                output.push('▀');
+            }
+
+            output.push_str("\x1b[0m\n");
+        }
+
+        output
+    }
+
+    /// Encodes the image as sixel graphics, with the colors reduced to a uniform palette.
+    fn encode_sixel(&self) -> String {
+        let levels = SIXEL_LEVELS;
+        let quantize = |value: u8| (value as u32 * (levels - 1) + 127) / 255;
+
+        let palette: Vec<u32> = self
+            .data
+            .chunks(3)
+            .map(|pixel| {
+                pixel
+                    .iter()
+                    .fold(0, |index, &value| index * levels + quantize(value))
+            })
+            .collect();
+
+        // Start the sequence with square pixels and the size of the image.
+        let mut output = format!("\x1bPq\"1;1;{};{}", self.width, self.height);
+
+        let mut used = vec![false; levels.pow(3) as usize];
+        for &index in palette.iter() {
+            used[index as usize] = true;
+        }
+        for (index, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            let index = index as u32;
+            let percent = |level: u32| level * 100 / (levels - 1);
+            write!(
+                output,
+                "#{index};2;{};{};{}",
+                percent(index / (levels * levels)),
+                percent(index / levels % levels),
+                percent(index % levels)
+            )
+            .unwrap();
+        }
+
+        // Every band of six rows is drawn once per color, with a bit for each row it covers.
+        let width = self.width as usize;
+        for band in (0..self.height as usize).step_by(6) {
+            let rows = band..(band + 6).min(self.height as usize);
+
+            let mut colors: Vec<u32> = rows
+                .clone()
+                .flat_map(|y| palette[y * width..(y + 1) * width].iter().copied())
+                .collect();
+            colors.sort_unstable();
+            colors.dedup();
+
+            for color in colors {
+                write!(output, "#{color}").unwrap();
+
+                let sixels = (0..width).map(|x| {
+                    rows.clone()
+                        .filter(|y| palette[y * width + x] == color)
+                        .fold(0, |bits, y| bits | 1 << (y - band))
+                });
+                encode_runs(&mut output, sixels);
+
+                // Return to the start of the band for the next color.
+                output.push('$');
+            }
+
+            output.push('-');
+        }
+
+        output.push_str("\x1b\\");
+        output
+    }
+}
+
+/// Writes sixels with runs of repeated sixels compressed.
+fn encode_runs<I: Iterator<Item = u8>>(output: &mut String, sixels: I) {
+    let mut sixels = sixels.peekable();
+
+    while let Some(bits) = sixels.next() {
+        let mut count = 1;
+        while sixels.next_if_eq(&bits).is_some() {
+            count += 1;
+        }
+
+        let char = (b'?' + bits) as char;
+        if count > 3 {
+            write!(output, "!{count}{char}").unwrap();
+        } else {
+            output.extend(iter::repeat_n(char, count));
+        }
+    }
+}
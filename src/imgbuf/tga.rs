@@ -0,0 +1,97 @@
+use std::io::Read;
+
+use super::ImageBuffer;
+
+/// The size of the TGA file header in bytes.
+const HEADER_SIZE: usize = 18;
+
+/// Decodes an uncompressed or run-length encoded true color or grayscale TGA image.
+pub fn decode<R: Read>(mut reader: R) -> Result<ImageBuffer, &'static str> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|_| "failed to read image data")?;
+
+    if bytes.len() < HEADER_SIZE {
+        return Err("failed to read image info");
+    }
+
+    let id_length = bytes[0] as usize;
+    let color_map_type = bytes[1];
+    let image_type = bytes[2];
+    let width = u16::from_le_bytes([bytes[12], bytes[13]]) as u32;
+    let height = u16::from_le_bytes([bytes[14], bytes[15]]) as u32;
+    let pixel_depth = bytes[16];
+    let top_to_bottom = bytes[17] & 0x20 != 0;
+
+    if color_map_type != 0 {
+        return Err("color mapped TGA images are not supported");
+    }
+
+    let (grayscale, compressed) = match image_type {
+        2 => (false, false),
+        3 => (true, false),
+        10 => (false, true),
+        11 => (true, true),
+        _ => return Err("unsupported TGA image type"),
+    };
+
+    let pixel_size = match (grayscale, pixel_depth) {
+        (true, 8) => 1,
+        (false, 24) => 3,
+        (false, 32) => 4,
+        _ => return Err("unsupported TGA pixel depth"),
+    };
+
+    let pixel_count = width as usize * height as usize;
+    let mut src = &bytes[HEADER_SIZE + id_length.min(bytes.len() - HEADER_SIZE)..];
+
+    // Collect the raw pixels in file order, expanding run-length packets.
+    let mut pixels = Vec::with_capacity(pixel_count * pixel_size);
+    if compressed {
+        while pixels.len() < pixel_count * pixel_size {
+            let (&packet, rest) = src.split_first().ok_or("failed to read image data")?;
+            let count = (packet & 0x7f) as usize + 1;
+
+            if packet & 0x80 != 0 {
+                let pixel = rest.get(..pixel_size).ok_or("failed to read image data")?;
+                for _ in 0..count {
+                    pixels.extend_from_slice(pixel);
+                }
+                src = &rest[pixel_size..];
+            } else {
+                let run = rest
+                    .get(..count * pixel_size)
+                    .ok_or("failed to read image data")?;
+                pixels.extend_from_slice(run);
+                src = &rest[count * pixel_size..];
+            }
+        }
+        pixels.truncate(pixel_count * pixel_size);
+    } else {
+        let data = src
+            .get(..pixel_count * pixel_size)
+            .ok_or("failed to read image data")?;
+        pixels.extend_from_slice(data);
+    }
+
+    // Convert the BGR(A) or grayscale pixels to RGB, flipping bottom-up images.
+    let mut image = ImageBuffer::new(width, height);
+    for (idx, pixel) in pixels.chunks_exact(pixel_size).enumerate() {
+        let x = idx as u32 % width;
+        let mut y = idx as u32 / width;
+        if !top_to_bottom {
+            y = height - 1 - y;
+        }
+
+        let rgb = if grayscale {
+            [pixel[0], pixel[0], pixel[0]]
+        } else {
+            [pixel[2], pixel[1], pixel[0]]
+        };
+
+        image[(x, y)].copy_from_slice(&rgb);
+    }
+
+    Ok(image)
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use png::ColorType;
+
+use super::{hdr::Scanlines, ImageBuffer};
+
+/// The width and height of a tile in pixels.
+const TILE_SIZE: u32 = 64;
+
+/// The number of independently locked parts of the tile cache, so threads rarely wait.
+const CACHE_SHARDS: usize = 16;
+
+/// The number of tiles kept in memory unless set otherwise, 4 MiB of RGBE tiles.
+pub const DEFAULT_CACHE_TILES: usize = 256;
+
+/// Counts the tile files created by this process, to give each a unique name.
+static TILE_FILES: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+/// An image stored in square tiles in a temporary file, of which only the recently used tiles
+/// are kept in memory. Large images are decoded row by row into the file, so they never have
+/// to fit into memory as a whole.
+pub struct TiledImage {
+    /// The width of the image in pixels.
+    pub width: u32,
+    /// The height of the image in pixels.
+    pub height: u32,
+    /// The number of bytes of a pixel, at most 4.
+    bytes_per_pixel: usize,
+    /// The number of tiles in a row of tiles.
+    tiles_x: u32,
+    /// The temporary file holding the tiles, which is deleted when the image is dropped.
+    path: PathBuf,
+    /// The open tile file.
+    file: Mutex<File>,
+    /// The recently used tiles, split into shards by tile index.
+    shards: Vec<Mutex<TileCache>>,
+}
+
+impl TiledImage {
+    /// Loads an 8-bit RGB image from a file, keeping at most the given number of tiles in memory.
+    /// PNG images are decoded row by row, other formats are decoded as a whole first.
+    pub fn load<P: AsRef<Path>>(path: P, cache_tiles: usize) -> Result<TiledImage, &'static str> {
+        let path = path.as_ref();
+        let is_png = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+
+        if !is_png {
+            let image = ImageBuffer::load(path.display())?;
+            let mut rows = image.data.chunks(image.width as usize * 3);
+
+            return Self::from_rows(image.width, image.height, 3, cache_tiles, |row| {
+                row.copy_from_slice(rows.next().ok_or("failed to read image data")?);
+                Ok(())
+            });
+        }
+
+        let file = File::open(path).map_err(|_| "failed to open file")?;
+        let mut reader = png::Decoder::new(BufReader::new(file))
+            .read_info()
+            .map_err(|_| "failed to read image info")?;
+
+        let info = reader.info();
+        if info.color_type != ColorType::Rgb || info.bit_depth != png::BitDepth::Eight {
+            return Err("image must be in RGB color type");
+        }
+        if info.interlaced {
+            return Err("interlaced images can't be loaded row by row");
+        }
+        let (width, height) = (info.width, info.height);
+
+        Self::from_rows(width, height, 3, cache_tiles, |row| {
+            let next = reader
+                .next_row()
+                .map_err(|_| "failed to read image data")?
+                .ok_or("failed to read image data")?;
+            row.copy_from_slice(next.data());
+            Ok(())
+        })
+    }
+
+    /// Loads a Radiance HDR image from a file, keeping at most the given number of tiles
+    /// in memory. The pixels are stored as RGBE, four bytes per pixel.
+    pub fn load_hdr<P: AsRef<Path>>(
+        path: P,
+        cache_tiles: usize,
+    ) -> Result<TiledImage, &'static str> {
+        let file = File::open(path).map_err(|_| "failed to open file")?;
+        let mut scanlines = Scanlines::new(BufReader::new(file))?;
+
+        Self::from_rows(scanlines.width, scanlines.height, 4, cache_tiles, |row| {
+            scanlines.next(row)
+        })
+    }
+
+    /// Creates a tiled image from rows of pixels, which the callback writes into the given
+    /// buffer from the top row down.
+    pub fn from_rows<F>(
+        width: u32,
+        height: u32,
+        bytes_per_pixel: usize,
+        cache_tiles: usize,
+        mut next_row: F,
+    ) -> Result<TiledImage, &'static str>
+    where
+        F: FnMut(&mut [u8]) -> Result<(), &'static str>,
+    {
+        assert!(
+            (1..=4).contains(&bytes_per_pixel),
+            "pixels must have 1 to 4 bytes"
+        );
+        if width == 0 || height == 0 {
+            return Err("image must not be empty");
+        }
+
+        let tiles_x = width.div_ceil(TILE_SIZE);
+        let tile_row_len = TILE_SIZE as usize * bytes_per_pixel;
+
+        let path = env::temp_dir().join(format!(
+            "rusty-ray-tiles-{}-{}.bin",
+            process::id(),
+            TILE_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|_| "failed to create tile file")?;
+
+        // The image takes ownership of the file first, so it is deleted if writing fails.
+        let image = TiledImage {
+            width,
+            height,
+            bytes_per_pixel,
+            tiles_x,
+            path,
+            file: Mutex::new(file),
+            shards: (0..CACHE_SHARDS)
+                .map(|_| Mutex::new(TileCache::new(cache_tiles.div_ceil(CACHE_SHARDS))))
+                .collect(),
+        };
+
+        // Collect one row of tiles at a time and write its tiles one after another,
+        // with the tiles at the right and bottom edges padded to the full size.
+        let row_len = width as usize * bytes_per_pixel;
+        let mut band = vec![0u8; row_len * TILE_SIZE as usize];
+        let mut tile_row = vec![0u8; tile_row_len];
+
+        let file = image.file.lock().unwrap();
+        let mut writer = BufWriter::new(&*file);
+
+        for band_y in (0..height).step_by(TILE_SIZE as usize) {
+            let rows = TILE_SIZE.min(height - band_y) as usize;
+            band.fill(0);
+            for row in band.chunks_mut(row_len).take(rows) {
+                next_row(row)?;
+            }
+
+            for tile_x in 0..tiles_x {
+                let start = (tile_x * TILE_SIZE) as usize * bytes_per_pixel;
+                let len = tile_row_len.min(row_len - start);
+
+                for row in band.chunks(row_len) {
+                    tile_row.fill(0);
+                    tile_row[..len].copy_from_slice(&row[start..start + len]);
+                    writer
+                        .write_all(&tile_row)
+                        .map_err(|_| "failed to write tile file")?;
+                }
+            }
+        }
+
+        writer.flush().map_err(|_| "failed to write tile file")?;
+        drop(writer);
+        drop(file);
+
+        Ok(image)
+    }
+
+    /// Returns the bytes of the pixel at the given position, the unused bytes are zero.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let tile_index = (y / TILE_SIZE * self.tiles_x + x / TILE_SIZE) as usize;
+        let tile = self.tile(tile_index);
+
+        let offset = ((y % TILE_SIZE * TILE_SIZE + x % TILE_SIZE) as usize) * self.bytes_per_pixel;
+        let mut pixel = [0; 4];
+        pixel[..self.bytes_per_pixel].copy_from_slice(&tile[offset..offset + self.bytes_per_pixel]);
+        pixel
+    }
+
+    /// Returns the tile with the given index, reading it from the file if it isn't cached.
+    fn tile(&self, index: usize) -> Arc<[u8]> {
+        let mut shard = self.shards[index % CACHE_SHARDS].lock().unwrap();
+        if let Some(tile) = shard.get(index) {
+            return tile;
+        }
+
+        let tile_len = (TILE_SIZE * TILE_SIZE) as usize * self.bytes_per_pixel;
+        let mut tile = vec![0; tile_len];
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start((index * tile_len) as u64))
+                .and_then(|_| file.read_exact(&mut tile))
+                .expect("failed to read tile file");
+        }
+
+        let tile: Arc<[u8]> = tile.into();
+        shard.insert(index, Arc::clone(&tile));
+        tile
+    }
+}
+
+impl Drop for TiledImage {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug)]
+/// A least recently used cache of tiles.
+struct TileCache {
+    /// The cached tiles with the time they were last used.
+    tiles: HashMap<usize, (Arc<[u8]>, u64)>,
+    /// The maximum number of cached tiles.
+    capacity: usize,
+    /// Increases with every access, to order the tiles by their last use.
+    clock: u64,
+}
+
+impl TileCache {
+    /// Creates an empty cache holding at most the given number of tiles.
+    fn new(capacity: usize) -> Self {
+        Self {
+            tiles: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    /// Returns the cached tile and marks it as used.
+    fn get(&mut self, index: usize) -> Option<Arc<[u8]>> {
+        self.clock += 1;
+        let (tile, last_used) = self.tiles.get_mut(&index)?;
+        *last_used = self.clock;
+        Some(Arc::clone(tile))
+    }
+
+    /// Caches a tile, evicting the least recently used tile if the cache is full.
+    fn insert(&mut self, index: usize, tile: Arc<[u8]>) {
+        if self.tiles.len() >= self.capacity {
+            let oldest = self
+                .tiles
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(&index, _)| index);
+
+            if let Some(oldest) = oldest {
+                self.tiles.remove(&oldest);
+            }
+        }
+
+        self.tiles.insert(index, (tile, self.clock));
+    }
+}
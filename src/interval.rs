@@ -1,3 +1,5 @@
+use crate::float::{Float, Scalar};
+
 #[macro_export]
 /// Create a new Interval with the given start and end.
 macro_rules! intr {
@@ -9,25 +11,72 @@ macro_rules! intr {
     };
 }
 
+/// An interval using the floating point type used for rendering.
+pub type Interval = GenericInterval<Float>;
+
 #[derive(Debug, Clone, Copy)]
-/// An interval from a start to an end.
-pub struct Interval {
+/// An interval from a start to an end of any scalar type.
+pub struct GenericInterval<T> {
     /// The start of the interval.
-    pub start: f64,
+    pub start: T,
     /// The end of the interval.
-    pub end: f64,
+    pub end: T,
 }
 
-impl Interval {
+impl<T: Scalar> GenericInterval<T> {
     /// Constant empty interval.
     pub const EMPTY: Self = Self {
-        start: f64::INFINITY,
-        end: f64::NEG_INFINITY,
+        start: T::INFINITY,
+        end: T::NEG_INFINITY,
+    };
+
+    /// Constant interval containing every value.
+    pub const UNIVERSE: Self = Self {
+        start: T::NEG_INFINITY,
+        end: T::INFINITY,
     };
 
+    #[inline]
+    /// Creates the smallest interval containing both intervals.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    #[inline]
+    /// Returns the size of the interval.
+    pub fn size(&self) -> T {
+        self.end - self.start
+    }
+
+    #[inline]
+    /// Returns the interval grown by half of delta on both sides.
+    pub fn expand(&self, delta: T) -> Self {
+        let padding = delta / T::from_f64(2.0);
+
+        Self {
+            start: self.start - padding,
+            end: self.end + padding,
+        }
+    }
+
     #[inline]
     /// Checks if the interval contains a value.
-    pub fn contains(&self, value: f64) -> bool {
+    pub fn contains(&self, value: T) -> bool {
         self.start <= value && value <= self.end
     }
+
+    #[inline]
+    /// Checks if the interval contains a value, excluding its bounds.
+    pub fn surrounds(&self, value: T) -> bool {
+        self.start < value && value < self.end
+    }
+
+    #[inline]
+    /// Clamps a value to the interval.
+    pub fn clamp(&self, value: T) -> T {
+        value.max(self.start).min(self.end)
+    }
 }
@@ -0,0 +1,369 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{camera::CameraBuilder, float::Float};
+
+/// The frame rate of jobs that don't set one.
+const DEFAULT_FPS: Float = 24.0;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A render job, which renders a range of frames of a scene file to numbered images.
+///
+/// Job files describe one setting per line, lines starting with `#` are comments:
+///
+/// ```text
+/// scene <path>
+/// output <pattern>
+/// motion <pattern>
+/// frames <first> <last>
+/// fps <rate>
+/// resolution <width> <height>
+/// samples <count>
+/// seed <seed>
+/// temporal <weight>
+/// ```
+///
+/// The scene and output are required, paths are relative to the job file. The `#` characters
+/// in the output patterns are replaced by the frame number, padded with zeros to their count,
+/// and are required when rendering more than one frame.
+/// With a motion pattern, the motion vectors towards the next frame are saved as PFM images.
+/// With a temporal weight, every frame is blended with the earlier frames reprojected onto it,
+/// getting at least the given weight, which reduces the noise of mostly static shots.
+/// Without frames only frame 0 is rendered, the other settings default to the scene file.
+pub struct Job {
+    /// The scene file to render.
+    pub scene: PathBuf,
+    /// The pattern of the output paths.
+    pub output: PathBuf,
+    /// The pattern of the paths of the motion vectors.
+    pub motion: Option<PathBuf>,
+    /// The frames to render.
+    pub frames: RangeInclusive<u32>,
+    /// The frame rate, which converts frame numbers into animation time.
+    pub fps: Float,
+    /// The width and height of the images.
+    pub resolution: Option<(u32, u32)>,
+    /// The number of samples per pixel.
+    pub samples: Option<u32>,
+    /// The seed of the random number generator.
+    pub seed: Option<u64>,
+    /// The smallest weight of a frame blended with the earlier frames, or `None` to render
+    /// every frame on its own.
+    pub temporal: Option<Float>,
+}
+
+impl Job {
+    /// Loads a job file from the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Job, JobError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|_| JobError {
+            line: None,
+            message: "failed to read file",
+        })?;
+
+        Self::parse(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a job file, resolving relative paths against the given directory.
+    pub fn parse(source: &str, dir: &Path) -> Result<Job, JobError> {
+        let mut job = Job {
+            scene: PathBuf::new(),
+            output: PathBuf::new(),
+            motion: None,
+            frames: 0..=0,
+            fps: DEFAULT_FPS,
+            resolution: None,
+            samples: None,
+            seed: None,
+            temporal: None,
+        };
+
+        for (idx, line) in source.lines().enumerate() {
+            // Comments only span whole lines, as output patterns contain `#`.
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some((setting, arguments)) = tokens.split_first() else {
+                continue;
+            };
+
+            job.set(setting, arguments, dir)
+                .map_err(|message| JobError {
+                    line: Some(idx + 1),
+                    message,
+                })?;
+        }
+
+        let missing = |message| JobError {
+            line: None,
+            message,
+        };
+        if job.scene.as_os_str().is_empty() {
+            return Err(missing("missing scene"));
+        }
+        if job.output.as_os_str().is_empty() {
+            return Err(missing("missing output"));
+        }
+
+        // Without a frame number in the patterns, every frame would overwrite the same file.
+        if job.frame_count() > 1 {
+            if !has_frame_number(&job.output) {
+                return Err(missing("output pattern needs a # for the frame number"));
+            }
+            if job
+                .motion
+                .as_deref()
+                .is_some_and(|motion| !has_frame_number(motion))
+            {
+                return Err(missing("motion pattern needs a # for the frame number"));
+            }
+        }
+
+        Ok(job)
+    }
+
+    /// Parses a single setting.
+    fn set(&mut self, setting: &str, arguments: &[&str], dir: &Path) -> Result<(), &'static str> {
+        let expected = match setting {
+            "scene" | "output" | "motion" | "fps" | "samples" | "seed" | "temporal" => 1,
+            "frames" | "resolution" => 2,
+            _ => return Err("unknown setting"),
+        };
+
+        match arguments.len().cmp(&expected) {
+            Ordering::Less => return Err("missing argument"),
+            Ordering::Greater => return Err("too many arguments"),
+            Ordering::Equal => {}
+        }
+
+        match setting {
+            "scene" => self.scene = dir.join(arguments[0]),
+            "output" => self.output = dir.join(arguments[0]),
+            "motion" => self.motion = Some(dir.join(arguments[0])),
+            "frames" => {
+                let (first, last) = (number(arguments[0])?, number(arguments[1])?);
+                if first > last {
+                    return Err("first frame is after the last frame");
+                }
+                self.frames = first..=last;
+            }
+            "fps" => {
+                self.fps = number(arguments[0])?;
+                if !self.fps.is_finite() || self.fps <= 0.0 {
+                    return Err("frame rate must be positive");
+                }
+            }
+            "resolution" => {
+                let (width, height) = (number(arguments[0])?, number(arguments[1])?);
+                if width == 0 || height == 0 {
+                    return Err("resolution must not be zero");
+                }
+                self.resolution = Some((width, height));
+            }
+            "samples" => self.samples = Some(number(arguments[0])?),
+            "temporal" => {
+                let weight = number(arguments[0])?;
+                if !(0.0..=1.0).contains(&weight) || weight == 0.0 {
+                    return Err("temporal weight must be between 0 and 1");
+                }
+                self.temporal = Some(weight);
+            }
+            _ => self.seed = Some(number(arguments[0])?),
+        }
+
+        Ok(())
+    }
+
+    /// Applies the settings of the job to the camera settings of the scene.
+    pub fn configure(&self, builder: &mut CameraBuilder) {
+        if let Some((width, height)) = self.resolution {
+            builder
+                .with_image_width(width)
+                .with_aspect_ratio(width as Float / height as Float);
+        }
+        if let Some(samples) = self.samples {
+            builder.with_sample_count(samples);
+        }
+        if let Some(seed) = self.seed {
+            builder.with_seed(seed);
+        }
+    }
+
+    /// Returns the number of frames to render.
+    pub fn frame_count(&self) -> u64 {
+        (self.frames.end() - self.frames.start()) as u64 + 1
+    }
+
+    /// Returns the animation time of the frame in seconds.
+    pub fn frame_time(&self, frame: u32) -> Float {
+        frame as Float / self.fps
+    }
+
+    /// Returns the output path of the frame.
+    pub fn output_path(&self, frame: u32) -> PathBuf {
+        frame_path(&self.output, frame)
+    }
+
+    /// Returns the path of the motion vectors of the frame, if they are saved.
+    pub fn motion_path(&self, frame: u32) -> Option<PathBuf> {
+        Some(frame_path(self.motion.as_ref()?, frame))
+    }
+
+    /// Splits the job into at most the given number of jobs over consecutive frame ranges
+    /// of similar length, so they can be rendered independently.
+    pub fn split(&self, count: u32) -> Vec<Job> {
+        let first = *self.frames.start();
+        let frame_count = self.frame_count();
+        let count = (count as u64).clamp(1, frame_count);
+
+        (0..count)
+            .map(|part| {
+                let start = first + (frame_count * part / count) as u32;
+                let end = first + (frame_count * (part + 1) / count - 1) as u32;
+
+                Job {
+                    frames: start..=end,
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns whether the path pattern has `#` characters for the frame number.
+fn has_frame_number(pattern: &Path) -> bool {
+    pattern.to_string_lossy().contains('#')
+}
+
+/// Replaces the `#` characters of a path pattern with the frame number.
+fn frame_path(pattern: &Path, frame: u32) -> PathBuf {
+    let pattern = pattern.to_string_lossy();
+
+    let Some(start) = pattern.find('#') else {
+        return PathBuf::from(pattern.as_ref());
+    };
+    let width = pattern[start..].chars().take_while(|&c| c == '#').count();
+
+    let mut path = pattern[..start].to_string();
+    path.push_str(&format!("{frame:0width$}"));
+    path.push_str(&pattern[start + width..]);
+    PathBuf::from(path)
+}
+
+/// Parses a token as a number.
+fn number<T: FromStr>(token: &str) -> Result<T, &'static str> {
+    token.parse().map_err(|_| "invalid number")
+}
+
+#[derive(Debug, Clone)]
+/// An error in a job file.
+pub struct JobError {
+    /// The line of the error, or `None` if it doesn't belong to a line.
+    pub line: Option<usize>,
+    /// A description of the error.
+    pub message: &'static str,
+}
+
+impl Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings() {
+        let job = Job::parse(
+            "# a turntable\n\
+             scene turntable.txt\n\
+             output frames/####.png\n\
+             frames 1 12\n\
+             fps 12\n\
+             resolution 320 240\n",
+            Path::new("shots"),
+        )
+        .unwrap();
+
+        assert_eq!(job.scene, Path::new("shots/turntable.txt"));
+        assert_eq!(job.frames, 1..=12);
+        assert_eq!(job.resolution, Some((320, 240)));
+        assert_eq!(job.frame_time(6), 0.5);
+        assert_eq!(job.output_path(7), Path::new("shots/frames/0007.png"));
+        assert_eq!(job.motion_path(7), None);
+    }
+
+    #[test]
+    fn reports_line_of_error() {
+        let err = Job::parse("scene a.txt\nframes 3 1\n", Path::new(""))
+            .err()
+            .unwrap();
+        assert_eq!(err.line, Some(2));
+        assert_eq!(err.message, "first frame is after the last frame");
+
+        let err = Job::parse("scene a.txt\n", Path::new("")).err().unwrap();
+        assert_eq!(err.line, None);
+        assert_eq!(err.message, "missing output");
+
+        let err = Job::parse("scene a.txt\noutput a.png\nframes 0 1\n", Path::new(""))
+            .err()
+            .unwrap();
+        assert_eq!(err.message, "output pattern needs a # for the frame number");
+
+        let err = Job::parse(
+            "scene a.txt\noutput a_#.png\nmotion a.pfm\nframes 0 1\n",
+            Path::new(""),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.message, "motion pattern needs a # for the frame number");
+
+        for setting in [
+            "fps 0",
+            "fps nan",
+            "fps inf",
+            "resolution 0 240",
+            "temporal 2",
+            "samples",
+            "seed 1 2",
+        ] {
+            let source = format!("scene a.txt\noutput a.png\n{setting}\n");
+            assert!(Job::parse(&source, Path::new("")).is_err());
+        }
+    }
+
+    #[test]
+    fn split_covers_all_frames() {
+        let job = Job::parse("scene a.txt\noutput a_#.png\nframes 0 9\n", Path::new("")).unwrap();
+
+        let parts = job.split(3);
+        let frames: Vec<_> = parts.iter().map(|part| part.frames.clone()).collect();
+        assert_eq!(frames, [0..=2, 3..=5, 6..=9]);
+
+        assert_eq!(job.split(20).len(), 10);
+
+        let job = Job::parse(
+            "scene a.txt\noutput a_#.png\nframes 0 4294967295\n",
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(job.frame_count(), 1 << 32);
+
+        let parts = job.split(2);
+        assert_eq!(parts[0].frames, 0..=(1 << 31) - 1);
+        assert_eq!(parts[1].frames, 1 << 31..=u32::MAX);
+    }
+}
@@ -0,0 +1,65 @@
+pub mod aabb;
+pub mod accumulation;
+pub mod animation;
+pub mod aperture;
+pub mod bvh;
+pub mod camera;
+pub mod cancel;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod config;
+pub mod densities;
+pub mod density;
+pub mod dispatch;
+pub mod effects;
+pub mod environment;
+pub mod environments;
+pub mod exposure;
+pub mod filter;
+pub mod float;
+pub mod framebuffer;
+pub mod group;
+pub mod guiding;
+pub mod heatmap;
+pub mod hittable;
+pub mod imgbuf;
+pub mod interval;
+pub mod job;
+pub mod light;
+pub mod light_group;
+pub mod lightmap;
+pub mod loader;
+pub mod lod;
+pub mod material;
+pub mod materials;
+pub mod mesh;
+pub mod nan_check;
+pub mod noise;
+pub mod objects;
+pub mod onb;
+pub mod pdf;
+pub mod portal;
+pub mod postprocess;
+pub mod priority;
+pub mod profiler;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod random;
+pub mod ray;
+pub mod render_handle;
+pub mod resources;
+pub mod sampler;
+pub mod samplers;
+pub mod scene;
+pub mod scene_file;
+pub mod scenes;
+pub mod stereo;
+pub mod temporal;
+pub mod texture;
+pub mod textures;
+pub mod transform;
+pub mod vector;
+#[cfg(feature = "viewer")]
+pub mod viewer;
+pub mod watch;
@@ -0,0 +1,92 @@
+use std::fmt::{self, Debug};
+
+use crate::{
+    float::Float,
+    hittable::Hittable,
+    scene::{ObjectId, Scene},
+    vector::{Point3, Vec3},
+};
+
+/// A light that directions can be sampled towards, to send more rays to where light comes from.
+/// Lights only guide sampling, their light is gathered by rays hitting emissive objects.
+pub trait Light: Debug + Send + Sync {
+    /// Returns the probability density, with respect to solid angle, of sampling
+    /// the given direction from the origin at the given time with [`Light::random`].
+    fn pdf_value(&self, origin: Point3, dir: Vec3, time: Float) -> Float;
+
+    /// Samples a random direction from the origin towards the light at the given time.
+    fn random(&self, origin: Point3, time: Float) -> Vec3;
+}
+
+impl<H: Hittable + ?Sized> Light for H {
+    fn pdf_value(&self, origin: Point3, dir: Vec3, time: Float) -> Float {
+        Hittable::pdf_value(self, origin, dir, time)
+    }
+
+    fn random(&self, origin: Point3, time: Float) -> Vec3 {
+        Hittable::random(self, origin, time)
+    }
+}
+
+#[derive(Clone, Copy)]
+/// The lights of a scene, which are its emissive objects followed by the lights added to it.
+pub struct Lights<'a> {
+    /// The scene the lights belong to.
+    scene: &'a Scene,
+    /// The emissive objects of the scene.
+    emitters: &'a [ObjectId],
+    /// The lights added to the scene.
+    lights: &'a [Box<dyn Light>],
+}
+
+impl<'a> Lights<'a> {
+    /// Creates the list of lights from the emissive objects and added lights of a scene.
+    pub(crate) fn new(
+        scene: &'a Scene,
+        emitters: &'a [ObjectId],
+        lights: &'a [Box<dyn Light>],
+    ) -> Self {
+        Self {
+            scene,
+            emitters,
+            lights,
+        }
+    }
+
+    /// Returns the number of lights.
+    pub fn len(&self) -> usize {
+        self.emitters.len() + self.lights.len()
+    }
+
+    /// Returns whether there are no lights.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the light with the given index.
+    pub fn get(&self, index: usize) -> &'a dyn Light {
+        match self.emitters.get(index) {
+            Some(&id) => &self.scene[id],
+            None => &*self.lights[index - self.emitters.len()],
+        }
+    }
+
+    /// Returns the emissive objects among the lights.
+    pub fn emitters(&self) -> &'a [ObjectId] {
+        self.emitters
+    }
+
+    /// Returns an iterator over all lights.
+    pub fn iter(&self) -> impl Iterator<Item = &'a dyn Light> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+}
+
+impl Debug for Lights<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lights")
+            .field("emitters", &self.emitters)
+            .field("lights", &self.lights)
+            .finish_non_exhaustive()
+    }
+}
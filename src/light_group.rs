@@ -0,0 +1,150 @@
+use crate::{
+    accumulation::Accumulation, float::Float, framebuffer::FrameBuffer, scene::Scene, vector::Color,
+};
+
+/// The maximum number of light groups of a scene, so the light of a path split by
+/// the groups fits into a fixed array instead of being allocated for every bounce.
+pub const MAX_LIGHT_GROUPS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// An ID for a light group in a scene.
+pub struct LightGroupId(pub(crate) usize);
+
+#[derive(Debug, Clone)]
+/// The light of a render split by the light groups it was emitted by, so the balance of
+/// the lights can be adjusted without rendering again.
+pub struct LightGroupLayers {
+    /// The names of the light groups, in the order of their layers.
+    pub names: Vec<String>,
+    /// The light emitted by each light group.
+    pub layers: Vec<FrameBuffer>,
+    /// The light emitted by lights without a group.
+    pub other: FrameBuffer,
+    /// The number of samples per pixel.
+    pub samples: u32,
+}
+
+impl LightGroupLayers {
+    /// Creates black layers for the light groups of the scene.
+    pub(crate) fn new(scene: &Scene, width: u32, height: u32, samples: u32) -> Self {
+        Self {
+            names: scene.light_groups().to_vec(),
+            layers: vec![FrameBuffer::new(width, height); scene.light_groups().len()],
+            other: FrameBuffer::new(width, height),
+            samples,
+        }
+    }
+
+    /// Stores the light of a pixel in the layers.
+    pub(crate) fn set(&mut self, x: u32, y: u32, light: LayeredColor) {
+        for (layer, color) in self.layers.iter_mut().zip(light.0) {
+            layer[(x, y)] = color;
+        }
+        self.other[(x, y)] = light.0[MAX_LIGHT_GROUPS];
+    }
+
+    /// Returns the layer of the light group with the given name.
+    pub fn layer(&self, name: &str) -> Option<&FrameBuffer> {
+        let idx = self.names.iter().position(|group| group == name)?;
+        Some(&self.layers[idx])
+    }
+
+    /// Adds up the layers, each scaled by the weight of its light group, into an accumulation
+    /// the camera develops into an image. Groups without a weight and the lights without
+    /// a group keep their brightness.
+    pub fn mix(&self, weights: &[Color]) -> Accumulation {
+        let mut fb = self.other.clone();
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let weight = weights.get(idx).copied().unwrap_or(Color::ONE);
+            for (pixel, light) in fb.data.iter_mut().zip(layer.data.iter()) {
+                *pixel += *light * weight;
+            }
+        }
+
+        Accumulation::new(fb, self.samples)
+    }
+}
+
+/// The light carried along a path, either as a whole or split by light groups.
+pub(crate) trait Radiance: Sized {
+    /// Returns no light.
+    fn black() -> Self;
+
+    /// Returns the light emitted by a light of the given group.
+    fn emitted(color: Color, group: Option<LightGroupId>) -> Self;
+
+    /// Adds the light of both paths.
+    fn add(self, other: Self) -> Self;
+
+    /// Scales the light by the attenuation of a bounce.
+    fn scale(self, attenuation: Color) -> Self;
+
+    /// Returns the luminance of all of the light.
+    fn luminance(&self) -> Float;
+}
+
+impl Radiance for Color {
+    fn black() -> Self {
+        Color::ZERO
+    }
+
+    fn emitted(color: Color, _group: Option<LightGroupId>) -> Self {
+        color
+    }
+
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn scale(self, attenuation: Color) -> Self {
+        self * attenuation
+    }
+
+    fn luminance(&self) -> Float {
+        Color::luminance(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Light split by light groups, the last entry holds the light without a group.
+pub(crate) struct LayeredColor(pub [Color; MAX_LIGHT_GROUPS + 1]);
+
+impl LayeredColor {
+    /// Returns the sum of the light of all groups.
+    pub fn total(&self) -> Color {
+        self.0
+            .iter()
+            .fold(Color::ZERO, |total, &color| total + color)
+    }
+}
+
+impl Radiance for LayeredColor {
+    fn black() -> Self {
+        Self([Color::ZERO; MAX_LIGHT_GROUPS + 1])
+    }
+
+    fn emitted(color: Color, group: Option<LightGroupId>) -> Self {
+        let mut layers = Self::black();
+        layers.0[group.map_or(MAX_LIGHT_GROUPS, |group| group.0)] = color;
+        layers
+    }
+
+    fn add(mut self, other: Self) -> Self {
+        for (color, other) in self.0.iter_mut().zip(other.0) {
+            *color += other;
+        }
+        self
+    }
+
+    fn scale(mut self, attenuation: Color) -> Self {
+        for color in self.0.iter_mut() {
+            *color *= attenuation;
+        }
+        self
+    }
+
+    fn luminance(&self) -> Float {
+        self.total().luminance()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    float::Float,
+    framebuffer::FrameBuffer,
+    mesh::Mesh,
+    vector::{Point3, Vec3},
+};
+
+/// The number of texels the baked texels are extended by past the edges of the islands of
+/// texture coordinates, so filtering and mipmapping don't blend in the empty texels.
+const DILATION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What is baked into the texels of a lightmap.
+pub enum BakeMode {
+    /// The light arriving at the surface, weighted by the cosine to the normal, as reflected
+    /// by a white diffuse surface. Multiplying it with the albedo gives the diffuse light.
+    Irradiance,
+    /// The fraction of the light from the hemisphere above the surface that isn't blocked
+    /// by surfaces closer than the given distance, weighted by the cosine to the normal.
+    AmbientOcclusion(Float),
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A texel of a lightmap covered by a mesh, with the point of the mesh at its center.
+pub struct Texel {
+    pub x: u32,
+    pub y: u32,
+    /// The point of the mesh whose texture coordinates are at the center of the texel.
+    pub point: Point3,
+    /// The smooth shading normal at the point.
+    pub normal: Vec3,
+    /// The normal of the triangle the point is on, which the baked light comes from above.
+    pub face_normal: Vec3,
+}
+
+/// Rasterizes the triangles of a mesh in the space of its texture coordinates, returning
+/// the texels of a lightmap of the given dimensions whose centers they cover, row by row.
+/// Where triangles overlap in texture space, the first one covers the texel. The points
+/// of the texels are where the mesh is, so it must be placed in the scene untransformed.
+pub fn texels(mesh: &Mesh, width: u32, height: u32) -> Result<Vec<Texel>, &'static str> {
+    if mesh.uvs.is_empty() {
+        return Err("mesh has no texture coordinates");
+    }
+
+    let normals = mesh.vertex_normals();
+    let mut covered = vec![false; (width * height) as usize];
+    let mut texels = Vec::new();
+
+    // Split every polygon into a fan of triangles around its first vertex, like mesh objects.
+    // Faces with fewer than three vertices have no triangles.
+    let triangles = mesh
+        .faces
+        .iter()
+        .filter(|face| face.len() >= 3)
+        .flat_map(|face| (1..face.len() - 1).map(|i| [face[0], face[i], face[i + 1]]));
+
+    for [a, b, c] in triangles {
+        // The corners of the triangle in texels.
+        let [(x0, y0), (x1, y1), (x2, y2)] = [a, b, c].map(|vertex| {
+            let (u, v) = mesh.uvs[vertex];
+            (u * width as Float, v * height as Float)
+        });
+
+        let area = (x1 - x0) * (y2 - y0) - (x2 - x0) * (y1 - y0);
+        if area.abs() <= Float::EPSILON {
+            continue;
+        }
+
+        // Triangles without area on the mesh, like at the poles of spheres, have no light to bake.
+        let face_normal =
+            (mesh.positions[b] - mesh.positions[a]).cross(mesh.positions[c] - mesh.positions[a]);
+        if face_normal.near_zero() {
+            continue;
+        }
+        let face_normal = face_normal.unit();
+
+        let texel_range = |low: Float, high: Float, size: u32| {
+            let start = (low - 0.5).ceil().max(0.0) as u32;
+            let end = ((high - 0.5).floor() + 1.0).clamp(0.0, size as Float) as u32;
+            start..end
+        };
+
+        for y in texel_range(y0.min(y1).min(y2), y0.max(y1).max(y2), height) {
+            for x in texel_range(x0.min(x1).min(x2), x0.max(x1).max(x2), width) {
+                let idx = (y * width + x) as usize;
+                if covered[idx] {
+                    continue;
+                }
+
+                // The barycentric coordinates of the center of the texel.
+                let (px, py) = (x as Float + 0.5, y as Float + 0.5);
+                let b1 = ((px - x0) * (y2 - y0) - (x2 - x0) * (py - y0)) / area;
+                let b2 = ((x1 - x0) * (py - y0) - (px - x0) * (y1 - y0)) / area;
+                let b0 = 1.0 - b1 - b2;
+                if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 {
+                    continue;
+                }
+
+                let mut normal = normals[a] * b0 + normals[b] * b1 + normals[c] * b2;
+                if normal.near_zero() {
+                    normal = face_normal;
+                }
+
+                covered[idx] = true;
+                texels.push(Texel {
+                    x,
+                    y,
+                    point: mesh.positions[a] * b0 + mesh.positions[b] * b1 + mesh.positions[c] * b2,
+                    normal: normal.unit(),
+                    face_normal,
+                });
+            }
+        }
+    }
+
+    texels.sort_by_key(|texel| (texel.y, texel.x));
+    Ok(texels)
+}
+
+/// Extends the baked texels into the empty texels next to them, averaging the baked
+/// neighbours of every empty texel, one texel at a time.
+pub fn dilate(fb: &mut FrameBuffer, covered: &mut [bool]) {
+    let (width, height) = (fb.width, fb.height);
+
+    for _ in 0..DILATION {
+        let mut grown = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if covered[(y * width + x) as usize] {
+                    continue;
+                }
+
+                let mut sum = Vec3::ZERO;
+                let mut count = 0;
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                        if covered[(ny * width + nx) as usize] {
+                            sum += fb[(nx, ny)];
+                            count += 1;
+                        }
+                    }
+                }
+
+                if count > 0 {
+                    grown.push((x, y, sum / count as Float));
+                }
+            }
+        }
+
+        for (x, y, color) in grown {
+            fb[(x, y)] = color;
+            covered[(y * width + x) as usize] = true;
+        }
+    }
+}
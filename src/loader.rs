@@ -0,0 +1,201 @@
+use std::{
+    fmt::Display,
+    ops::Index,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    imgbuf::ImageBuffer,
+    mesh::Mesh,
+    resources::{Resources, TextureId},
+    textures::image::ImageSampling,
+};
+
+#[derive(Debug, Default)]
+/// A batch of image textures and meshes, which are loaded and decoded in parallel.
+pub struct AssetLoader {
+    /// The paths of the queued assets.
+    assets: Vec<(AssetKind, PathBuf)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The kind of a queued asset.
+enum AssetKind {
+    Texture(ImageSampling),
+    Mesh,
+}
+
+/// A decoded asset.
+enum Asset {
+    Texture(ImageBuffer),
+    Mesh(Mesh),
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A placeholder for an image texture queued in an asset loader.
+pub struct TextureSlot(usize);
+
+#[derive(Debug, Clone, Copy)]
+/// A placeholder for a mesh queued in an asset loader.
+pub struct MeshSlot(usize);
+
+impl AssetLoader {
+    /// Creates a new empty asset loader.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an sRGB encoded image texture to be loaded from the file at the given path.
+    pub fn add_texture<T: ToString>(&mut self, path: T) -> TextureSlot {
+        self.add_texture_with_sampling(path, ImageSampling::default())
+    }
+
+    /// Queues an image texture with the given sampling settings to be loaded from the file at the given path.
+    pub fn add_texture_with_sampling<T: ToString>(
+        &mut self,
+        path: T,
+        sampling: ImageSampling,
+    ) -> TextureSlot {
+        self.assets.push((
+            AssetKind::Texture(sampling),
+            PathBuf::from(path.to_string()),
+        ));
+        TextureSlot(self.assets.len() - 1)
+    }
+
+    /// Queues a mesh to be loaded from the Wavefront OBJ file at the given path.
+    pub fn add_mesh<T: ToString>(&mut self, path: T) -> MeshSlot {
+        self.assets
+            .push((AssetKind::Mesh, PathBuf::from(path.to_string())));
+        MeshSlot(self.assets.len() - 1)
+    }
+
+    /// Loads all queued assets in parallel and adds the textures to the resources.
+    /// If any asset fails to load, nothing is added and the errors of all failed assets are returned.
+    pub fn load(self, resources: &mut Resources) -> Result<LoadedAssets, Vec<LoadError>> {
+        let next = AtomicUsize::new(0);
+        let workers = thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1)
+            .min(self.assets.len());
+
+        // Every worker takes the next queued asset until none are left.
+        let mut results: Vec<(usize, Result<Asset, &'static str>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+
+                        loop {
+                            let idx = next.fetch_add(1, Ordering::Relaxed);
+                            let Some((kind, path)) = self.assets.get(idx) else {
+                                break;
+                            };
+
+                            let _span =
+                                tracing::info_span!("load asset", path = %path.display()).entered();
+
+                            let asset = match kind {
+                                AssetKind::Texture(_) => {
+                                    ImageBuffer::load(path.display()).map(Asset::Texture)
+                                }
+                                AssetKind::Mesh => Mesh::load_obj(path.display()).map(Asset::Mesh),
+                            };
+                            results.push((idx, asset));
+                        }
+
+                        results
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("asset loader thread panicked"))
+                .collect()
+        });
+        results.sort_by_key(|(idx, _)| *idx);
+
+        let errors: Vec<LoadError> = results
+            .iter()
+            .zip(self.assets.iter())
+            .filter_map(|((_, result), (_, path))| {
+                let message = result.as_ref().err()?;
+                Some(LoadError {
+                    path: path.clone(),
+                    message,
+                })
+            })
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let assets = results
+            .into_iter()
+            .zip(self.assets)
+            .map(|((_, result), (kind, path))| match (result, kind) {
+                (Ok(Asset::Texture(image)), AssetKind::Texture(sampling)) => {
+                    LoadedAsset::Texture(resources.add_loaded_image_texture(image, path, sampling))
+                }
+                (Ok(Asset::Mesh(mesh)), _) => LoadedAsset::Mesh(mesh),
+                _ => unreachable!("failed assets were reported"),
+            })
+            .collect();
+
+        Ok(LoadedAssets { assets })
+    }
+}
+
+#[derive(Debug)]
+/// An asset that was added to the resources or loaded on its own.
+enum LoadedAsset {
+    Texture(TextureId),
+    Mesh(Mesh),
+}
+
+#[derive(Debug)]
+/// The assets loaded by an asset loader, indexed by the slots returned when queueing them.
+pub struct LoadedAssets {
+    /// The loaded assets in the order they were queued.
+    assets: Vec<LoadedAsset>,
+}
+
+impl LoadedAssets {
+    /// Returns the identifier of a loaded image texture.
+    pub fn texture(&self, slot: TextureSlot) -> TextureId {
+        match self.assets[slot.0] {
+            LoadedAsset::Texture(id) => id,
+            LoadedAsset::Mesh(_) => unreachable!("texture slot refers to a mesh"),
+        }
+    }
+}
+
+impl Index<MeshSlot> for LoadedAssets {
+    type Output = Mesh;
+
+    fn index(&self, slot: MeshSlot) -> &Self::Output {
+        match &self.assets[slot.0] {
+            LoadedAsset::Mesh(mesh) => mesh,
+            LoadedAsset::Texture(_) => unreachable!("mesh slot refers to a texture"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An error that occurred while loading an asset.
+pub struct LoadError {
+    /// The path of the asset that failed to load.
+    pub path: PathBuf,
+    /// A description of the error.
+    pub message: &'static str,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
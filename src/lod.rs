@@ -0,0 +1,121 @@
+use crate::{
+    aabb::Aabb,
+    camera::Camera,
+    float::Float,
+    mesh::Mesh,
+    objects::mesh::MeshObject,
+    resources::MaterialId,
+    scene::{ObjectId, Scene},
+};
+
+/// The fraction of the triangles of the previous level each level of detail keeps,
+/// a mesh twice as far away covers a quarter of the pixels.
+const LEVEL_RATIO: Float = 0.25;
+
+/// The fewest triangles a level of detail is simplified to.
+const MIN_TRIANGLES: usize = 64;
+
+#[derive(Debug, Clone)]
+/// A mesh with simplified levels of detail, of which the coarsest one that still has
+/// enough triangles for the size the mesh appears at in the image can be chosen.
+pub struct LodMesh {
+    /// The levels of detail, starting with the full mesh.
+    levels: Vec<Mesh>,
+    /// The bounding box of the full mesh.
+    bounding_box: Aabb,
+}
+
+impl LodMesh {
+    /// Creates up to the given number of levels of detail, each simplified from the previous
+    /// one to a quarter of its triangles. The first level is the mesh itself.
+    pub fn new(mesh: Mesh, levels: usize) -> Self {
+        let bounding_box = mesh
+            .positions
+            .iter()
+            .fold(Aabb::EMPTY, |bounding_box, &position| {
+                Aabb::union(&bounding_box, &Aabb::from_point(position))
+            });
+
+        let mut chain = vec![mesh];
+        while chain.len() < levels {
+            let previous = chain.last().unwrap().triangle_count();
+            let target = (previous as Float * LEVEL_RATIO) as usize;
+            if target < MIN_TRIANGLES {
+                break;
+            }
+
+            let simplified = chain.last().unwrap().simplify(target);
+            chain.push(simplified);
+        }
+
+        Self {
+            levels: chain,
+            bounding_box,
+        }
+    }
+
+    /// Returns the levels of detail, starting with the full mesh.
+    pub fn levels(&self) -> &[Mesh] {
+        &self.levels
+    }
+
+    /// Returns the bounding box of the full mesh.
+    pub fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    /// Returns the index of the coarsest level with at least `detail` triangles
+    /// per pixel the mesh covers, or the full mesh if no level has enough.
+    pub fn select(&self, pixels: Float, detail: Float) -> usize {
+        let needed = pixels * detail;
+
+        (0..self.levels.len())
+            .rev()
+            .find(|&level| self.levels[level].triangle_count() as Float >= needed)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A mesh object in a scene that is shown at one of the levels of detail of a mesh.
+pub struct LodInstance {
+    /// The object showing the mesh.
+    pub object: ObjectId,
+    /// The levels of detail of the mesh.
+    pub mesh: LodMesh,
+    /// The material of the mesh.
+    pub material: MaterialId,
+    /// The level of detail the object currently shows.
+    level: usize,
+}
+
+impl LodInstance {
+    /// Creates an instance whose object currently shows the given level of detail.
+    pub fn new(object: ObjectId, mesh: LodMesh, material: MaterialId, level: usize) -> Self {
+        Self {
+            object,
+            mesh,
+            material,
+            level,
+        }
+    }
+
+    /// Returns the level of detail the object currently shows.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Shows the level of detail chosen for the size of the mesh in the image of the camera,
+    /// with at least `detail` triangles per pixel it covers. The object is only replaced if
+    /// the level changes, which requires building the BVH of the scene again.
+    pub fn select(&mut self, scene: &mut Scene, camera: &Camera, detail: Float) {
+        let pixels = camera.screen_area(&self.mesh.bounding_box());
+        let level = self.mesh.select(pixels, detail);
+
+        if level != self.level {
+            let mesh = &self.mesh.levels()[level];
+            scene.replace(self.object, MeshObject::new(mesh, self.material));
+            self.level = level;
+        }
+    }
+}
@@ -1,30 +1,51 @@
-use camera::Camera;
-use console::{style, Emoji};
-use imgbuf::ImageBuffer;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use console::{style, Emoji, Term};
 use indicatif::{ProgressBar, ProgressStyle};
-use materials::{dielectric::DielectricMaterial, lambertian::LambertianMaterial};
-use objects::sphere::SphereObject;
-use resources::Resources;
-use scene::Scene;
-use textures::{image::ImageTexture, solid::SolidTexture};
-use vector::Vec3;
-
-pub mod aabb;
-pub mod bvh;
-pub mod camera;
-pub mod hittable;
-pub mod imgbuf;
-pub mod interval;
-pub mod material;
-pub mod materials;
-pub mod objects;
-pub mod random;
-pub mod ray;
-pub mod resources;
-pub mod scene;
-pub mod texture;
-pub mod textures;
-pub mod vector;
+#[cfg(feature = "viewer")]
+use raytracer_base::viewer::LiveViewer;
+use raytracer_base::{
+    accumulation::Accumulation,
+    animation::Animation,
+    bvh,
+    camera::{Camera, CameraBuilder},
+    cancel::CancelToken,
+    config::RenderConfig,
+    environments::gradient::GradientEnvironment,
+    exposure::ExposureStats,
+    float::Float,
+    framebuffer::FrameBuffer,
+    heatmap::VarianceHeatmap,
+    imgbuf::{colorspace::ColorSpace, compare, terminal::TerminalFormat, ImageBuffer},
+    job::Job,
+    light_group::LightGroupLayers,
+    lightmap::BakeMode,
+    loader::AssetLoader,
+    lod::LodInstance,
+    materials::{dielectric::DielectricMaterial, lambertian::LambertianMaterial},
+    mesh::Mesh,
+    nan_check,
+    objects::{plane::PlaneObject, sphere::SphereObject},
+    priority::PriorityMap,
+    profiler,
+    progress::ProgressEvent,
+    resources::Resources,
+    scene::Scene,
+    scene_file::SceneFile,
+    scenes::RandomSpheres,
+    temporal::TemporalAccumulator,
+    textures::solid::SolidTexture,
+    vec3,
+    vector::Color,
+    watch::FileWatcher,
+};
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
 
 static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍 ", "");
 static TRUCK: Emoji<'_, '_> = Emoji("🚚 ", "");
@@ -32,87 +53,956 @@ static CLIP: Emoji<'_, '_> = Emoji("🔗 ", "");
 static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "");
 static PACKAGE: Emoji<'_, '_> = Emoji("📦 ", "");
 
+/// The interval at which watched files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The default width in pixels of sixel previews printed to the terminal.
+const SIXEL_PREVIEW_WIDTH: u32 = 640;
+
+/// The sample count of the previews rendered in watch mode.
+const WATCH_SAMPLES: u32 = 8;
+
+/// The triangles per covered pixel of the levels of detail of meshes in previews.
+const WATCH_DETAIL: Float = 0.25;
+
+/// The render config file loaded from the working directory without `--config`.
+const CONFIG_FILE: &str = "raytracer.toml";
+
+/// The default width and height in texels of baked lightmaps.
+const BAKE_SIZE: u32 = 512;
+
+/// The pairs of flags of the same render mode that can't be combined, as one would be ignored.
+const CONFLICTING_FLAGS: [(&str, &str); 1] = [("--time", "--guide")];
+
 fn main() {
-    // Create a new resources object to store textures and materials.
-    println!(
-        "{} {}Loading resources...",
-        style("[1/5]").bold().dim(),
-        LOOKING_GLASS
-    );
-    let mut resources = Resources::default();
+    let args: Vec<String> = std::env::args().collect();
 
-    let glass_material = resources.add_material(DielectricMaterial::new(1.5));
+    // Log the spans of the render stages with their durations, `debug` also logs every tile.
+    if let Some(level) = option_value(&args, "--trace") {
+        let level: Level = level.parse().expect("trace level must be a log level");
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(std::io::stderr)
+            .init();
+    }
 
-    let rock_texture = resources.add_texture(ImageTexture::new(
-        ImageBuffer::load("textures/rock.png").expect("failed to load rock texture"),
-    ));
-    let rock_material = resources.add_material(LambertianMaterial::new(rock_texture));
+    // Merge the accumulations of independent renders instead of rendering.
+    if args.get(1).map(String::as_str) == Some("merge") {
+        merge(&args[2..]);
+        return;
+    }
 
-    let green_texture = resources.add_texture(SolidTexture::new(vec3!(0.0, 1.0, 0.0)));
-    let green_material = resources.add_material(LambertianMaterial::new(green_texture));
+    // Measure how closely an image matches a reference instead of rendering.
+    if args.get(1).map(String::as_str) == Some("compare") {
+        compare_images(&args[2..]);
+        return;
+    }
 
-    // Create a new scene with a background color of blue.
+    // Render the frames of job files instead of a single image.
+    if args.get(1).map(String::as_str) == Some("job") {
+        run_jobs(&args[2..]);
+        return;
+    }
+
+    let mode = render_mode(&args);
+
+    // Collect render statistics when requested.
+    if args.iter().any(|arg| arg == "--profile") {
+        profiler::enable();
+    }
+
+    // Check the samples for NaN and infinite values, optionally painting their pixels magenta.
+    let highlight_nan = args.iter().any(|arg| arg == "--highlight-nan");
+    if highlight_nan || args.iter().any(|arg| arg == "--check-nan") {
+        nan_check::enable(highlight_nan);
+    }
+
+    // Load the BVHs of earlier renders of the same scene instead of building them again.
+    if let Some(dir) = option_value(&args, "--bvh-cache") {
+        bvh::set_cache_dir(Some(dir.into()));
+    }
+
+    // Load the scene from a scene file, or set up the builtin scene.
     println!(
-        "{} {}Setting up scene...",
-        style("[2/5]").bold().dim(),
-        TRUCK
+        "{} {}Loading scene...",
+        style("[1/4]").bold().dim(),
+        LOOKING_GLASS
     );
 
-    let sky_background = |dir: Vec3| {
-        let unit_dir = dir.unit();
-        let a = 0.5 * (unit_dir.y + 1.0);
-
-        (1.0 - a) * vec3!(1, 1, 1) + a * vec3!(0.5, 0.7, 1.0)
+    let scene_path = option_value(&args, "--scene").map(Path::new);
+    let random_spheres = option_value(&args, "--random-spheres")
+        .map(|count| count.parse().expect("sphere count must be a number"));
+    let SceneFile {
+        mut scene,
+        mut resources,
+        camera: mut builder,
+        lods,
+        ..
+    } = match (scene_path, random_spheres) {
+        (Some(path), _) => SceneFile::load(path).unwrap_or_else(|err| {
+            eprintln!("failed to load scene {}: {err}", path.display());
+            std::process::exit(1);
+        }),
+        // Generate the final scene of "Ray Tracing in One Weekend" with the given number of spheres.
+        (None, Some(count)) => RandomSpheres::new().with_count(count).build(),
+        (None, None) => builtin_scene(),
     };
 
-    let mut scene = Scene::new(sky_background);
+    // The render settings of the project are overridden by the environment, then by the flags.
+    let config = render_config(&args);
+    config.configure(&mut builder);
+    let output = config.output.as_path();
 
-    scene.add(SphereObject::new(vec3!(0, 0, -1), 0.5, glass_material));
-    scene.add(SphereObject::new(vec3!(0, 1, -1), 0.5, rock_material));
-    scene.add(SphereObject::new(
-        vec3!(0, -100.5, -1),
-        100.0,
-        green_material,
-    ));
+    // Brighten the scene with grey ambient light, like for quick previews of dark scenes.
+    if let Some(ambient) = option_value(&args, "--ambient") {
+        let ambient: Float = ambient.parse().expect("ambient must be a number");
+        scene.set_ambient(vec3!(ambient));
+    }
+
+    let preview = preview_settings(&args);
 
     // Build the scene with a bounding volume hierarchy.
     println!(
         "{} {}Building scene BVH...",
-        style("[3/5]").bold().dim(),
+        style("[2/4]").bold().dim(),
         CLIP
     );
-    scene.build_bvh();
+    profiler::time_stage("build bvh", || scene.build_bvh());
+
+    // Print the statistics of the scene instead of rendering it.
+    if args.iter().any(|arg| arg == "--stats") {
+        print!("{}", scene.stats(&resources));
+        return;
+    }
+
+    // Keep rendering previews whenever the scene changes.
+    if mode == RenderMode::Watch {
+        watch(
+            scene_path, &config, preview, builder, scene, resources, lods,
+        );
+        return;
+    }
 
-    // Setup the camera.
     println!(
         "{} {}Rendering scene...",
-        style("[4/5]").bold().dim(),
+        style("[3/4]").bold().dim(),
         SPARKLE
     );
 
-    let camera = Camera::builder()
+    let camera = builder.build();
+
+    // Show the images of the render in a browser, the progressive passes update it as they finish.
+    #[cfg(feature = "viewer")]
+    let viewer = option_value(&args, "--serve").map(|addr| {
+        let viewer = LiveViewer::bind(addr).expect("failed to start live viewer");
+        println!("Serving live view at http://{}", viewer.local_addr());
+        viewer
+    });
+
+    // Setup the progress bar.
+    let bar_style = ProgressStyle::with_template(
+        "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {percent}% ({msg}) ",
+    )
+    .unwrap();
+    let bar = ProgressBar::new(100).with_style(bar_style);
+
+    let progress = |event| {
+        if let ProgressEvent::SamplesCompleted {
+            completed,
+            total,
+            remaining,
+            ..
+        } = event
+        {
+            bar.set_length(total);
+            bar.set_position(completed);
+            bar.set_message(format!("{}s left", remaining.as_secs()));
+        }
+    };
+
+    // Render the scene with the camera and resources, for a fixed time if a budget is given.
+    let budget = option_value(&args, "--time").map(|time| {
+        parse_duration(time).expect("time must be a number of seconds, or end in ms, s, m or h")
+    });
+    // Path guiding learns the indirect light over the given number of passes.
+    let guide_passes = option_value(&args, "--guide").map(|passes| {
+        passes
+            .parse::<u32>()
+            .expect("guide passes must be a number")
+    });
+
+    let rendered = profiler::time_stage("render", || match mode {
+        // Bake the light arriving at a mesh of the scene into a lightmap instead of rendering the view.
+        // The mesh file has to be the one placed in the scene without a transformation.
+        RenderMode::Bake => {
+            let path = option_value(&args, "--bake").expect("bake needs a mesh");
+            let mesh = Mesh::load_obj(path).expect("failed to load mesh to bake");
+            let size = option_value(&args, "--bake-size").map_or(BAKE_SIZE, |size| {
+                size.parse().expect("bake size must be a number")
+            });
+            let bake_mode = match option_value(&args, "--ao") {
+                None => BakeMode::Irradiance,
+                Some(distance) => BakeMode::AmbientOcclusion(
+                    distance.parse().expect("ao distance must be a number"),
+                ),
+            };
+
+            let fb = camera
+                .bake(&scene, &resources, &mesh, (size, size), bake_mode, progress)
+                .expect("failed to bake lightmap");
+            Rendered::Lightmap(fb)
+        }
+        // Preview the shapes of the surfaces shaded with a matcap image, with one ray per pixel.
+        RenderMode::Matcap => {
+            let path = option_value(&args, "--matcap").expect("matcap needs an image");
+            let matcap = resources
+                .add_image_texture(path)
+                .expect("failed to load matcap image");
+            Rendered::Matcap(camera.render_matcap(&scene, &resources, matcap))
+        }
+        // Render in front of a transparent background, for compositing the image onto a backplate.
+        RenderMode::Alpha => {
+            let (image, alpha) = camera.render_with_alpha(&scene, &resources, progress);
+            Rendered::Alpha(image, alpha)
+        }
+        // Render passes that clean up the important regions first, saving the image after every pass.
+        RenderMode::Progressive => {
+            let passes = option_value(&args, "--progressive")
+                .and_then(|passes| passes.parse().ok())
+                .expect("passes must be a number");
+            let (width, height) = (camera.image_width(), camera.image_height());
+            let priority = match option_value(&args, "--priority") {
+                None => PriorityMap::uniform(width, height),
+                Some("center") => PriorityMap::centered(width, height),
+                Some(path) => {
+                    let mask = ImageBuffer::load(path).expect("failed to load priority mask");
+                    PriorityMap::from_image(&mask, width, height)
+                }
+            };
+
+            let accumulation = camera.render_progressive(
+                &scene,
+                &resources,
+                &priority,
+                passes,
+                progress,
+                |average| {
+                    let preview = camera.develop(&Accumulation::new(average.clone(), 0));
+                    #[cfg(feature = "viewer")]
+                    if let Some(viewer) = &viewer {
+                        if let Err(err) = viewer.publish(&preview) {
+                            eprintln!("failed to publish pass: {err}");
+                        }
+                    }
+                    if let Err(err) = preview.save_atomic(output) {
+                        eprintln!("failed to save pass: {err}");
+                    }
+                },
+            );
+            Rendered::Accumulation(accumulation)
+        }
+        // Render the variances of the pixels into a false color image next to the image.
+        RenderMode::Heatmap => {
+            let (image, heatmap) = camera.render_with_heatmap(&scene, &resources, progress);
+            Rendered::Heatmap(image, heatmap)
+        }
+        // Render the light groups into their own layers, and save each of them next to the image.
+        RenderMode::LightGroups => Rendered::LightGroups(camera.render_light_groups(
+            &scene,
+            &resources,
+            &CancelToken::new(),
+            progress,
+        )),
+        RenderMode::Accumulation => Rendered::Accumulation(match (budget, guide_passes) {
+            (Some(budget), _) => camera.render_for(&scene, &resources, budget, progress),
+            (None, Some(passes)) => camera.render_guided(&scene, &resources, passes, progress),
+            (None, None) => camera.render_accumulation(&scene, &resources, progress),
+        }),
+        RenderMode::Watch => unreachable!("watch mode renders previews"),
+    });
+
+    bar.finish_and_clear();
+
+    if let Rendered::Accumulation(accumulation) = &rendered {
+        if budget.is_some() || guide_passes.is_some() {
+            println!("Rendered {} samples per pixel", accumulation.samples);
+        }
+    }
+
+    let what = match rendered {
+        Rendered::Lightmap(_) => "lightmap",
+        Rendered::Heatmap(..) | Rendered::LightGroups(_) => "images",
+        _ => "image",
+    };
+    println!(
+        "{} {}Saving {what}...",
+        style("[4/4]").bold().dim(),
+        PACKAGE
+    );
+
+    let saved = profiler::time_stage("save", || match rendered {
+        // Keep the exact light next to the sRGB encoded image textures expect.
+        Rendered::Lightmap(fb) => {
+            fb.save_pfm(output.with_extension("pfm").display())?;
+            fb.quantize_to(&ColorSpace::SRGB).save(output.display())
+        }
+        Rendered::Matcap(fb) => fb.quantize_to(&ColorSpace::SRGB).save(output.display()),
+        Rendered::Alpha(image, alpha) => image.save_with_alpha(&alpha, output.display()),
+        Rendered::Heatmap(image, heatmap) => {
+            image.save(output.display())?;
+            heatmap
+                .variance_image()
+                .save(side_output(output, "variance").display())
+        }
+        Rendered::LightGroups(layers) => {
+            for (name, layer) in layers.names.iter().zip(layers.layers.iter()) {
+                let alone = Accumulation::new(layer.clone(), layers.samples);
+                camera
+                    .develop(&alone)
+                    .save(side_output(output, name).display())?;
+            }
+
+            camera.develop(&layers.mix(&[])).save(output.display())
+        }
+        Rendered::Accumulation(accumulation) => {
+            // Keep the raw samples, so they can be merged with other renders later.
+            if let Some(path) = option_value(&args, "--accumulation") {
+                accumulation.save(path)?;
+            }
+
+            let fb = camera.develop(&accumulation);
+
+            // Analyze the exposure of the render, as JSON or as a histogram drawn onto a copy of the image.
+            if let Some(path) = option_value(&args, "--exposure-stats") {
+                let stats = ExposureStats::new(&accumulation.buffer);
+                if path.ends_with(".json") {
+                    fs::write(path, stats.to_json())
+                        .map_err(|_| "failed to save exposure statistics")?;
+                } else {
+                    let mut overlay = ImageBuffer::with_data(fb.width, fb.height, fb.data.clone());
+                    stats.draw_overlay(&mut overlay);
+                    overlay.save(path)?;
+                }
+            }
+
+            if let Some((format, width)) = preview {
+                print!("{}", fb.terminal_preview(format, width));
+            }
+
+            #[cfg(feature = "viewer")]
+            if let Some(viewer) = &viewer {
+                if let Err(err) = viewer.publish(&fb) {
+                    eprintln!("failed to publish image: {err}");
+                }
+            }
+
+            fb.save(output.display())
+        }
+    });
+
+    if let Err(err) = saved {
+        eprintln!("failed to save {what}: {err}");
+        std::process::exit(1);
+    }
+
+    print_reports();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The kinds of renders, chosen by their flags. Without any of them, the samples of the
+/// scene are accumulated into an image.
+enum RenderMode {
+    Bake,
+    Matcap,
+    Alpha,
+    Progressive,
+    Heatmap,
+    LightGroups,
+    Watch,
+    Accumulation,
+}
+
+impl RenderMode {
+    /// The flags choosing a render mode, with the modes they choose.
+    const FLAGS: [(&'static str, RenderMode); 7] = [
+        ("--bake", RenderMode::Bake),
+        ("--matcap", RenderMode::Matcap),
+        ("--alpha", RenderMode::Alpha),
+        ("--progressive", RenderMode::Progressive),
+        ("--heatmap", RenderMode::Heatmap),
+        ("--light-groups", RenderMode::LightGroups),
+        ("--watch", RenderMode::Watch),
+    ];
+
+    /// Returns the flag choosing the mode, `None` for accumulating the samples.
+    fn flag(self) -> Option<&'static str> {
+        Self::FLAGS
+            .iter()
+            .find(|(_, mode)| *mode == self)
+            .map(|(flag, _)| *flag)
+    }
+
+    /// Returns the flags the mode supports that some other modes don't.
+    fn options(self) -> &'static [&'static str] {
+        match self {
+            RenderMode::Bake => &["--bake-size", "--ao"],
+            RenderMode::Progressive => &[
+                "--priority",
+                "--accumulation",
+                "--exposure-stats",
+                "--preview",
+                "--preview-width",
+                "--serve",
+            ],
+            RenderMode::Watch => &["--preview", "--preview-width"],
+            RenderMode::Accumulation => &[
+                "--time",
+                "--guide",
+                "--accumulation",
+                "--exposure-stats",
+                "--preview",
+                "--preview-width",
+                "--serve",
+            ],
+            RenderMode::Matcap
+            | RenderMode::Alpha
+            | RenderMode::Heatmap
+            | RenderMode::LightGroups => &[],
+        }
+    }
+}
+
+/// The result of a render, which is saved according to its mode.
+enum Rendered {
+    /// The light baked into a lightmap.
+    Lightmap(FrameBuffer),
+    /// A preview shaded with a matcap.
+    Matcap(FrameBuffer),
+    /// An image with its alpha channel.
+    Alpha(ImageBuffer, ImageBuffer),
+    /// An image with the variances of its pixels.
+    Heatmap(ImageBuffer, VarianceHeatmap),
+    /// The layers of the light groups.
+    LightGroups(LightGroupLayers),
+    /// The accumulated samples of the image.
+    Accumulation(Accumulation),
+}
+
+/// Returns the render mode chosen by the flags. Exits if several modes are chosen, or if
+/// flags are given that the mode would ignore.
+fn render_mode(args: &[String]) -> RenderMode {
+    let chosen: Vec<_> = RenderMode::FLAGS
+        .iter()
+        .filter(|(flag, _)| args.iter().any(|arg| arg == flag))
+        .collect();
+
+    let mode = match chosen.as_slice() {
+        [] => RenderMode::Accumulation,
+        [(_, mode)] => *mode,
+        [(first, _), (second, _), ..] => {
+            eprintln!("{first} can't be combined with {second}");
+            std::process::exit(1);
+        }
+    };
+
+    let modes = RenderMode::FLAGS
+        .iter()
+        .map(|(_, mode)| *mode)
+        .chain([RenderMode::Accumulation]);
+    for arg in args.iter().map(String::as_str) {
+        let owners: Vec<_> = modes
+            .clone()
+            .filter(|owner| owner.options().contains(&arg))
+            .collect();
+        if owners.is_empty() || owners.contains(&mode) {
+            continue;
+        }
+
+        match mode.flag() {
+            Some(flag) => eprintln!("{arg} can't be combined with {flag}"),
+            None => {
+                let flags: Vec<_> = owners.iter().filter_map(|owner| owner.flag()).collect();
+                eprintln!("{arg} requires {}", flags.join(" or "));
+            }
+        }
+        std::process::exit(1);
+    }
+
+    for (first, second) in CONFLICTING_FLAGS {
+        if args.iter().any(|arg| arg == first) && args.iter().any(|arg| arg == second) {
+            eprintln!("{first} can't be combined with {second}");
+            std::process::exit(1);
+        }
+    }
+
+    mode
+}
+
+/// Prints the statistics of the profiler and the invalid samples, if they were collected.
+fn print_reports() {
+    if profiler::is_enabled() {
+        println!("{}", profiler::report());
+    }
+
+    if nan_check::is_enabled() {
+        println!("Found {} invalid samples", nan_check::invalid_count());
+        if let Some(invalid) = nan_check::first_invalid() {
+            println!("The first was {invalid}");
+        }
+    }
+}
+
+/// Sets up the builtin scene, which is rendered when no scene file is given.
+fn builtin_scene() -> SceneFile {
+    let mut resources = Resources::default();
+
+    let glass_material = resources.add_material(DielectricMaterial::new(1.5));
+
+    // Load the image textures and meshes of the scene in parallel.
+    let mut loader = AssetLoader::new();
+    let rock_slot = loader.add_texture("textures/rock.png");
+
+    let assets = loader.load(&mut resources).unwrap_or_else(|errors| {
+        for error in errors {
+            eprintln!("failed to load asset {error}");
+        }
+        std::process::exit(1);
+    });
+
+    let rock_texture = assets.texture(rock_slot);
+    let rock_material = resources.add_material(LambertianMaterial::new(rock_texture));
+
+    let green_texture = resources.add_texture(SolidTexture::new(vec3!(0.0, 1.0, 0.0)));
+    let green_material = resources.add_material(LambertianMaterial::new(green_texture));
+
+    // Create a new scene with a background color of blue.
+    let sky_background =
+        resources.add_environment(GradientEnvironment::new(Color::WHITE, vec3!(0.5, 0.7, 1.0)));
+
+    let mut scene = Scene::new(sky_background);
+
+    scene.add(SphereObject::new(vec3!(0, 0, -1), 0.5, glass_material));
+    scene.add(SphereObject::new(vec3!(0, 1, -1), 0.5, rock_material));
+    scene.add(PlaneObject::new(
+        vec3!(0, -0.5, 0),
+        vec3!(0, 1, 0),
+        green_material,
+    ));
+
+    let mut camera = Camera::builder();
+    camera
         .with_look_from(vec3!(2, 0.5, 2))
         .with_look_at(vec3!(0, 1, -1))
         .with_aspect_ratio(16.0 / 9.0)
         .with_image_width(1280)
         .with_vfov(90.0)
-        .with_sample_count(100)
-        .build();
+        .with_sample_count(100);
+
+    SceneFile {
+        scene,
+        resources,
+        camera,
+        animation: Animation::new(),
+        lods: Vec::new(),
+    }
+}
+
+/// Renders previews of the scene whenever its scene file or one of its texture files changes,
+/// until the process is stopped. A render is restarted if a file changes while it is running.
+/// Previews are rendered with fewer samples and coarser meshes, and replace the output image
+/// atomically, so an image viewer can refresh it while watching.
+fn watch(
+    scene_path: Option<&Path>,
+    config: &RenderConfig,
+    preview: Option<(TerminalFormat, u32)>,
+    mut builder: CameraBuilder,
+    mut scene: Scene,
+    mut resources: Resources,
+    mut lods: Vec<LodInstance>,
+) {
+    let mut watcher = FileWatcher::new();
+    if let Some(path) = scene_path {
+        watcher.watch(path);
+    }
+    for path in resources.texture_files() {
+        watcher.watch(path);
+    }
+
+    // Render the first preview right away.
+    let mut changed = Vec::new();
+    let mut outdated = true;
+
+    loop {
+        if !outdated {
+            println!(
+                "{} {}Watching scene for changes...",
+                style("[watch]").bold().dim(),
+                LOOKING_GLASS
+            );
+            changed = watcher.wait(WATCH_INTERVAL);
+        }
+
+        let scene_changed = scene_path.is_some_and(|path| changed.iter().any(|file| file == path));
+
+        if let (true, Some(path)) = (scene_changed, scene_path) {
+            println!(
+                "{} {}Reloading scene...",
+                style("[watch]").bold().dim(),
+                TRUCK
+            );
+
+            match SceneFile::load(path) {
+                Ok(file) => {
+                    scene = file.scene;
+                    resources = file.resources;
+                    builder = file.camera;
+                    lods = file.lods;
+
+                    // Keep the render settings, so previews stay comparable.
+                    config.configure(&mut builder);
+
+                    scene.build_bvh();
+                    for path in resources.texture_files() {
+                        watcher.watch(path);
+                    }
+                }
+                Err(err) => {
+                    eprintln!("failed to load scene {}: {err}", path.display());
+                    outdated = false;
+                    continue;
+                }
+            }
+        } else if !changed.is_empty() {
+            println!(
+                "{} {}Reloading {} texture(s)...",
+                style("[watch]").bold().dim(),
+                TRUCK,
+                changed.len()
+            );
+            if let Err(err) = resources.reload_textures(&changed) {
+                eprintln!("failed to reload textures: {err}");
+                outdated = false;
+                continue;
+            }
+        }
+
+        println!(
+            "{} {}Rendering preview...",
+            style("[watch]").bold().dim(),
+            SPARKLE
+        );
+
+        let camera = builder.clone().with_sample_count(WATCH_SAMPLES).build();
+        for lod in lods.iter_mut() {
+            lod.select(&mut scene, &camera, WATCH_DETAIL);
+        }
+
+        // Poll for changes while rendering, so the render can be restarted early.
+        let cancel = CancelToken::new();
+        let finished = CancelToken::new();
+        let (image, changed_during) = thread::scope(|scope| {
+            let poller = scope.spawn(|| loop {
+                if finished.is_cancelled() {
+                    return Vec::new();
+                }
+
+                let changed = watcher.changed();
+                if !changed.is_empty() {
+                    cancel.cancel();
+                    return changed;
+                }
+
+                thread::sleep(WATCH_INTERVAL);
+            });
+
+            let image = camera.render_cancellable(&scene, &resources, &cancel, |_| {});
+            finished.cancel();
+
+            (image, poller.join().expect("watch thread panicked"))
+        });
+
+        changed = changed_during;
+        outdated = !changed.is_empty();
+        if !outdated {
+            println!(
+                "{} {}Saving preview...",
+                style("[watch]").bold().dim(),
+                PACKAGE
+            );
+            if let Some((format, width)) = preview {
+                print!("{}", image.terminal_preview(format, width));
+            }
+            if let Err(err) = image.save_atomic(&config.output) {
+                eprintln!("failed to save image: {err}");
+            }
+        }
+    }
+}
+
+/// Loads the render settings from the file given with `--config`, or from `raytracer.toml` in the
+/// working directory if it exists, and overrides them with the environment and the flags.
+fn render_config(args: &[String]) -> RenderConfig {
+    let path =
+        option_value(args, "--config").or(Path::new(CONFIG_FILE).exists().then_some(CONFIG_FILE));
+
+    let mut config = match path {
+        Some(path) => RenderConfig::load(path).unwrap_or_else(|err| {
+            eprintln!("failed to load config {path}: {err}");
+            std::process::exit(1);
+        }),
+        None => RenderConfig::default(),
+    };
+
+    if let Err(err) = config.apply_env(std::env::vars()) {
+        eprintln!("invalid environment variable: {err}");
+        std::process::exit(1);
+    }
+
+    for key in RenderConfig::KEYS {
+        if let Some(value) = option_value(args, &format!("--{key}")) {
+            if let Err(err) = config.set(key, value) {
+                eprintln!("invalid --{key}: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    config
+}
+
+/// Returns the format and width of the terminal preview requested with `--preview ansi|sixel`,
+/// the width defaults to the terminal width for ANSI and can be set with `--preview-width`.
+fn preview_settings(args: &[String]) -> Option<(TerminalFormat, u32)> {
+    let format = match option_value(args, "--preview")? {
+        "ansi" => TerminalFormat::Ansi,
+        "sixel" => TerminalFormat::Sixel,
+        _ => {
+            eprintln!("preview must be ansi or sixel");
+            std::process::exit(1);
+        }
+    };
+
+    let width = match option_value(args, "--preview-width") {
+        Some(width) => width.parse().expect("preview width must be a number"),
+        None if format == TerminalFormat::Ansi => Term::stdout().size().1 as u32,
+        None => SIXEL_PREVIEW_WIDTH,
+    };
+
+    Some((format, width))
+}
+
+/// Parses a duration like `90`, `1.5s`, `500ms`, `2m` or `1h`, plain numbers are seconds.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, unit) = match text.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(idx) => text.split_at(idx),
+        None => (text, "s"),
+    };
+
+    let scale = match unit {
+        "ms" => 0.001,
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(number.parse::<f64>().ok()? * scale).ok()
+}
+
+/// Returns the path of an image saved next to the output, named after it with a suffix,
+/// like `renders/shot_variance.png` for `renders/shot.png`.
+fn side_output(output: &Path, suffix: &str) -> PathBuf {
+    // Suffixes like the names of light groups come from scene files, so they must not
+    // reach into other directories.
+    let suffix: String = suffix
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let stem = output
+        .file_stem()
+        .map_or("output".into(), |stem| stem.to_string_lossy());
+    output.with_file_name(format!("{stem}_{suffix}.png"))
+}
+
+/// Returns the value following the option with the given name.
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let idx = args.iter().position(|arg| arg == name)?;
+    args.get(idx + 1).map(String::as_str)
+}
+
+/// Renders the frames of job files one after another, usage: `job <job file>...`.
+/// A job that fails doesn't stop the following jobs, but the process exits with an error.
+fn run_jobs(paths: &[String]) {
+    if paths.is_empty() {
+        eprintln!("usage: job <job file>...");
+        std::process::exit(1);
+    }
+
+    // Load every job up front, so mistakes surface before hours of rendering.
+    let jobs: Vec<Job> = paths
+        .iter()
+        .map(|path| {
+            Job::load(path).unwrap_or_else(|err| {
+                eprintln!("failed to load job {path}: {err}");
+                std::process::exit(1);
+            })
+        })
+        .collect();
 
-    // Setup the progress bar.
     let bar_style = ProgressStyle::with_template(
-        "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta}) ",
+        "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] frame {pos}/{len} ",
     )
     .unwrap();
-    let bar = ProgressBar::new(camera.image_height() as u64).with_style(bar_style);
+    let mut failed = false;
 
-    // Render the scene with the camera and resources.
-    let fb = camera.render(&scene, &resources, |_| bar.inc(1));
+    for (idx, job) in jobs.iter().enumerate() {
+        println!(
+            "{} {}Rendering {}...",
+            style(format!("[{}/{}]", idx + 1, jobs.len())).bold().dim(),
+            SPARKLE,
+            paths[idx]
+        );
 
-    bar.finish_and_clear();
+        let SceneFile {
+            mut scene,
+            mut resources,
+            camera: mut builder,
+            animation,
+            ..
+        } = match SceneFile::load(&job.scene) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("failed to load scene {}: {err}", job.scene.display());
+                failed = true;
+                continue;
+            }
+        };
+        job.configure(&mut builder);
+
+        let bar = ProgressBar::new(job.frame_count()).with_style(bar_style.clone());
+        let mut temporal = job.temporal.map(TemporalAccumulator::new);
+
+        for frame in job.frames.clone() {
+            let time = job.frame_time(frame);
+            animation.apply(time, 1.0 / job.fps, &mut scene, &mut resources);
+
+            let camera = animation.camera(&builder, time);
+            let image = match temporal.as_mut() {
+                None => camera.render(&scene, &resources, |_| {}),
+                Some(temporal) => {
+                    // Blend the frame with the earlier frames, moved to where they are seen now.
+                    // Every frame continues the samples of the earlier ones, so their noise averages out.
+                    let camera = camera.pass(frame - job.frames.start());
+                    let accumulation = camera.render_accumulation(&scene, &resources, |_| {});
+                    let previous =
+                        animation.camera(&builder, job.frame_time(frame) - 1.0 / job.fps);
+                    let motion = camera.render_previous_motion_vectors(&scene, &previous);
+
+                    let blended = temporal.accumulate(&accumulation.buffer, &motion);
+                    camera.develop(&Accumulation::new(blended, accumulation.samples))
+                }
+            };
+
+            let path = job.output_path(frame);
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            if let Err(err) = image.save(path.display()) {
+                bar.suspend(|| eprintln!("failed to save frame {}: {err}", path.display()));
+                failed = true;
+            }
 
-    // Save the framebuffer to a file.
-    println!("{} {}Saving image...", style("[5/5]").bold().dim(), PACKAGE);
+            // The motion vectors point towards where the surfaces are seen in the next frame.
+            if let Some(path) = job.motion_path(frame) {
+                let next = animation.camera(&builder, job.frame_time(frame) + 1.0 / job.fps);
+                let motion = camera.render_motion_vectors(&scene, &next);
+
+                if let Err(err) = motion.save_pfm(path.display()) {
+                    bar.suspend(|| eprintln!("failed to save motion {}: {err}", path.display()));
+                    failed = true;
+                }
+            }
+
+            bar.inc(1);
+        }
+
+        bar.finish_and_clear();
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Prints the PSNR and SSIM of an image against a reference image,
+/// usage: `compare <reference> <image> [diff]`. The diff image shows where their structure differs.
+fn compare_images(args: &[String]) {
+    let [reference, image, diff @ ..] = args else {
+        eprintln!("usage: compare <reference> <image> [diff]");
+        std::process::exit(1);
+    };
+
+    let reference = ImageBuffer::load(reference).unwrap_or_else(|err| {
+        eprintln!("failed to load reference {reference}: {err}");
+        std::process::exit(1);
+    });
+    let image = ImageBuffer::load(image).unwrap_or_else(|err| {
+        eprintln!("failed to load image {image}: {err}");
+        std::process::exit(1);
+    });
+    let metrics = compare::metrics(&reference, &image).unwrap_or_else(|err| {
+        eprintln!("failed to compare images: {err}");
+        std::process::exit(1);
+    });
+
+    println!("PSNR: {:.2} dB", metrics.psnr);
+    println!("SSIM: {:.4}", metrics.ssim);
+
+    if let Some(path) = diff.first() {
+        if let Err(err) = metrics.diff.save(path) {
+            eprintln!("failed to save diff image {path}: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Merges saved accumulations into one image, usage: `merge <output> <accumulation>...`.
+/// The output is saved as an accumulation if it ends with `.racc`, and as an image otherwise.
+fn merge(args: &[String]) {
+    let [output, inputs @ ..] = args else {
+        eprintln!("usage: merge <output> <accumulation>...");
+        std::process::exit(1);
+    };
+
+    println!(
+        "{} {}Loading accumulations...",
+        style("[1/3]").bold().dim(),
+        LOOKING_GLASS
+    );
+    let accumulations: Vec<Accumulation> = inputs
+        .iter()
+        .map(|path| Accumulation::load(path).expect("failed to load accumulation"))
+        .collect();
+
+    println!(
+        "{} {}Merging accumulations...",
+        style("[2/3]").bold().dim(),
+        CLIP
+    );
+    let merged = Accumulation::merge(&accumulations).expect("failed to merge accumulations");
 
-    fb.save("output.png").unwrap();
+    println!("{} {}Saving image...", style("[3/3]").bold().dim(), PACKAGE);
+    if output.ends_with(".racc") {
+        merged.save(output).expect("failed to save accumulation");
+    } else {
+        merged
+            .buffer
+            .quantize()
+            .save(output)
+            .expect("failed to save image");
+    }
 }
@@ -1,11 +1,31 @@
 use std::fmt::Debug;
 
 use crate::{
+    float::Float,
+    pdf::Pdf,
     ray::{Intersection, Ray},
     resources::Resources,
     vector::Color,
 };
 
+#[derive(Debug)]
+/// The way a ray is scattered off a material.
+pub enum Scatter {
+    /// The ray is scattered into a single direction, which can't be importance sampled.
+    Specular(Ray),
+    /// The scattered direction follows a probability density.
+    Pdf(Box<dyn Pdf>),
+}
+
+#[derive(Debug)]
+/// The result of scattering a ray off a material.
+pub struct ScatterRecord {
+    /// The attenuation of the scattered light.
+    pub attenuation: Color,
+    /// How the ray is scattered.
+    pub scatter: Scatter,
+}
+
 /// A material that can be assigned to an object in a scene.
 pub trait Material: Debug + Send + Sync {
     /// Scatter a ray off the material at a given intersection point.
@@ -14,12 +34,34 @@ pub trait Material: Debug + Send + Sync {
         _resources: &Resources,
         _ray: &Ray,
         _hit: &Intersection,
-    ) -> Option<(Ray, Color)> {
+    ) -> Option<ScatterRecord> {
         None
     }
 
+    /// Returns the probability density of the material scattering the ray into the scattered ray.
+    fn scattering_pdf(
+        &self,
+        _resources: &Resources,
+        _ray: &Ray,
+        _hit: &Intersection,
+        _scattered: &Ray,
+    ) -> Float {
+        0.0
+    }
+
     /// Emit light from the material at a given intersection point.
     fn emit(&self, _resources: &Resources, _hit: &Intersection) -> Color {
         Color::ZERO
     }
+
+    /// Returns whether the material emits light, so objects using it are sampled as lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Returns whether the material is a shadow catcher, which the camera sees only through
+    /// the shadows and light it receives from other objects.
+    fn is_shadow_catcher(&self) -> bool {
+        false
+    }
 }
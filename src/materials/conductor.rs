@@ -0,0 +1,99 @@
+use crate::{
+    float::Float,
+    material::{Material, Scatter, ScatterRecord},
+    ray::{Intersection, Ray},
+    resources::Resources,
+    vec3,
+    vector::{Color, Vec3},
+};
+
+#[derive(Debug)]
+/// A metal whose reflectance follows the fresnel equations of a conductor,
+/// which tints reflections towards the edges like real metals.
+pub struct ConductorMaterial {
+    /// The real part of the complex index of refraction per color channel.
+    eta: Color,
+    /// The imaginary part of the complex index of refraction per color channel.
+    k: Color,
+    /// The fuzziness of the reflection.
+    fuzz: Float,
+}
+
+impl ConductorMaterial {
+    /// Creates a new conductor with the given complex index of refraction and fuzziness.
+    pub const fn new(eta: Color, k: Color, fuzz: Float) -> Self {
+        Self { eta, k, fuzz }
+    }
+
+    /// Creates a gold conductor with the given fuzziness.
+    pub fn gold(fuzz: Float) -> Self {
+        Self::new(vec3!(0.143, 0.374, 1.442), vec3!(3.983, 2.385, 1.603), fuzz)
+    }
+
+    /// Creates a silver conductor with the given fuzziness.
+    pub fn silver(fuzz: Float) -> Self {
+        Self::new(vec3!(0.155, 0.117, 0.138), vec3!(4.828, 3.122, 2.147), fuzz)
+    }
+
+    /// Creates a copper conductor with the given fuzziness.
+    pub fn copper(fuzz: Float) -> Self {
+        Self::new(vec3!(0.200, 0.924, 1.102), vec3!(3.912, 2.452, 2.142), fuzz)
+    }
+
+    /// Creates an aluminum conductor with the given fuzziness.
+    pub fn aluminum(fuzz: Float) -> Self {
+        Self::new(vec3!(1.657, 0.880, 0.521), vec3!(9.224, 6.270, 4.837), fuzz)
+    }
+
+    /// Creates an iron conductor with the given fuzziness.
+    pub fn iron(fuzz: Float) -> Self {
+        Self::new(vec3!(2.911, 2.950, 2.585), vec3!(3.089, 2.932, 2.767), fuzz)
+    }
+
+    /// Computes the unpolarized fresnel reflectance of a conductor for a single channel.
+    fn fresnel(cos: Float, eta: Float, k: Float) -> Float {
+        let cos2 = cos * cos;
+        let sin2 = 1.0 - cos2;
+        let eta2 = eta * eta;
+        let k2 = k * k;
+
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        (rs + rp) / 2.0
+    }
+}
+
+impl Material for ConductorMaterial {
+    fn scatter(
+        &self,
+        _resources: &Resources,
+        ray: &Ray,
+        hit: &Intersection,
+    ) -> Option<ScatterRecord> {
+        let unit_direction = ray.dir.unit();
+        let cos = (-unit_direction).dot(hit.normal).clamp(0.0, 1.0);
+
+        let attenuation = vec3!(
+            Self::fresnel(cos, self.eta.x, self.k.x),
+            Self::fresnel(cos, self.eta.y, self.k.y),
+            Self::fresnel(cos, self.eta.z, self.k.z)
+        );
+
+        let mut reflected = unit_direction.reflect(hit.normal);
+        reflected += Vec3::random_in_unit_sphere() * self.fuzz;
+
+        Some(ScatterRecord {
+            attenuation,
+            scatter: Scatter::Specular(ray.bounce(hit, reflected)),
+        })
+    }
+}
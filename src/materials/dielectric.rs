@@ -1,5 +1,6 @@
 use crate::{
-    material::Material,
+    float::Float,
+    material::{Material, Scatter, ScatterRecord},
     ray::{Intersection, Ray},
     resources::Resources,
     vector::Color,
@@ -9,12 +10,12 @@ use crate::{
 /// A dielectric material, which refracts light through the object.
 pub struct DielectricMaterial {
     /// The refractive index of the material.
-    pub refraction_index: f64,
+    pub refraction_index: Float,
 }
 
 impl DielectricMaterial {
     /// Constructs a new dielectric material with the given refractive index.
-    pub const fn new(refraction_index: f64) -> Self {
+    pub const fn new(refraction_index: Float) -> Self {
         Self { refraction_index }
     }
 }
@@ -25,7 +26,7 @@ impl Material for DielectricMaterial {
         _resources: &Resources,
         ray: &Ray,
         hit: &Intersection,
-    ) -> Option<(Ray, Color)> {
+    ) -> Option<ScatterRecord> {
         let ri = if hit.front_face {
             1.0 / self.refraction_index
         } else {
@@ -44,8 +45,11 @@ impl Material for DielectricMaterial {
             unit_direction.refract(hit.normal, ri)
         };
 
-        let scattered_ray = Ray::new(hit.point, direction);
+        let scattered_ray = ray.bounce(hit, direction);
 
-        Some((scattered_ray, Color::WHITE))
+        Some(ScatterRecord {
+            attenuation: Color::WHITE,
+            scatter: Scatter::Specular(scattered_ray),
+        })
     }
 }
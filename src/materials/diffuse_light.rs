@@ -21,6 +21,10 @@ impl DiffuseLightMaterial {
 
 impl Material for DiffuseLightMaterial {
     fn emit(&self, resources: &Resources, hit: &Intersection) -> Color {
-        resources[self.texture].color(resources, hit.u, hit.v)
+        resources[self.texture].color(resources, hit.u, hit.v, hit.point, hit.footprint)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
     }
 }
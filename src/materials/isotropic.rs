@@ -0,0 +1,50 @@
+use crate::float::consts::PI;
+
+use crate::{
+    float::Float,
+    material::{Material, Scatter, ScatterRecord},
+    pdf::SpherePdf,
+    ray::{Intersection, Ray},
+    resources::{Resources, TextureId},
+};
+
+#[derive(Debug)]
+/// An isotropic material for participating media, which scatters rays uniformly in every direction.
+pub struct IsotropicMaterial {
+    /// The texture of the material's albedo.
+    albedo: TextureId,
+}
+
+impl IsotropicMaterial {
+    /// Create a new isotropic material with the given albedo texture.
+    pub fn new(albedo: TextureId) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for IsotropicMaterial {
+    fn scatter(
+        &self,
+        resources: &Resources,
+        _ray: &Ray,
+        hit: &Intersection,
+    ) -> Option<ScatterRecord> {
+        let albedo =
+            resources[self.albedo].color(resources, hit.u, hit.v, hit.point, hit.footprint);
+
+        Some(ScatterRecord {
+            attenuation: albedo,
+            scatter: Scatter::Pdf(Box::new(SpherePdf)),
+        })
+    }
+
+    fn scattering_pdf(
+        &self,
+        _resources: &Resources,
+        _ray: &Ray,
+        _hit: &Intersection,
+        _scattered: &Ray,
+    ) -> Float {
+        1.0 / (4.0 * PI)
+    }
+}
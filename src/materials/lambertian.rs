@@ -1,8 +1,11 @@
+use crate::float::consts::PI;
+
 use crate::{
-    material::Material,
+    float::Float,
+    material::{Material, Scatter, ScatterRecord},
+    pdf::CosinePdf,
     ray::{Intersection, Ray},
     resources::{Resources, TextureId},
-    vector::{Color, Vec3},
 };
 
 #[derive(Debug)]
@@ -25,17 +28,24 @@ impl Material for LambertianMaterial {
         resources: &Resources,
         _ray: &Ray,
         hit: &Intersection,
-    ) -> Option<(Ray, Color)> {
-        let mut scatter_dir = hit.normal + Vec3::random_in_unit_sphere().unit();
-
-        if scatter_dir.near_zero() {
-            scatter_dir = hit.normal;
-        }
+    ) -> Option<ScatterRecord> {
+        let albedo =
+            resources[self.albedo].color(resources, hit.u, hit.v, hit.point, hit.footprint);
 
-        let albedo = resources[self.albedo].color(resources, hit.u, hit.v);
-
-        let scattered_ray = Ray::new(hit.point, scatter_dir);
+        Some(ScatterRecord {
+            attenuation: albedo,
+            scatter: Scatter::Pdf(Box::new(CosinePdf::new(hit.normal))),
+        })
+    }
 
-        Some((scattered_ray, albedo))
+    fn scattering_pdf(
+        &self,
+        _resources: &Resources,
+        _ray: &Ray,
+        hit: &Intersection,
+        scattered: &Ray,
+    ) -> Float {
+        let cos_theta = hit.normal.dot(scattered.dir.unit());
+        (cos_theta / PI).max(0.0)
     }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    material::Material,
+    float::Float,
+    material::{Material, Scatter, ScatterRecord},
     ray::{Intersection, Ray},
     resources::Resources,
     vector::{Color, Vec3},
@@ -10,12 +11,12 @@ pub struct MetalMaterial {
     /// The albedo of the material.
     albedo: Color,
     /// The fuzziness of the material.
-    fuzz: f64,
+    fuzz: Float,
 }
 
 impl MetalMaterial {
     /// Creates a new metal material with the given albedo and fuzziness.
-    pub const fn new(albedo: Color, fuzz: f64) -> Self {
+    pub const fn new(albedo: Color, fuzz: Float) -> Self {
         Self { albedo, fuzz }
     }
 }
@@ -26,13 +27,16 @@ impl Material for MetalMaterial {
         _resources: &Resources,
         ray: &Ray,
         hit: &Intersection,
-    ) -> Option<(Ray, Color)> {
+    ) -> Option<ScatterRecord> {
         let mut reflected = ray.dir.reflect(hit.normal).unit();
 
         reflected += Vec3::random_in_unit_sphere() * self.fuzz;
 
-        let ray = Ray::new(hit.point, reflected);
+        let ray = ray.bounce(hit, reflected);
 
-        Some((ray, self.albedo))
+        Some(ScatterRecord {
+            attenuation: self.albedo,
+            scatter: Scatter::Specular(ray),
+        })
     }
 }
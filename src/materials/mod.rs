@@ -1,4 +1,7 @@
+pub mod conductor;
 pub mod dielectric;
 pub mod diffuse_light;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod shadow_catcher;
@@ -0,0 +1,50 @@
+use crate::{
+    float::Float,
+    material::{Material, ScatterRecord},
+    materials::lambertian::LambertianMaterial,
+    ray::{Intersection, Ray},
+    resources::{Resources, TextureId},
+};
+
+#[derive(Debug)]
+/// A material for stand-ins of real surfaces, like the ground of a photographic backplate.
+/// Seen by the camera it is invisible except for the shadows and light other objects cast
+/// onto it, other rays see a Lambertian surface with the given albedo.
+pub struct ShadowCatcherMaterial {
+    /// The Lambertian surface seen by rays that don't come from the camera.
+    surface: LambertianMaterial,
+}
+
+impl ShadowCatcherMaterial {
+    /// Create a new shadow catcher with the given albedo texture.
+    pub fn new(albedo: TextureId) -> Self {
+        Self {
+            surface: LambertianMaterial::new(albedo),
+        }
+    }
+}
+
+impl Material for ShadowCatcherMaterial {
+    fn scatter(
+        &self,
+        resources: &Resources,
+        ray: &Ray,
+        hit: &Intersection,
+    ) -> Option<ScatterRecord> {
+        self.surface.scatter(resources, ray, hit)
+    }
+
+    fn scattering_pdf(
+        &self,
+        resources: &Resources,
+        ray: &Ray,
+        hit: &Intersection,
+        scattered: &Ray,
+    ) -> Float {
+        self.surface.scattering_pdf(resources, ray, hit, scattered)
+    }
+
+    fn is_shadow_catcher(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,725 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fs,
+};
+
+use crate::{
+    float::Float,
+    resources::{Resources, TextureId},
+    vector::{Point3, Vec3},
+};
+
+/// The weight of the planes that keep open boundaries in place during simplification,
+/// relative to the planes of the faces.
+const BOUNDARY_WEIGHT: Float = 100.0;
+
+/// The smallest cosine between the normals of a face before and after collapsing an edge,
+/// collapses turning a face further are rejected so faces don't fold over.
+const MIN_COLLAPSE_COS: Float = 0.2;
+
+#[derive(Debug, Clone, Default)]
+/// A polygon mesh, whose faces share their vertices.
+pub struct Mesh {
+    /// The positions of the vertices.
+    pub positions: Vec<Point3>,
+    /// The texture coordinates of the vertices, empty if the mesh has none.
+    pub uvs: Vec<(Float, Float)>,
+    /// The faces of the mesh, as lists of vertex indices in counter clockwise order.
+    pub faces: Vec<Vec<usize>>,
+}
+
+impl Mesh {
+    /// Creates a new mesh from its vertices and faces.
+    pub fn new(positions: Vec<Point3>, uvs: Vec<(Float, Float)>, faces: Vec<Vec<usize>>) -> Self {
+        Self {
+            positions,
+            uvs,
+            faces,
+        }
+    }
+
+    /// Loads a mesh from a Wavefront OBJ file at the given path.
+    pub fn load_obj<T: ToString>(path: T) -> Result<Mesh, &'static str> {
+        let source = fs::read_to_string(path.to_string()).map_err(|_| "failed to read file")?;
+
+        Self::parse_obj(&source)
+    }
+
+    /// Parses a mesh from the contents of a Wavefront OBJ file.
+    /// Only vertex positions, texture coordinates and faces are read, the texture
+    /// coordinates of the first face using a vertex are assigned to the vertex.
+    pub fn parse_obj(source: &str) -> Result<Mesh, &'static str> {
+        let mut positions = Vec::new();
+        let mut tex_coords = Vec::new();
+        let mut faces = Vec::new();
+        let mut uvs: Vec<Option<(Float, Float)>> = Vec::new();
+
+        // Resolves a one based or negative relative index.
+        let resolve = |index: &str, count: usize| -> Result<usize, &'static str> {
+            let index: i64 = index.parse().map_err(|_| "invalid index")?;
+
+            let resolved = if index < 0 {
+                count as i64 + index
+            } else {
+                index - 1
+            };
+
+            if resolved < 0 || resolved >= count as i64 {
+                return Err("index out of range");
+            }
+
+            Ok(resolved as usize)
+        };
+
+        for line in source.lines() {
+            let mut parts = line.split_whitespace();
+
+            match parts.next() {
+                Some("v") => {
+                    let coords = parts
+                        .take(3)
+                        .map(|part| part.parse::<Float>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| "invalid vertex")?;
+
+                    let [x, y, z] = coords[..] else {
+                        return Err("invalid vertex");
+                    };
+
+                    positions.push(Vec3 { x, y, z });
+                    uvs.push(None);
+                }
+                Some("vt") => {
+                    let coords = parts
+                        .take(2)
+                        .map(|part| part.parse::<Float>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| "invalid texture coordinate")?;
+
+                    let [u, v] = coords[..] else {
+                        return Err("invalid texture coordinate");
+                    };
+
+                    tex_coords.push((u, v));
+                }
+                Some("f") => {
+                    let mut face = Vec::new();
+
+                    for corner in parts {
+                        let mut indices = corner.split('/');
+
+                        let vertex = resolve(indices.next().unwrap_or(""), positions.len())?;
+
+                        if let Some(tex_coord) = indices.next().filter(|index| !index.is_empty()) {
+                            let tex_coord = resolve(tex_coord, tex_coords.len())?;
+                            uvs[vertex].get_or_insert(tex_coords[tex_coord]);
+                        }
+
+                        face.push(vertex);
+                    }
+
+                    if face.len() < 3 {
+                        return Err("face with less than three vertices");
+                    }
+
+                    faces.push(face);
+                }
+                _ => {}
+            }
+        }
+
+        let uvs = if tex_coords.is_empty() {
+            Vec::new()
+        } else {
+            uvs.into_iter().map(|uv| uv.unwrap_or((0.0, 0.0))).collect()
+        };
+
+        Ok(Mesh::new(positions, uvs, faces))
+    }
+
+    /// Returns the normal of every vertex, averaged from the faces using it weighted by their area.
+    pub fn vertex_normals(&self) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::ZERO; self.positions.len()];
+
+        for face in self.faces.iter() {
+            // Newell's method gives the area weighted normal of any planar polygon.
+            let mut normal = Vec3::ZERO;
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                normal += self.positions[a].cross(self.positions[b]);
+            }
+
+            for &vertex in face.iter() {
+                normals[vertex] += normal;
+            }
+        }
+
+        normals
+            .into_iter()
+            .map(|normal| {
+                if normal.near_zero() {
+                    normal
+                } else {
+                    normal.unit()
+                }
+            })
+            .collect()
+    }
+
+    /// Subdivides every face into quads by connecting its center with the midpoints of its edges.
+    pub fn subdivide(&self) -> Mesh {
+        self.refine(false)
+    }
+
+    /// Smooths the mesh with the given number of levels of Catmull-Clark subdivision.
+    /// Every level splits each face into quads and moves the vertices towards the limit surface.
+    pub fn catmull_clark(&self, levels: u32) -> Mesh {
+        let mut mesh = self.clone();
+        for _ in 0..levels {
+            mesh = mesh.refine(true);
+        }
+        mesh
+    }
+
+    /// Splits every face into quads, with the Catmull-Clark rules for the
+    /// new vertex positions if smooth, or linear interpolation otherwise.
+    fn refine(&self, smooth: bool) -> Mesh {
+        let has_uvs = !self.uvs.is_empty();
+
+        let mut positions = self.positions.clone();
+        let mut uvs = self.uvs.clone();
+        let mut faces = Vec::new();
+
+        let face_points: Vec<Point3> = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .fold(Vec3::ZERO, |sum, &vertex| sum + self.positions[vertex])
+                    / face.len() as Float
+            })
+            .collect();
+
+        // The faces adjacent to every edge.
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (idx, face) in self.faces.iter().enumerate() {
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                edge_faces
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(idx);
+            }
+        }
+
+        // The vertices inserted at the edges, shared between neighbouring faces.
+        let mut edge_points: HashMap<(usize, usize), usize> = HashMap::new();
+
+        for (idx, face) in self.faces.iter().enumerate() {
+            let count = face.len() as Float;
+
+            let center = positions.len();
+            positions.push(face_points[idx]);
+            if has_uvs {
+                let (u, v) = face.iter().fold((0.0, 0.0), |(u, v), &vertex| {
+                    (u + self.uvs[vertex].0, v + self.uvs[vertex].1)
+                });
+                uvs.push((u / count, v / count));
+            }
+
+            let mut edges = Vec::with_capacity(face.len());
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                let key = (a.min(b), a.max(b));
+
+                let edge = *edge_points.entry(key).or_insert_with(|| {
+                    let midpoint = (self.positions[a] + self.positions[b]) / 2.0;
+
+                    // Interior edges are pulled towards the centers of both adjacent faces.
+                    positions.push(match edge_faces[&key][..] {
+                        [f1, f2] if smooth => {
+                            (midpoint + (face_points[f1] + face_points[f2]) / 2.0) / 2.0
+                        }
+                        _ => midpoint,
+                    });
+
+                    if has_uvs {
+                        let (ua, va) = self.uvs[a];
+                        let (ub, vb) = self.uvs[b];
+                        uvs.push(((ua + ub) / 2.0, (va + vb) / 2.0));
+                    }
+                    positions.len() - 1
+                });
+
+                edges.push(edge);
+            }
+
+            // Every corner of the face becomes a quad with the neighbouring edge points and the center.
+            for (i, &corner) in face.iter().enumerate() {
+                let previous = edges[(i + face.len() - 1) % face.len()];
+                faces.push(vec![corner, edges[i], center, previous]);
+            }
+        }
+
+        if smooth {
+            self.smooth_vertices(&mut positions, &face_points, &edge_faces);
+        }
+
+        Mesh::new(positions, uvs, faces)
+    }
+
+    /// Moves the original vertices of a Catmull-Clark refinement to their new positions.
+    fn smooth_vertices(
+        &self,
+        positions: &mut [Point3],
+        face_points: &[Point3],
+        edge_faces: &HashMap<(usize, usize), Vec<usize>>,
+    ) {
+        let vertex_count = self.positions.len();
+
+        // Sum up the neighbourhood of every vertex.
+        let mut face_sums = vec![(Vec3::ZERO, 0usize); vertex_count];
+        for (idx, face) in self.faces.iter().enumerate() {
+            for &vertex in face.iter() {
+                face_sums[vertex].0 += face_points[idx];
+                face_sums[vertex].1 += 1;
+            }
+        }
+
+        let mut edge_sums = vec![(Vec3::ZERO, 0usize); vertex_count];
+        let mut boundary_sums = vec![(Vec3::ZERO, 0usize); vertex_count];
+        for (&(a, b), faces) in edge_faces.iter() {
+            let midpoint = (self.positions[a] + self.positions[b]) / 2.0;
+
+            for (vertex, other) in [(a, b), (b, a)] {
+                edge_sums[vertex].0 += midpoint;
+                edge_sums[vertex].1 += 1;
+
+                if faces.len() == 1 {
+                    boundary_sums[vertex].0 += self.positions[other];
+                    boundary_sums[vertex].1 += 1;
+                }
+            }
+        }
+
+        for vertex in 0..vertex_count {
+            let original = self.positions[vertex];
+            let (face_sum, face_count) = face_sums[vertex];
+            let (edge_sum, edge_count) = edge_sums[vertex];
+            let (boundary_sum, boundary_count) = boundary_sums[vertex];
+
+            positions[vertex] = match boundary_count {
+                // Unused vertices stay where they are.
+                _ if face_count == 0 => original,
+                // Interior vertices are averaged with their surrounding face and edge points.
+                0 => {
+                    let n = face_count as Float;
+                    let q = face_sum / n;
+                    let r = edge_sum / edge_count as Float;
+
+                    (q + r * 2.0 + original * (n - 3.0)) / n
+                }
+                // Boundary vertices are only smoothed along the boundary, keeping open edges in place.
+                2 => original * 0.75 + boundary_sum * 0.125,
+                // Corners where several boundaries meet stay sharp.
+                _ => original,
+            };
+        }
+    }
+
+    /// Returns the number of triangles the faces of the mesh are split into.
+    pub fn triangle_count(&self) -> usize {
+        self.faces
+            .iter()
+            .map(|face| face.len().saturating_sub(2))
+            .sum()
+    }
+
+    /// Simplifies the mesh to about the given number of triangles, by collapsing the edges whose
+    /// removal changes the shape least first. The change is measured with the quadric error
+    /// metrics of Garland and Heckbert. Open boundaries are kept in place, and the faces of the
+    /// simplified mesh are triangles.
+    pub fn simplify(&self, target_triangles: usize) -> Mesh {
+        let mut positions = self.positions.clone();
+        let mut uvs = self.uvs.clone();
+        let mut triangles: Vec<Option<[usize; 3]>> = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len().saturating_sub(1)).map(|i| Some([face[0], face[i], face[i + 1]]))
+            })
+            .collect();
+
+        // The triangles around every vertex, and the number of triangles at every edge.
+        let mut vertex_triangles = vec![Vec::new(); positions.len()];
+        let mut edge_counts: HashMap<(usize, usize), usize> = HashMap::new();
+        for (idx, triangle) in triangles.iter().enumerate() {
+            let Some(triangle) = triangle else {
+                continue;
+            };
+
+            for (i, &a) in triangle.iter().enumerate() {
+                let b = triangle[(i + 1) % 3];
+                vertex_triangles[a].push(idx);
+                *edge_counts.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+
+        // Every vertex measures its squared distance to the planes of its faces,
+        // and to planes standing on the open boundaries it is part of.
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        for triangle in triangles.iter().flatten() {
+            let [a, b, c] = triangle.map(|vertex| positions[vertex]);
+            let normal = (b - a).cross(c - a);
+            if normal.near_zero() {
+                continue;
+            }
+
+            let face = Quadric::plane(normal.unit(), a, normal.len() / 2.0);
+            for &vertex in triangle {
+                quadrics[vertex].add(&face);
+            }
+
+            for (i, &start) in triangle.iter().enumerate() {
+                let end = triangle[(i + 1) % 3];
+                if edge_counts[&(start.min(end), start.max(end))] != 1 {
+                    continue;
+                }
+
+                let edge = positions[end] - positions[start];
+                let boundary = edge.cross(normal);
+                if boundary.near_zero() {
+                    continue;
+                }
+
+                let wall = Quadric::plane(
+                    boundary.unit(),
+                    positions[start],
+                    edge.len_sq() * BOUNDARY_WEIGHT,
+                );
+                quadrics[start].add(&wall);
+                quadrics[end].add(&wall);
+            }
+        }
+
+        // Every change of a vertex invalidates the collapses planned for its edges.
+        let mut versions = vec![0u32; positions.len()];
+        let mut collapses: BinaryHeap<Collapse> = edge_counts
+            .keys()
+            .map(|&(a, b)| Collapse::new(&positions, &quadrics, &versions, a, b))
+            .collect();
+
+        let mut live = triangles.iter().flatten().count();
+        while live > target_triangles {
+            let Some(collapse) = collapses.pop() else {
+                break;
+            };
+
+            let (a, b) = (collapse.keep, collapse.remove);
+            if collapse.versions != (versions[a], versions[b])
+                || !Self::can_collapse(&triangles, &vertex_triangles, &positions, &collapse)
+            {
+                continue;
+            }
+
+            // Move the kept vertex and drop the triangles of the edge, the other triangles
+            // of the removed vertex use the kept vertex instead.
+            for idx in std::mem::take(&mut vertex_triangles[b]) {
+                let Some(triangle) = triangles[idx].as_mut() else {
+                    continue;
+                };
+
+                if triangle.contains(&a) {
+                    triangles[idx] = None;
+                    live -= 1;
+                } else {
+                    for vertex in triangle.iter_mut().filter(|vertex| **vertex == b) {
+                        *vertex = a;
+                    }
+                    vertex_triangles[a].push(idx);
+                }
+            }
+            vertex_triangles[a].retain(|&idx| triangles[idx].is_some());
+
+            if !uvs.is_empty() {
+                // Take the texture coordinates from the closest point on the edge.
+                let edge = positions[b] - positions[a];
+                let along = if edge.near_zero() {
+                    0.0
+                } else {
+                    ((collapse.target - positions[a]).dot(edge) / edge.len_sq()).clamp(0.0, 1.0)
+                };
+                let ((ua, va), (ub, vb)) = (uvs[a], uvs[b]);
+                uvs[a] = (ua + (ub - ua) * along, va + (vb - va) * along);
+            }
+
+            positions[a] = collapse.target;
+            let removed = quadrics[b];
+            quadrics[a].add(&removed);
+            versions[a] += 1;
+            versions[b] += 1;
+
+            for neighbour in Self::neighbours(&triangles, &vertex_triangles, a) {
+                collapses.push(Collapse::new(
+                    &positions, &quadrics, &versions, a, neighbour,
+                ));
+            }
+        }
+
+        // Keep only the vertices still used by a triangle.
+        let mut remap = vec![None; positions.len()];
+        let mut kept_positions = Vec::new();
+        let mut kept_uvs = Vec::new();
+        let mut faces = Vec::with_capacity(live);
+        for triangle in triangles.into_iter().flatten() {
+            let mut face = Vec::with_capacity(3);
+            for vertex in triangle {
+                let idx = *remap[vertex].get_or_insert_with(|| {
+                    kept_positions.push(positions[vertex]);
+                    if let Some(&uv) = uvs.get(vertex) {
+                        kept_uvs.push(uv);
+                    }
+                    kept_positions.len() - 1
+                });
+                face.push(idx);
+            }
+            faces.push(face);
+        }
+
+        Mesh::new(kept_positions, kept_uvs, faces)
+    }
+
+    /// Returns the vertices sharing a triangle with the vertex.
+    fn neighbours(
+        triangles: &[Option<[usize; 3]>],
+        vertex_triangles: &[Vec<usize>],
+        vertex: usize,
+    ) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = vertex_triangles[vertex]
+            .iter()
+            .filter_map(|&idx| triangles[idx])
+            .flatten()
+            .filter(|&other| other != vertex)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    /// Checks that collapsing the edge keeps the surface a manifold and doesn't fold any face over.
+    fn can_collapse(
+        triangles: &[Option<[usize; 3]>],
+        vertex_triangles: &[Vec<usize>],
+        positions: &[Point3],
+        collapse: &Collapse,
+    ) -> bool {
+        let (a, b) = (collapse.keep, collapse.remove);
+
+        // The vertices next to both ends may only be the opposite corners of the edge's triangles,
+        // otherwise the collapse would pinch the surface together.
+        let shared = vertex_triangles[a]
+            .iter()
+            .filter_map(|&idx| triangles[idx])
+            .filter(|triangle| triangle.contains(&b))
+            .count();
+        let neighbours_b = Self::neighbours(triangles, vertex_triangles, b);
+        let common = Self::neighbours(triangles, vertex_triangles, a)
+            .into_iter()
+            .filter(|vertex| neighbours_b.binary_search(vertex).is_ok())
+            .count();
+        if shared == 0 || common != shared {
+            return false;
+        }
+
+        vertex_triangles[a]
+            .iter()
+            .chain(vertex_triangles[b].iter())
+            .filter_map(|&idx| triangles[idx])
+            .filter(|triangle| !(triangle.contains(&a) && triangle.contains(&b)))
+            .all(|triangle| {
+                let normal = |moved: bool| {
+                    let [p0, p1, p2] = triangle.map(|vertex| {
+                        if moved && (vertex == a || vertex == b) {
+                            collapse.target
+                        } else {
+                            positions[vertex]
+                        }
+                    });
+                    (p1 - p0).cross(p2 - p0)
+                };
+
+                // Faces without an area have no side to fold over to.
+                let (before, after) = (normal(false), normal(true));
+                before.near_zero()
+                    || !after.near_zero() && before.unit().dot(after.unit()) >= MIN_COLLAPSE_COS
+            })
+    }
+
+    /// Displaces the mesh along its vertex normals by the brightness of a height texture,
+    /// after subdividing it the given number of times to add the necessary detail.
+    pub fn displace(
+        &self,
+        resources: &Resources,
+        height: TextureId,
+        scale: Float,
+        subdivisions: u32,
+    ) -> Mesh {
+        let mut mesh = self.clone();
+        for _ in 0..subdivisions {
+            mesh = mesh.subdivide();
+        }
+
+        let normals = mesh.vertex_normals();
+
+        for (vertex, position) in mesh.positions.iter_mut().enumerate() {
+            let (u, v) = mesh.uvs.get(vertex).copied().unwrap_or((0.0, 0.0));
+            let offset = resources[height]
+                .color(resources, u, v, *position, 0.0)
+                .luminance();
+
+            *position += normals[vertex] * (offset * scale);
+        }
+
+        mesh
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// A symmetric 4x4 matrix measuring the sum of the weighted squared distances of a point
+/// to a set of planes, stored as its upper triangle.
+struct Quadric([Float; 10]);
+
+impl Quadric {
+    /// Creates the quadric of the plane through the point with the given unit normal.
+    fn plane(normal: Vec3, point: Point3, weight: Float) -> Self {
+        let [a, b, c] = [normal.x, normal.y, normal.z];
+        let d = -normal.dot(point);
+
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|value| value * weight),
+        )
+    }
+
+    /// Adds the planes of another quadric.
+    fn add(&mut self, other: &Self) {
+        for (value, other) in self.0.iter_mut().zip(other.0) {
+            *value += other;
+        }
+    }
+
+    /// Returns the sum of the weighted squared distances of the point to the planes.
+    fn error(&self, p: Point3) -> Float {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.0;
+
+        p.x * (aa * p.x + 2.0 * (ab * p.y + ac * p.z + ad))
+            + p.y * (bb * p.y + 2.0 * (bc * p.z + bd))
+            + p.z * (cc * p.z + 2.0 * cd)
+            + dd
+    }
+
+    /// Returns the point with the least error, or `None` if there is no single one,
+    /// like for planes that are all parallel.
+    fn minimum(&self) -> Option<Point3> {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, _] = self.0;
+
+        // Solve the linear system with Cramer's rule.
+        let det = aa * (bb * cc - bc * bc) - ab * (ab * cc - bc * ac) + ac * (ab * bc - bb * ac);
+        let scale = aa + bb + cc;
+        if det.abs() <= 1e-6 * scale * scale * scale {
+            return None;
+        }
+
+        let (x, y, z) = (-ad, -bd, -cd);
+        Some(Vec3 {
+            x: (x * (bb * cc - bc * bc) - ab * (y * cc - bc * z) + ac * (y * bc - bb * z)) / det,
+            y: (aa * (y * cc - bc * z) - x * (ab * cc - bc * ac) + ac * (ab * z - y * ac)) / det,
+            z: (aa * (bb * z - y * bc) - ab * (ab * z - y * ac) + x * (ab * bc - bb * ac)) / det,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A planned collapse of an edge into a single vertex, ordered so the cheapest comes first.
+struct Collapse {
+    /// The error the collapse adds to the surface.
+    cost: Float,
+    /// The vertex that is moved to the target.
+    keep: usize,
+    /// The vertex that is removed.
+    remove: usize,
+    /// The position of the remaining vertex.
+    target: Point3,
+    /// The versions of both vertices when the collapse was planned.
+    versions: (u32, u32),
+}
+
+impl Collapse {
+    /// Plans the collapse of an edge, at the point on or near the edge with the least error.
+    fn new(
+        positions: &[Point3],
+        quadrics: &[Quadric],
+        versions: &[u32],
+        keep: usize,
+        remove: usize,
+    ) -> Self {
+        let mut quadric = quadrics[keep];
+        quadric.add(&quadrics[remove]);
+
+        let (a, b) = (positions[keep], positions[remove]);
+        let midpoint = (a + b) / 2.0;
+
+        // The optimum of nearly parallel planes can be far away, only use it close to the edge.
+        let optimum = quadric
+            .minimum()
+            .filter(|point| (*point - midpoint).len_sq() <= (b - a).len_sq());
+
+        let (cost, target) = optimum
+            .into_iter()
+            .chain([a, b, midpoint])
+            .map(|point| (quadric.error(point), point))
+            .min_by(|(x, _), (y, _)| x.total_cmp(y))
+            .unwrap();
+
+        Self {
+            cost,
+            keep,
+            remove,
+            target,
+            versions: (versions[keep], versions[remove]),
+        }
+    }
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // The heap pops the greatest element, so lower costs compare greater.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    cell::Cell,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{
+    float::Float,
+    resources::MaterialId,
+    vector::{Color, Point3, Vec3},
+};
+
+/// Whether the samples are checked for NaN and infinite values.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the pixels with invalid samples are painted magenta.
+static HIGHLIGHT: AtomicBool = AtomicBool::new(false);
+
+/// The number of invalid samples found so far.
+static INVALID: AtomicU64 = AtomicU64::new(0);
+
+/// The first invalid sample found, with the bounce of its path that turned it invalid.
+static FIRST: Mutex<Option<InvalidSample>> = Mutex::new(None);
+
+/// The color invalid pixels are painted with.
+pub const HIGHLIGHT_COLOR: Color = Color {
+    x: 1.0,
+    y: 0.0,
+    z: 1.0,
+};
+
+thread_local! {
+    /// The deepest bounce of the current path on this thread whose light is invalid.
+    static BOUNCE: Cell<Option<InvalidBounce>> = const { Cell::new(None) };
+}
+
+/// Enables checking the samples for NaN and infinite values, which comes at a small cost.
+/// Optionally the pixels with invalid samples are painted magenta.
+pub fn enable(highlight: bool) {
+    ENABLED.store(true, Ordering::Relaxed);
+    HIGHLIGHT.store(highlight, Ordering::Relaxed);
+}
+
+#[inline]
+/// Returns true if the samples are checked for invalid values.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns true if the pixels with invalid samples are painted magenta.
+pub fn highlights() -> bool {
+    HIGHLIGHT.load(Ordering::Relaxed)
+}
+
+/// Returns the number of invalid samples found so far.
+pub fn invalid_count() -> u64 {
+    INVALID.load(Ordering::Relaxed)
+}
+
+/// Returns the first invalid sample found, if any.
+pub fn first_invalid() -> Option<InvalidSample> {
+    *FIRST.lock().unwrap()
+}
+
+/// Resets the number of invalid samples and forgets the first one.
+pub fn reset() {
+    INVALID.store(0, Ordering::Relaxed);
+    *FIRST.lock().unwrap() = None;
+}
+
+/// Forgets the invalid bounce of the previous path on this thread, before a new path starts.
+pub(crate) fn start_path() {
+    if is_enabled() {
+        BOUNCE.with(|bounce| bounce.set(None));
+    }
+}
+
+/// Records a bounce whose light is invalid, unless a deeper bounce of the path already was.
+pub(crate) fn record_bounce(bounce: InvalidBounce) {
+    BOUNCE.with(|current| {
+        if current.get().is_none() {
+            current.set(Some(bounce));
+        }
+    });
+}
+
+/// Counts an invalid sample of a pixel. The first one is kept and logged with the bounce
+/// of its path that turned it invalid.
+pub(crate) fn record_sample(x: u32, y: u32, sample: u32, value: Color) {
+    INVALID.fetch_add(1, Ordering::Relaxed);
+
+    let mut first = FIRST.lock().unwrap();
+    if first.is_none() {
+        let invalid = InvalidSample {
+            x,
+            y,
+            sample,
+            value,
+            bounce: BOUNCE.with(Cell::get),
+        };
+        tracing::warn!("{invalid}");
+        *first = Some(invalid);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The state of a path at the bounce where its light turned NaN or infinite.
+pub struct InvalidBounce {
+    /// The number of bounces left when the ray hit the surface.
+    pub depth: u32,
+    /// The point of the hit.
+    pub point: Point3,
+    /// The shading normal at the hit.
+    pub normal: Vec3,
+    /// The direction of the ray that hit the surface.
+    pub direction: Vec3,
+    /// The material of the surface.
+    pub material: MaterialId,
+    /// The luminance of the light emitted at the hit.
+    pub emitted: Float,
+    /// The luminance of the light scattered at the hit.
+    pub scattered: Float,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A sample whose light is NaN or infinite.
+pub struct InvalidSample {
+    /// The x coordinate of the pixel.
+    pub x: u32,
+    /// The y coordinate of the pixel.
+    pub y: u32,
+    /// The index of the sample in the pixel.
+    pub sample: u32,
+    /// The light of the sample.
+    pub value: Color,
+    /// The bounce of the path that turned its light invalid, `None` if it wasn't a bounce,
+    /// like the light of the background or of a shadow catcher.
+    pub bounce: Option<InvalidBounce>,
+}
+
+impl Display for InvalidSample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid sample {} of pixel ({}, {}): {:?}",
+            self.sample, self.x, self.y, self.value
+        )?;
+
+        if let Some(bounce) = self.bounce {
+            write!(
+                f,
+                "\n  at depth {} with {:?}, emitted {} and scattered {}\n  point {:?}\n  normal {:?}\n  direction {:?}",
+                bounce.depth,
+                bounce.material,
+                bounce.emitted,
+                bounce.scattered,
+                bounce.point,
+                bounce.normal,
+                bounce.direction
+            )?;
+        }
+
+        Ok(())
+    }
+}
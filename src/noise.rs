@@ -0,0 +1,204 @@
+use crate::{
+    float::Float,
+    random::Random,
+    vec3,
+    vector::{Point3, Vec3},
+};
+
+/// The number of lattice points along each axis before the noise repeats.
+const POINT_COUNT: usize = 256;
+
+#[derive(Debug, Clone)]
+/// A Perlin gradient noise generator.
+pub struct Perlin {
+    /// The random gradient vectors at the lattice points.
+    gradients: Box<[Vec3]>,
+    /// The permutations of the lattice coordinates along each axis.
+    perm: [Box<[usize]>; 3],
+}
+
+impl Perlin {
+    /// Creates a new noise generator with gradients from the given seed.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Random::new(seed);
+
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                vec3!(
+                    rng.random_float() * 2.0 - 1.0,
+                    rng.random_float() * 2.0 - 1.0,
+                    rng.random_float() * 2.0 - 1.0
+                )
+                .unit()
+            })
+            .collect();
+
+        let perm = [
+            permutation(&mut rng),
+            permutation(&mut rng),
+            permutation(&mut rng),
+        ];
+
+        Self { gradients, perm }
+    }
+
+    /// Returns the noise at the given point, in the range [-1, 1].
+    pub fn noise(&self, p: Point3) -> Float {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (u, v, w) = (p.x - fx, p.y - fy, p.z - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        // Smooth the interpolation weights with a hermite curve.
+        let (uu, vv, ww) = (
+            u * u * (3.0 - 2.0 * u),
+            v * v * (3.0 - 2.0 * v),
+            w * w * (3.0 - 2.0 * w),
+        );
+
+        let mut sum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.perm[0][((i + di) & 255) as usize]
+                        ^ self.perm[1][((j + dj) & 255) as usize]
+                        ^ self.perm[2][((k + dk) & 255) as usize];
+
+                    let (a, b, c) = (di as Float, dj as Float, dk as Float);
+                    let weight = vec3!(u - a, v - b, w - c);
+
+                    sum += (a * uu + (1.0 - a) * (1.0 - uu))
+                        * (b * vv + (1.0 - b) * (1.0 - vv))
+                        * (c * ww + (1.0 - c) * (1.0 - ww))
+                        * self.gradients[index].dot(weight);
+                }
+            }
+        }
+
+        sum
+    }
+
+    /// Returns the sum of the given number of octaves of noise, with each octave
+    /// having double the frequency and half the amplitude of the previous one.
+    pub fn fbm(&self, p: Point3, octaves: u32) -> Float {
+        let mut sum = 0.0;
+        let mut point = p;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            sum += weight * self.noise(point);
+            weight *= 0.5;
+            point *= 2.0;
+        }
+
+        sum
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How cellular noise measures the distance to the feature points, which shapes the cells.
+pub enum DistanceMetric {
+    #[default]
+    /// The straight line distance, which gives round cells.
+    Euclidean,
+    /// The sum of the distances along the axes, which gives diamond shaped cells.
+    Manhattan,
+    /// The largest distance along an axis, which gives square cells.
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    /// Returns the length of the vector in the metric.
+    fn length(self, v: Vec3) -> Float {
+        let v = v.abs();
+        match self {
+            Self::Euclidean => v.len(),
+            Self::Manhattan => v.x + v.y + v.z,
+            Self::Chebyshev => v.x.max(v.y).max(v.z),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A Worley cellular noise generator, which scatters a feature point in every cell of the
+/// lattice and measures the distances to the closest ones.
+pub struct Worley {
+    /// The random positions of the feature points in their cells, in the range [0, 1).
+    offsets: Box<[Vec3]>,
+    /// The permutations of the lattice coordinates along each axis.
+    perm: [Box<[usize]>; 3],
+    /// How far the feature points are moved from the centers of the cells, between
+    /// zero for a regular grid and one for anywhere in the cell.
+    jitter: Float,
+    /// How the distances to the feature points are measured.
+    metric: DistanceMetric,
+}
+
+impl Worley {
+    /// Creates a new noise generator with feature points from the given seed.
+    pub fn new(seed: u64, jitter: Float, metric: DistanceMetric) -> Self {
+        let mut rng = Random::new(seed);
+
+        let offsets = (0..POINT_COUNT)
+            .map(|_| vec3!(rng.random_float(), rng.random_float(), rng.random_float()))
+            .collect();
+
+        let perm = [
+            permutation(&mut rng),
+            permutation(&mut rng),
+            permutation(&mut rng),
+        ];
+
+        Self {
+            offsets,
+            perm,
+            jitter: jitter.clamp(0.0, 1.0),
+            metric,
+        }
+    }
+
+    /// Returns the distances from the point to the closest and the second closest feature point,
+    /// in units of the cells, known as F1 and F2.
+    pub fn distances(&self, p: Point3) -> (Float, Float) {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+        let local = vec3!(p.x - fx, p.y - fy, p.z - fz);
+
+        // The feature points stay within their cells, so the closest ones are in the cells around.
+        let (mut f1, mut f2) = (Float::INFINITY, Float::INFINITY);
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let index = self.perm[0][((i + di) & 255) as usize]
+                        ^ self.perm[1][((j + dj) & 255) as usize]
+                        ^ self.perm[2][((k + dk) & 255) as usize];
+
+                    let feature = vec3!(di as Float, dj as Float, dk as Float)
+                        + vec3!(0.5)
+                        + (self.offsets[index] - vec3!(0.5)) * self.jitter;
+                    let distance = self.metric.length(feature - local);
+
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+
+        (f1, f2)
+    }
+}
+
+/// Creates a random permutation of the lattice coordinates.
+fn permutation(rng: &mut Random) -> Box<[usize]> {
+    let mut perm: Box<[usize]> = (0..POINT_COUNT).collect();
+
+    for i in (1..POINT_COUNT).rev() {
+        let target = (rng.random_float() * (i + 1) as Float) as usize;
+        perm.swap(i, target.min(i));
+    }
+
+    perm
+}
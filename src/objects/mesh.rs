@@ -0,0 +1,229 @@
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    float::Float,
+    hittable::Hittable,
+    interval::Interval,
+    mesh::Mesh,
+    onb::Onb,
+    profiler::{self, Counter},
+    ray::{Intersection, Ray},
+    resources::MaterialId,
+    vector::{Point3, Vec3},
+};
+
+#[derive(Debug)]
+/// A triangulated mesh object with smooth shading normals.
+pub struct MeshObject {
+    /// The positions of the vertices.
+    positions: Vec<Point3>,
+    /// The normals of the vertices.
+    normals: Vec<Vec3>,
+    /// The texture coordinates of the vertices, empty if the mesh has none.
+    uvs: Vec<(Float, Float)>,
+    /// The vertex indices of the triangles.
+    triangles: Vec<[usize; 3]>,
+    /// The hierarchy of bounding volumes over the triangles.
+    bvh: Bvh<usize>,
+    /// The bounding box of the mesh.
+    bounding_box: Aabb,
+    /// The material of the mesh.
+    material: MaterialId,
+}
+
+impl MeshObject {
+    /// Creates a new mesh object, splitting the faces of the mesh into triangles.
+    pub fn new(mesh: &Mesh, material: MaterialId) -> Self {
+        // Split every polygon into a fan of triangles around its first vertex.
+        let triangles: Vec<[usize; 3]> = mesh
+            .faces
+            .iter()
+            .flat_map(|face| (1..face.len() - 1).map(|i| [face[0], face[i], face[i + 1]]))
+            .collect();
+
+        let boxes: Vec<(usize, Aabb)> = triangles
+            .iter()
+            .enumerate()
+            .map(|(idx, triangle)| {
+                let mut bounding_box = Aabb::EMPTY;
+                for &vertex in triangle {
+                    bounding_box.grow(&Aabb::from_point(mesh.positions[vertex]));
+                }
+                (idx, bounding_box.pad(0.0001))
+            })
+            .collect();
+
+        let bounding_box = boxes.iter().fold(Aabb::EMPTY, |bounding_box, (_, bb)| {
+            Aabb::union(&bounding_box, bb)
+        });
+
+        Self {
+            positions: mesh.positions.clone(),
+            normals: mesh.vertex_normals(),
+            uvs: mesh.uvs.clone(),
+            triangles,
+            bvh: Bvh::new(boxes),
+            bounding_box,
+            material,
+        }
+    }
+
+    /// Intersects the ray with a triangle using the Möller–Trumbore algorithm.
+    /// Returns the distance and the barycentric coordinates of the hit.
+    fn hit_triangle(
+        &self,
+        r: &Ray,
+        triangle: [usize; 3],
+        time: Interval,
+    ) -> Option<(Float, Float, Float)> {
+        let [a, b, c] = triangle.map(|vertex| self.positions[vertex]);
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = r.dir.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let offset = r.orig - a;
+        let u = offset.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = offset.cross(edge1);
+        let v = r.dir.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_det;
+        if !time.surrounds(t) {
+            return None;
+        }
+
+        Some((t, u, v))
+    }
+
+    /// Returns the change of the points of a triangle per unit of its texture coordinates.
+    /// Without texture coordinates, the barycentric coordinates of the corners are used.
+    fn derivatives(&self, triangle: [usize; 3]) -> (Vec3, Vec3) {
+        let [a, b, c] = triangle;
+        let edge1 = self.positions[b] - self.positions[a];
+        let edge2 = self.positions[c] - self.positions[a];
+
+        if self.uvs.is_empty() {
+            return (edge1, edge2);
+        }
+
+        let [(u0, v0), (u1, v1), (u2, v2)] = triangle.map(|vertex| self.uvs[vertex]);
+        let (du1, dv1, du2, dv2) = (u1 - u0, v1 - v0, u2 - u0, v2 - v0);
+        let det = du1 * dv2 - du2 * dv1;
+
+        // Triangles without area in texture space get any tangents across the surface.
+        if det.abs() < 1e-12 {
+            let tangents = Onb::new(edge1.cross(edge2));
+            return (tangents.u, tangents.v);
+        }
+
+        (
+            (edge1 * dv2 - edge2 * dv1) / det,
+            (edge2 * du1 - edge1 * du2) / det,
+        )
+    }
+}
+
+impl Hittable for MeshObject {
+    fn hit(&self, r: &Ray, mut time: Interval) -> Option<Intersection> {
+        let candidates = self.bvh.hit(r, time)?;
+
+        let mut closest = None;
+        for idx in candidates {
+            profiler::count(Counter::PrimitiveTests);
+
+            if let Some(hit) = self.hit_triangle(r, self.triangles[idx], time) {
+                time.end = hit.0;
+                closest = Some((idx, hit));
+            }
+        }
+
+        let (idx, (t, b1, b2)) = closest?;
+        let [a, b, c] = self.triangles[idx];
+        let b0 = 1.0 - b1 - b2;
+
+        // Interpolate the vertex attributes with the barycentric coordinates.
+        let mut outward_normal = self.normals[a] * b0 + self.normals[b] * b1 + self.normals[c] * b2;
+        if outward_normal.near_zero() {
+            outward_normal = (self.positions[b] - self.positions[a])
+                .cross(self.positions[c] - self.positions[a]);
+        }
+
+        let (u, v) = if self.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            (
+                self.uvs[a].0 * b0 + self.uvs[b].0 * b1 + self.uvs[c].0 * b2,
+                self.uvs[a].1 * b0 + self.uvs[b].1 * b1 + self.uvs[c].1 * b2,
+            )
+        };
+        let (dpdu, dpdv) = self.derivatives([a, b, c]);
+
+        // The texture coordinates change by the ratio of the triangle's area in texture space.
+        let face_normal =
+            (self.positions[b] - self.positions[a]).cross(self.positions[c] - self.positions[a]);
+        let uv_area = if self.uvs.is_empty() {
+            1.0
+        } else {
+            let [(u0, v0), (u1, v1), (u2, v2)] = [a, b, c].map(|vertex| self.uvs[vertex]);
+            ((u1 - u0) * (v2 - v0) - (u2 - u0) * (v1 - v0)).abs()
+        };
+        let uv_per_unit = (uv_area / face_normal.len()).sqrt();
+
+        let (front_face, normal) = Intersection::face_normal(r, outward_normal.unit());
+        let (_, geometric_normal) = Intersection::face_normal(r, face_normal.unit());
+
+        Some(Intersection {
+            point: r.at(t),
+            normal,
+            geometric_normal,
+            front_face,
+            material: self.material,
+            t,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            footprint: Intersection::texture_footprint(r, t, face_normal, uv_per_unit),
+        })
+    }
+
+    fn occluded(&self, r: &Ray, time: Interval) -> bool {
+        self.bvh.any_hit(r, time, |idx| {
+            profiler::count(Counter::PrimitiveTests);
+            self.hit_triangle(r, self.triangles[idx], time).is_some()
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn kind(&self) -> &'static str {
+        "mesh"
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of_val(self)
+            + self.positions.capacity() * size_of::<Point3>()
+            + self.normals.capacity() * size_of::<Vec3>()
+            + self.uvs.capacity() * size_of::<(Float, Float)>()
+            + self.triangles.capacity() * size_of::<[usize; 3]>()
+            + self.bvh.memory_size()
+    }
+}
@@ -1 +1,6 @@
+pub mod mesh;
+pub mod plane;
 pub mod sphere;
+pub mod transformed;
+pub mod volume;
+pub mod voxel_grid;
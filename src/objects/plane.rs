@@ -0,0 +1,83 @@
+use crate::{
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    onb::Onb,
+    ray::{Intersection, Ray},
+    resources::MaterialId,
+    vector::{Point3, Vec3},
+};
+
+#[derive(Debug)]
+/// An infinite plane through a point, like a ground or a backdrop.
+pub struct PlaneObject {
+    /// A point on the plane, which is the origin of its texture coordinates.
+    point: Point3,
+    /// The unit normal of the plane.
+    normal: Vec3,
+    /// The tangents along which the texture coordinates increase.
+    tangents: Onb,
+    /// The material of the plane.
+    material: MaterialId,
+}
+
+impl PlaneObject {
+    /// Create a new plane through the given point facing in the direction of the normal.
+    /// The texture coordinates are distances along the plane, so textures repeat every unit.
+    pub fn new(point: Point3, normal: Vec3, material: MaterialId) -> Self {
+        let normal = normal.unit();
+
+        Self {
+            point,
+            normal,
+            tangents: Onb::new(normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for PlaneObject {
+    fn hit(&self, r: &Ray, time: Interval) -> Option<Intersection> {
+        // Rays parallel to the plane never hit it.
+        let denom = self.normal.dot(r.dir);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.point - r.orig).dot(self.normal) / denom;
+        if !time.surrounds(t) {
+            return None;
+        }
+
+        let point = r.at(t);
+        let offset = point - self.point;
+        let (front_face, normal) = Intersection::face_normal(r, self.normal);
+
+        Some(Intersection {
+            point,
+            normal,
+            geometric_normal: normal,
+            front_face,
+            material: self.material,
+            t,
+            u: offset.dot(self.tangents.u),
+            v: offset.dot(self.tangents.v),
+            dpdu: self.tangents.u,
+            dpdv: self.tangents.v,
+            footprint: Intersection::texture_footprint(r, t, self.normal, 1.0),
+        })
+    }
+
+    fn occluded(&self, r: &Ray, time: Interval) -> bool {
+        let denom = self.normal.dot(r.dir);
+        denom.abs() >= 1e-8 && time.surrounds((self.point - r.orig).dot(self.normal) / denom)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+
+    fn kind(&self) -> &'static str {
+        "plane"
+    }
+}
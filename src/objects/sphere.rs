@@ -1,13 +1,18 @@
-use std::f64::consts::PI;
+use crate::float::consts::PI;
 
 use crate::{
     aabb::Aabb,
+    float::Float,
     hittable::Hittable,
     interval::Interval,
+    intr,
+    onb::Onb,
+    random::random_float,
     ray::{Intersection, Ray},
     resources::MaterialId,
+    transform::Transform,
     vec3,
-    vector::Point3,
+    vector::{Point3, Vec3},
 };
 
 #[derive(Debug)]
@@ -16,16 +21,18 @@ pub struct SphereObject {
     /// The center of the sphere.
     center: Point3,
     /// The radius of the sphere.
-    radius: f64,
+    radius: Float,
     /// The material of the sphere.
     material: MaterialId,
     /// The bounding box of the sphere.
     bounding_box: Aabb,
+    /// The rotation from the directions on the sphere into the space of its texture coordinates.
+    uv_rotation: Transform,
 }
 
 impl SphereObject {
     /// Create a new sphere object with the given center, radius, and material.
-    pub fn new(center: Point3, radius: f64, material: MaterialId) -> Self {
+    pub fn new(center: Point3, radius: Float, material: MaterialId) -> Self {
         let bounding_box = Self::calculate_aabb(center, radius);
 
         Self {
@@ -33,11 +40,29 @@ impl SphereObject {
             radius,
             material,
             bounding_box,
+            uv_rotation: Transform::IDENTITY,
         }
     }
 
-    /// Get the UV coordinates of a point on the sphere.
-    fn get_sphere_uv(p: Point3) -> (f64, f64) {
+    /// Create a new sphere object whose texture coordinates are rotated with the given rotation,
+    /// which turns textures away from the poles at ±y and the seam at -x.
+    /// A rotation around the y axis turns the texture around the poles.
+    pub fn with_uv_rotation(
+        center: Point3,
+        radius: Float,
+        material: MaterialId,
+        rotation: Transform,
+    ) -> Self {
+        Self {
+            uv_rotation: rotation.inverse(),
+            ..Self::new(center, radius, material)
+        }
+    }
+
+    /// Get the UV coordinates of a point on the unit sphere.
+    fn get_sphere_uv(&self, p: Point3) -> (Float, Float) {
+        let p = self.uv_rotation.apply_vector(p);
+
         let theta = (-p.y).acos();
         let phi = (-p.z).atan2(p.x) + PI;
 
@@ -47,8 +72,23 @@ impl SphereObject {
         (u, v)
     }
 
+    /// Get the change of a point on the sphere per unit of its UV coordinates.
+    fn get_sphere_derivatives(&self, u: Float, v: Float) -> (Vec3, Vec3) {
+        let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+        let (sin_theta, cos_theta) = (PI * v).sin_cos();
+
+        let dpdu = vec3!(sin_phi * sin_theta, 0, cos_phi * sin_theta) * (2.0 * PI * self.radius);
+        let dpdv = vec3!(-cos_phi * cos_theta, sin_theta, sin_phi * cos_theta) * (PI * self.radius);
+
+        // The inverse of the rotation into the space of the texture coordinates is its transpose.
+        (
+            self.uv_rotation.apply_transposed(dpdu),
+            self.uv_rotation.apply_transposed(dpdv),
+        )
+    }
+
     /// Calculate the axis-aligned bounding box of the sphere.
-    fn calculate_aabb(center: Point3, radius: f64) -> Aabb {
+    fn calculate_aabb(center: Point3, radius: Float) -> Aabb {
         let min = center - vec3!(radius, radius, radius);
         let max = center + vec3!(radius, radius, radius);
 
@@ -73,9 +113,9 @@ impl Hittable for SphereObject {
         let sqrt_d = discriminant.sqrt();
 
         let mut t = (h - sqrt_d) / a;
-        if t <= time.start || time.end <= t {
+        if !time.surrounds(t) {
             t = (h + sqrt_d) / a;
-            if t <= time.start || time.end <= t {
+            if !time.surrounds(t) {
                 return None;
             }
         }
@@ -85,21 +125,100 @@ impl Hittable for SphereObject {
         let outward_normal = (point - self.center) / self.radius;
 
         let material = self.material;
-        let (u, v) = SphereObject::get_sphere_uv(outward_normal);
+        let (u, v) = self.get_sphere_uv(outward_normal);
+        let (dpdu, dpdv) = self.get_sphere_derivatives(u, v);
         let (front_face, normal) = Intersection::face_normal(r, outward_normal);
 
+        // The texture spans half the circumference from pole to pole.
+        let footprint =
+            Intersection::texture_footprint(r, t, outward_normal, 1.0 / (PI * self.radius));
+
         Some(Intersection {
             point,
             normal,
+            geometric_normal: normal,
             front_face,
             material,
             t,
             u,
             v,
+            dpdu,
+            dpdv,
+            footprint,
         })
     }
 
+    fn occluded(&self, r: &Ray, time: Interval) -> bool {
+        let oc = self.center - r.orig;
+        let a = r.dir.len_sq();
+        let h = oc.dot(r.dir);
+        let c = oc.len_sq() - self.radius * self.radius;
+
+        let discriminant = h * h - a * c;
+        if discriminant < 0.0 {
+            return false;
+        }
+
+        let sqrt_d = discriminant.sqrt();
+        time.surrounds((h - sqrt_d) / a) || time.surrounds((h + sqrt_d) / a)
+    }
+
     fn bounding_box(&self) -> Aabb {
         self.bounding_box
     }
+
+    fn kind(&self) -> &'static str {
+        "sphere"
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3, time: Float) -> Float {
+        if self
+            .hit(
+                &Ray::with_time(origin, dir, time),
+                intr!(0.0, Float::INFINITY),
+            )
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        let dist_sq = (self.center - origin).len_sq();
+        let radius_sq = self.radius * self.radius;
+
+        // From inside the sphere every direction is sampled uniformly.
+        if dist_sq <= radius_sq {
+            return 1.0 / (4.0 * PI);
+        }
+
+        // Directions are sampled uniformly from the cone the sphere subtends.
+        let cos_theta_max = (1.0 - radius_sq / dist_sq).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: Point3, _time: Float) -> Vec3 {
+        let direction = self.center - origin;
+        let dist_sq = direction.len_sq();
+        let radius_sq = self.radius * self.radius;
+
+        if dist_sq <= radius_sq {
+            return Vec3::random_in_unit_sphere().unit();
+        }
+
+        // Sample a direction inside the cone around the direction to the center.
+        let r1 = random_float();
+        let r2 = random_float();
+        let z = 1.0 + r2 * ((1.0 - radius_sq / dist_sq).sqrt() - 1.0);
+
+        let phi = 2.0 * PI * r1;
+        let x = phi.cos() * (1.0 - z * z).sqrt();
+        let y = phi.sin() * (1.0 - z * z).sqrt();
+
+        Onb::new(direction).transform(vec3!(x, y, z))
+    }
+
+    fn light_material(&self) -> Option<MaterialId> {
+        Some(self.material)
+    }
 }
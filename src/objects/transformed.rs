@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    float::Float,
+    hittable::Hittable,
+    interval::Interval,
+    ray::{Intersection, Ray},
+    resources::MaterialId,
+    transform::{DecomposedTransform, Transform},
+    vector::{Point3, Vec3},
+};
+
+/// The number of intervals the motion is split into to bound a moving object.
+const MOTION_BOUND_STEPS: u32 = 32;
+
+#[derive(Debug, Clone)]
+/// An object placed into the scene with an affine transformation.
+pub struct TransformedObject {
+    /// The object in its local space.
+    object: Arc<dyn Hittable>,
+    /// The transformation from local to world space.
+    transform: Transform,
+    /// The transformation from world to local space.
+    inverse: Transform,
+    /// The motion over the shutter interval, if the object is moving.
+    motion: Option<Box<Motion>>,
+    /// The bounding box of the transformed object, over the whole shutter interval.
+    bounding_box: Aabb,
+}
+
+#[derive(Debug, Clone)]
+/// The motion of a moving object over the shutter interval.
+struct Motion {
+    /// The transformation at the end of the shutter interval.
+    end: Transform,
+    /// The transformations at the start and the end, decomposed once
+    /// so they are only interpolated for every ray.
+    keyframes: [DecomposedTransform; 2],
+}
+
+impl TransformedObject {
+    /// Create a new transformed object from an object and its local to world transformation.
+    pub fn new<H: Hittable + 'static>(object: H, transform: Transform) -> Self {
+        Self::from_shared(Arc::new(object), transform)
+    }
+
+    /// Create a new transformed object from a shared object, so one object can be instanced many times.
+    pub fn from_shared(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        let bounding_box = transform.apply_aabb(&object.bounding_box());
+
+        Self {
+            object,
+            transform,
+            inverse: transform.inverse(),
+            motion: None,
+            bounding_box,
+        }
+    }
+
+    /// Create a new moving object, whose transformation is interpolated from the start to the
+    /// end transformation over the shutter interval, to blur it with the motion.
+    pub fn with_motion<H: Hittable + 'static>(object: H, start: Transform, end: Transform) -> Self {
+        Self::from_shared_with_motion(Arc::new(object), start, end)
+    }
+
+    /// Create a new moving object from a shared object.
+    pub fn from_shared_with_motion(
+        object: Arc<dyn Hittable>,
+        start: Transform,
+        end: Transform,
+    ) -> Self {
+        let keyframes = [start.decompose(), end.decompose()];
+
+        // Bound the object at a number of points in time along its motion.
+        let local_box = object.bounding_box();
+        let mut bounding_box = Aabb::EMPTY;
+        for step in 0..=MOTION_BOUND_STEPS {
+            let t = step as Float / MOTION_BOUND_STEPS as Float;
+            let transform = keyframes[0].interpolate(&keyframes[1], t);
+            bounding_box.grow(&transform.apply_aabb(&local_box));
+        }
+
+        Self {
+            object,
+            transform: start,
+            inverse: start.inverse(),
+            motion: Some(Box::new(Motion { end, keyframes })),
+            bounding_box: bounding_box.pad(0.001),
+        }
+    }
+
+    /// Returns the transformation and its inverse at the given time.
+    fn transforms_at(&self, time: Float) -> (Transform, Transform) {
+        match self.motion.as_deref() {
+            Some(Motion {
+                keyframes: [start, end],
+                ..
+            }) => {
+                let transform = start.interpolate(end, time);
+                (transform, transform.inverse())
+            }
+            None => (self.transform, self.inverse),
+        }
+    }
+
+    /// Returns the local to world transformation of the object at the start of the shutter interval.
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+}
+
+impl Hittable for TransformedObject {
+    fn hit(&self, r: &Ray, time: Interval) -> Option<Intersection> {
+        // Move the ray into the local space of the object, the direction is not
+        // normalized, so the distances along the ray stay the same in both spaces.
+        let (transform, inverse) = self.transforms_at(r.time);
+        // The cone keeps its angle, so its footprint scales with the length of the direction.
+        let local_ray = Ray {
+            orig: inverse.apply_point(r.orig),
+            dir: inverse.apply_vector(r.dir),
+            ..r.clone()
+        };
+
+        let mut hit = self.object.hit(&local_ray, time)?;
+
+        // Move the intersection back into world space.
+        hit.point = transform.apply_point(hit.point);
+        hit.normal = inverse.apply_transposed(hit.normal).unit();
+        hit.geometric_normal = inverse.apply_transposed(hit.geometric_normal).unit();
+        hit.dpdu = transform.apply_vector(hit.dpdu);
+        hit.dpdv = transform.apply_vector(hit.dpdv);
+
+        Some(hit)
+    }
+
+    fn occluded(&self, r: &Ray, time: Interval) -> bool {
+        let (_, inverse) = self.transforms_at(r.time);
+        let local_ray = Ray {
+            orig: inverse.apply_point(r.orig),
+            dir: inverse.apply_vector(r.dir),
+            ..r.clone()
+        };
+
+        self.object.occluded(&local_ray, time)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    /// The density is evaluated in local space and converted back, as scaling the object
+    /// unevenly changes the solid angle the directions towards it spread over.
+    fn pdf_value(&self, origin: Point3, dir: Vec3, time: Float) -> Float {
+        let (_, inverse) = self.transforms_at(time);
+        let local_dir = inverse.apply_vector(dir.unit());
+        let density = self
+            .object
+            .pdf_value(inverse.apply_point(origin), local_dir, time);
+
+        // A unit direction turns into one of length l in local space,
+        // and the solid angle around it is scaled by det / l³.
+        density * inverse.determinant().abs() / local_dir.len().powi(3)
+    }
+
+    fn random(&self, origin: Point3, time: Float) -> Vec3 {
+        let (transform, inverse) = self.transforms_at(time);
+        let local = self.object.random(inverse.apply_point(origin), time);
+        transform.apply_vector(local)
+    }
+
+    fn light_material(&self) -> Option<MaterialId> {
+        self.object.light_material()
+    }
+
+    fn kind(&self) -> &'static str {
+        "transformed"
+    }
+
+    fn triangle_count(&self) -> usize {
+        self.object.triangle_count()
+    }
+
+    /// The memory of a shared object is split evenly between the objects sharing it.
+    fn memory_size(&self) -> usize {
+        size_of_val(self) + self.object.memory_size() / Arc::strong_count(&self.object)
+    }
+
+    fn motion(&self, point: Point3, time: Float) -> Vec3 {
+        let (transform, inverse) = self.transforms_at(time);
+        let local = inverse.apply_point(point);
+        let moved = local + self.object.motion(local, time);
+
+        self.motion
+            .as_ref()
+            .map_or(transform, |motion| motion.end)
+            .apply_point(moved)
+            - point
+    }
+}
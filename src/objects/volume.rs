@@ -0,0 +1,98 @@
+use crate::{
+    aabb::Aabb,
+    density::Density,
+    float::Float,
+    hittable::Hittable,
+    interval::Interval,
+    intr,
+    random::random_float,
+    ray::{Intersection, Ray},
+    resources::MaterialId,
+    vec3,
+};
+
+#[derive(Debug)]
+/// A participating medium like smoke or fog, filling the inside of a closed boundary object.
+pub struct VolumeObject {
+    /// The object enclosing the medium.
+    boundary: Box<dyn Hittable>,
+    /// The density of the medium.
+    density: Box<dyn Density>,
+    /// An upper bound of the density inside the boundary.
+    max_density: Float,
+    /// The phase material of the medium.
+    material: MaterialId,
+}
+
+impl VolumeObject {
+    /// Creates a new volume filling the boundary with the given density and material.
+    /// The density must never exceed the maximum density.
+    pub fn new<H: Hittable + 'static, D: Density + 'static>(
+        boundary: H,
+        density: D,
+        max_density: Float,
+        material: MaterialId,
+    ) -> Self {
+        Self {
+            boundary: Box::new(boundary),
+            density: Box::new(density),
+            max_density,
+            material,
+        }
+    }
+}
+
+impl Hittable for VolumeObject {
+    fn hit(&self, r: &Ray, time: Interval) -> Option<Intersection> {
+        // Find where the ray enters and leaves the boundary.
+        let enter = self.boundary.hit(r, Interval::UNIVERSE)?;
+        let exit = self
+            .boundary
+            .hit(r, intr!(enter.t + 0.0001, Float::INFINITY))?;
+
+        let mut t = enter.t.max(time.start).max(0.0);
+        let end = exit.t.min(time.end);
+        if t >= end || self.max_density <= 0.0 {
+            return None;
+        }
+
+        // Delta tracking: take exponential steps through a medium with the maximum density,
+        // and reject the collisions that fall into the thinner parts of the real medium.
+        let ray_length = r.dir.len();
+        loop {
+            t -= (1.0 - random_float()).ln() / (self.max_density * ray_length);
+            if t >= end {
+                return None;
+            }
+
+            let point = r.at(t);
+            if random_float() * self.max_density < self.density.density(point) {
+                return Some(Intersection {
+                    point,
+                    normal: vec3!(1, 0, 0),
+                    geometric_normal: vec3!(1, 0, 0),
+                    front_face: true,
+                    material: self.material,
+                    t,
+                    u: 0.0,
+                    v: 0.0,
+                    dpdu: vec3!(0),
+                    dpdv: vec3!(0),
+                    footprint: 0.0,
+                });
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn kind(&self) -> &'static str {
+        "volume"
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of_val(self) + self.boundary.memory_size() + size_of_val(&*self.density)
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{
+    aabb::Aabb,
+    float::Float,
+    hittable::Hittable,
+    interval::Interval,
+    ray::{Intersection, Ray},
+    resources::MaterialId,
+    vec3,
+    vector::{Point3, Vec3},
+};
+
+/// The number of voxels along each axis of a brick.
+const BRICK_SIZE: usize = 8;
+
+/// A brick of voxels, only allocated once one of its voxels is set.
+type Brick = Box<[Option<MaterialId>; BRICK_SIZE * BRICK_SIZE * BRICK_SIZE]>;
+
+#[derive(Debug)]
+/// A sparse grid of axis-aligned voxels, each optionally holding a material.
+/// Rays are traversed through the grid with a 3D DDA, so the whole grid is a
+/// single object in the scene instead of one box per voxel.
+pub struct VoxelGridObject {
+    /// The minimum corner of the grid.
+    origin: Point3,
+    /// The edge length of a single voxel.
+    voxel_size: Float,
+    /// The number of voxels along each axis.
+    dims: [usize; 3],
+    /// The number of bricks along each axis.
+    brick_dims: [usize; 3],
+    /// The lazily allocated bricks of the grid.
+    bricks: Vec<Option<Brick>>,
+    /// The bounding box of the grid.
+    bounding_box: Aabb,
+}
+
+impl VoxelGridObject {
+    /// Create a new empty voxel grid with its minimum corner at `origin`.
+    /// Panics if any of the dimensions is zero.
+    pub fn new(
+        origin: Point3,
+        voxel_size: Float,
+        width: usize,
+        height: usize,
+        depth: usize,
+    ) -> Self {
+        assert!(
+            width > 0 && height > 0 && depth > 0,
+            "Voxel grid dimensions must not be zero"
+        );
+
+        let dims = [width, height, depth];
+        let brick_dims = dims.map(|dim| dim.div_ceil(BRICK_SIZE));
+
+        let max = origin + vec3!(width as Float, height as Float, depth as Float) * voxel_size;
+
+        Self {
+            origin,
+            voxel_size,
+            dims,
+            brick_dims,
+            bricks: (0..brick_dims.iter().product()).map(|_| None).collect(),
+            bounding_box: Aabb::new(origin, max),
+        }
+    }
+
+    /// Get the material of the voxel at the given cell, if it is filled.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<MaterialId> {
+        if x >= self.dims[0] || y >= self.dims[1] || z >= self.dims[2] {
+            return None;
+        }
+
+        let (brick, voxel) = self.locate(x, y, z);
+        self.bricks[brick].as_ref()?[voxel]
+    }
+
+    /// Set the material of the voxel at the given cell, `None` clears it.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, material: Option<MaterialId>) {
+        assert!(
+            x < self.dims[0] && y < self.dims[1] && z < self.dims[2],
+            "Voxel out of bounds"
+        );
+
+        let (brick, voxel) = self.locate(x, y, z);
+
+        match (&mut self.bricks[brick], material) {
+            (Some(brick), material) => brick[voxel] = material,
+            (slot @ None, Some(material)) => {
+                let mut brick = Box::new([None; BRICK_SIZE * BRICK_SIZE * BRICK_SIZE]);
+                brick[voxel] = Some(material);
+                *slot = Some(brick);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Returns the index of the brick and the index of the voxel inside that brick.
+    fn locate(&self, x: usize, y: usize, z: usize) -> (usize, usize) {
+        let (bx, by, bz) = (x / BRICK_SIZE, y / BRICK_SIZE, z / BRICK_SIZE);
+        let (vx, vy, vz) = (x % BRICK_SIZE, y % BRICK_SIZE, z % BRICK_SIZE);
+
+        let brick = (bz * self.brick_dims[1] + by) * self.brick_dims[0] + bx;
+        let voxel = (vz * BRICK_SIZE + vy) * BRICK_SIZE + vx;
+
+        (brick, voxel)
+    }
+
+    /// Clips the ray against the bounds of the grid.
+    /// Returns the entry and exit distance and the axis the ray entered through,
+    /// which is `None` when the ray starts inside the grid.
+    fn clip(&self, r: &Ray, time: Interval) -> Option<(Float, Float, Option<usize>)> {
+        let mut t_min = time.start;
+        let mut t_max = time.end;
+        let mut entry_axis = None;
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / r.dir[axis];
+
+            let t0 = (self.bounding_box[axis].start - r.orig[axis]) * inv_d;
+            let t1 = (self.bounding_box[axis].end - r.orig[axis]) * inv_d;
+
+            let (t0, t1) = if t1 < t0 { (t1, t0) } else { (t0, t1) };
+
+            if t0 > t_min {
+                t_min = t0;
+                entry_axis = Some(axis);
+            }
+
+            if t1 < t_max {
+                t_max = t1;
+            }
+
+            if t_max <= t_min {
+                return None;
+            }
+        }
+
+        Some((t_min, t_max, entry_axis))
+    }
+}
+
+impl Hittable for VoxelGridObject {
+    fn hit(&self, r: &Ray, time: Interval) -> Option<Intersection> {
+        let (t_enter, t_exit, entry_axis) = self.clip(r, time)?;
+
+        let entry = r.at(t_enter);
+
+        let mut cell = [0isize; 3];
+        let mut step = [0isize; 3];
+        let mut t_next = [Float::INFINITY; 3];
+        let mut t_delta = [Float::INFINITY; 3];
+
+        // Set up the DDA state for every axis.
+        for axis in 0..3 {
+            let local = (entry[axis] - self.origin[axis]) / self.voxel_size;
+            cell[axis] = (local.floor() as isize).clamp(0, self.dims[axis] as isize - 1);
+
+            let dir = r.dir[axis];
+            if dir > 0.0 {
+                step[axis] = 1;
+                let boundary = self.origin[axis] + (cell[axis] + 1) as Float * self.voxel_size;
+                t_next[axis] = (boundary - r.orig[axis]) / dir;
+                t_delta[axis] = self.voxel_size / dir;
+            } else if dir < 0.0 {
+                step[axis] = -1;
+                let boundary = self.origin[axis] + cell[axis] as Float * self.voxel_size;
+                t_next[axis] = (boundary - r.orig[axis]) / dir;
+                t_delta[axis] = -self.voxel_size / dir;
+            }
+        }
+
+        let mut t = t_enter;
+        let mut crossed_axis = entry_axis;
+
+        loop {
+            let material = self.get(cell[0] as usize, cell[1] as usize, cell[2] as usize);
+
+            // A filled voxel is only hit when the ray crossed one of its faces,
+            // rays starting inside a voxel leave it without an intersection.
+            if let (Some(material), Some(axis)) = (material, crossed_axis) {
+                let point = r.at(t);
+
+                let mut outward_normal = Vec3::ZERO;
+                match axis {
+                    0 => outward_normal.x = -step[0] as Float,
+                    1 => outward_normal.y = -step[1] as Float,
+                    _ => outward_normal.z = -step[2] as Float,
+                }
+
+                // Use the position inside the voxel along the other two axes as UV.
+                let local = (point - self.origin) / self.voxel_size;
+                let u = local[(axis + 1) % 3].rem_euclid(1.0);
+                let v = local[(axis + 2) % 3].rem_euclid(1.0);
+
+                // The UV coordinates repeat with every voxel along the two axes.
+                let mut dpdu = Vec3::ZERO;
+                let mut dpdv = Vec3::ZERO;
+                match axis {
+                    0 => (dpdu.y, dpdv.z) = (self.voxel_size, self.voxel_size),
+                    1 => (dpdu.z, dpdv.x) = (self.voxel_size, self.voxel_size),
+                    _ => (dpdu.x, dpdv.y) = (self.voxel_size, self.voxel_size),
+                }
+
+                let (front_face, normal) = Intersection::face_normal(r, outward_normal);
+
+                return Some(Intersection {
+                    point,
+                    normal,
+                    geometric_normal: normal,
+                    front_face,
+                    material,
+                    t,
+                    u,
+                    v,
+                    dpdu,
+                    dpdv,
+                    footprint: Intersection::texture_footprint(
+                        r,
+                        t,
+                        outward_normal,
+                        1.0 / self.voxel_size,
+                    ),
+                });
+            }
+
+            // Step into the neighbouring voxel with the closest boundary.
+            let axis = if t_next[0] < t_next[1] {
+                if t_next[0] < t_next[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_next[1] < t_next[2] {
+                1
+            } else {
+                2
+            };
+
+            if t_next[axis] > t_exit {
+                return None;
+            }
+
+            t = t_next[axis];
+            cell[axis] += step[axis];
+            t_next[axis] += t_delta[axis];
+            crossed_axis = Some(axis);
+
+            if cell[axis] < 0 || cell[axis] >= self.dims[axis] as isize {
+                return None;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn kind(&self) -> &'static str {
+        "voxel grid"
+    }
+
+    fn memory_size(&self) -> usize {
+        let allocated = self.bricks.iter().flatten().count();
+        size_of_val(self)
+            + self.bricks.capacity() * size_of::<Option<Brick>>()
+            + allocated * size_of::<[Option<MaterialId>; BRICK_SIZE * BRICK_SIZE * BRICK_SIZE]>()
+    }
+}
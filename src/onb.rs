@@ -0,0 +1,37 @@
+use crate::{vec3, vector::Vec3};
+
+#[derive(Debug, Clone, Copy)]
+/// An orthonormal basis, used to move sampled directions into the space around a normal.
+pub struct Onb {
+    /// The first tangent of the basis.
+    pub u: Vec3,
+    /// The second tangent of the basis.
+    pub v: Vec3,
+    /// The normal of the basis.
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Creates a new orthonormal basis around the given normal.
+    pub fn new(normal: Vec3) -> Self {
+        let w = normal.unit();
+
+        // Pick a helper axis that is not parallel to the normal.
+        let a = if w.x.abs() > 0.9 {
+            vec3!(0, 1, 0)
+        } else {
+            vec3!(1, 0, 0)
+        };
+
+        let v = w.cross(a).unit();
+        let u = w.cross(v);
+
+        Self { u, v, w }
+    }
+
+    #[inline]
+    /// Transforms a vector from the local space of the basis to world space.
+    pub fn transform(&self, local: Vec3) -> Vec3 {
+        self.u * local.x + self.v * local.y + self.w * local.z
+    }
+}
@@ -0,0 +1,251 @@
+use crate::float::consts::PI;
+use std::fmt::Debug;
+
+use crate::{
+    float::Float,
+    hittable::Hittable,
+    light::Lights,
+    onb::Onb,
+    portal::Portal,
+    random::random_float,
+    vec3,
+    vector::{Point3, Vec3},
+};
+
+/// A probability density function over directions.
+pub trait Pdf: Debug {
+    /// Returns the probability density of the given direction.
+    fn value(&self, dir: Vec3) -> Float;
+
+    /// Samples a random direction following the density.
+    fn generate(&self) -> Vec3;
+}
+
+#[derive(Debug)]
+/// A density that samples every direction uniformly.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _dir: Vec3) -> Float {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vec3 {
+        Vec3::random_in_unit_sphere().unit()
+    }
+}
+
+#[derive(Debug)]
+/// A cosine weighted density over the hemisphere around a normal.
+pub struct CosinePdf {
+    /// The basis around the normal.
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    /// Creates a new cosine weighted density around the given normal.
+    pub fn new(normal: Vec3) -> Self {
+        Self {
+            uvw: Onb::new(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, dir: Vec3) -> Float {
+        let cos_theta = dir.unit().dot(self.uvw.w);
+        (cos_theta / PI).max(0.0)
+    }
+
+    fn generate(&self) -> Vec3 {
+        let r1 = random_float();
+        let r2 = random_float();
+
+        let phi = 2.0 * PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1.0 - r2).sqrt();
+
+        self.uvw.transform(vec3!(x, y, z))
+    }
+}
+
+#[derive(Debug)]
+/// A density that samples directions uniformly in a cone around an axis.
+pub struct ConePdf {
+    /// The basis around the axis.
+    uvw: Onb,
+    /// The cosine of the angle between the axis and the edge of the cone.
+    cos_max: Float,
+}
+
+impl ConePdf {
+    /// Creates a new density over the cone with the given half angle in radians around the axis.
+    pub fn new(axis: Vec3, angle: Float) -> Self {
+        Self {
+            uvw: Onb::new(axis),
+            cos_max: angle.cos().min(1.0 - Float::EPSILON),
+        }
+    }
+}
+
+impl Pdf for ConePdf {
+    fn value(&self, dir: Vec3) -> Float {
+        if dir.unit().dot(self.uvw.w) < self.cos_max {
+            return 0.0;
+        }
+
+        1.0 / (2.0 * PI * (1.0 - self.cos_max))
+    }
+
+    fn generate(&self) -> Vec3 {
+        let r1 = random_float();
+        let r2 = random_float();
+
+        let cos_theta = 1.0 - r1 * (1.0 - self.cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * r2;
+
+        self.uvw.transform(vec3!(
+            phi.cos() * sin_theta,
+            phi.sin() * sin_theta,
+            cos_theta
+        ))
+    }
+}
+
+#[derive(Debug)]
+/// A density that samples directions towards an object.
+pub struct HittablePdf<'a> {
+    /// The object to sample.
+    object: &'a dyn Hittable,
+    /// The origin from which the object is sampled.
+    origin: Point3,
+    /// The time at which the object is sampled.
+    time: Float,
+}
+
+impl<'a> HittablePdf<'a> {
+    /// Creates a new density sampling the object from the origin at the given time.
+    pub fn new(object: &'a dyn Hittable, origin: Point3, time: Float) -> Self {
+        Self {
+            object,
+            origin,
+            time,
+        }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, dir: Vec3) -> Float {
+        self.object.pdf_value(self.origin, dir, self.time)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.object.random(self.origin, self.time)
+    }
+}
+
+#[derive(Debug)]
+/// An equal mixture of two densities.
+pub struct MixturePdf<'a> {
+    /// The densities that are mixed.
+    pdfs: [&'a dyn Pdf; 2],
+}
+
+impl<'a> MixturePdf<'a> {
+    /// Creates a new density that picks either density with equal probability.
+    pub fn new(a: &'a dyn Pdf, b: &'a dyn Pdf) -> Self {
+        Self { pdfs: [a, b] }
+    }
+}
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, dir: Vec3) -> Float {
+        0.5 * self.pdfs[0].value(dir) + 0.5 * self.pdfs[1].value(dir)
+    }
+
+    fn generate(&self) -> Vec3 {
+        if random_float() < 0.5 {
+            self.pdfs[0].generate()
+        } else {
+            self.pdfs[1].generate()
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A density that samples directions through the light portals of a scene.
+pub struct PortalPdf<'a> {
+    /// The portals to sample, picked with equal probability.
+    portals: &'a [Portal],
+    /// The origin from which the portals are sampled.
+    origin: Point3,
+}
+
+impl<'a> PortalPdf<'a> {
+    /// Creates a new density sampling the portals from the origin.
+    /// There has to be at least one portal.
+    pub fn new(portals: &'a [Portal], origin: Point3) -> Self {
+        Self { portals, origin }
+    }
+}
+
+impl Pdf for PortalPdf<'_> {
+    fn value(&self, dir: Vec3) -> Float {
+        let sum: Float = self
+            .portals
+            .iter()
+            .map(|portal| portal.pdf_value(self.origin, dir))
+            .sum();
+
+        sum / self.portals.len() as Float
+    }
+
+    fn generate(&self) -> Vec3 {
+        let index = (random_float() * self.portals.len() as Float) as usize;
+        self.portals[index.min(self.portals.len() - 1)].random(self.origin)
+    }
+}
+
+#[derive(Debug)]
+/// A density that samples directions towards the lights of a scene.
+pub struct LightPdf<'a> {
+    /// The lights to sample, picked with equal probability.
+    lights: Lights<'a>,
+    /// The origin from which the lights are sampled.
+    origin: Point3,
+    /// The time at which the lights are sampled.
+    time: Float,
+}
+
+impl<'a> LightPdf<'a> {
+    /// Creates a new density sampling the lights from the origin at the given time.
+    /// There has to be at least one light.
+    pub fn new(lights: Lights<'a>, origin: Point3, time: Float) -> Self {
+        Self {
+            lights,
+            origin,
+            time,
+        }
+    }
+}
+
+impl Pdf for LightPdf<'_> {
+    fn value(&self, dir: Vec3) -> Float {
+        let sum: Float = self
+            .lights
+            .iter()
+            .map(|light| light.pdf_value(self.origin, dir, self.time))
+            .sum();
+
+        sum / self.lights.len() as Float
+    }
+
+    fn generate(&self) -> Vec3 {
+        let index = (random_float() * self.lights.len() as Float) as usize;
+        self.lights
+            .get(index.min(self.lights.len() - 1))
+            .random(self.origin, self.time)
+    }
+}
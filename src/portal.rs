@@ -0,0 +1,73 @@
+use crate::{
+    float::Float,
+    random::random_float,
+    vector::{Point3, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An opening like a window, through which the environment lights the scene.
+/// Portals are invisible, they only guide scattered rays towards the opening.
+pub struct Portal {
+    /// A corner of the opening.
+    corner: Point3,
+    /// The first edge of the opening, starting at the corner.
+    u: Vec3,
+    /// The second edge of the opening, starting at the corner.
+    v: Vec3,
+    /// The unit normal of the opening.
+    normal: Vec3,
+    /// The area of the opening.
+    area: Float,
+}
+
+impl Portal {
+    /// Creates a new portal spanning the parallelogram with the given corner and edges.
+    pub fn new(corner: Point3, u: Vec3, v: Vec3) -> Self {
+        let n = u.cross(v);
+
+        Self {
+            corner,
+            u,
+            v,
+            normal: n.unit(),
+            area: n.len(),
+        }
+    }
+
+    /// Returns the probability density, with respect to solid angle, of sampling
+    /// the given direction from the origin with [`Portal::random`].
+    pub fn pdf_value(&self, origin: Point3, dir: Vec3) -> Float {
+        let denom = self.normal.dot(dir);
+        if denom.abs() < 1e-8 {
+            return 0.0;
+        }
+
+        // Intersect the plane of the portal.
+        let t = self.normal.dot(self.corner - origin) / denom;
+        if t <= 0.0 {
+            return 0.0;
+        }
+
+        // Check whether the point lies within the parallelogram.
+        let n = self.u.cross(self.v);
+        let w = n / n.len_sq();
+        let offset = origin + dir * t - self.corner;
+        let alpha = w.dot(offset.cross(self.v));
+        let beta = w.dot(self.u.cross(offset));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return 0.0;
+        }
+
+        // Convert the uniform density over the area to a density over solid angle.
+        let dist_sq = t * t * dir.len_sq();
+        let cosine = (denom / dir.len()).abs();
+
+        dist_sq / (cosine * self.area)
+    }
+
+    /// Samples a random direction from the origin through the portal.
+    pub fn random(&self, origin: Point3) -> Vec3 {
+        let point = self.corner + self.u * random_float() + self.v * random_float();
+        point - origin
+    }
+}
@@ -0,0 +1,46 @@
+use std::{fmt::Debug, sync::Arc};
+
+use crate::framebuffer::FrameBuffer;
+
+/// An effect that is applied to the HDR frame buffer after rendering.
+pub trait PostProcess: Debug + Send + Sync {
+    /// Apply the effect to the frame buffer in place.
+    fn apply(&self, fb: &mut FrameBuffer);
+}
+
+#[derive(Debug, Clone, Default)]
+/// An ordered list of post processing effects.
+pub struct PostProcessPipeline {
+    /// The effects in the order they are applied.
+    effects: Vec<Arc<dyn PostProcess>>,
+}
+
+impl PostProcessPipeline {
+    /// Creates a new empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an effect to the end of the pipeline.
+    pub fn with<P: PostProcess + 'static>(mut self, effect: P) -> Self {
+        self.effects.push(Arc::new(effect));
+        self
+    }
+
+    /// Appends an effect to the end of the pipeline.
+    pub fn add<P: PostProcess + 'static>(&mut self, effect: P) {
+        self.effects.push(Arc::new(effect));
+    }
+
+    /// Returns true if the pipeline contains no effects.
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Applies every effect of the pipeline in order.
+    pub fn apply(&self, fb: &mut FrameBuffer) {
+        for effect in self.effects.iter() {
+            effect.apply(fb);
+        }
+    }
+}
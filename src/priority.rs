@@ -0,0 +1,88 @@
+use crate::{
+    float::Float,
+    imgbuf::{ops::ResizeFilter, ImageBuffer},
+};
+
+/// The fraction of the passes of a progressive render by which the pixels of the lowest
+/// priority start later than the pixels of the highest priority.
+const MAX_DELAY: Float = 0.5;
+
+#[derive(Debug, Clone)]
+/// The priority of every pixel of a progressive render in the range [0, 1]. Pixels of
+/// higher priority get their first samples earlier, so the subject of the image cleans
+/// up before the periphery.
+pub struct PriorityMap {
+    pub width: u32,
+    pub height: u32,
+    /// The priorities, row by row.
+    values: Vec<Float>,
+}
+
+impl PriorityMap {
+    /// Creates a map giving all pixels the highest priority.
+    pub fn uniform(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            values: vec![1.0; width as usize * height as usize],
+        }
+    }
+
+    /// Creates a map whose priority falls off smoothly from the center of the image
+    /// to its corners.
+    pub fn centered(width: u32, height: u32) -> Self {
+        let (center_x, center_y) = (width as Float / 2.0, height as Float / 2.0);
+        let half_diagonal = (center_x * center_x + center_y * center_y).sqrt().max(1.0);
+
+        let mut values = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (x as Float + 0.5 - center_x, y as Float + 0.5 - center_y);
+                let distance = ((dx * dx + dy * dy).sqrt() / half_diagonal).min(1.0);
+                values.push(1.0 - distance * distance * (3.0 - 2.0 * distance));
+            }
+        }
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// Uses the red channel of a mask image as the priorities, where white is the highest
+    /// priority. The mask is resized to the given dimensions.
+    pub fn from_image(mask: &ImageBuffer, width: u32, height: u32) -> Self {
+        let mask = mask.resize(width, height, ResizeFilter::Bilinear);
+
+        Self {
+            width,
+            height,
+            values: mask
+                .data
+                .chunks(3)
+                .map(|pixel| pixel[0] as Float / 255.0)
+                .collect(),
+        }
+    }
+
+    /// Returns the priority of a pixel.
+    pub fn value(&self, x: u32, y: u32) -> Float {
+        self.values[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Returns the pass of a progressive render with the given number of passes per pixel
+    /// in which the pixel gets its first samples.
+    pub fn start_pass(&self, x: u32, y: u32, passes: u32) -> u32 {
+        let delay = (1.0 - self.value(x, y).clamp(0.0, 1.0)) * MAX_DELAY * passes as Float;
+        delay.round() as u32
+    }
+
+    /// Returns the number of passes of a progressive render with the given number of passes
+    /// per pixel, until the pixels that start last are finished.
+    pub fn total_passes(&self, passes: u32) -> u32 {
+        let lowest = self.values.iter().copied().fold(1.0, Float::min);
+        let delay = (1.0 - lowest.clamp(0.0, 1.0)) * MAX_DELAY * passes as Float;
+        passes + delay.round() as u32
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Whether the profiler is collecting statistics.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The values of all counters, indexed by [`Counter`].
+static COUNTERS: [AtomicU64; Counter::COUNT] = [const { AtomicU64::new(0) }; Counter::COUNT];
+
+/// The recorded stages and their durations, in the order they finished.
+static STAGES: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An event counted by the profiler.
+pub enum Counter {
+    /// Rays sent from the camera into the scene.
+    PrimaryRays,
+    /// Rays spawned by scattering off a material.
+    SecondaryRays,
+    /// Rays only testing the visibility between two points.
+    ShadowRays,
+    /// Bounding box tests of BVH nodes.
+    BvhNodeTests,
+    /// Intersection tests against objects.
+    PrimitiveTests,
+}
+
+impl Counter {
+    /// The number of counters.
+    const COUNT: usize = 5;
+
+    /// All counters in the order they are reported.
+    const ALL: [Counter; Counter::COUNT] = [
+        Counter::PrimaryRays,
+        Counter::SecondaryRays,
+        Counter::ShadowRays,
+        Counter::BvhNodeTests,
+        Counter::PrimitiveTests,
+    ];
+
+    /// Returns a human readable name of the counter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Counter::PrimaryRays => "primary rays",
+            Counter::SecondaryRays => "secondary rays",
+            Counter::ShadowRays => "shadow rays",
+            Counter::BvhNodeTests => "BVH node tests",
+            Counter::PrimitiveTests => "primitive tests",
+        }
+    }
+}
+
+/// Enables the collection of statistics.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+/// Returns true if the profiler is collecting statistics.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[inline]
+/// Increments a counter by one, if the profiler is enabled.
+pub fn count(counter: Counter) {
+    if is_enabled() {
+        COUNTERS[counter as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Runs the function in a tracing span of the given stage name and emits its duration as an event.
+/// The duration is also recorded under the stage name, if the profiler is enabled.
+pub fn time_stage<T, F: FnOnce() -> T>(name: &'static str, f: F) -> T {
+    let _span = tracing::info_span!("stage", name).entered();
+
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+
+    tracing::info!(?elapsed, "finished {name}");
+    if is_enabled() {
+        STAGES.lock().unwrap().push((name, elapsed));
+    }
+
+    result
+}
+
+/// Resets all counters and recorded stages.
+pub fn reset() {
+    for counter in COUNTERS.iter() {
+        counter.store(0, Ordering::Relaxed);
+    }
+
+    STAGES.lock().unwrap().clear();
+}
+
+/// Returns a snapshot of the collected statistics.
+pub fn report() -> Report {
+    Report {
+        counters: Counter::ALL
+            .map(|counter| (counter, COUNTERS[counter as usize].load(Ordering::Relaxed))),
+        stages: STAGES.lock().unwrap().clone(),
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A snapshot of the statistics collected by the profiler.
+pub struct Report {
+    /// The value of every counter.
+    pub counters: [(Counter, u64); Counter::COUNT],
+    /// The recorded stages and their durations.
+    pub stages: Vec<(&'static str, Duration)>,
+}
+
+impl Report {
+    /// Returns the value of a counter.
+    pub fn get(&self, counter: Counter) -> u64 {
+        self.counters[counter as usize].1
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Counters:")?;
+        for (counter, value) in self.counters.iter() {
+            writeln!(f, "  {:<16} {:>14}", counter.name(), value)?;
+        }
+
+        let rays = self.get(Counter::PrimaryRays)
+            + self.get(Counter::SecondaryRays)
+            + self.get(Counter::ShadowRays);
+        if rays > 0 {
+            writeln!(
+                f,
+                "  {:<16} {:>14.2}",
+                "tests per ray",
+                (self.get(Counter::BvhNodeTests) + self.get(Counter::PrimitiveTests)) as f64
+                    / rays as f64
+            )?;
+        }
+
+        writeln!(f, "Stages:")?;
+        for (name, duration) in self.stages.iter() {
+            writeln!(f, "  {:<16} {:>12.3?}", name, duration)?;
+        }
+
+        Ok(())
+    }
+}
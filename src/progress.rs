@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A rectangular region of the image that is rendered as a unit.
+pub struct Tile {
+    /// The x coordinate of the top left pixel of the tile.
+    pub x: u32,
+    /// The y coordinate of the top left pixel of the tile.
+    pub y: u32,
+    /// The width of the tile in pixels.
+    pub width: u32,
+    /// The height of the tile in pixels.
+    pub height: u32,
+}
+
+impl Tile {
+    /// Splits an image into tiles of at most the given size, in row-major order.
+    pub fn grid(image_width: u32, image_height: u32, tile_size: u32) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+
+        for y in (0..image_height).step_by(tile_size as usize) {
+            for x in (0..image_width).step_by(tile_size as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_size.min(image_width - x),
+                    height: tile_size.min(image_height - y),
+                });
+            }
+        }
+
+        tiles
+    }
+
+    /// Returns the number of pixels in the tile.
+    pub fn pixel_count(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Returns an iterator over the pixel coordinates of the tile in row-major order.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> {
+        let tile = *self;
+
+        (tile.y..tile.y + tile.height)
+            .flat_map(move |y| (tile.x..tile.x + tile.width).map(move |x| (x, y)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A stage of rendering an image.
+pub enum RenderStage {
+    /// Samples are traced through the scene.
+    Rendering,
+    /// The post processing effects are applied.
+    PostProcessing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An event reporting the progress of a render.
+pub enum ProgressEvent {
+    /// A new stage of the render started.
+    StageStarted(RenderStage),
+    /// A tile started rendering.
+    TileStarted(Tile),
+    /// A tile finished rendering.
+    TileFinished(Tile),
+    /// Samples were completed.
+    SamplesCompleted {
+        /// The number of samples completed so far.
+        completed: u64,
+        /// The total number of samples of the render.
+        total: u64,
+        /// The time elapsed since the render started.
+        elapsed: Duration,
+        /// The estimated time until all samples are completed.
+        remaining: Duration,
+    },
+    /// The render was cancelled, the remaining tiles are skipped.
+    Cancelled,
+    /// The render finished.
+    Finished {
+        /// The total time the render took.
+        elapsed: Duration,
+    },
+}
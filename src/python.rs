@@ -0,0 +1,382 @@
+// The pyo3 macros convert errors into `PyErr` even if they already are one.
+#![allow(clippy::useless_conversion)]
+
+use std::{
+    os::raw::{c_int, c_void},
+    path::PathBuf,
+    ptr,
+};
+
+use pyo3::{
+    exceptions::{PyBufferError, PyValueError},
+    ffi,
+    prelude::*,
+};
+
+use crate::{
+    camera::{Camera, CameraBuilder},
+    environments::{
+        gradient::GradientEnvironment, image::ImageEnvironment, sky::SkyEnvironment,
+        solid::SolidEnvironment,
+    },
+    float::Float,
+    framebuffer::FrameBuffer,
+    imgbuf::ImageBuffer,
+    materials::{
+        dielectric::DielectricMaterial, diffuse_light::DiffuseLightMaterial,
+        lambertian::LambertianMaterial, metal::MetalMaterial,
+    },
+    mesh::Mesh,
+    objects::{mesh::MeshObject, sphere::SphereObject},
+    resources::{EnvironmentId, MaterialId, Resources, TextureId},
+    scene::Scene,
+    scene_file::SceneFile,
+    textures::{checker::CheckerTexture, image::ImageTexture, solid::SolidTexture},
+    vector::Vec3,
+};
+
+/// A vector as passed from Python, a tuple of three numbers.
+type PyVec3 = (Float, Float, Float);
+
+/// Converts a vector passed from Python.
+fn vec3((x, y, z): PyVec3) -> Vec3 {
+    Vec3 { x, y, z }
+}
+
+/// Converts an error of the renderer into a Python exception.
+fn error(message: &'static str) -> PyErr {
+    PyValueError::new_err(message)
+}
+
+#[pyclass(name = "TextureId", frozen)]
+#[derive(Clone, Copy)]
+/// An identifier for a texture, returned by the resources.
+pub struct PyTextureId(TextureId);
+
+#[pyclass(name = "MaterialId", frozen)]
+#[derive(Clone, Copy)]
+/// An identifier for a material, returned by the resources.
+pub struct PyMaterialId(MaterialId);
+
+#[pyclass(name = "EnvironmentId", frozen)]
+#[derive(Clone, Copy)]
+/// An identifier for an environment, returned by the resources.
+pub struct PyEnvironmentId(EnvironmentId);
+
+#[pyclass(name = "Resources")]
+#[derive(Default)]
+/// The textures, materials and environments of a scene.
+pub struct PyResources(Resources);
+
+#[pymethods]
+impl PyResources {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a texture of a single color.
+    fn add_solid_texture(&mut self, color: PyVec3) -> PyTextureId {
+        PyTextureId(self.0.add_texture(SolidTexture::new(vec3(color))))
+    }
+
+    /// Loads an image texture from a file.
+    fn add_image_texture(&mut self, path: PathBuf) -> PyResult<PyTextureId> {
+        let id = self.0.add_image_texture(path.display()).map_err(error)?;
+        Ok(PyTextureId(id))
+    }
+
+    /// Adds a texture from RGB bytes, row by row from the top left.
+    fn add_image_texture_from_rgb(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    ) -> PyResult<PyTextureId> {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(3));
+        if len != Some(data.len()) {
+            return Err(error("data length does not match dimensions"));
+        }
+
+        let image = ImageBuffer::with_data(width, height, data);
+        Ok(PyTextureId(self.0.add_texture(ImageTexture::new(image))))
+    }
+
+    /// Adds a 3D checker pattern alternating between two textures.
+    fn add_checker_texture(
+        &mut self,
+        scale: Float,
+        even: PyTextureId,
+        odd: PyTextureId,
+    ) -> PyTextureId {
+        PyTextureId(
+            self.0
+                .add_texture(CheckerTexture::new(scale, even.0, odd.0)),
+        )
+    }
+
+    /// Adds a diffuse material.
+    fn add_lambertian_material(&mut self, albedo: PyTextureId) -> PyMaterialId {
+        PyMaterialId(self.0.add_material(LambertianMaterial::new(albedo.0)))
+    }
+
+    /// Adds a reflective material, blurred by the fuzz.
+    fn add_metal_material(&mut self, albedo: PyVec3, fuzz: Float) -> PyMaterialId {
+        PyMaterialId(self.0.add_material(MetalMaterial::new(vec3(albedo), fuzz)))
+    }
+
+    /// Adds a transparent material with the given refraction index.
+    fn add_dielectric_material(&mut self, refraction_index: Float) -> PyMaterialId {
+        PyMaterialId(
+            self.0
+                .add_material(DielectricMaterial::new(refraction_index)),
+        )
+    }
+
+    /// Adds a material that emits light.
+    fn add_light_material(&mut self, emission: PyTextureId) -> PyMaterialId {
+        PyMaterialId(self.0.add_material(DiffuseLightMaterial::new(emission.0)))
+    }
+
+    /// Adds an environment of a single color.
+    fn add_solid_environment(&mut self, color: PyVec3) -> PyEnvironmentId {
+        PyEnvironmentId(self.0.add_environment(SolidEnvironment::new(vec3(color))))
+    }
+
+    /// Adds an environment blending from the bottom to the top color.
+    fn add_gradient_environment(&mut self, bottom: PyVec3, top: PyVec3) -> PyEnvironmentId {
+        let environment = GradientEnvironment::new(vec3(bottom), vec3(top));
+        PyEnvironmentId(self.0.add_environment(environment))
+    }
+
+    /// Adds a daylight sky with the sun in the given direction.
+    fn add_sky_environment(&mut self, sun_direction: PyVec3) -> PyEnvironmentId {
+        let environment = SkyEnvironment::daylight(vec3(sun_direction));
+        PyEnvironmentId(self.0.add_environment(environment))
+    }
+
+    /// Loads an environment from a Radiance HDR image.
+    fn add_hdr_environment(
+        &mut self,
+        path: PathBuf,
+        intensity: Float,
+    ) -> PyResult<PyEnvironmentId> {
+        let image = FrameBuffer::load_hdr(path.display()).map_err(error)?;
+        let environment = ImageEnvironment::new(image, intensity);
+        Ok(PyEnvironmentId(self.0.add_environment(environment)))
+    }
+}
+
+#[pyclass(name = "Scene")]
+/// The objects to render, surrounded by an environment.
+pub struct PyScene(Scene);
+
+#[pymethods]
+impl PyScene {
+    #[new]
+    fn new(background: PyEnvironmentId) -> Self {
+        Self(Scene::new(background.0))
+    }
+
+    /// Adds a sphere.
+    fn add_sphere(&mut self, center: PyVec3, radius: Float, material: PyMaterialId) {
+        self.0
+            .add(SphereObject::new(vec3(center), radius, material.0));
+    }
+
+    /// Loads a mesh from a Wavefront OBJ file and adds it.
+    fn add_mesh(&mut self, path: PathBuf, material: PyMaterialId) -> PyResult<()> {
+        let mesh = Mesh::load_obj(path.display()).map_err(error)?;
+        self.0.add(MeshObject::new(&mesh, material.0));
+        Ok(())
+    }
+
+    /// Builds the bounding volume hierarchy ahead of the first render.
+    fn build_bvh(&mut self) {
+        self.0.build_bvh();
+    }
+}
+
+#[pyclass(name = "Camera")]
+#[derive(Clone)]
+/// The settings of the camera a scene is rendered with.
+pub struct PyCamera(CameraBuilder);
+
+#[pymethods]
+impl PyCamera {
+    #[new]
+    #[pyo3(signature = (
+        look_from,
+        look_at,
+        vfov = 90.0,
+        image_width = 400,
+        aspect_ratio = 16.0 / 9.0,
+        sample_count = None,
+        max_bounces = None,
+        seed = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        look_from: PyVec3,
+        look_at: PyVec3,
+        vfov: Float,
+        image_width: u32,
+        aspect_ratio: Float,
+        sample_count: Option<u32>,
+        max_bounces: Option<u32>,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut builder = Camera::builder();
+        builder
+            .with_look_from(vec3(look_from))
+            .with_look_at(vec3(look_at))
+            .with_vfov(vfov)
+            .with_image_width(image_width)
+            .with_aspect_ratio(aspect_ratio);
+
+        if let Some(sample_count) = sample_count {
+            builder.with_sample_count(sample_count);
+        }
+        if let Some(max_bounces) = max_bounces {
+            builder.with_max_bounces(max_bounces);
+        }
+        if let Some(seed) = seed {
+            builder.with_seed(seed);
+        }
+
+        Self(builder)
+    }
+
+    /// Renders the scene, releasing the interpreter while rendering.
+    fn render(&self, py: Python<'_>, scene: &PyScene, resources: &PyResources) -> PyImage {
+        let camera = self.0.build();
+        let image = py.allow_threads(|| camera.render(&scene.0, &resources.0, |_| {}));
+
+        PyImage::new(image)
+    }
+}
+
+#[pyclass(name = "Image", frozen)]
+/// A rendered image, which supports the buffer protocol, so `numpy.asarray(image)`
+/// returns an array of bytes with the shape `(height, width, 3)` without copying.
+pub struct PyImage {
+    /// The rendered image.
+    image: ImageBuffer,
+    /// The shape of the buffer, which must outlive every view of it.
+    shape: [ffi::Py_ssize_t; 3],
+    /// The strides of the buffer, which must outlive every view of it.
+    strides: [ffi::Py_ssize_t; 3],
+}
+
+impl PyImage {
+    /// Wraps a rendered image.
+    fn new(image: ImageBuffer) -> Self {
+        let width = image.width as ffi::Py_ssize_t;
+        let height = image.height as ffi::Py_ssize_t;
+
+        Self {
+            image,
+            shape: [height, width, 3],
+            strides: [width * 3, 3, 1],
+        }
+    }
+}
+
+#[pymethods]
+impl PyImage {
+    /// The width of the image in pixels.
+    #[getter]
+    fn width(&self) -> u32 {
+        self.image.width
+    }
+
+    /// The height of the image in pixels.
+    #[getter]
+    fn height(&self) -> u32 {
+        self.image.height
+    }
+
+    /// Returns a copy of the RGB bytes, row by row from the top left.
+    fn to_bytes(&self) -> Vec<u8> {
+        self.image.data.to_vec()
+    }
+
+    /// Saves the image to a PNG file.
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        let image =
+            ImageBuffer::with_data(self.image.width, self.image.height, self.image.data.clone());
+        image.save(path.display()).map_err(error)
+    }
+
+    /// Exposes the pixels as a read-only buffer of bytes.
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("view is null"));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("image is read-only"));
+        }
+
+        let image = slf.get();
+        let view = &mut *view;
+
+        view.buf = image.image.data.as_ptr() as *mut c_void;
+        view.len = image.image.data.len() as ffi::Py_ssize_t;
+        view.readonly = 1;
+        view.itemsize = 1;
+        view.format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            c"B".as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        view.ndim = 3;
+        view.shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            image.shape.as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        view.strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            image.strides.as_ptr() as *mut _
+        } else {
+            ptr::null_mut()
+        };
+        view.suboffsets = ptr::null_mut();
+        view.internal = ptr::null_mut();
+
+        // The view keeps the image alive until it is released.
+        view.obj = slf.into_any().into_ptr();
+
+        Ok(())
+    }
+}
+
+/// Loads a scene file, returning its scene, resources and camera.
+#[pyfunction]
+fn load_scene(path: PathBuf) -> PyResult<(PyScene, PyResources, PyCamera)> {
+    let file = SceneFile::load(path).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok((
+        PyScene(file.scene),
+        PyResources(file.resources),
+        PyCamera(file.camera),
+    ))
+}
+
+/// The Python module, named after the library.
+#[pymodule]
+fn raytracer_base(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyTextureId>()?;
+    module.add_class::<PyMaterialId>()?;
+    module.add_class::<PyEnvironmentId>()?;
+    module.add_class::<PyResources>()?;
+    module.add_class::<PyScene>()?;
+    module.add_class::<PyCamera>()?;
+    module.add_class::<PyImage>()?;
+    module.add_function(wrap_pyfunction!(load_scene, module)?)?;
+    Ok(())
+}
@@ -1,15 +1,68 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, sync::Arc};
+
+use crate::{float::Float, sampler::Sampler};
 
 thread_local! {
     /// A thread-local random number generator.
     pub static THREAD_RNG: RefCell<Random> = const { RefCell::new(Random::new(0xdeadbeef)) };
 }
 
+/// Returns a random number in the range [0, 1) from the thread-local generator.
+pub fn random_float() -> Float {
+    THREAD_RNG.with(|rng| rng.borrow_mut().random_float())
+}
+
+/// Reseeds the thread-local generator, so the following numbers are reproducible.
+/// The generator can't start from zero, so a seed of zero is replaced by `u64::MAX`.
+pub fn seed(seed: u64) {
+    let seed = if seed == 0 { u64::MAX } else { seed };
+    THREAD_RNG.with(|rng| *rng.borrow_mut() = Random::new(seed));
+}
+
+/// Takes the following numbers of the thread-local generator from the sampler, as the
+/// dimensions of a sample of a pixel, until the sample is finished.
+/// The pixel takes `count` samples in total.
+pub fn start_sample(sampler: &Arc<dyn Sampler>, pixel: (u32, u32), index: u32, count: u32) {
+    THREAD_RNG.with(|rng| {
+        rng.borrow_mut().sample = Some(PixelSample {
+            sampler: Arc::clone(sampler),
+            pixel,
+            index,
+            count,
+            dimension: 0,
+        })
+    });
+}
+
+/// Returns the thread-local generator to independent random numbers after a sample.
+pub fn finish_sample() {
+    THREAD_RNG.with(|rng| rng.borrow_mut().sample = None);
+}
+
+#[derive(Debug)]
+/// A sample of a pixel whose numbers are taken from a sampler.
+struct PixelSample {
+    /// The sampler generating the numbers.
+    sampler: Arc<dyn Sampler>,
+    /// The pixel of the sample.
+    pixel: (u32, u32),
+    /// The index of the sample in the pixel.
+    index: u32,
+    /// The number of samples of the pixel.
+    count: u32,
+    /// The dimension of the next number.
+    dimension: u32,
+}
+
 #[derive(Debug)]
 /// A random number generator.
 pub struct Random {
     /// The state of the random number generator.
     state: u64,
+    /// Decorrelates the samples of samplers between generators with different seeds.
+    scramble: u32,
+    /// The sample whose numbers are currently returned instead of random numbers.
+    sample: Option<PixelSample>,
 }
 
 impl Random {
@@ -17,8 +70,11 @@ impl Random {
     pub const fn new(seed: u64) -> Self {
         assert!(seed != 0, "seed must not be zero");
 
+        let state = Self::jenkins_hash(seed);
         Self {
-            state: Self::jenkins_hash(seed),
+            state,
+            scramble: (state >> 32) as u32 ^ state as u32,
+            sample: None,
         }
     }
 
@@ -26,11 +82,11 @@ impl Random {
     /// hash function to the input.
     /// See https://www.burtleburtle.net/bob/hash/doobs.html.
     const fn jenkins_hash(mut input: u64) -> u64 {
-        input += input << 10;
+        input = input.wrapping_add(input << 10);
         input ^= input >> 6;
-        input += input << 3;
+        input = input.wrapping_add(input << 3);
         input ^= input >> 11;
-        input += input << 15;
+        input = input.wrapping_add(input << 15);
         input
     }
 
@@ -44,14 +100,43 @@ impl Random {
         x
     }
 
-    /// Returns a random 64-bit floating point number in the range [0, 1].
+    /// Returns a random floating point number in the range [0, 1), or the next number
+    /// of the current sample of a sampler.
+    pub fn random_float(&mut self) -> Float {
+        match self.sample.as_mut() {
+            Some(sample) => {
+                sample.dimension += 1;
+                sample.sampler.sample(
+                    sample.pixel,
+                    sample.index,
+                    sample.count,
+                    sample.dimension - 1,
+                    self.scramble,
+                )
+            }
+            None => self.uniform_float(),
+        }
+    }
+
+    /// Returns a random floating point number in the range [0, 1].
     /// This sets the exponent to zero and sets the 52 most significant bits
     /// of a random 64 bit integer as the mantissa, this generates a
     /// number from [1.0, 1.9999999] which is then mapped to [0, 0.999999]
     /// by subtracting one. See Ray Tracing Gems II, Section 14.3.4.
-    pub fn random_f64(&mut self) -> f64 {
+    #[cfg(not(feature = "f32"))]
+    fn uniform_float(&mut self) -> Float {
         let rand = self.xor_shift64();
         let bits = 0x3ff0000000000000 | (rand >> 12);
         f64::from_bits(bits) - 1.0
     }
+
+    /// Returns a random floating point number in the range [0, 1].
+    /// This uses the 23 most significant bits of a random 64 bit integer
+    /// as the mantissa, like the 64-bit version.
+    #[cfg(feature = "f32")]
+    fn uniform_float(&mut self) -> Float {
+        let rand = self.xor_shift64();
+        let bits = 0x3f800000 | (rand >> 41) as u32;
+        f32::from_bits(bits) - 1.0
+    }
 }
@@ -1,8 +1,21 @@
 use crate::{
+    float::Float,
     resources::MaterialId,
     vector::{Point3, Vec3},
 };
 
+/// The smallest cosine between a ray and a surface used to stretch its footprint,
+/// which limits the blur of textures seen at grazing angles.
+const MIN_FOOTPRINT_COS: Float = 0.1;
+
+/// The distance rays leaving a hit are moved off the surface, relative to the magnitude of the
+/// coordinates involved, which is well above the rounding error of the hit point.
+const ORIGIN_OFFSET_SCALE: Float = 1024.0 * Float::EPSILON;
+
+/// The smallest magnitude the offset of rays leaving a hit is scaled by, for hits
+/// close to the origin of the scene.
+const MIN_ORIGIN_MAGNITUDE: Float = 1e-3;
+
 #[derive(Debug, Clone)]
 /// A ray in 3D space, with an origin and a direction.
 pub struct Ray {
@@ -10,18 +23,110 @@ pub struct Ray {
     pub orig: Point3,
     /// The direction of the ray.
     pub dir: Vec3,
+    /// The point in time during the shutter interval at which the ray was cast, in the range [0, 1].
+    pub time: Float,
+    /// The width of the cone around the ray at its origin, which approximates its footprint.
+    pub cone_width: Float,
+    /// The angle in radians by which the cone around the ray widens per unit of distance.
+    pub cone_spread: Float,
+    /// The roughness the specular surfaces the ray hits are blurred to, zero for sharp surfaces.
+    pub min_roughness: Float,
+    /// The roughness the specular surfaces are blurred to after a diffuse bounce, which removes
+    /// the noise of the paths from diffuse surfaces over specular ones to small lights.
+    pub roughness_clamp: Float,
 }
 
 impl Ray {
-    /// Create a new ray with the given origin and direction.
+    /// Create a new ray with the given origin and direction at the start of the shutter interval.
     pub fn new(orig: Point3, dir: Vec3) -> Self {
-        Self { orig, dir }
+        Self::with_time(orig, dir, 0.0)
+    }
+
+    /// Create a new ray with the given origin and direction at the given time.
+    pub fn with_time(orig: Point3, dir: Vec3, time: Float) -> Self {
+        Self {
+            orig,
+            dir,
+            time,
+            cone_width: 0.0,
+            cone_spread: 0.0,
+            min_roughness: 0.0,
+            roughness_clamp: 0.0,
+        }
     }
 
     /// Get the point along the ray at a given distance.
-    pub fn at(&self, t: f64) -> Point3 {
+    pub fn at(&self, t: Float) -> Point3 {
         self.orig + self.dir * t
     }
+
+    /// Returns the width of the cone around the ray at the given distance along it.
+    pub fn footprint(&self, t: Float) -> Float {
+        self.cone_width + self.cone_spread * t * self.dir.len()
+    }
+
+    /// Create a ray leaving a hit in the given direction, at the same time as this ray.
+    /// The cone continues from its width at the hit, the curvature of the surface is ignored.
+    /// The origin is moved off the surface to the side the ray leaves on, so it doesn't hit
+    /// the surface again, without skipping nearby surfaces at any scale of the scene.
+    pub fn bounce(&self, hit: &Intersection, dir: Vec3) -> Self {
+        Self {
+            cone_width: self.footprint(hit.t),
+            cone_spread: self.cone_spread,
+            min_roughness: self.min_roughness,
+            roughness_clamp: self.roughness_clamp,
+            ..Self::with_time(self.offset_origin(hit, dir), dir, self.time)
+        }
+    }
+
+    /// Create a ray leaving a point on a surface with the given normal, which isn't the hit
+    /// of another ray. The origin is moved off the surface like for rays leaving hits.
+    pub fn leaving(point: Point3, normal: Vec3, dir: Vec3) -> Self {
+        Self::new(offset_point(point, normal, max_abs(point), dir), dir)
+    }
+
+    /// Returns the ray with the reciprocals of its direction, for testing it against
+    /// many bounding boxes.
+    pub fn inverse(&self) -> InverseRay {
+        InverseRay {
+            orig: self.orig,
+            inv_dir: self.dir.map(|d| 1.0 / d),
+        }
+    }
+
+    /// Moves a hit of the ray along the normal to the side the direction points to, by a
+    /// distance scaled by the magnitude of the coordinates the hit point was computed from.
+    fn offset_origin(&self, hit: &Intersection, dir: Vec3) -> Point3 {
+        let magnitude = max_abs(hit.point).max(max_abs(self.orig));
+        offset_point(hit.point, hit.geometric_normal, magnitude, dir)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A ray prepared for traversing a BVH, whose direction is divided by once per ray
+/// instead of for every bounding box it is tested against.
+pub struct InverseRay {
+    /// The origin of the ray.
+    pub orig: Point3,
+    /// The reciprocals of the components of the direction, infinite for zero components.
+    pub inv_dir: Vec3,
+}
+
+/// Returns the largest magnitude of the coordinates of a point.
+fn max_abs(point: Point3) -> Float {
+    let abs = point.abs();
+    abs.x.max(abs.y).max(abs.z)
+}
+
+/// Moves a point along the normal to the side the direction points to, by a distance scaled
+/// by the magnitude of the coordinates the point was computed from.
+fn offset_point(point: Point3, normal: Vec3, magnitude: Float, dir: Vec3) -> Point3 {
+    let offset = normal * (ORIGIN_OFFSET_SCALE * magnitude.max(MIN_ORIGIN_MAGNITUDE));
+    if dir.dot(normal) >= 0.0 {
+        point + offset
+    } else {
+        point - offset
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,16 +136,26 @@ pub struct Intersection {
     pub point: Point3,
     /// The normal vector of the object at the point of intersection.
     pub normal: Vec3,
+    /// The normal of the actual surface at the point of intersection, on the side of the ray.
+    /// It differs from the interpolated shading normal of smooth meshes, and rays leaving the
+    /// hit are moved off the surface along it, so they don't start below the surface.
+    pub geometric_normal: Vec3,
     /// Whether the ray hit the object from the inside.
     pub front_face: bool,
     /// The material of the object that was hit.
     pub material: MaterialId,
     /// The distance from the ray's origin to the point of intersection.
-    pub t: f64,
+    pub t: Float,
     /// The u texture coordinate of the hit.
-    pub u: f64,
+    pub u: Float,
     /// The v texture coordinate of the hit.
-    pub v: f64,
+    pub v: Float,
+    /// The change of the point per unit of the u texture coordinate along the surface.
+    pub dpdu: Vec3,
+    /// The change of the point per unit of the v texture coordinate along the surface.
+    pub dpdv: Vec3,
+    /// The width of the ray's footprint at the hit in texture coordinates, used to filter textures.
+    pub footprint: Float,
 }
 
 impl Intersection {
@@ -57,4 +172,17 @@ impl Intersection {
 
         (front_face, normal)
     }
+
+    /// Calculate the width of the ray's footprint in texture coordinates at the given distance,
+    /// from the change of the texture coordinates per unit of distance along the surface.
+    /// The footprint stretches as the ray grazes the surface.
+    pub fn texture_footprint(
+        ray: &Ray,
+        t: Float,
+        outward_normal: Vec3,
+        uv_per_unit: Float,
+    ) -> Float {
+        let cos = ray.dir.unit().dot(outward_normal.unit()).abs();
+        ray.footprint(t) * uv_per_unit / cos.max(MIN_FOOTPRINT_COS)
+    }
 }
@@ -0,0 +1,87 @@
+use std::{
+    sync::mpsc::{Receiver, TryRecvError},
+    thread::JoinHandle,
+};
+
+use crate::{
+    cancel::CancelToken,
+    imgbuf::ImageBuffer,
+    progress::{ProgressEvent, Tile},
+    vector::Color,
+};
+
+#[derive(Debug, Clone)]
+/// A tile that finished rendering, with its HDR pixels before post processing.
+pub struct RenderedTile {
+    /// The region of the image the tile covers.
+    pub tile: Tile,
+    /// The pixels of the tile in row-major order.
+    pub pixels: Vec<Color>,
+}
+
+#[derive(Debug, Clone)]
+/// An event sent over a channel by a render, for frontends that receive them on another thread.
+pub enum RenderEvent {
+    /// The render made progress.
+    Progress(ProgressEvent),
+    /// A tile finished rendering.
+    Tile(RenderedTile),
+}
+
+#[derive(Debug)]
+/// A handle to a render running on a background thread.
+pub struct RenderHandle {
+    /// The tiles completed by the render.
+    tiles: Receiver<RenderedTile>,
+    /// The thread running the render.
+    thread: JoinHandle<ImageBuffer>,
+    /// The token to cancel the render.
+    cancel: CancelToken,
+}
+
+impl RenderHandle {
+    /// Creates a new handle from the parts of a spawned render.
+    pub(crate) fn new(
+        tiles: Receiver<RenderedTile>,
+        thread: JoinHandle<ImageBuffer>,
+        cancel: CancelToken,
+    ) -> Self {
+        Self {
+            tiles,
+            thread,
+            cancel,
+        }
+    }
+
+    /// Blocks until the next tile is completed.
+    /// Returns `None` once the render finished and all tiles were received.
+    pub fn next_tile(&self) -> Option<RenderedTile> {
+        self.tiles.recv().ok()
+    }
+
+    /// Returns the next completed tile without blocking.
+    /// Returns `Err(TryRecvError::Empty)` if no tile is ready yet.
+    pub fn try_next_tile(&self) -> Result<RenderedTile, TryRecvError> {
+        self.tiles.try_recv()
+    }
+
+    /// Returns a blocking iterator over the completed tiles.
+    pub fn tiles(&self) -> impl Iterator<Item = RenderedTile> + '_ {
+        self.tiles.iter()
+    }
+
+    /// Requests the cancellation of the render, the final image will be partial.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Returns true if the render thread has finished.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the render to finish and returns the final image.
+    pub fn join(self) -> ImageBuffer {
+        self.thread.join().expect("render thread panicked")
+    }
+}
@@ -1,30 +1,212 @@
-use std::ops::Index;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    ops::Index,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{material::Material, texture::Texture};
+use crate::{
+    dispatch::MaterialKind,
+    environment::Environment,
+    imgbuf::ImageBuffer,
+    material::Material,
+    texture::Texture,
+    textures::{
+        atlas::AtlasBuilder,
+        image::{ImageSampling, ImageTexture},
+    },
+};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 /// Resources that can be used a scene.
+/// Cloning the resources is cheap, as the clones share their materials, textures and environments.
 pub struct Resources {
     /// A list of materials that can be assigned to objects in the scene.
-    pub materials: Vec<Box<dyn Material>>,
+    pub materials: Vec<Arc<MaterialKind>>,
     /// A list of textures that can be used by materials in the scene.
-    pub textures: Vec<Box<dyn Texture>>,
+    pub textures: Vec<Arc<dyn Texture>>,
+    /// A list of environments that can surround the scene.
+    pub environments: Vec<Arc<dyn Environment>>,
+    /// The files image textures were loaded from, so they can be reloaded.
+    texture_files: Vec<TextureFile>,
+    /// The structural keys of materials added with deduplication.
+    material_keys: HashMap<String, MaterialId>,
+    /// The structural keys of textures added with deduplication.
+    texture_keys: HashMap<String, TextureId>,
 }
 
 impl Resources {
     /// Adds a material to the resources and returns its identifier.
     pub fn add_material<M: Material + 'static>(&mut self, material: M) -> MaterialId {
         let id = MaterialId(self.materials.len());
-        self.materials.push(Box::new(material));
+        self.materials.push(Arc::new(MaterialKind::new(material)));
         id
     }
 
+    /// Adds a material that is shared with other resources and returns its identifier.
+    pub fn add_shared_material(&mut self, material: Arc<dyn Material>) -> MaterialId {
+        let id = MaterialId(self.materials.len());
+        self.materials
+            .push(Arc::new(MaterialKind::Dynamic(material)));
+        id
+    }
+
+    /// Returns a shared handle to the material with the given identifier.
+    pub fn shared_material(&self, id: MaterialId) -> Arc<dyn Material> {
+        self.materials[id.0].clone()
+    }
+
+    /// Adds a material to the resources, unless a material with identical parameters
+    /// was already added this way, in which case the existing identifier is returned.
+    pub fn add_material_dedup<M: Material + 'static>(&mut self, material: M) -> MaterialId {
+        let key = structural_key(&material);
+        if let Some(id) = self.material_keys.get(&key) {
+            return *id;
+        }
+
+        let id = self.add_material(material);
+        self.material_keys.insert(key, id);
+        id
+    }
+
+    /// Replaces the material with the given identifier, so every object using it changes with it.
+    /// Clones of the resources keep the previous material.
+    pub fn replace_material<M: Material + 'static>(&mut self, id: MaterialId, material: M) {
+        self.materials[id.0] = Arc::new(MaterialKind::new(material));
+        self.material_keys.retain(|_, existing| existing.0 != id.0);
+    }
+
     /// Adds a texture to the resources and returns its identifier.
     pub fn add_texture<T: Texture + 'static>(&mut self, texture: T) -> TextureId {
         let id = TextureId(self.textures.len());
-        self.textures.push(Box::new(texture));
+        self.textures.push(Arc::new(texture));
         id
     }
+
+    /// Adds a texture that is shared with other resources and returns its identifier.
+    pub fn add_shared_texture(&mut self, texture: Arc<dyn Texture>) -> TextureId {
+        let id = TextureId(self.textures.len());
+        self.textures.push(texture);
+        id
+    }
+
+    /// Returns a shared handle to the texture with the given identifier.
+    pub fn shared_texture(&self, id: TextureId) -> Arc<dyn Texture> {
+        Arc::clone(&self.textures[id.0])
+    }
+
+    /// Adds a texture to the resources, unless a texture with identical parameters
+    /// was already added this way, in which case the existing identifier is returned.
+    pub fn add_texture_dedup<T: Texture + 'static>(&mut self, texture: T) -> TextureId {
+        let key = structural_key(&texture);
+        if let Some(id) = self.texture_keys.get(&key) {
+            return *id;
+        }
+
+        let id = self.add_texture(texture);
+        self.texture_keys.insert(key, id);
+        id
+    }
+
+    /// Loads an image texture from the file at the given path and returns its identifier.
+    /// The file is remembered, so the texture can be reloaded when it changes.
+    pub fn add_image_texture<T: ToString>(&mut self, path: T) -> Result<TextureId, &'static str> {
+        self.add_image_texture_with_sampling(path, ImageSampling::default())
+    }
+
+    /// Loads an image texture with the given sampling settings from the file at the given path
+    /// and returns its identifier. The file is remembered, so the texture can be reloaded.
+    pub fn add_image_texture_with_sampling<T: ToString>(
+        &mut self,
+        path: T,
+        sampling: ImageSampling,
+    ) -> Result<TextureId, &'static str> {
+        let path = path.to_string();
+        let image = ImageBuffer::load(&path)?;
+
+        Ok(self.add_loaded_image_texture(image, PathBuf::from(path), sampling))
+    }
+
+    /// Adds an image texture that was already loaded from the file at the given path.
+    pub(crate) fn add_loaded_image_texture(
+        &mut self,
+        image: ImageBuffer,
+        path: PathBuf,
+        sampling: ImageSampling,
+    ) -> TextureId {
+        let id = self.add_texture(ImageTexture::with_sampling(image, sampling));
+        self.texture_files.push(TextureFile { id, path, sampling });
+        id
+    }
+
+    /// Decodes an image texture from the bytes of an encoded file and returns its identifier.
+    pub fn add_image_texture_from_bytes(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<TextureId, &'static str> {
+        Ok(self.add_texture(ImageTexture::new(ImageBuffer::from_bytes(bytes)?)))
+    }
+
+    /// Packs the images of the atlas into a single image and adds a texture for each of them,
+    /// returning their identifiers in the order the images were added to the atlas.
+    pub fn add_texture_atlas(
+        &mut self,
+        atlas: AtlasBuilder,
+        sampling: ImageSampling,
+    ) -> Vec<TextureId> {
+        atlas
+            .build(sampling)
+            .into_iter()
+            .map(|texture| self.add_texture(texture))
+            .collect()
+    }
+
+    /// Returns the files image textures were loaded from.
+    pub fn texture_files(&self) -> impl Iterator<Item = &Path> {
+        self.texture_files.iter().map(|file| file.path.as_path())
+    }
+
+    /// Reloads the image textures loaded from any of the changed files.
+    /// Returns the identifiers of the reloaded textures.
+    pub fn reload_textures(&mut self, changed: &[PathBuf]) -> Result<Vec<TextureId>, &'static str> {
+        let mut reloaded = Vec::new();
+
+        for file in self.texture_files.iter() {
+            if changed.contains(&file.path) {
+                let image = ImageBuffer::load(file.path.display())?;
+                self.textures[file.id.0] =
+                    Arc::new(ImageTexture::with_sampling(image, file.sampling));
+                reloaded.push(file.id);
+            }
+        }
+
+        Ok(reloaded)
+    }
+
+    /// Adds an environment to the resources and returns its identifier.
+    pub fn add_environment<E: Environment + 'static>(&mut self, environment: E) -> EnvironmentId {
+        let id = EnvironmentId(self.environments.len());
+        self.environments.push(Arc::new(environment));
+        id
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An image texture loaded from a file, with the settings to reload it.
+struct TextureFile {
+    /// The identifier of the texture.
+    id: TextureId,
+    /// The path of the file.
+    path: PathBuf,
+    /// How the texture is sampled.
+    sampling: ImageSampling,
+}
+
+/// Returns a key describing the structure of a value, which is its debug representation.
+/// Values with equal keys have identical parameters.
+fn structural_key(value: &dyn Debug) -> String {
+    format!("{value:?}")
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -32,10 +214,10 @@ impl Resources {
 pub struct MaterialId(usize);
 
 impl Index<MaterialId> for Resources {
-    type Output = dyn Material;
+    type Output = MaterialKind;
 
     fn index(&self, index: MaterialId) -> &Self::Output {
-        &*self.materials[index.0]
+        &self.materials[index.0]
     }
 }
 
@@ -50,3 +232,15 @@ impl Index<TextureId> for Resources {
         &*self.textures[index.0]
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+/// An identifier for an environment.
+pub struct EnvironmentId(usize);
+
+impl Index<EnvironmentId> for Resources {
+    type Output = dyn Environment;
+
+    fn index(&self, index: EnvironmentId) -> &Self::Output {
+        &*self.environments[index.0]
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt::Debug;
+
+use crate::float::Float;
+
+/// Generates the random numbers of the samples of pixels in place of independent random numbers.
+/// A sample takes the numbers of its dimensions one after another, the first two place
+/// the sample in the pixel.
+pub trait Sampler: Debug + Send + Sync {
+    /// Returns the number in the range [0, 1) of a dimension of a sample of the pixel,
+    /// which takes `count` samples in total. The scramble differs between renders with
+    /// different seeds, so their noise is independent.
+    fn sample(
+        &self,
+        pixel: (u32, u32),
+        index: u32,
+        count: u32,
+        dimension: u32,
+        scramble: u32,
+    ) -> Float;
+}
+
+/// Mixes the bits of a value into a well distributed hash, to decorrelate dimensions and pixels.
+pub(crate) fn hash(mut value: u32) -> u32 {
+    value ^= value >> 16;
+    value = value.wrapping_mul(0x7feb352d);
+    value ^= value >> 15;
+    value = value.wrapping_mul(0x846ca68b);
+    value ^= value >> 16;
+    value
+}
+
+/// Maps the bits of a hash to a number in the range [0, 1), keeping the 24 most significant
+/// bits so the number is exact with both float precisions.
+pub(crate) fn unit_float(bits: u32) -> Float {
+    (bits >> 8) as Float / (1 << 24) as Float
+}
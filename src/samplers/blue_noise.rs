@@ -0,0 +1,309 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{
+    float::Float,
+    imgbuf::ImageBuffer,
+    random::Random,
+    sampler::{hash, unit_float, Sampler},
+};
+
+/// The edge length of the mask used unless set otherwise.
+pub const DEFAULT_MASK_SIZE: u32 = 64;
+
+/// The seed of the random initial pattern of the mask used unless set otherwise.
+const DEFAULT_MASK_SEED: u64 = 0x5eed;
+
+/// The standard deviation in pixels of the Gaussian that measures how clustered pixels are.
+const ENERGY_SIGMA: Float = 1.5;
+
+/// The fraction of pixels set in the initial pattern of the void and cluster method.
+const INITIAL_DENSITY: Float = 0.1;
+
+/// The increments of the even and odd dimensions from one sample to the next, which are
+/// the R2 sequence, so every pair of dimensions is spread evenly over the unit square.
+#[cfg(not(feature = "f32"))]
+const R2_STEPS: [Float; 2] = [0.754_877_666_246_692_7, 0.569_840_290_998_053_2];
+
+#[cfg(feature = "f32")]
+const R2_STEPS: [Float; 2] = [0.754_877_7, 0.569_840_3];
+
+/// The mask generated with the default settings, which is shared by all default samplers.
+static DEFAULT_MASK: OnceLock<Arc<BlueNoiseMask>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+/// A square mask of thresholds that tiles seamlessly, in which the pixels below any threshold
+/// are spread evenly without clumps, like blue noise.
+pub struct BlueNoiseMask {
+    /// The edge length of the mask in pixels.
+    size: u32,
+    /// The thresholds in the range [0, 1), row by row.
+    values: Vec<Float>,
+}
+
+impl BlueNoiseMask {
+    /// Generates a mask with the void and cluster method, starting from a random pattern
+    /// with the given seed. The seed must not be zero.
+    pub fn generate(size: u32, seed: u64) -> Self {
+        let size = size.max(2);
+        let len = size as usize * size as usize;
+
+        // The energy of an offset between pixels, wrapped around the edges.
+        let mut kernel = vec![0.0; len];
+        for dy in 0..size {
+            for dx in 0..size {
+                let wrapped = |d: u32| d.min(size - d) as Float;
+                let dist_sq = wrapped(dx).powi(2) + wrapped(dy).powi(2);
+                kernel[dy as usize * size as usize + dx as usize] =
+                    (-dist_sq / (2.0 * ENERGY_SIGMA * ENERGY_SIGMA)).exp();
+            }
+        }
+
+        // Start with a random pattern and move its tightest clusters into its largest voids,
+        // until the pattern is spread evenly.
+        let mut initial = Pattern::new(size, &kernel);
+        let mut rng = Random::new(seed);
+        let ones = ((len as Float * INITIAL_DENSITY) as usize).max(1);
+        while initial.count < ones {
+            let idx = ((rng.random_float() * len as Float) as usize).min(len - 1);
+            if !initial.set[idx] {
+                initial.toggle(idx);
+            }
+        }
+
+        loop {
+            let cluster = initial.tightest_cluster();
+            initial.toggle(cluster);
+            let void = initial.largest_void();
+            initial.toggle(void);
+
+            if void == cluster {
+                break;
+            }
+        }
+
+        // Rank the initial pixels by removing the tightest clusters first,
+        // then rank the remaining pixels by filling the largest voids first.
+        let mut ranks = vec![0; len];
+
+        let mut pattern = initial.clone();
+        for rank in (0..ones).rev() {
+            let cluster = pattern.tightest_cluster();
+            pattern.toggle(cluster);
+            ranks[cluster] = rank;
+        }
+
+        let mut pattern = initial;
+        for rank in ones..len {
+            let void = pattern.largest_void();
+            pattern.toggle(void);
+            ranks[void] = rank;
+        }
+
+        Self {
+            size,
+            values: ranks
+                .into_iter()
+                .map(|rank| (rank as Float + 0.5) / len as Float)
+                .collect(),
+        }
+    }
+
+    /// Uses the red channel of a precomputed square mask image as the thresholds.
+    pub fn from_image(image: &ImageBuffer) -> Result<Self, &'static str> {
+        if image.width != image.height || image.width == 0 {
+            return Err("blue noise mask must be square");
+        }
+
+        Ok(Self {
+            size: image.width,
+            values: image
+                .data
+                .chunks(3)
+                .map(|pixel| (pixel[0] as Float + 0.5) / 256.0)
+                .collect(),
+        })
+    }
+
+    /// Returns the edge length of the mask in pixels.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the threshold at the given position, the mask repeats in both directions.
+    pub fn value(&self, x: u32, y: u32) -> Float {
+        let size = self.size as usize;
+        self.values[(y as usize % size) * size + x as usize % size]
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A binary pattern of pixels, with the energy every pixel receives from the set pixels.
+struct Pattern<'a> {
+    /// The edge length of the pattern.
+    size: u32,
+    /// The energy of the offsets between pixels.
+    kernel: &'a [Float],
+    /// Whether each pixel is set.
+    set: Vec<bool>,
+    /// The energy each pixel receives from the set pixels.
+    energy: Vec<Float>,
+    /// The number of set pixels.
+    count: usize,
+}
+
+impl<'a> Pattern<'a> {
+    /// Creates an empty pattern.
+    fn new(size: u32, kernel: &'a [Float]) -> Self {
+        Self {
+            size,
+            kernel,
+            set: vec![false; kernel.len()],
+            energy: vec![0.0; kernel.len()],
+            count: 0,
+        }
+    }
+
+    /// Sets or clears a pixel, updating the energy of all pixels.
+    fn toggle(&mut self, idx: usize) {
+        let sign = if self.set[idx] { -1.0 } else { 1.0 };
+        self.set[idx] = !self.set[idx];
+        if self.set[idx] {
+            self.count += 1;
+        } else {
+            self.count -= 1;
+        }
+
+        let size = self.size as usize;
+        let (x, y) = (idx % size, idx / size);
+        for (other, energy) in self.energy.iter_mut().enumerate() {
+            let dx = (other % size + size - x) % size;
+            let dy = (other / size + size - y) % size;
+            *energy += sign * self.kernel[dy * size + dx];
+        }
+    }
+
+    /// Returns the set pixel with the most energy.
+    fn tightest_cluster(&self) -> usize {
+        self.extreme(true, |a, b| a > b)
+    }
+
+    /// Returns the pixel that isn't set with the least energy.
+    fn largest_void(&self) -> usize {
+        self.extreme(false, |a, b| a < b)
+    }
+
+    /// Returns the first pixel in the given state whose energy is better than all others.
+    fn extreme<F: Fn(Float, Float) -> bool>(&self, set: bool, better: F) -> usize {
+        let mut best = None;
+        for (idx, &energy) in self.energy.iter().enumerate() {
+            if self.set[idx] != set {
+                continue;
+            }
+            if best.is_none_or(|(_, best)| better(energy, best)) {
+                best = Some((idx, energy));
+            }
+        }
+
+        best.map_or(0, |(idx, _)| idx)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A sampler that shifts the numbers of every pixel by a blue noise mask, so the errors of
+/// neighbouring pixels differ. The noise of low sample counts is spread evenly over the image
+/// instead of forming clumps.
+pub struct BlueNoiseSampler {
+    /// The mask the numbers are shifted by.
+    mask: Arc<BlueNoiseMask>,
+}
+
+impl BlueNoiseSampler {
+    /// Create a new blue noise sampler with a mask generated on first use.
+    pub fn new() -> Self {
+        let mask = DEFAULT_MASK.get_or_init(|| {
+            Arc::new(BlueNoiseMask::generate(
+                DEFAULT_MASK_SIZE,
+                DEFAULT_MASK_SEED,
+            ))
+        });
+
+        Self::with_mask(Arc::clone(mask))
+    }
+
+    /// Create a new blue noise sampler with the given mask.
+    pub fn with_mask(mask: Arc<BlueNoiseMask>) -> Self {
+        Self { mask }
+    }
+}
+
+impl Default for BlueNoiseSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn sample(
+        &self,
+        (x, y): (u32, u32),
+        index: u32,
+        _count: u32,
+        dimension: u32,
+        scramble: u32,
+    ) -> Float {
+        // Every dimension uses the mask at a different offset, so they are uncorrelated.
+        let key = hash(dimension.wrapping_mul(0x9e37_79b9) ^ scramble);
+        let shift = hash(key);
+        let threshold = self
+            .mask
+            .value(x.wrapping_add(shift & 0xffff), y.wrapping_add(shift >> 16));
+
+        let step = R2_STEPS[dimension as usize % 2];
+        (unit_float(key) + index as Float * step + threshold).fract()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_ranks_every_pixel_once() {
+        let mask = BlueNoiseMask::generate(8, 3);
+        assert_eq!(mask.size(), 8);
+
+        let mut ranks: Vec<_> = mask
+            .values
+            .iter()
+            .map(|value| (value * 64.0) as usize)
+            .collect();
+        ranks.sort();
+        assert_eq!(ranks, (0..64).collect::<Vec<_>>());
+
+        // The mask tiles seamlessly.
+        assert_eq!(mask.value(3, 5), mask.value(3 + 8, 5 + 16));
+    }
+
+    #[test]
+    fn from_image_requires_square() {
+        assert!(BlueNoiseMask::from_image(&ImageBuffer::new(4, 2)).is_err());
+        assert!(BlueNoiseMask::from_image(&ImageBuffer::new(0, 0)).is_err());
+        assert_eq!(
+            BlueNoiseMask::from_image(&ImageBuffer::new(4, 4))
+                .unwrap()
+                .size(),
+            4
+        );
+    }
+
+    #[test]
+    fn samples_are_in_range() {
+        let sampler = BlueNoiseSampler::with_mask(Arc::new(BlueNoiseMask::generate(8, 3)));
+        for dimension in 0..4 {
+            for index in 0..16 {
+                let value = sampler.sample((5, 9), index, 16, dimension, 1);
+                assert!((0.0..1.0).contains(&value));
+            }
+        }
+    }
+}
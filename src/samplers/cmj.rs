@@ -0,0 +1,162 @@
+use crate::{
+    float::Float,
+    sampler::{hash, unit_float, Sampler},
+};
+
+#[derive(Debug, Clone, Copy, Default)]
+/// A sampler that places the samples of a pixel with correlated multi-jittering, after Kensler,
+/// "Correlated Multi-Jittered Sampling". Every pair of dimensions, like the position in the
+/// pixel or on the lens, forms a pattern that is stratified in both directions at once and
+/// along each axis alone, at any sample count. Every pixel and pair uses a different pattern.
+pub struct CmjSampler;
+
+impl CmjSampler {
+    /// Create a new correlated multi-jittered sampler.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sampler for CmjSampler {
+    fn sample(
+        &self,
+        (x, y): (u32, u32),
+        index: u32,
+        count: u32,
+        dimension: u32,
+        scramble: u32,
+    ) -> Float {
+        let count = count.max(1);
+
+        // Samples beyond the count continue with another pattern.
+        let round = index / count;
+        let pattern = hash(hash(hash(hash(x ^ scramble) ^ y) ^ (dimension / 2)) ^ round);
+
+        let (sx, sy) = cmj(index % count, count, pattern);
+        if dimension.is_multiple_of(2) {
+            sx
+        } else {
+            sy
+        }
+    }
+}
+
+/// Returns a sample of a square correlated multi-jittered pattern of `count` samples.
+/// The samples are placed in the cells of a grid whose columns and rows are shuffled
+/// the same way for all samples, and jittered within their cell.
+fn cmj(index: u32, count: u32, pattern: u32) -> (Float, Float) {
+    let columns = ((count as Float).sqrt() as u32).max(1);
+    let rows = count.div_ceil(columns);
+
+    let index = permute(index, count, pattern.wrapping_mul(0x51633e2d));
+    let (column, row) = (index % columns, index / columns);
+
+    let shuffled_x = permute(column, columns, pattern.wrapping_mul(0x68bc21eb));
+    let shuffled_y = permute(row, rows, pattern.wrapping_mul(0x02e5be93));
+    let jitter_x = unit_float(jitter(index, pattern.wrapping_mul(0x967a889b)));
+    let jitter_y = unit_float(jitter(index, pattern.wrapping_mul(0x368cc8b7)));
+
+    let x = (column as Float + (shuffled_y as Float + jitter_x) / rows as Float) / columns as Float;
+    let y = (row as Float + (shuffled_x as Float + jitter_y) / columns as Float) / rows as Float;
+
+    // Guard against rounding up to one.
+    (x.min(1.0 - Float::EPSILON), y.min(1.0 - Float::EPSILON))
+}
+
+/// Returns the position of the index in a random permutation of `len` elements chosen by
+/// the pattern, hashing within the next power of two until the index falls in range.
+fn permute(mut index: u32, len: u32, pattern: u32) -> u32 {
+    let mut mask = len - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    loop {
+        index ^= pattern;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= pattern >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= pattern >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= pattern >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | pattern >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= mask;
+        index ^= index >> 5;
+
+        if index < len {
+            return index.wrapping_add(pattern) % len;
+        }
+    }
+}
+
+/// Hashes the index into the random bits of its jitter within its cell.
+fn jitter(mut index: u32, pattern: u32) -> u32 {
+    index ^= pattern;
+    index ^= index >> 17;
+    index ^= index >> 10;
+    index = index.wrapping_mul(0xb36534e5);
+    index ^= index >> 12;
+    index ^= index >> 21;
+    index = index.wrapping_mul(0x93fc4795);
+    index ^= 0xdf6e307f;
+    index ^= index >> 17;
+    index.wrapping_mul(1 | pattern >> 18)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stratifies_both_axes() {
+        let sampler = CmjSampler::new();
+        let count = 16;
+
+        let samples: Vec<_> = (0..count)
+            .map(|index| {
+                let x = sampler.sample((3, 5), index, count, 0, 0);
+                let y = sampler.sample((3, 5), index, count, 1, 0);
+                (x, y)
+            })
+            .collect();
+        assert!(samples
+            .iter()
+            .all(|&(x, y)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y)));
+
+        // Every one of the narrow strata along each axis holds one sample, and every
+        // cell of the grid holds one sample.
+        let mut strata_x: Vec<_> = samples.iter().map(|(x, _)| (x * 16.0) as u32).collect();
+        let mut strata_y: Vec<_> = samples.iter().map(|(_, y)| (y * 16.0) as u32).collect();
+        let mut cells: Vec<_> = samples
+            .iter()
+            .map(|(x, y)| ((x * 4.0) as u32, (y * 4.0) as u32))
+            .collect();
+        strata_x.sort();
+        strata_y.sort();
+        cells.sort();
+        cells.dedup();
+
+        assert_eq!(strata_x, (0..16).collect::<Vec<_>>());
+        assert_eq!(strata_y, (0..16).collect::<Vec<_>>());
+        assert_eq!(cells.len(), 16);
+    }
+
+    #[test]
+    fn permute_is_a_permutation() {
+        for len in [1, 5, 16, 33] {
+            let mut permuted: Vec<_> = (0..len).map(|index| permute(index, len, 0x1234)).collect();
+            permuted.sort();
+            assert_eq!(permuted, (0..len).collect::<Vec<_>>());
+        }
+    }
+}
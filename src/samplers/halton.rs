@@ -0,0 +1,83 @@
+use crate::{
+    float::Float,
+    sampler::{hash, unit_float, Sampler},
+};
+
+/// The bases of the dimensions, the first primes. Later dimensions use random numbers,
+/// as the Halton sequence in large bases is poorly spread over few samples.
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+/// A sampler that takes the numbers of the samples of a pixel from the Halton sequence,
+/// which is spread evenly over all dimensions. The sequence is shifted by a random offset
+/// per pixel, so the errors of neighbouring pixels are independent.
+pub struct HaltonSampler;
+
+impl HaltonSampler {
+    /// Create a new Halton sampler.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn sample(
+        &self,
+        (x, y): (u32, u32),
+        index: u32,
+        _count: u32,
+        dimension: u32,
+        scramble: u32,
+    ) -> Float {
+        let key = hash(hash(hash(x ^ scramble) ^ y) ^ dimension);
+
+        match PRIMES.get(dimension as usize) {
+            // Rotate the sequence by a random offset per pixel and dimension.
+            Some(&base) => (radical_inverse(base, index) + unit_float(key)).fract(),
+            None => unit_float(hash(key ^ index)),
+        }
+    }
+}
+
+/// Mirrors the digits of the index in the base around the decimal point.
+fn radical_inverse(base: u32, mut index: u32) -> Float {
+    let inverse_base = 1.0 / base as f64;
+    let mut digits = 0u64;
+    let mut scale = 1.0;
+
+    while index > 0 {
+        digits = digits * base as u64 + (index % base) as u64;
+        scale *= inverse_base;
+        index /= base;
+    }
+
+    (digits as f64 * scale) as Float
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radical_inverse_mirrors_digits() {
+        assert_eq!(radical_inverse(2, 0), 0.0);
+        assert_eq!(radical_inverse(2, 1), 0.5);
+        assert_eq!(radical_inverse(2, 3), 0.75);
+        assert_eq!(radical_inverse(2, 6), 0.375);
+        assert!((radical_inverse(3, 5) - 7.0 / 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn samples_are_in_range() {
+        let sampler = HaltonSampler::new();
+        for dimension in [0, 1, 31, 32, 100] {
+            for index in 0..64 {
+                let value = sampler.sample((7, 2), index, 64, dimension, 9);
+                assert!((0.0..1.0).contains(&value));
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod blue_noise;
+pub mod cmj;
+pub mod halton;
@@ -1,113 +1,605 @@
-use std::ops::Index;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    ops::{Deref, DerefMut, Index},
+    sync::OnceLock,
+};
 
 use crate::{
-    bvh::Bvh,
+    aabb::Aabb,
+    bvh::{Bvh, BvhObject},
+    dispatch::Primitive,
+    group::{ChildId, Group},
     hittable::Hittable,
     interval::Interval,
+    light::{Light, Lights},
+    light_group::{LightGroupId, MAX_LIGHT_GROUPS},
+    material::Material,
+    portal::Portal,
+    profiler::{self, Counter},
     ray::{Intersection, Ray},
-    vector::{Color, Vec3},
+    resources::{EnvironmentId, Resources},
+    vector::Color,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// An ID for an object in a scene.
 pub struct ObjectId(usize);
 
+impl BvhObject for ObjectId {
+    fn index(self) -> u64 {
+        self.0 as u64
+    }
+
+    fn from_index(index: u64) -> Self {
+        ObjectId(index as usize)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// An ID for a group in a scene.
+pub struct GroupId(usize);
+
+/// A group in a scene, together with the objects it was flattened into.
+struct GroupEntry {
+    /// The group itself.
+    group: Group,
+    /// The ids of the objects the group was flattened into, by the path of child ids to them.
+    objects: HashMap<Vec<ChildId>, ObjectId>,
+    /// Whether the group was changed since it was last flattened.
+    dirty: bool,
+}
+
 /// A scene containing objects to be rendered.
 pub struct Scene {
-    /// The objects in the scene.
-    objects: Vec<Box<dyn Hittable>>,
-    /// The function to calculate the background color of the scene.
-    background_func: Box<dyn Fn(Vec3) -> Color>,
-    // /// The hierarchy of bounding volumes for the scene.
-    bvh: Option<Bvh>,
+    /// The objects in the scene, removed objects leave an empty slot
+    /// behind so the ids of the other objects stay valid.
+    objects: Vec<Option<Primitive>>,
+    /// The names of objects in the scene.
+    names: HashMap<String, ObjectId>,
+    /// The groups of the scene, which are flattened into objects.
+    groups: Vec<GroupEntry>,
+    /// The openings through which the background lights the scene.
+    portals: Vec<Portal>,
+    /// The environment surrounding the scene.
+    background: EnvironmentId,
+    /// The hierarchy of bounding volumes for the scene, which is built
+    /// lazily on the first hit after the scene was changed.
+    bvh: OnceLock<Bvh>,
+    /// The lights added to the scene.
+    lights: Vec<Box<dyn Light>>,
+    /// The objects with emissive materials, which are collected lazily
+    /// when the lights are first needed after the scene was changed.
+    emitters: OnceLock<Vec<ObjectId>>,
+    /// The names of the light groups of the scene.
+    light_groups: Vec<String>,
+    /// The light group of every object, indexed by object id.
+    object_light_groups: Vec<Option<LightGroupId>>,
+    /// The light group of the environment surrounding the scene.
+    background_light_group: Option<LightGroupId>,
+    /// The constant light arriving at diffuse bounces from every direction,
+    /// on top of the light of the scene.
+    ambient: Color,
 }
 
 impl Scene {
-    /// Creates a new scene with the given background color.
-    pub fn new<F: Fn(Vec3) -> Color + 'static>(background: F) -> Self {
+    /// Creates a new scene surrounded by the given environment.
+    pub fn new(background: EnvironmentId) -> Self {
         Self {
             objects: Vec::new(),
-            background_func: Box::new(background),
-            bvh: None,
+            names: HashMap::new(),
+            groups: Vec::new(),
+            portals: Vec::new(),
+            background,
+            bvh: OnceLock::new(),
+            lights: Vec::new(),
+            emitters: OnceLock::new(),
+            light_groups: Vec::new(),
+            object_light_groups: Vec::new(),
+            background_light_group: None,
+            ambient: Color::ZERO,
         }
     }
 
-    /// Builds the bounding volume hierarchy for the scene.
+    /// Discards the BVH and the emissive objects, which are collected again when needed.
+    fn changed(&mut self) {
+        self.bvh.take();
+        self.emitters.take();
+    }
+
+    /// Builds the bounding volume hierarchy for the scene ahead of time.
+    /// Otherwise it is built on the first hit after the scene was changed.
     pub fn build_bvh(&mut self) {
+        let _span = tracing::info_span!("build bvh", objects = self.objects.len()).entered();
+
+        self.flatten_groups();
+        self.bvh = OnceLock::from(self.collect_bvh());
+    }
+
+    /// Builds a bounding volume hierarchy over the current objects of the scene.
+    fn collect_bvh(&self) -> Bvh {
         // Collect bounding boxes for all objects
         let objects_with_bbs = self
             .objects
             .iter()
             .enumerate()
-            .map(|(id, object)| (ObjectId(id), object.bounding_box()))
+            .filter_map(|(id, object)| Some((ObjectId(id), object.as_ref()?.bounding_box())))
             .collect();
 
         // Construct the BVH from the bounding boxes
-        self.bvh = Some(Bvh::new(objects_with_bbs));
+        Bvh::new(objects_with_bbs)
     }
 
     /// Adds an object to the scene.
     pub fn add<H: Hittable + 'static>(&mut self, object: H) -> ObjectId {
         let id = ObjectId(self.objects.len());
-        self.objects.push(Box::new(object));
+        self.objects.push(Some(Primitive::new(object)));
+        self.changed();
+        id
+    }
+
+    /// Adds an object to the scene under the given name.
+    /// If the name is already taken, it refers to the new object afterwards.
+    pub fn add_named<N: ToString, H: Hittable + 'static>(
+        &mut self,
+        name: N,
+        object: H,
+    ) -> ObjectId {
+        let id = self.add(object);
+        self.names.insert(name.to_string(), id);
         id
     }
 
-    #[inline]
-    /// Get the background color of the scene.
-    pub fn background(&self, dir: Vec3) -> Color {
-        (self.background_func)(dir)
+    /// Sets the name of an object in the scene.
+    pub fn set_name<N: ToString>(&mut self, id: ObjectId, name: N) {
+        self.names.retain(|_, named| *named != id);
+        self.names.insert(name.to_string(), id);
+    }
+
+    /// Returns the id of the object with the given name.
+    pub fn find(&self, name: &str) -> Option<ObjectId> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns the object with the given name.
+    pub fn get_named(&self, name: &str) -> Option<&dyn Hittable> {
+        self.get(self.find(name)?)
+    }
+
+    /// Returns the name of the object with the given id.
+    pub fn name_of(&self, id: ObjectId) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|(_, named)| **named == id)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns an iterator over all names and the ids of their objects.
+    pub fn names(&self) -> impl Iterator<Item = (&str, ObjectId)> + '_ {
+        self.names.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+
+    /// Removes an object from the scene and returns it.
+    /// The ids of the other objects stay valid.
+    pub fn remove(&mut self, id: ObjectId) -> Option<Box<dyn Hittable>> {
+        let object = self.objects.get_mut(id.0)?.take()?;
+        self.names.retain(|_, named| *named != id);
+        if let Some(group) = self.object_light_groups.get_mut(id.0) {
+            *group = None;
+        }
+        self.changed();
+        Some(object.into_boxed())
+    }
+
+    /// Replaces an object in the scene and returns the previous object.
+    /// Returns `None` and leaves the scene unchanged if the object was removed.
+    pub fn replace<H: Hittable + 'static>(
+        &mut self,
+        id: ObjectId,
+        object: H,
+    ) -> Option<Box<dyn Hittable>> {
+        let slot = self.objects.get_mut(id.0)?.as_mut()?;
+        let previous = std::mem::replace(slot, Primitive::new(object));
+        self.changed();
+        Some(previous.into_boxed())
+    }
+
+    /// Returns the object with the given id, if it wasn't removed.
+    pub fn get(&self, id: ObjectId) -> Option<&dyn Hittable> {
+        Some(self.objects.get(id.0)?.as_ref()?)
+    }
+
+    /// Returns a mutable reference to the object with the given id, if it wasn't removed.
+    /// As the object may be moved, the BVH is rebuilt on the next hit.
+    pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut dyn Hittable> {
+        let object = self.objects.get_mut(id.0)?.as_mut()?;
+        self.bvh.take();
+        self.emitters.take();
+        Some(object)
+    }
+
+    /// Adds a group to the scene, its objects are flattened into the scene immediately.
+    pub fn add_group(&mut self, group: Group) -> GroupId {
+        let id = GroupId(self.groups.len());
+        self.groups.push(GroupEntry {
+            group,
+            objects: HashMap::new(),
+            dirty: true,
+        });
+        self.flatten_groups();
+        id
+    }
+
+    /// Returns the group with the given id.
+    pub fn group(&self, id: GroupId) -> &Group {
+        &self.groups[id.0].group
+    }
+
+    /// Returns a mutable reference to the group with the given id.
+    /// Changes are applied to the scene objects when the reference is dropped.
+    pub fn group_mut(&mut self, id: GroupId) -> GroupMut<'_> {
+        self.groups[id.0].dirty = true;
+        GroupMut { scene: self, id }
+    }
+
+    /// Flattens all changed groups into the objects of the scene.
+    /// Objects keep their ids as long as their child stays in the group, and
+    /// objects of groups that were removed from the scene are not added again.
+    pub fn flatten_groups(&mut self) {
+        for entry in self.groups.iter_mut().filter(|entry| entry.dirty) {
+            let flattened = entry.group.flatten();
+            let mut ids = HashMap::with_capacity(flattened.len());
+
+            for (path, object) in flattened {
+                let object = Primitive::new(object);
+
+                match entry.objects.remove(&path) {
+                    Some(id) => {
+                        // Objects removed from the scene stay removed.
+                        if let Some(slot) = &mut self.objects[id.0] {
+                            *slot = object;
+                        }
+                        ids.insert(path, id);
+                    }
+                    None => {
+                        ids.insert(path, ObjectId(self.objects.len()));
+                        self.objects.push(Some(object));
+                    }
+                }
+            }
+
+            // Remove the objects of children that no longer exist.
+            for id in entry.objects.values() {
+                self.objects[id.0] = None;
+            }
+
+            entry.objects = ids;
+            entry.dirty = false;
+            self.bvh.take();
+            self.emitters.take();
+        }
+    }
+
+    /// Returns an iterator over the ids of all objects in the scene.
+    pub fn ids(&self) -> impl Iterator<Item = ObjectId> + '_ {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| object.is_some())
+            .map(|(id, _)| ObjectId(id))
+    }
+
+    /// Adds a light portal, which guides sampling of the background through an opening.
+    pub fn add_portal(&mut self, portal: Portal) {
+        self.portals.push(portal);
+    }
+
+    /// Returns the light portals of the scene.
+    pub fn portals(&self) -> &[Portal] {
+        &self.portals
+    }
+
+    /// Adds a light, which guides sampling towards it without being visible itself.
+    pub fn add_light<L: Light + 'static>(&mut self, light: L) {
+        self.lights.push(Box::new(light));
+    }
+
+    /// Collects the objects with emissive materials ahead of time, or again after materials
+    /// of the resources were replaced. Otherwise they are collected when the lights are first
+    /// needed after the scene was changed.
+    pub fn collect_lights(&mut self, resources: &Resources) {
+        self.emitters = OnceLock::from(self.collect_emitters(resources));
+    }
+
+    /// Returns the ids of the objects that can be sampled and have emissive materials.
+    fn collect_emitters(&self, resources: &Resources) -> Vec<ObjectId> {
+        self.ids()
+            .filter(|&id| {
+                self[id]
+                    .light_material()
+                    .is_some_and(|material| resources[material].is_emissive())
+            })
+            .collect()
+    }
+
+    /// Returns the lights of the scene, the emissive objects followed by the added lights.
+    pub fn lights(&self, resources: &Resources) -> Lights<'_> {
+        let emitters = self
+            .emitters
+            .get_or_init(|| self.collect_emitters(resources));
+
+        Lights::new(self, emitters, &self.lights)
+    }
+
+    /// Adds a light group, which collects the light of its objects into its own layer
+    /// when rendering light groups. Panics if the scene already has [`MAX_LIGHT_GROUPS`] groups.
+    pub fn add_light_group<N: ToString>(&mut self, name: N) -> LightGroupId {
+        assert!(
+            self.light_groups.len() < MAX_LIGHT_GROUPS,
+            "Scene has too many light groups"
+        );
+        self.light_groups.push(name.to_string());
+        LightGroupId(self.light_groups.len() - 1)
+    }
+
+    /// Returns the names of the light groups, in the order they were added.
+    pub fn light_groups(&self) -> &[String] {
+        &self.light_groups
+    }
+
+    /// Returns the light group with the given name.
+    pub fn find_light_group(&self, name: &str) -> Option<LightGroupId> {
+        let idx = self.light_groups.iter().position(|group| group == name)?;
+        Some(LightGroupId(idx))
+    }
+
+    /// Puts an object into a light group, or takes it out of its group with `None`.
+    pub fn set_light_group(&mut self, id: ObjectId, group: Option<LightGroupId>) {
+        if self.object_light_groups.len() <= id.0 {
+            self.object_light_groups.resize(id.0 + 1, None);
+        }
+        self.object_light_groups[id.0] = group;
+    }
+
+    /// Returns the light group of an object.
+    pub fn light_group(&self, id: ObjectId) -> Option<LightGroupId> {
+        self.object_light_groups.get(id.0).copied().flatten()
+    }
+
+    /// Puts the environment surrounding the scene into a light group.
+    pub fn set_background_light_group(&mut self, group: Option<LightGroupId>) {
+        self.background_light_group = group;
+    }
+
+    /// Returns the light group of the environment surrounding the scene.
+    pub fn background_light_group(&self) -> Option<LightGroupId> {
+        self.background_light_group
+    }
+
+    /// Returns the environment surrounding the scene.
+    pub fn background(&self) -> EnvironmentId {
+        self.background
+    }
+
+    /// Sets the environment surrounding the scene.
+    pub fn set_background(&mut self, background: EnvironmentId) {
+        self.background = background;
+    }
+
+    /// Returns the constant light arriving at diffuse bounces from every direction.
+    pub fn ambient(&self) -> Color {
+        self.ambient
+    }
+
+    /// Sets the constant light arriving at diffuse bounces from every direction, which brightens
+    /// the scene without lights or shadows. It isn't seen directly and belongs to no light group.
+    pub fn set_ambient(&mut self, ambient: Color) {
+        self.ambient = ambient;
     }
 
     /// Checks for intersections between the ray and the objects in the scene.
     pub fn hit(&self, ray: &Ray, time: Interval) -> Option<Intersection> {
-        if self.bvh.is_some() {
-            self.hit_fast(ray, time)
-        } else {
-            self.hit_slow(ray, time)
-        }
+        Some(self.hit_object(ray, time)?.1)
+    }
+
+    /// Returns whether the ray hits any object in the interval, stopping at the first one
+    /// it finds, like for shadow rays.
+    pub fn occluded(&self, ray: &Ray, time: Interval) -> bool {
+        profiler::count(Counter::ShadowRays);
+        let bvh = self.bvh.get_or_init(|| self.collect_bvh());
+
+        bvh.any_hit(ray, time, |object_id| {
+            profiler::count(Counter::PrimitiveTests);
+            self[object_id].occluded(ray, time)
+        })
     }
 
-    /// Checks for intersections between the ray and the objects in the scene using the BVH.
-    fn hit_fast(&self, ray: &Ray, mut time: Interval) -> Option<Intersection> {
+    /// Checks for intersections between the ray and the objects in the scene,
+    /// returning the closest intersection together with the id of the object that was hit.
+    pub fn hit_object(&self, ray: &Ray, mut time: Interval) -> Option<(ObjectId, Intersection)> {
         // Get the objects that could be hit by the ray.
-        let objects_to_check = self.bvh.as_ref().unwrap().hit(ray, time)?;
+        let bvh = self.bvh.get_or_init(|| self.collect_bvh());
+        let objects_to_check = bvh.hit(ray, time)?;
 
         let mut closest = None;
 
         // Check each possible object for intersections.
         for object_id in objects_to_check {
+            profiler::count(Counter::PrimitiveTests);
+
             if let Some(intersection) = self[object_id].hit(ray, time) {
                 // Update the closest intersection.
                 time.end = intersection.t;
-                closest = Some(intersection);
+                closest = Some((object_id, intersection));
             }
         }
 
         closest
     }
 
-    /// Checks for every object in the scene if the ray intersects with it.
-    fn hit_slow(&self, ray: &Ray, mut time: Interval) -> Option<Intersection> {
-        let mut closest = None;
+    /// Returns statistics of the current objects and lights of the scene,
+    /// building the BVH if it is out of date.
+    pub fn stats(&self, resources: &Resources) -> SceneStats {
+        let mut stats = SceneStats {
+            objects: BTreeMap::new(),
+            triangles: 0,
+            bounds: Aabb::EMPTY,
+            unbounded: 0,
+            lights: self.lights(resources).len(),
+            portals: self.portals.len(),
+            object_memory: 0,
+            bvh_memory: self.bvh.get_or_init(|| self.collect_bvh()).memory_size(),
+        };
 
-        // Check each object in the scene for intersections.
-        for object in self.objects.iter() {
-            if let Some(intersection) = object.hit(ray, time) {
-                // Update the closest intersection.
-                time.end = intersection.t;
-                closest = Some(intersection);
+        for id in self.ids() {
+            let object = &self[id];
+            *stats.objects.entry(object.kind()).or_default() += 1;
+            stats.triangles += object.triangle_count();
+            stats.object_memory += object.memory_size();
+
+            let bounding_box = object.bounding_box();
+            if bounding_box.is_bounded() {
+                stats.bounds.grow(&bounding_box);
+            } else {
+                stats.unbounded += 1;
             }
         }
 
-        closest
+        stats
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Statistics of the objects and lights of a scene, to sanity check imported scenes.
+pub struct SceneStats {
+    /// The number of objects of every kind.
+    pub objects: BTreeMap<&'static str, usize>,
+    /// The number of triangles of all objects.
+    pub triangles: usize,
+    /// The bounds of all objects with finite bounds.
+    pub bounds: Aabb,
+    /// The number of objects without finite bounds, like planes.
+    pub unbounded: usize,
+    /// The number of lights, emissive objects included.
+    pub lights: usize,
+    /// The number of light portals.
+    pub portals: usize,
+    /// An estimate of the bytes of memory used by the objects.
+    pub object_memory: usize,
+    /// The bytes of memory used by the BVH of the scene.
+    pub bvh_memory: usize,
+}
+
+impl SceneStats {
+    /// Returns the total number of objects.
+    pub fn object_count(&self) -> usize {
+        self.objects.values().sum()
+    }
+}
+
+impl Display for SceneStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Objects:")?;
+        for (kind, count) in self.objects.iter() {
+            writeln!(f, "  {:<16} {:>14}", kind, count)?;
+        }
+        writeln!(f, "  {:<16} {:>14}", "total", self.object_count())?;
+        writeln!(f, "  {:<16} {:>14}", "triangles", self.triangles)?;
+        writeln!(f, "  {:<16} {:>14}", "unbounded", self.unbounded)?;
+
+        writeln!(f, "Lights:")?;
+        writeln!(f, "  {:<16} {:>14}", "lights", self.lights)?;
+        writeln!(f, "  {:<16} {:>14}", "portals", self.portals)?;
+
+        writeln!(f, "Bounds:")?;
+        if self.bounds.is_bounded() {
+            for (name, axis) in [
+                ("x", self.bounds.x),
+                ("y", self.bounds.y),
+                ("z", self.bounds.z),
+            ] {
+                writeln!(f, "  {:<16} {:>14.3} {:>14.3}", name, axis.start, axis.end)?;
+            }
+        } else {
+            writeln!(f, "  none")?;
+        }
+
+        writeln!(f, "Memory:")?;
+        for (name, bytes) in [("objects", self.object_memory), ("BVH", self.bvh_memory)] {
+            writeln!(f, "  {:<16} {:>10.1} KiB", name, bytes as f64 / 1024.0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A mutable reference to a group in a scene, which applies the changes
+/// to the objects of the scene when it is dropped.
+pub struct GroupMut<'a> {
+    /// The scene the group belongs to.
+    scene: &'a mut Scene,
+    /// The id of the group.
+    id: GroupId,
+}
+
+impl Deref for GroupMut<'_> {
+    type Target = Group;
+
+    fn deref(&self) -> &Self::Target {
+        &self.scene.groups[self.id.0].group
+    }
+}
+
+impl DerefMut for GroupMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.scene.groups[self.id.0].group
+    }
+}
+
+impl Drop for GroupMut<'_> {
+    fn drop(&mut self) {
+        self.scene.flatten_groups();
     }
 }
 
 impl Index<ObjectId> for Scene {
-    type Output = Box<dyn Hittable>;
+    type Output = Primitive;
 
     fn index(&self, id: ObjectId) -> &Self::Output {
-        &self.objects[id.0]
+        self.objects[id.0].as_ref().expect("object was removed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        environments::solid::SolidEnvironment, materials::lambertian::LambertianMaterial,
+        objects::sphere::SphereObject, textures::solid::SolidTexture, transform::Transform, vec3,
+    };
+
+    #[test]
+    fn group_objects_keep_their_ids() {
+        let mut resources = Resources::default();
+        let background = resources.add_environment(SolidEnvironment::new(Color::ZERO));
+        let albedo = resources.add_texture(SolidTexture::new(Color::ONE));
+        let material = resources.add_material(LambertianMaterial::new(albedo));
+
+        let mut group = Group::new(Transform::IDENTITY);
+        let first = group.add(SphereObject::new(vec3!(0, 0, 0), 0.1, material));
+        group.add(SphereObject::new(vec3!(1, 0, 0), 0.1, material));
+        group.add(SphereObject::new(vec3!(2, 0, 0), 0.1, material));
+
+        let mut scene = Scene::new(background);
+        let id = scene.add_group(group);
+        let ids: Vec<_> = scene.ids().collect();
+        assert_eq!(ids.len(), 3);
+
+        // Removing the second object and then the first child leaves only the third.
+        scene.remove(ids[1]);
+        scene.group_mut(id).remove(first);
+
+        assert_eq!(scene.ids().collect::<Vec<_>>(), [ids[2]]);
+        assert_eq!(scene[ids[2]].bounding_box().x.start, 1.9);
     }
 }
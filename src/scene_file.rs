@@ -0,0 +1,630 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    animation::{Animation, Easing, Keyframes},
+    camera::{Camera, CameraBuilder, Preset},
+    dispatch::Primitive,
+    environments::{
+        gradient::GradientEnvironment, image::ImageEnvironment, sky::SkyEnvironment,
+        solid::SolidEnvironment,
+    },
+    filter::PixelFilter,
+    float::Float,
+    framebuffer::FrameBuffer,
+    hittable::Hittable,
+    light_group::MAX_LIGHT_GROUPS,
+    lod::{LodInstance, LodMesh},
+    materials::{
+        dielectric::DielectricMaterial, diffuse_light::DiffuseLightMaterial,
+        lambertian::LambertianMaterial, metal::MetalMaterial,
+        shadow_catcher::ShadowCatcherMaterial,
+    },
+    mesh::Mesh,
+    noise::DistanceMetric,
+    objects::{mesh::MeshObject, plane::PlaneObject, sphere::SphereObject},
+    resources::{EnvironmentId, MaterialId, Resources, TextureId},
+    samplers::{blue_noise::BlueNoiseSampler, cmj::CmjSampler, halton::HaltonSampler},
+    scene::Scene,
+    textures::{
+        checker::CheckerTexture,
+        solid::SolidTexture,
+        worley::{WorleyFeature, WorleyTexture},
+    },
+    vec3,
+    vector::{Color, Vec3},
+};
+
+/// A scene loaded from a scene file, with the resources it uses and its camera settings.
+///
+/// Scene files describe one statement per line, `#` starts a comment:
+///
+/// ```text
+/// background gradient <bottom r g b> <top r g b>
+/// background solid <r g b> | sky <sun x y z> | hdr <path> <intensity>
+/// ambient <r g b>
+/// texture <name> solid <r g b> | image <path> | checker <scale> <even> <odd>
+/// texture <name> worley <scale> <low> <high> [f1 | f2 | edge [euclidean | manhattan | chebyshev
+///     [<jitter>]]]
+/// material <name> lambertian <texture> | metal <r g b> <fuzz> | dielectric <ior> | light <texture>
+///     | catcher <texture>
+/// sphere <x y z> <radius> <material>
+/// plane <x y z> <normal x y z> <material>
+/// mesh <path> <material> [lod <levels>]
+/// camera from <x y z> | at <x y z> | vfov <degrees> | width <pixels>
+/// camera aspect <ratio> | samples <count> | bounces <count> | regularize <roughness>
+/// camera preset draft | preview | final
+/// camera near <distance> | far <distance>
+/// camera tilt <degrees> <swing degrees> | shift <x> <y>
+/// camera filter box <radius> | tent <radius> | gaussian <sigma> | blackman-harris <radius>
+/// camera sampler bluenoise | halton | cmj
+/// keyframe from <time> <x y z> | at <time> <x y z>
+/// lightgroup <name> | none
+/// ```
+///
+/// Paths are relative to the scene file. Without camera settings the camera is at the origin
+/// looking down the negative z axis, with a vertical fov of 90 degrees and a 400 pixel wide
+/// image with an aspect ratio of 16:9. Keyframes animate the camera over time in seconds.
+/// The objects and background following a light group statement belong to that light group.
+pub struct SceneFile {
+    /// The objects of the scene.
+    pub scene: Scene,
+    /// The textures, materials and environments of the scene.
+    pub resources: Resources,
+    /// The camera settings of the scene.
+    pub camera: CameraBuilder,
+    /// The animation of the camera.
+    pub animation: Animation,
+    /// The meshes with levels of detail, which show the full mesh until a level is chosen.
+    pub lods: Vec<LodInstance>,
+}
+
+impl SceneFile {
+    /// Loads a scene file from the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SceneFile, SceneFileError> {
+        let path = path.as_ref();
+        let _span = tracing::info_span!("load scene", path = %path.display()).entered();
+        let source = fs::read_to_string(path).map_err(|_| SceneFileError {
+            line: None,
+            message: "failed to read file",
+        })?;
+
+        Self::parse(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a scene file, resolving relative paths against the given directory.
+    pub fn parse(source: &str, dir: &Path) -> Result<SceneFile, SceneFileError> {
+        let mut parser = Parser {
+            dir: dir.to_path_buf(),
+            resources: Resources::default(),
+            camera: Self::default_camera(),
+            background: None,
+            ambient: Color::ZERO,
+            look_from: None,
+            look_at: None,
+            objects: Vec::new(),
+            lods: Vec::new(),
+            light_groups: Vec::new(),
+            light_group: None,
+            background_light_group: None,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+        };
+
+        for (idx, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let mut tokens = line.split_whitespace();
+
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+
+            parser
+                .statement(keyword, &mut Tokens(tokens))
+                .map_err(|message| SceneFileError {
+                    line: Some(idx + 1),
+                    message,
+                })?;
+        }
+
+        let background = match parser.background {
+            Some(background) => background,
+            None => parser
+                .resources
+                .add_environment(SolidEnvironment::new(Color::ZERO)),
+        };
+
+        let mut scene = Scene::new(background);
+        scene.set_ambient(parser.ambient);
+        let light_groups: Vec<_> = parser
+            .light_groups
+            .iter()
+            .map(|name| scene.add_light_group(name))
+            .collect();
+
+        let ids: Vec<_> = parser
+            .objects
+            .into_iter()
+            .map(|(object, group)| {
+                let id = scene.add(object);
+                scene.set_light_group(id, group.map(|idx| light_groups[idx]));
+                id
+            })
+            .collect();
+        let lods = parser
+            .lods
+            .into_iter()
+            .map(|(idx, mesh, material)| LodInstance::new(ids[idx], mesh, material, 0))
+            .collect();
+        scene
+            .set_background_light_group(parser.background_light_group.map(|idx| light_groups[idx]));
+
+        let mut animation = Animation::new();
+        if let Some(look_from) = parser.look_from {
+            animation.with_look_from(look_from);
+        }
+        if let Some(look_at) = parser.look_at {
+            animation.with_look_at(look_at);
+        }
+
+        Ok(SceneFile {
+            scene,
+            resources: parser.resources,
+            camera: parser.camera,
+            animation,
+            lods,
+        })
+    }
+
+    /// Returns the camera settings used unless the scene file changes them.
+    fn default_camera() -> CameraBuilder {
+        let mut camera = Camera::builder();
+        camera
+            .with_look_from(Vec3::ZERO)
+            .with_look_at(vec3!(0, 0, -1))
+            .with_vfov(90.0)
+            .with_image_width(400)
+            .with_aspect_ratio(16.0 / 9.0);
+        camera
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An error in a scene file.
+pub struct SceneFileError {
+    /// The line of the error, or `None` if the file couldn't be read.
+    pub line: Option<usize>,
+    /// A description of the error.
+    pub message: &'static str,
+}
+
+impl Display for SceneFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The remaining tokens of a statement.
+struct Tokens<'a, I: Iterator<Item = &'a str>>(I);
+
+impl<'a, I: Iterator<Item = &'a str>> Tokens<'a, I> {
+    /// Returns the next token.
+    fn word(&mut self) -> Result<&'a str, &'static str> {
+        self.optional_word().ok_or("missing argument")
+    }
+
+    /// Returns the next token of an optional argument, if there is one.
+    fn optional_word(&mut self) -> Option<&'a str> {
+        self.0.next()
+    }
+
+    /// Parses the next token as a number.
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, &'static str> {
+        self.word()?.parse().map_err(|_| "invalid number")
+    }
+
+    /// Parses the next token of an optional argument as a number, if there is one.
+    fn optional_number<T: std::str::FromStr>(&mut self) -> Result<Option<T>, &'static str> {
+        self.optional_word()
+            .map(|word| word.parse().map_err(|_| "invalid number"))
+            .transpose()
+    }
+
+    /// Parses the next three tokens as a vector.
+    fn vector(&mut self) -> Result<Vec3, &'static str> {
+        Ok(Vec3 {
+            x: self.number()?,
+            y: self.number()?,
+            z: self.number()?,
+        })
+    }
+
+    /// Fails if there are tokens left.
+    fn end(&mut self) -> Result<(), &'static str> {
+        match self.optional_word() {
+            Some(_) => Err("too many arguments"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The state of parsing a scene file.
+struct Parser {
+    /// The directory relative paths are resolved against.
+    dir: PathBuf,
+    /// The resources created so far.
+    resources: Resources,
+    /// The camera settings.
+    camera: CameraBuilder,
+    /// The environment surrounding the scene.
+    background: Option<EnvironmentId>,
+    /// The constant light arriving at diffuse bounces.
+    ambient: Color,
+    /// The keyframes of the camera position.
+    look_from: Option<Keyframes<Vec3>>,
+    /// The keyframes of the point the camera looks at.
+    look_at: Option<Keyframes<Vec3>>,
+    /// The objects of the scene, with the index of their light group.
+    objects: Vec<(Primitive, Option<usize>)>,
+    /// The meshes with levels of detail, with the index of their object.
+    lods: Vec<(usize, LodMesh, MaterialId)>,
+    /// The names of the light groups.
+    light_groups: Vec<String>,
+    /// The index of the light group of the following objects.
+    light_group: Option<usize>,
+    /// The index of the light group of the background.
+    background_light_group: Option<usize>,
+    /// The named textures.
+    textures: HashMap<String, TextureId>,
+    /// The named materials.
+    materials: HashMap<String, MaterialId>,
+}
+
+impl Parser {
+    /// Parses a single statement.
+    fn statement<'a, I: Iterator<Item = &'a str>>(
+        &mut self,
+        keyword: &str,
+        tokens: &mut Tokens<'a, I>,
+    ) -> Result<(), &'static str> {
+        match keyword {
+            "background" => {
+                let environment = match tokens.word()? {
+                    "solid" => self
+                        .resources
+                        .add_environment(SolidEnvironment::new(tokens.vector()?)),
+                    "gradient" => {
+                        let bottom = tokens.vector()?;
+                        let top = tokens.vector()?;
+                        self.resources
+                            .add_environment(GradientEnvironment::new(bottom, top))
+                    }
+                    "sky" => self
+                        .resources
+                        .add_environment(SkyEnvironment::daylight(tokens.vector()?)),
+                    "hdr" => {
+                        let image = FrameBuffer::load_hdr(self.path(tokens.word()?).display())?;
+                        let intensity = tokens.number()?;
+                        self.resources
+                            .add_environment(ImageEnvironment::new(image, intensity))
+                    }
+                    _ => return Err("unknown background"),
+                };
+
+                self.background = Some(environment);
+                self.background_light_group = self.light_group;
+            }
+            "ambient" => self.ambient = tokens.vector()?,
+            "texture" => {
+                let name = tokens.word()?.to_string();
+
+                let texture = match tokens.word()? {
+                    "solid" => self
+                        .resources
+                        .add_texture(SolidTexture::new(tokens.vector()?)),
+                    "image" => {
+                        let path = self.path(tokens.word()?);
+                        self.resources.add_image_texture(path.display())?
+                    }
+                    "checker" => {
+                        let scale = tokens.number()?;
+                        let even = self.texture(tokens.word()?)?;
+                        let odd = self.texture(tokens.word()?)?;
+                        self.resources
+                            .add_texture(CheckerTexture::new(scale, even, odd))
+                    }
+                    "worley" => {
+                        let scale = tokens.number()?;
+                        let low = self.texture(tokens.word()?)?;
+                        let high = self.texture(tokens.word()?)?;
+
+                        let feature = match tokens.optional_word() {
+                            None | Some("f1") => WorleyFeature::F1,
+                            Some("f2") => WorleyFeature::F2,
+                            Some("edge") => WorleyFeature::Edge,
+                            Some(_) => return Err("unknown worley feature"),
+                        };
+                        let metric = match tokens.optional_word() {
+                            None | Some("euclidean") => DistanceMetric::Euclidean,
+                            Some("manhattan") => DistanceMetric::Manhattan,
+                            Some("chebyshev") => DistanceMetric::Chebyshev,
+                            Some(_) => return Err("unknown distance metric"),
+                        };
+                        let jitter = tokens.optional_number()?.unwrap_or(1.0);
+
+                        self.resources.add_texture(WorleyTexture::new(
+                            scale, feature, metric, jitter, low, high,
+                        ))
+                    }
+                    _ => return Err("unknown texture"),
+                };
+
+                self.textures.insert(name, texture);
+            }
+            "material" => {
+                let name = tokens.word()?.to_string();
+
+                let material = match tokens.word()? {
+                    "lambertian" => {
+                        let albedo = self.texture(tokens.word()?)?;
+                        self.resources.add_material(LambertianMaterial::new(albedo))
+                    }
+                    "metal" => {
+                        let albedo = tokens.vector()?;
+                        let fuzz = tokens.number()?;
+                        self.resources
+                            .add_material(MetalMaterial::new(albedo, fuzz))
+                    }
+                    "dielectric" => self
+                        .resources
+                        .add_material(DielectricMaterial::new(tokens.number()?)),
+                    "light" => {
+                        let emission = self.texture(tokens.word()?)?;
+                        self.resources
+                            .add_material(DiffuseLightMaterial::new(emission))
+                    }
+                    "catcher" => {
+                        let albedo = self.texture(tokens.word()?)?;
+                        self.resources
+                            .add_material(ShadowCatcherMaterial::new(albedo))
+                    }
+                    _ => return Err("unknown material"),
+                };
+
+                self.materials.insert(name, material);
+            }
+            "sphere" => {
+                let center = tokens.vector()?;
+                let radius = tokens.number()?;
+                let material = self.material(tokens.word()?)?;
+
+                self.add(SphereObject::new(center, radius, material));
+            }
+            "plane" => {
+                let point = tokens.vector()?;
+                let normal = tokens.vector()?;
+                let material = self.material(tokens.word()?)?;
+
+                if normal.len_sq() == 0.0 {
+                    return Err("plane normal must not be zero");
+                }
+
+                self.add(PlaneObject::new(point, normal, material));
+            }
+            "mesh" => {
+                let mesh = Mesh::load_obj(self.path(tokens.word()?).display())?;
+                let material = self.material(tokens.word()?)?;
+
+                match tokens.optional_word() {
+                    None => self.add(MeshObject::new(&mesh, material)),
+                    Some("lod") => {
+                        // Show the full mesh until a level is chosen for a camera.
+                        self.add(MeshObject::new(&mesh, material));
+                        let lod = LodMesh::new(mesh, tokens.number()?);
+                        self.lods.push((self.objects.len() - 1, lod, material));
+                    }
+                    Some(_) => return Err("unknown mesh option"),
+                }
+            }
+            "camera" => match tokens.word()? {
+                "from" => {
+                    self.camera.with_look_from(tokens.vector()?);
+                }
+                "at" => {
+                    self.camera.with_look_at(tokens.vector()?);
+                }
+                "vfov" => {
+                    self.camera.with_vfov(tokens.number()?);
+                }
+                "width" => {
+                    self.camera.with_image_width(tokens.number()?);
+                }
+                "aspect" => {
+                    self.camera.with_aspect_ratio(tokens.number::<Float>()?);
+                }
+                "samples" => {
+                    self.camera.with_sample_count(tokens.number()?);
+                }
+                "bounces" => {
+                    self.camera.with_max_bounces(tokens.number()?);
+                }
+                "preset" => {
+                    let preset = Preset::from_name(tokens.word()?).ok_or("unknown preset")?;
+                    self.camera.with_preset(preset);
+                }
+                "regularize" => {
+                    self.camera.with_roughness_clamp(tokens.number()?);
+                }
+                "tilt" => {
+                    self.camera.with_tilt(tokens.number()?, tokens.number()?);
+                }
+                "shift" => {
+                    self.camera.with_shift(tokens.number()?, tokens.number()?);
+                }
+                "near" => {
+                    self.camera.with_near(tokens.number()?);
+                }
+                "far" => {
+                    self.camera.with_far(tokens.number()?);
+                }
+                "filter" => {
+                    let filter = match tokens.word()? {
+                        "box" => PixelFilter::Box {
+                            radius: tokens.number()?,
+                        },
+                        "tent" => PixelFilter::Tent {
+                            radius: tokens.number()?,
+                        },
+                        "gaussian" => PixelFilter::Gaussian {
+                            sigma: tokens.number()?,
+                        },
+                        "blackman-harris" => PixelFilter::BlackmanHarris {
+                            radius: tokens.number()?,
+                        },
+                        _ => return Err("unknown filter"),
+                    };
+
+                    if filter.radius() <= 0.0 {
+                        return Err("filter radius must be positive");
+                    }
+                    self.camera.with_pixel_filter(filter);
+                }
+                "sampler" => match tokens.word()? {
+                    "bluenoise" => {
+                        self.camera.with_sampler(BlueNoiseSampler::new());
+                    }
+                    "halton" => {
+                        self.camera.with_sampler(HaltonSampler::new());
+                    }
+                    "cmj" => {
+                        self.camera.with_sampler(CmjSampler::new());
+                    }
+                    _ => return Err("unknown sampler"),
+                },
+                _ => return Err("unknown camera setting"),
+            },
+            "keyframe" => {
+                let keyframes = match tokens.word()? {
+                    "from" => &mut self.look_from,
+                    "at" => &mut self.look_at,
+                    _ => return Err("unknown keyframe"),
+                };
+
+                let time = tokens.number()?;
+                let value = tokens.vector()?;
+                keyframes
+                    .get_or_insert_with(|| Keyframes::new(Easing::Linear))
+                    .add(time, value);
+            }
+            "lightgroup" => {
+                self.light_group = match tokens.word()? {
+                    "none" => None,
+                    name => match self.light_groups.iter().position(|group| group == name) {
+                        Some(idx) => Some(idx),
+                        None if self.light_groups.len() == MAX_LIGHT_GROUPS => {
+                            return Err("too many light groups");
+                        }
+                        None => {
+                            self.light_groups.push(name.to_string());
+                            Some(self.light_groups.len() - 1)
+                        }
+                    },
+                };
+            }
+            _ => return Err("unknown statement"),
+        }
+
+        tokens.end()
+    }
+
+    /// Adds an object in the current light group.
+    fn add<H: Hittable + 'static>(&mut self, object: H) {
+        self.objects
+            .push((Primitive::new(object), self.light_group));
+    }
+
+    /// Resolves a path relative to the scene file.
+    fn path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    /// Looks up a named texture.
+    fn texture(&self, name: &str) -> Result<TextureId, &'static str> {
+        self.textures
+            .get(name)
+            .copied()
+            .ok_or("unknown texture name")
+    }
+
+    /// Looks up a named material.
+    fn material(&self, name: &str) -> Result<MaterialId, &'static str> {
+        self.materials
+            .get(name)
+            .copied()
+            .ok_or("unknown material name")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<SceneFile, SceneFileError> {
+        SceneFile::parse(source, Path::new(""))
+    }
+
+    #[test]
+    fn parses_objects_and_camera() {
+        let file = parse(
+            "texture white solid 1 1 1\n\
+             material matte lambertian white\n\
+             \n\
+             sphere 0 0 -1 0.5 matte # the subject\n\
+             sphere 0 -100.5 -1 100 matte\n\
+             camera width 64\n",
+        )
+        .unwrap();
+
+        assert_eq!(file.scene.ids().count(), 2);
+        assert_eq!(file.camera.build().image_width(), 64);
+    }
+
+    #[test]
+    fn reports_line_of_error() {
+        let err = parse("texture white solid 1 1 1\nsphere 0 0 -1 0.5 missing\n")
+            .err()
+            .unwrap();
+        assert_eq!(err.line, Some(2));
+        assert_eq!(err.message, "unknown material name");
+
+        let err = parse("camera width 64 32").err().unwrap();
+        assert_eq!(err.line, Some(1));
+        assert_eq!(err.message, "too many arguments");
+    }
+
+    #[test]
+    fn parses_optional_worley_arguments() {
+        let textures = "texture a solid 0 0 0\ntexture b solid 1 1 1\n";
+
+        for options in ["", "f2", "edge manhattan", "f1 chebyshev 0.5"] {
+            assert!(parse(&format!("{textures}texture w worley 2 a b {options}")).is_ok());
+        }
+
+        let err = parse(&format!("{textures}texture w worley 2 a b f3"))
+            .err()
+            .unwrap();
+        assert_eq!(err.message, "unknown worley feature");
+
+        let err = parse(&format!("{textures}texture w worley 2 a b f1 euclidean x"))
+            .err()
+            .unwrap();
+        assert_eq!(err.message, "invalid number");
+    }
+}
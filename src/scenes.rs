@@ -0,0 +1,228 @@
+use crate::{
+    animation::Animation,
+    camera::Camera,
+    environments::gradient::GradientEnvironment,
+    float::Float,
+    materials::{
+        dielectric::DielectricMaterial, lambertian::LambertianMaterial, metal::MetalMaterial,
+    },
+    objects::{plane::PlaneObject, sphere::SphereObject},
+    random::Random,
+    resources::{MaterialId, Resources},
+    scene::Scene,
+    scene_file::SceneFile,
+    textures::solid::SolidTexture,
+    vec3,
+    vector::{Color, Point3},
+};
+
+/// The number of small spheres of the final scene of "Ray Tracing in One Weekend".
+const CLASSIC_COUNT: u32 = 484;
+
+/// The radius of the small spheres.
+const SMALL_RADIUS: Float = 0.2;
+
+/// The centers of the three large spheres in the middle of the scene, with a radius of one.
+const LARGE_CENTERS: [Point3; 3] = [
+    Point3 {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    },
+    Point3 {
+        x: -4.0,
+        y: 1.0,
+        z: 0.0,
+    },
+    Point3 {
+        x: 4.0,
+        y: 1.0,
+        z: 0.0,
+    },
+];
+
+#[derive(Debug, Clone, Copy)]
+/// The relative amounts of the materials of generated spheres, which don't need to sum to one.
+pub struct MaterialMix {
+    /// The amount of diffuse spheres.
+    pub diffuse: Float,
+    /// The amount of metal spheres.
+    pub metal: Float,
+    /// The amount of glass spheres.
+    pub glass: Float,
+}
+
+impl Default for MaterialMix {
+    fn default() -> Self {
+        Self {
+            diffuse: 0.8,
+            metal: 0.15,
+            glass: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Generates the final scene of "Ray Tracing in One Weekend", a field of small random spheres
+/// around three large ones, which makes a substantial scene for demos and benchmarks.
+pub struct RandomSpheres {
+    /// The number of small spheres.
+    count: u32,
+    /// The seed the spheres are generated from, must not be zero.
+    seed: u64,
+    /// The relative amounts of the materials of the small spheres.
+    mix: MaterialMix,
+}
+
+impl Default for RandomSpheres {
+    fn default() -> Self {
+        Self {
+            count: CLASSIC_COUNT,
+            seed: 1,
+            mix: MaterialMix::default(),
+        }
+    }
+}
+
+impl RandomSpheres {
+    /// Creates a generator of the classic scene with 484 small spheres.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of small spheres, which are spread over a larger area for higher counts.
+    pub fn with_count(&mut self, count: u32) -> &mut Self {
+        self.count = count;
+        self
+    }
+
+    /// Sets the seed the spheres are generated from, the same seed generates the same scene.
+    pub fn with_seed(&mut self, seed: u64) -> &mut Self {
+        assert!(seed != 0, "seed must not be zero");
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the relative amounts of the materials of the small spheres.
+    pub fn with_mix(&mut self, mix: MaterialMix) -> &mut Self {
+        self.mix = mix;
+        self
+    }
+
+    /// Generates the scene, with a camera looking at the large spheres from the side.
+    pub fn build(&self) -> SceneFile {
+        let mut resources = Resources::default();
+        let mut rng = Random::new(self.seed);
+
+        let sky =
+            resources.add_environment(GradientEnvironment::new(Color::WHITE, vec3!(0.5, 0.7, 1.0)));
+        let mut scene = Scene::new(sky);
+
+        let ground = lambertian(&mut resources, vec3!(0.5));
+        scene.add(PlaneObject::new(vec3!(0), vec3!(0, 1, 0), ground));
+
+        let glass = resources.add_material(DielectricMaterial::new(1.5));
+        let large = [
+            glass,
+            lambertian(&mut resources, vec3!(0.4, 0.2, 0.1)),
+            resources.add_material(MetalMaterial::new(vec3!(0.7, 0.6, 0.5), 0.0)),
+        ];
+        for (center, material) in LARGE_CENTERS.into_iter().zip(large) {
+            scene.add(SphereObject::new(center, 1.0, material));
+        }
+
+        let centers = self.centers(&mut rng);
+        let total = self.mix.diffuse + self.mix.metal + self.mix.glass;
+        for center in centers.iter().copied() {
+            let choice = rng.random_float() * total;
+
+            let material = if choice < self.mix.diffuse {
+                let albedo = random_color(&mut rng) * random_color(&mut rng);
+                lambertian(&mut resources, albedo)
+            } else if choice < self.mix.diffuse + self.mix.metal {
+                let albedo = random_color(&mut rng) * 0.5 + vec3!(0.5);
+                let fuzz = rng.random_float() * 0.5;
+                resources.add_material(MetalMaterial::new(albedo, fuzz))
+            } else {
+                glass
+            };
+
+            scene.add(SphereObject::new(center, SMALL_RADIUS, material));
+        }
+
+        // Back off further from larger fields of spheres, so they fill the same part of the view.
+        let distance = (self.side() as Float / 22.0).max(1.0);
+        let mut camera = Camera::builder();
+        camera
+            .with_look_from(vec3!(13, 2, 3) * distance)
+            .with_look_at(vec3!(0))
+            .with_aspect_ratio(3.0 / 2.0)
+            .with_image_width(1200)
+            .with_vfov(20.0)
+            .with_sample_count(500)
+            .with_max_bounces(50)
+            .with_defocus_angle(0.6)
+            .with_focus_dist(10.0 * distance);
+
+        SceneFile {
+            scene,
+            resources,
+            camera,
+            animation: Animation::new(),
+            lods: Vec::new(),
+        }
+    }
+
+    /// Returns the number of cells along the sides of the grid of the small spheres.
+    fn side(&self) -> u32 {
+        (self.count as Float).sqrt().ceil() as u32
+    }
+
+    /// Places the small spheres at random in the cells of a grid centered on the large spheres,
+    /// skipping the cells where they would intersect them. The grid grows until all fit.
+    fn centers(&self, rng: &mut Random) -> Vec<Point3> {
+        let mut side = self.side();
+
+        loop {
+            let half = side as i32 / 2;
+            let mut centers = Vec::with_capacity(self.count as usize);
+
+            for a in -half..side as i32 - half {
+                for b in -half..side as i32 - half {
+                    if centers.len() == self.count as usize {
+                        return centers;
+                    }
+
+                    let center = vec3!(
+                        a as Float + 0.9 * rng.random_float(),
+                        SMALL_RADIUS,
+                        b as Float + 0.9 * rng.random_float()
+                    );
+
+                    let clear = LARGE_CENTERS
+                        .iter()
+                        .all(|&large| (center - large).len() > 1.0 + SMALL_RADIUS);
+                    if clear {
+                        centers.push(center);
+                    }
+                }
+            }
+
+            if centers.len() == self.count as usize {
+                return centers;
+            }
+            side += 1;
+        }
+    }
+}
+
+/// Adds a diffuse material of a solid color to the resources.
+fn lambertian(resources: &mut Resources, albedo: Color) -> MaterialId {
+    let texture = resources.add_texture(SolidTexture::new(albedo));
+    resources.add_material(LambertianMaterial::new(texture))
+}
+
+/// Returns a color with random components in [0, 1).
+fn random_color(rng: &mut Random) -> Color {
+    vec3!(rng.random_float(), rng.random_float(), rng.random_float())
+}
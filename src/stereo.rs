@@ -0,0 +1,98 @@
+use crate::{
+    camera::{Camera, CameraBuilder},
+    float::Float,
+    imgbuf::ImageBuffer,
+    progress::ProgressEvent,
+    resources::Resources,
+    scene::Scene,
+    vec3,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The arrangement of both eyes in a stereo image.
+pub enum StereoLayout {
+    #[default]
+    /// The left eye is placed left of the right eye.
+    SideBySide,
+    /// The left eye is placed above the right eye.
+    OverUnder,
+}
+
+#[derive(Debug, Clone)]
+/// A pair of cameras rendering a stereo image for both eyes.
+pub struct StereoCamera {
+    /// The camera of the left eye.
+    left: Camera,
+    /// The camera of the right eye.
+    right: Camera,
+    /// The arrangement of both eyes in the output image.
+    layout: StereoLayout,
+}
+
+impl StereoCamera {
+    /// Creates a stereo camera from a camera builder, placing both eyes the
+    /// interpupillary distance apart with parallel view directions.
+    pub fn new(builder: &CameraBuilder, ipd: Float, layout: StereoLayout) -> Self {
+        let look_from = builder.look_from.expect("look from must be set");
+        let look_at = builder.look_at.expect("look at must be set");
+
+        // Move both eyes along the horizontal axis of the camera.
+        let w = (look_from - look_at).unit();
+        let u = vec3!(0, 1, 0).cross(w).unit();
+        let offset = u * (ipd / 2.0);
+
+        let mut left = builder.clone();
+        left.with_look_from(look_from - offset)
+            .with_look_at(look_at - offset);
+
+        let mut right = builder.clone();
+        right
+            .with_look_from(look_from + offset)
+            .with_look_at(look_at + offset);
+
+        Self {
+            left: left.build(),
+            right: right.build(),
+            layout,
+        }
+    }
+
+    /// Returns the camera of the left eye.
+    pub fn left(&self) -> &Camera {
+        &self.left
+    }
+
+    /// Returns the camera of the right eye.
+    pub fn right(&self) -> &Camera {
+        &self.right
+    }
+
+    /// Renders both eyes and combines them into a single image.
+    /// The callback receives the progress events of the left eye and then the right eye.
+    pub fn render<F: Fn(ProgressEvent)>(
+        &self,
+        scene: &Scene,
+        resources: &Resources,
+        callback: F,
+    ) -> ImageBuffer {
+        let left = self.left.render(scene, resources, &callback);
+        let right = self.right.render(scene, resources, &callback);
+
+        let (width, height) = (left.width, left.height);
+
+        match self.layout {
+            StereoLayout::SideBySide => {
+                let mut image = ImageBuffer::new(width * 2, height);
+                image.paste(&left, 0, 0);
+                image.paste(&right, width, 0);
+                image
+            }
+            StereoLayout::OverUnder => {
+                let mut image = ImageBuffer::new(width, height * 2);
+                image.paste(&left, 0, 0);
+                image.paste(&right, 0, height);
+                image
+            }
+        }
+    }
+}
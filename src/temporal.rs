@@ -0,0 +1,134 @@
+use crate::{float::Float, framebuffer::FrameBuffer, vec3, vector::Color};
+
+#[derive(Debug, Clone)]
+/// Accumulates the frames of an animation over time, reprojecting the earlier frames onto every
+/// new frame with motion vectors, so mostly static shots reuse the samples of earlier frames.
+/// The reprojected history is clamped to the colors around every pixel of the new frame,
+/// which rejects it where surfaces become visible that it didn't see.
+pub struct TemporalAccumulator {
+    /// The smallest weight of a new frame, which limits how many frames are accumulated
+    /// and how long changes of the lighting take to show.
+    min_weight: Float,
+    /// The accumulated frames and the number of frames accumulated in every pixel.
+    history: Option<(FrameBuffer, Vec<Float>)>,
+}
+
+impl TemporalAccumulator {
+    /// Creates an accumulator without history. A new frame gets at least the given weight
+    /// against the accumulated frames, one turns the accumulation off.
+    pub fn new(min_weight: Float) -> Self {
+        Self {
+            min_weight: min_weight.clamp(0.0, 1.0),
+            history: None,
+        }
+    }
+
+    /// Discards the accumulated frames, like at a cut between shots.
+    pub fn reset(&mut self) {
+        self.history = None;
+    }
+
+    /// Blends the HDR frame with the accumulated frames and returns the result. The motion
+    /// vectors point from every pixel of the frame to where it was seen in the previous frame,
+    /// as rendered by [`Camera::render_previous_motion_vectors`].
+    ///
+    /// [`Camera::render_previous_motion_vectors`]: crate::camera::Camera::render_previous_motion_vectors
+    pub fn accumulate(&mut self, frame: &FrameBuffer, motion: &FrameBuffer) -> FrameBuffer {
+        let (width, height) = (frame.width, frame.height);
+
+        let history = match self.history.take() {
+            Some((history, counts)) if history.width == width && history.height == height => {
+                Some((history, counts))
+            }
+            _ => None,
+        };
+
+        let mut result = frame.clone();
+        let mut counts = vec![1.0; frame.data.len()];
+
+        if let Some((history, history_counts)) = history {
+            for y in 0..height {
+                for x in 0..width {
+                    // The motion starts at the center of the pixel.
+                    let offset = motion[(x, y)];
+                    let (px, py) = (x as Float + 0.5 + offset.x, y as Float + 0.5 + offset.y);
+
+                    // Surfaces that weren't in view of the previous frame start over.
+                    if px < 0.0 || py < 0.0 || px >= width as Float || py >= height as Float {
+                        continue;
+                    }
+
+                    let (low, high) = neighbourhood(frame, x, y);
+                    let previous = sample_catmull_rom(&history, px, py);
+                    let previous = vec3!(
+                        previous.x.clamp(low.x, high.x),
+                        previous.y.clamp(low.y, high.y),
+                        previous.z.clamp(low.z, high.z)
+                    );
+
+                    let idx = (y * width + x) as usize;
+                    let count = history_counts[(py as u32 * width + px as u32) as usize] + 1.0;
+                    let weight = (1.0 / count).max(self.min_weight);
+
+                    result[(x, y)] = previous * (1.0 - weight) + frame[(x, y)] * weight;
+                    counts[idx] = count.min(1.0 / self.min_weight.max(Float::EPSILON));
+                }
+            }
+        }
+
+        self.history = Some((result.clone(), counts));
+        result
+    }
+}
+
+/// Returns the smallest and largest value of every channel in the 3x3 pixels around a pixel.
+fn neighbourhood(fb: &FrameBuffer, x: u32, y: u32) -> (Color, Color) {
+    let mut low = vec3!(Float::INFINITY);
+    let mut high = vec3!(Float::NEG_INFINITY);
+
+    for ny in y.saturating_sub(1)..(y + 2).min(fb.height) {
+        for nx in x.saturating_sub(1)..(x + 2).min(fb.width) {
+            let color = fb[(nx, ny)];
+            low = vec3!(low.x.min(color.x), low.y.min(color.y), low.z.min(color.z));
+            high = vec3!(
+                high.x.max(color.x),
+                high.y.max(color.y),
+                high.z.max(color.z)
+            );
+        }
+    }
+
+    (low, high)
+}
+
+/// Interpolates a continuous position in pixels with a Catmull-Rom spline through the sixteen
+/// pixels closest to it, which keeps the history sharp when it is reprojected again and again.
+fn sample_catmull_rom(fb: &FrameBuffer, x: Float, y: Float) -> Color {
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (wx, wy) = (catmull_rom_weights(x - x0), catmull_rom_weights(y - y0));
+
+    let mut color = vec3!(0);
+    for (j, weight_y) in wy.iter().enumerate() {
+        let py = (y0 as i64 + j as i64 - 1).clamp(0, fb.height as i64 - 1) as u32;
+        for (i, weight_x) in wx.iter().enumerate() {
+            let px = (x0 as i64 + i as i64 - 1).clamp(0, fb.width as i64 - 1) as u32;
+            color += fb[(px, py)] * (weight_x * weight_y);
+        }
+    }
+
+    color
+}
+
+/// Returns the weights of the four samples around a fraction between the two middle ones
+/// on a Catmull-Rom spline.
+fn catmull_rom_weights(t: Float) -> [Float; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
@@ -1,9 +1,22 @@
 use std::fmt::Debug;
 
-use crate::{resources::Resources, vector::Color};
+use crate::{
+    float::Float,
+    resources::Resources,
+    vector::{Color, Point3},
+};
 
 /// A texture that can be used by materials in a scene.
 pub trait Texture: Debug + Send + Sync {
-    /// Get the color of the texture at a given UV coordinate.
-    fn color(&self, resources: &Resources, u: f64, v: f64) -> Color;
+    /// Get the color of the texture at a given UV coordinate and point in space.
+    /// The footprint is the width of the area seen through the ray in texture coordinates,
+    /// textures may average over it to avoid aliasing, a footprint of zero is a single point.
+    fn color(
+        &self,
+        resources: &Resources,
+        u: Float,
+        v: Float,
+        point: Point3,
+        footprint: Float,
+    ) -> Color;
 }
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use crate::{
+    float::Float,
+    imgbuf::ImageBuffer,
+    resources::Resources,
+    texture::Texture,
+    vector::{Color, Point3},
+};
+
+use super::image::ImageSampling;
+
+#[derive(Debug, Default)]
+/// Collects many small images to pack them into a single atlas image,
+/// so they share one buffer instead of each having their own.
+pub struct AtlasBuilder {
+    /// The images in the order they were added.
+    images: Vec<ImageBuffer>,
+}
+
+impl AtlasBuilder {
+    /// Create a new empty atlas builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an image to the atlas and returns its index among the images of the atlas.
+    pub fn add_image(&mut self, image: ImageBuffer) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+
+    /// Loads an image from the file at the given path, adds it to the atlas
+    /// and returns its index among the images of the atlas.
+    pub fn add_image_file<T: ToString>(&mut self, path: T) -> Result<usize, &'static str> {
+        Ok(self.add_image(ImageBuffer::load(path)?))
+    }
+
+    /// Returns the number of images in the atlas.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Returns whether the atlas has no images.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Packs the images into rows of an atlas image, the tallest images first.
+    /// Returns the atlas and the region of every image in the order they were added.
+    pub fn pack(self) -> (ImageBuffer, Vec<AtlasRegion>) {
+        let area: u64 = self
+            .images
+            .iter()
+            .map(|image| image.width as u64 * image.height as u64)
+            .sum();
+        let widest = self
+            .images
+            .iter()
+            .map(|image| image.width)
+            .max()
+            .unwrap_or(0);
+
+        // Aim for a square atlas, which wastes little space with rows of similar heights.
+        let width = widest.max((area as f64).sqrt().ceil() as u32);
+
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&idx| std::cmp::Reverse(self.images[idx].height));
+
+        let mut regions = vec![AtlasRegion::default(); self.images.len()];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for &idx in order.iter() {
+            let image = &self.images[idx];
+            if x + image.width > width {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+
+            regions[idx] = AtlasRegion {
+                x,
+                y,
+                width: image.width,
+                height: image.height,
+            };
+            x += image.width;
+            row_height = row_height.max(image.height);
+        }
+
+        let mut atlas = ImageBuffer::new(width, y + row_height);
+        for (image, region) in self.images.iter().zip(regions.iter()) {
+            for row in 0..image.height {
+                let src = (row * image.width) as usize * 3;
+                let dst = ((region.y + row) * atlas.width + region.x) as usize * 3;
+                let len = image.width as usize * 3;
+
+                atlas.data[dst..dst + len].copy_from_slice(&image.data[src..src + len]);
+            }
+        }
+
+        (atlas, regions)
+    }
+
+    /// Packs the images into an atlas and creates a texture for each of them,
+    /// which all share the atlas image.
+    pub fn build(self, sampling: ImageSampling) -> Vec<AtlasTexture> {
+        let (atlas, regions) = self.pack();
+        let atlas = Arc::new(atlas);
+
+        regions
+            .into_iter()
+            .map(|region| AtlasTexture {
+                atlas: Arc::clone(&atlas),
+                region,
+                sampling,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The pixels an image occupies in an atlas.
+pub struct AtlasRegion {
+    /// The column of the left edge of the region.
+    pub x: u32,
+    /// The row of the top edge of the region.
+    pub y: u32,
+    /// The width of the region in pixels.
+    pub width: u32,
+    /// The height of the region in pixels.
+    pub height: u32,
+}
+
+#[derive(Debug, Clone)]
+/// A texture that uses a region of a shared atlas image as its source.
+/// Texture coordinates are remapped into the region, so the image is wrapped on its own.
+pub struct AtlasTexture {
+    /// The atlas image the region belongs to.
+    atlas: Arc<ImageBuffer>,
+    /// The region of the image in the atlas.
+    region: AtlasRegion,
+    /// How the image is sampled.
+    sampling: ImageSampling,
+}
+
+impl AtlasTexture {
+    /// Returns the region of the image in the atlas.
+    pub fn region(&self) -> AtlasRegion {
+        self.region
+    }
+}
+
+impl Texture for AtlasTexture {
+    fn color(
+        &self,
+        _resources: &Resources,
+        u: Float,
+        v: Float,
+        _point: Point3,
+        _footprint: Float,
+    ) -> Color {
+        let region = self.region;
+        // Empty images take up no pixels of the atlas.
+        if region.width == 0 || region.height == 0 {
+            return Color::ZERO;
+        }
+
+        let (x, y) = self.sampling.pixel(u, v, region.width, region.height);
+        self.sampling
+            .color(&self.atlas[(region.x + x, region.y + y)])
+    }
+}
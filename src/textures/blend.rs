@@ -0,0 +1,94 @@
+use crate::{
+    float::Float,
+    resources::{Resources, TextureId},
+    texture::Texture,
+    vector::{Color, Point3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The operator used to combine the colors of two textures.
+pub enum BlendMode {
+    /// Multiplies the colors, which darkens the base.
+    Multiply,
+    /// Adds the colors, which brightens the base.
+    Add,
+    /// Inverts, multiplies and inverts the colors again, which brightens without exceeding one.
+    Screen,
+}
+
+#[derive(Debug)]
+/// A texture that combines two textures with a blend mode.
+pub struct BlendTexture {
+    /// The blend mode used to combine the textures.
+    mode: BlendMode,
+    /// The base texture.
+    base: TextureId,
+    /// The texture that is blended onto the base.
+    layer: TextureId,
+}
+
+impl BlendTexture {
+    /// Creates a new texture that blends the layer onto the base.
+    pub fn new(mode: BlendMode, base: TextureId, layer: TextureId) -> Self {
+        Self { mode, base, layer }
+    }
+}
+
+impl Texture for BlendTexture {
+    fn color(
+        &self,
+        resources: &Resources,
+        u: Float,
+        v: Float,
+        point: Point3,
+        footprint: Float,
+    ) -> Color {
+        let base = resources[self.base].color(resources, u, v, point, footprint);
+        let layer = resources[self.layer].color(resources, u, v, point, footprint);
+
+        match self.mode {
+            BlendMode::Multiply => base * layer,
+            BlendMode::Add => base + layer,
+            BlendMode::Screen => Color::ONE - (Color::ONE - base) * (Color::ONE - layer),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A texture that interpolates between two textures by the brightness of a mask.
+pub struct MaskTexture {
+    /// The texture shown where the mask is black.
+    base: TextureId,
+    /// The texture shown where the mask is white.
+    layer: TextureId,
+    /// The texture whose luminance selects between the textures.
+    mask: TextureId,
+}
+
+impl MaskTexture {
+    /// Creates a new texture that shows the layer over the base where the mask is bright.
+    pub fn new(base: TextureId, layer: TextureId, mask: TextureId) -> Self {
+        Self { base, layer, mask }
+    }
+}
+
+impl Texture for MaskTexture {
+    fn color(
+        &self,
+        resources: &Resources,
+        u: Float,
+        v: Float,
+        point: Point3,
+        footprint: Float,
+    ) -> Color {
+        let t = resources[self.mask]
+            .color(resources, u, v, point, footprint)
+            .luminance()
+            .clamp(0.0, 1.0);
+
+        let base = resources[self.base].color(resources, u, v, point, footprint);
+        let layer = resources[self.layer].color(resources, u, v, point, footprint);
+
+        base * (1.0 - t) + layer * t
+    }
+}
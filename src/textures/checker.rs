@@ -0,0 +1,49 @@
+use crate::{
+    float::Float,
+    resources::{Resources, TextureId},
+    texture::Texture,
+    vector::{Color, Point3},
+};
+
+#[derive(Debug)]
+/// A solid checkerboard texture, which alternates between two textures in 3D cells.
+pub struct CheckerTexture {
+    /// The reciprocal of the edge length of a cell.
+    inv_scale: Float,
+    /// The texture of the even cells.
+    even: TextureId,
+    /// The texture of the odd cells.
+    odd: TextureId,
+}
+
+impl CheckerTexture {
+    /// Creates a new checker texture with cells of the given edge length.
+    pub fn new(scale: Float, even: TextureId, odd: TextureId) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn color(
+        &self,
+        resources: &Resources,
+        u: Float,
+        v: Float,
+        point: Point3,
+        footprint: Float,
+    ) -> Color {
+        let x = (point.x * self.inv_scale).floor() as i64;
+        let y = (point.y * self.inv_scale).floor() as i64;
+        let z = (point.z * self.inv_scale).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            resources[self.even].color(resources, u, v, point, footprint)
+        } else {
+            resources[self.odd].color(resources, u, v, point, footprint)
+        }
+    }
+}
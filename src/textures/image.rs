@@ -1,29 +1,235 @@
-use crate::{imgbuf::ImageBuffer, resources::Resources, texture::Texture, vec3, vector::Color};
+use std::sync::OnceLock;
+
+use crate::{
+    float::Float,
+    imgbuf::ImageBuffer,
+    resources::Resources,
+    texture::Texture,
+    vec3,
+    vector::{linear_to_srgb, srgb_to_linear, Color, Point3},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How texture coordinates outside of [0, 1) are mapped onto the image.
+pub enum WrapMode {
+    #[default]
+    /// Tiles the image.
+    Repeat,
+    /// Extends the pixels at the edges of the image.
+    Clamp,
+    /// Tiles the image, flipping every other tile.
+    Mirror,
+}
+
+impl WrapMode {
+    #[inline]
+    /// Maps a texture coordinate into [0, 1].
+    pub fn apply(&self, coord: Float) -> Float {
+        match self {
+            Self::Repeat => coord.rem_euclid(1.0),
+            Self::Clamp => coord.clamp(0.0, 1.0),
+            Self::Mirror => {
+                let coord = coord.rem_euclid(2.0);
+                if coord > 1.0 {
+                    2.0 - coord
+                } else {
+                    coord
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The settings used to sample an image texture.
+pub struct ImageSampling {
+    /// How texture coordinates outside of the image are handled.
+    pub wrap: WrapMode,
+    /// Whether the pixels are sRGB encoded and have to be linearized,
+    /// which is the case for color textures but not for data like normal maps.
+    pub srgb: bool,
+}
+
+impl ImageSampling {
+    /// The sampling of sRGB encoded color textures, which are repeated.
+    pub const COLOR: Self = Self {
+        wrap: WrapMode::Repeat,
+        srgb: true,
+    };
+
+    /// The sampling of linear data textures, which are repeated.
+    pub const DATA: Self = Self {
+        wrap: WrapMode::Repeat,
+        srgb: false,
+    };
+}
+
+impl ImageSampling {
+    #[inline]
+    /// Returns the pixel of an image with the given size at the texture coordinates.
+    pub(crate) fn pixel(&self, u: Float, v: Float, width: u32, height: u32) -> (u32, u32) {
+        // Coordinates of exactly 1 would be one past the last pixel.
+        let x = (width as Float * self.wrap.apply(u)) as u32;
+        let y = (height as Float * self.wrap.apply(v)) as u32;
+
+        (x.min(width - 1), y.min(height - 1))
+    }
+
+    #[inline]
+    /// Converts the channels of an 8-bit RGB pixel into a linear color.
+    pub(crate) fn color(&self, pixel: &[u8]) -> Color {
+        if self.srgb {
+            let table = srgb_table();
+            return vec3!(
+                table[pixel[0] as usize],
+                table[pixel[1] as usize],
+                table[pixel[2] as usize]
+            );
+        }
+
+        let r = pixel[0] as Float / 255.0;
+        let g = pixel[1] as Float / 255.0;
+        let b = pixel[2] as Float / 255.0;
+
+        vec3!(r, g, b)
+    }
+
+    /// Halves the size of an image, averaging the colors of every 2x2 block of pixels
+    /// as linear colors, so sRGB encoded images don't get darker.
+    fn downsample(&self, image: &ImageBuffer) -> ImageBuffer {
+        let width = (image.width / 2).max(1);
+        let height = (image.height / 2).max(1);
+        let mut result = ImageBuffer::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                // Odd sizes leave the last row or column to the block before it.
+                let mut sum = Color::ZERO;
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let sx = (x * 2 + dx).min(image.width - 1);
+                    let sy = (y * 2 + dy).min(image.height - 1);
+                    sum += self.color(&image[(sx, sy)]);
+                }
+
+                let average = sum / 4.0;
+                let encode = |value: Float| {
+                    let value = if self.srgb {
+                        linear_to_srgb(value)
+                    } else {
+                        value
+                    };
+                    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                };
+
+                result[(x, y)].copy_from_slice(&[
+                    encode(average.x),
+                    encode(average.y),
+                    encode(average.z),
+                ]);
+            }
+        }
+
+        result
+    }
+}
+
+impl Default for ImageSampling {
+    fn default() -> Self {
+        Self::COLOR
+    }
+}
 
 #[derive(Debug)]
 /// A texture that uses an image as its source.
 pub struct ImageTexture {
     /// The image buffer of the texture.
     image: ImageBuffer,
+    /// How the image is sampled.
+    sampling: ImageSampling,
+    /// The image halved in size again and again, built on the first lookup that needs it.
+    mipmaps: OnceLock<Vec<ImageBuffer>>,
 }
 
 impl ImageTexture {
-    /// Create a new image texture with the given image buffer.
+    /// Create a new sRGB encoded image texture with the given image buffer, which is repeated.
     pub fn new(image: ImageBuffer) -> Self {
-        Self { image }
+        Self::with_sampling(image, ImageSampling::default())
+    }
+
+    /// Create a new sRGB encoded image texture with the given image buffer and wrap mode.
+    pub fn with_wrap_mode(image: ImageBuffer, wrap: WrapMode) -> Self {
+        Self::with_sampling(
+            image,
+            ImageSampling {
+                wrap,
+                ..ImageSampling::default()
+            },
+        )
+    }
+
+    /// Create a new image texture with the given image buffer and sampling settings.
+    pub fn with_sampling(image: ImageBuffer, sampling: ImageSampling) -> Self {
+        Self {
+            image,
+            sampling,
+            mipmaps: OnceLock::new(),
+        }
+    }
+
+    /// Returns the levels of the mipmap below the image, down to a single pixel.
+    fn mipmaps(&self) -> &[ImageBuffer] {
+        self.mipmaps.get_or_init(|| {
+            let mut levels: Vec<ImageBuffer> = Vec::new();
+            loop {
+                let previous = levels.last().unwrap_or(&self.image);
+                if previous.width <= 1 && previous.height <= 1 {
+                    break levels;
+                }
+
+                levels.push(self.sampling.downsample(previous));
+            }
+        })
+    }
+
+    /// Returns the color of the nearest pixel of a level of the mipmap, level 0 is the image.
+    fn level_color(&self, level: usize, u: Float, v: Float) -> Color {
+        let image = match level {
+            0 => &self.image,
+            _ => &self.mipmaps()[level - 1],
+        };
+
+        let (x, y) = self.sampling.pixel(u, v, image.width, image.height);
+        self.sampling.color(&image[(x, y)])
     }
 }
 
+/// Returns the linear value of every sRGB encoded 8-bit channel value.
+fn srgb_table() -> &'static [Float; 256] {
+    static TABLE: OnceLock<[Float; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|value| srgb_to_linear(value as Float / 255.0)))
+}
+
 impl Texture for ImageTexture {
-    fn color(&self, _resources: &Resources, u: f64, v: f64) -> Color {
-        let x = self.image.width as f64 * u;
-        let y = self.image.height as f64 * v;
+    fn color(
+        &self,
+        _resources: &Resources,
+        u: Float,
+        v: Float,
+        _point: Point3,
+        footprint: Float,
+    ) -> Color {
+        // Footprints smaller than a pixel are sampled from the image directly.
+        let pixels = footprint * self.image.width.max(self.image.height) as Float;
+        if pixels <= 1.0 {
+            return self.level_color(0, u, v);
+        }
 
-        let pixel = &self.image[(x as u32, y as u32)];
-        let r = pixel[0] as f64 / 255.0;
-        let g = pixel[1] as f64 / 255.0;
-        let b = pixel[2] as f64 / 255.0;
+        // Blend between the two levels of the mipmap whose pixels match the footprint best.
+        let level = pixels.log2();
+        let lower = (level as usize).min(self.mipmaps().len());
+        let upper = (lower + 1).min(self.mipmaps().len());
+        let blend = (level - lower as Float).clamp(0.0, 1.0);
 
-        vec3!(r, g, b)
+        self.level_color(lower, u, v) * (1.0 - blend) + self.level_color(upper, u, v) * blend
     }
 }
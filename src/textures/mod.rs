@@ -1,2 +1,7 @@
+pub mod atlas;
+pub mod blend;
+pub mod checker;
 pub mod image;
 pub mod solid;
+pub mod tiled;
+pub mod worley;
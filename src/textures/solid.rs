@@ -1,4 +1,9 @@
-use crate::{resources::Resources, texture::Texture, vector::Color};
+use crate::{
+    float::Float,
+    resources::Resources,
+    texture::Texture,
+    vector::{Color, Point3},
+};
 
 #[derive(Debug)]
 /// A solid color texture.
@@ -15,7 +20,14 @@ impl SolidTexture {
 }
 
 impl Texture for SolidTexture {
-    fn color(&self, _resources: &Resources, _u: f64, _v: f64) -> Color {
+    fn color(
+        &self,
+        _resources: &Resources,
+        _u: Float,
+        _v: Float,
+        _point: Point3,
+        _footprint: Float,
+    ) -> Color {
         self.color
     }
 }
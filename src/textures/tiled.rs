@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use crate::{
+    float::Float,
+    imgbuf::tiled::{TiledImage, DEFAULT_CACHE_TILES},
+    resources::Resources,
+    texture::Texture,
+    vector::{Color, Point3},
+};
+
+use super::image::ImageSampling;
+
+#[derive(Debug)]
+/// A texture from an image too large to keep in memory, whose tiles are loaded when sampled.
+pub struct TiledImageTexture {
+    /// The tiled image of the texture.
+    image: TiledImage,
+    /// How the image is sampled.
+    sampling: ImageSampling,
+}
+
+impl TiledImageTexture {
+    /// Loads a tiled image texture from a file, keeping the default number of tiles in memory.
+    pub fn open<P: AsRef<Path>>(path: P, sampling: ImageSampling) -> Result<Self, &'static str> {
+        Self::open_with_cache(path, sampling, DEFAULT_CACHE_TILES)
+    }
+
+    /// Loads a tiled image texture from a file, keeping at most the given number of tiles in memory.
+    pub fn open_with_cache<P: AsRef<Path>>(
+        path: P,
+        sampling: ImageSampling,
+        cache_tiles: usize,
+    ) -> Result<Self, &'static str> {
+        Ok(Self {
+            image: TiledImage::load(path, cache_tiles)?,
+            sampling,
+        })
+    }
+}
+
+impl Texture for TiledImageTexture {
+    fn color(
+        &self,
+        _resources: &Resources,
+        u: Float,
+        v: Float,
+        _point: Point3,
+        _footprint: Float,
+    ) -> Color {
+        let (x, y) = self
+            .sampling
+            .pixel(u, v, self.image.width, self.image.height);
+        self.sampling.color(&self.image.pixel(x, y))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    float::Float,
+    noise::{DistanceMetric, Worley},
+    resources::{Resources, TextureId},
+    texture::Texture,
+    vector::{Color, Point3},
+};
+
+/// The seed of the feature points, which are the same in every render.
+const SEED: u64 = 0xce11;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The distances of cellular noise a Worley texture shows.
+pub enum WorleyFeature {
+    #[default]
+    /// The distance to the closest feature point, which is dark at the points, like scales.
+    F1,
+    /// The distance to the second closest feature point, which gives rounded bulging cells.
+    F2,
+    /// The difference between both distances, which is dark along the borders between the cells,
+    /// like cracked mud or the joints between stones.
+    Edge,
+}
+
+#[derive(Debug)]
+/// A solid cellular noise texture, which interpolates between two textures
+/// by the distances to the feature points scattered in 3D cells.
+pub struct WorleyTexture {
+    /// The noise generator.
+    worley: Worley,
+    /// The reciprocal of the edge length of a cell.
+    inv_scale: Float,
+    /// The distances that are shown.
+    feature: WorleyFeature,
+    /// The texture shown where the distance is zero.
+    low: TextureId,
+    /// The texture shown where the distance is one cell or more.
+    high: TextureId,
+}
+
+impl WorleyTexture {
+    /// Creates a new Worley texture with cells of the given edge length, whose feature
+    /// points are jittered by the given fraction of a cell, between zero and one.
+    pub fn new(
+        scale: Float,
+        feature: WorleyFeature,
+        metric: DistanceMetric,
+        jitter: Float,
+        low: TextureId,
+        high: TextureId,
+    ) -> Self {
+        Self {
+            worley: Worley::new(SEED, jitter, metric),
+            inv_scale: 1.0 / scale,
+            feature,
+            low,
+            high,
+        }
+    }
+}
+
+impl Texture for WorleyTexture {
+    fn color(
+        &self,
+        resources: &Resources,
+        u: Float,
+        v: Float,
+        point: Point3,
+        footprint: Float,
+    ) -> Color {
+        let (f1, f2) = self.worley.distances(point * self.inv_scale);
+        let t = match self.feature {
+            WorleyFeature::F1 => f1,
+            WorleyFeature::F2 => f2,
+            WorleyFeature::Edge => f2 - f1,
+        }
+        .clamp(0.0, 1.0);
+
+        let low = resources[self.low].color(resources, u, v, point, footprint);
+        let high = resources[self.high].color(resources, u, v, point, footprint);
+
+        low * (1.0 - t) + high * t
+    }
+}
@@ -0,0 +1,395 @@
+use std::ops::Mul;
+
+use crate::{aabb::Aabb, float::Float, vec3, vector::Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// An affine transformation consisting of a linear part and a translation.
+pub struct Transform {
+    /// The rows of the linear part of the transformation.
+    matrix: [Vec3; 3],
+    /// The translation of the transformation.
+    translation: Vec3,
+}
+
+impl Transform {
+    /// The identity transformation.
+    pub const IDENTITY: Self = Self {
+        matrix: [
+            Vec3 {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Vec3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+            Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+        ],
+        translation: Vec3::ZERO,
+    };
+
+    /// Creates a transformation that moves points by the given offset.
+    pub fn translation(offset: Vec3) -> Self {
+        Self {
+            translation: offset,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a transformation that scales along every axis by the given factors.
+    pub fn scale(factors: Vec3) -> Self {
+        Self {
+            matrix: [
+                vec3!(factors.x, 0, 0),
+                vec3!(0, factors.y, 0),
+                vec3!(0, 0, factors.z),
+            ],
+            translation: Vec3::ZERO,
+        }
+    }
+
+    /// Creates a transformation that rotates around the given axis by an angle in degrees.
+    pub fn rotation(axis: Vec3, degrees: Float) -> Self {
+        let axis = axis.unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        // Rodrigues' rotation formula in matrix form.
+        Self {
+            matrix: [
+                vec3!(
+                    t * axis.x * axis.x + cos,
+                    t * axis.x * axis.y - sin * axis.z,
+                    t * axis.x * axis.z + sin * axis.y
+                ),
+                vec3!(
+                    t * axis.x * axis.y + sin * axis.z,
+                    t * axis.y * axis.y + cos,
+                    t * axis.y * axis.z - sin * axis.x
+                ),
+                vec3!(
+                    t * axis.x * axis.z - sin * axis.y,
+                    t * axis.y * axis.z + sin * axis.x,
+                    t * axis.z * axis.z + cos
+                ),
+            ],
+            translation: Vec3::ZERO,
+        }
+    }
+
+    /// Returns the transformation that first applies `self` and then `other`.
+    pub fn then(&self, other: &Transform) -> Transform {
+        *other * *self
+    }
+
+    /// Returns the inverse of the transformation.
+    /// Panics if the linear part of the transformation is singular.
+    pub fn inverse(&self) -> Transform {
+        let [a, b, c] = self.matrix;
+
+        // The inverse of a 3x3 matrix is the transposed cofactor matrix divided by the determinant.
+        let det = self.determinant();
+        assert!(det.abs() > 1e-12, "Transform is not invertible");
+
+        let cols = [b.cross(c) / det, c.cross(a) / det, a.cross(b) / det];
+        let matrix = [
+            vec3!(cols[0].x, cols[1].x, cols[2].x),
+            vec3!(cols[0].y, cols[1].y, cols[2].y),
+            vec3!(cols[0].z, cols[1].z, cols[2].z),
+        ];
+
+        let linear = Transform {
+            matrix,
+            translation: Vec3::ZERO,
+        };
+
+        Transform {
+            matrix,
+            translation: -linear.apply_vector(self.translation),
+        }
+    }
+
+    /// Returns the determinant of the linear part, the factor by which volumes are scaled.
+    pub fn determinant(&self) -> Float {
+        let [a, b, c] = self.matrix;
+        a.dot(b.cross(c))
+    }
+
+    /// Interpolates between the transformation at `t = 0` and the other one at `t = 1`.
+    /// Both are decomposed into translation, rotation and stretch, which are interpolated
+    /// separately so that rotating objects keep their shape in between.
+    pub fn interpolate(&self, other: &Transform, t: Float) -> Transform {
+        self.decompose().interpolate(&other.decompose(), t)
+    }
+
+    /// Splits the transformation into a translation, a rotation and a symmetric stretch
+    /// using the polar decomposition. Decomposing is slow, so transformations that are
+    /// interpolated often should be decomposed once.
+    pub fn decompose(&self) -> DecomposedTransform {
+        // Average the matrix with its inverse transpose until it converges to the rotation.
+        let mut rotation = self.matrix;
+        for _ in 0..100 {
+            let inverse = Transform {
+                matrix: rotation,
+                translation: Vec3::ZERO,
+            }
+            .inverse();
+            let inverse_transposed = transpose(inverse.matrix);
+
+            let next = [0, 1, 2].map(|row| (rotation[row] + inverse_transposed[row]) * 0.5);
+            let change: Float = (0..3).map(|row| (next[row] - rotation[row]).len()).sum();
+            rotation = next;
+
+            // The rows of the rotation have unit length, so the change is relative to one.
+            if change < 8.0 * Float::EPSILON {
+                break;
+            }
+        }
+
+        // Mirroring transformations keep the mirroring in the stretch.
+        if rotation[0].dot(rotation[1].cross(rotation[2])) < 0.0 {
+            rotation = rotation.map(|row| -row);
+        }
+
+        // The stretch is the rotation undone from the matrix.
+        let stretch = (Transform {
+            matrix: transpose(rotation),
+            translation: Vec3::ZERO,
+        } * Transform {
+            matrix: self.matrix,
+            translation: Vec3::ZERO,
+        })
+        .matrix;
+
+        DecomposedTransform {
+            rotation: matrix_to_quaternion(rotation),
+            stretch,
+            translation: self.translation,
+        }
+    }
+
+    #[inline]
+    /// Applies the transformation to a point.
+    pub fn apply_point(&self, point: Vec3) -> Vec3 {
+        self.apply_vector(point) + self.translation
+    }
+
+    #[inline]
+    /// Applies the transformation to a direction, ignoring the translation.
+    pub fn apply_vector(&self, vector: Vec3) -> Vec3 {
+        vec3!(
+            self.matrix[0].dot(vector),
+            self.matrix[1].dot(vector),
+            self.matrix[2].dot(vector)
+        )
+    }
+
+    #[inline]
+    /// Applies the transposed linear part of the transformation to a vector.
+    /// Called on the inverse transformation, this transforms surface normals.
+    pub fn apply_transposed(&self, vector: Vec3) -> Vec3 {
+        self.matrix[0] * vector.x + self.matrix[1] * vector.y + self.matrix[2] * vector.z
+    }
+
+    /// Returns the bounding box containing the transformed bounding box.
+    pub fn apply_aabb(&self, aabb: &Aabb) -> Aabb {
+        // Infinite corners would turn into NaN, and unbounded boxes stay unbounded anyway.
+        if !aabb.is_bounded() {
+            return Aabb::UNIVERSE;
+        }
+
+        let mut result = Aabb::EMPTY;
+
+        for corner in 0..8 {
+            let x = if corner & 1 == 0 {
+                aabb.x.start
+            } else {
+                aabb.x.end
+            };
+            let y = if corner & 2 == 0 {
+                aabb.y.start
+            } else {
+                aabb.y.end
+            };
+            let z = if corner & 4 == 0 {
+                aabb.z.start
+            } else {
+                aabb.z.end
+            };
+
+            result.grow(&Aabb::from_point(self.apply_point(vec3!(x, y, z))));
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A transformation split into a translation, a rotation and a stretch, ready to be interpolated.
+pub struct DecomposedTransform {
+    /// The rotation as a unit quaternion stored as `[x, y, z, w]`.
+    rotation: [Float; 4],
+    /// The rows of the symmetric stretch applied before the rotation.
+    stretch: [Vec3; 3],
+    /// The translation applied last.
+    translation: Vec3,
+}
+
+impl DecomposedTransform {
+    /// Interpolates between this transformation at `t = 0` and the other one at `t = 1`.
+    pub fn interpolate(&self, other: &DecomposedTransform, t: Float) -> Transform {
+        let rotation = slerp(self.rotation, other.rotation, t);
+        let stretch = [0, 1, 2].map(|row| self.stretch[row] * (1.0 - t) + other.stretch[row] * t);
+
+        let linear = Transform {
+            matrix: quaternion_to_matrix(rotation),
+            translation: Vec3::ZERO,
+        } * Transform {
+            matrix: stretch,
+            translation: Vec3::ZERO,
+        };
+
+        Transform {
+            translation: self.translation * (1.0 - t) + other.translation * t,
+            ..linear
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    /// Composes two transformations, the right hand side is applied first.
+    fn mul(self, other: Transform) -> Transform {
+        let cols = [
+            self.apply_vector(vec3!(
+                other.matrix[0].x,
+                other.matrix[1].x,
+                other.matrix[2].x
+            )),
+            self.apply_vector(vec3!(
+                other.matrix[0].y,
+                other.matrix[1].y,
+                other.matrix[2].y
+            )),
+            self.apply_vector(vec3!(
+                other.matrix[0].z,
+                other.matrix[1].z,
+                other.matrix[2].z
+            )),
+        ];
+
+        Transform {
+            matrix: [
+                vec3!(cols[0].x, cols[1].x, cols[2].x),
+                vec3!(cols[0].y, cols[1].y, cols[2].y),
+                vec3!(cols[0].z, cols[1].z, cols[2].z),
+            ],
+            translation: self.apply_point(other.translation),
+        }
+    }
+}
+
+/// Returns the transposed matrix.
+fn transpose(m: [Vec3; 3]) -> [Vec3; 3] {
+    [
+        vec3!(m[0].x, m[1].x, m[2].x),
+        vec3!(m[0].y, m[1].y, m[2].y),
+        vec3!(m[0].z, m[1].z, m[2].z),
+    ]
+}
+
+/// Converts a rotation matrix into a unit quaternion stored as `[x, y, z, w]`.
+fn matrix_to_quaternion(m: [Vec3; 3]) -> [Float; 4] {
+    let trace = m[0].x + m[1].y + m[2].z;
+
+    if trace > 0.0 {
+        let s = 0.5 / (trace + 1.0).sqrt();
+        [
+            (m[2].y - m[1].z) * s,
+            (m[0].z - m[2].x) * s,
+            (m[1].x - m[0].y) * s,
+            0.25 / s,
+        ]
+    } else if m[0].x > m[1].y && m[0].x > m[2].z {
+        let s = 2.0 * (1.0 + m[0].x - m[1].y - m[2].z).sqrt();
+        [
+            0.25 * s,
+            (m[0].y + m[1].x) / s,
+            (m[0].z + m[2].x) / s,
+            (m[2].y - m[1].z) / s,
+        ]
+    } else if m[1].y > m[2].z {
+        let s = 2.0 * (1.0 + m[1].y - m[0].x - m[2].z).sqrt();
+        [
+            (m[0].y + m[1].x) / s,
+            0.25 * s,
+            (m[1].z + m[2].y) / s,
+            (m[0].z - m[2].x) / s,
+        ]
+    } else {
+        let s = 2.0 * (1.0 + m[2].z - m[0].x - m[1].y).sqrt();
+        [
+            (m[0].z + m[2].x) / s,
+            (m[1].z + m[2].y) / s,
+            0.25 * s,
+            (m[1].x - m[0].y) / s,
+        ]
+    }
+}
+
+/// Converts a unit quaternion stored as `[x, y, z, w]` into a rotation matrix.
+fn quaternion_to_matrix([x, y, z, w]: [Float; 4]) -> [Vec3; 3] {
+    [
+        vec3!(
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w)
+        ),
+        vec3!(
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w)
+        ),
+        vec3!(
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y)
+        ),
+    ]
+}
+
+/// Spherically interpolates between two unit quaternions along the shorter arc.
+fn slerp(a: [Float; 4], mut b: [Float; 4], t: Float) -> [Float; 4] {
+    let mut cos = a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3];
+    if cos < 0.0 {
+        b = b.map(|c| -c);
+        cos = -cos;
+    }
+
+    // Nearly parallel quaternions are interpolated linearly to avoid dividing by zero.
+    let (wa, wb) = if cos > 0.9995 {
+        (1.0 - t, t)
+    } else {
+        let theta = cos.acos();
+        let sin = theta.sin();
+        (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+    };
+
+    let q = [0, 1, 2, 3].map(|i| a[i] * wa + b[i] * wb);
+    let len = (q[0] * q[0] + q[1] * q[1] + q[2] * q[2] + q[3] * q[3]).sqrt();
+
+    q.map(|c| c / len)
+}
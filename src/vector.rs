@@ -1,19 +1,20 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use crate::float::{Float, Scalar};
 use crate::random::THREAD_RNG;
 
 #[macro_export]
 /// Create a new Vec3 with the given x, y, and z components.
 macro_rules! vec3 {
     ($i:expr) => {{
-        let i: f64 = $i.into();
+        let i = $crate::float::IntoFloat::into_float($i);
         $crate::vector::Vec3 { x: i, y: i, z: i }
     }};
     ($x:expr, $y:expr, $z:expr) => {
         $crate::vector::Vec3 {
-            x: $x.into(),
-            y: $y.into(),
-            z: $z.into(),
+            x: $crate::float::IntoFloat::into_float($x),
+            y: $crate::float::IntoFloat::into_float($y),
+            z: $crate::float::IntoFloat::into_float($z),
         }
     };
 }
@@ -30,79 +31,260 @@ impl Color {
         y: 1.0,
         z: 1.0,
     };
+
+    #[inline]
+    /// Calculates the relative luminance of a linear Rec. 709 color.
+    pub fn luminance(&self) -> Float {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
+    /// Creates a linear color from an sRGB encoded hex value like `0xff8000`.
+    pub fn from_hex(hex: u32) -> Color {
+        Self::from_u8((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    /// Creates a linear color from sRGB encoded 8-bit channels.
+    pub fn from_u8(r: u8, g: u8, b: u8) -> Color {
+        Self::from_srgb(vec3!(r, g, b) / 255.0)
+    }
+
+    /// Converts a color with sRGB encoded channels in [0, 1] to linear.
+    pub fn from_srgb(color: Color) -> Color {
+        color.map(srgb_to_linear)
+    }
+
+    /// Converts the linear color to sRGB encoded channels.
+    pub fn to_srgb(&self) -> Color {
+        self.map(linear_to_srgb)
+    }
+
+    /// Creates a color from a hue in degrees, and a saturation and value in [0, 1].
+    /// The channels are computed without applying any transfer function.
+    pub fn from_hsv(hue: Float, saturation: Float, value: Float) -> Color {
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma) + Color::ONE * (value - chroma)
+    }
+
+    /// Converts the color into a hue in degrees, and a saturation and value in [0, 1].
+    pub fn to_hsv(&self) -> (Float, Float, Float) {
+        let (hue, chroma, max, _) = self.hue_chroma();
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+
+        (hue, saturation, max)
+    }
+
+    /// Creates a color from a hue in degrees, and a saturation and lightness in [0, 1].
+    /// The channels are computed without applying any transfer function.
+    pub fn from_hsl(hue: Float, saturation: Float, lightness: Float) -> Color {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue(hue, chroma) + Color::ONE * (lightness - chroma / 2.0)
+    }
+
+    /// Converts the color into a hue in degrees, and a saturation and lightness in [0, 1].
+    pub fn to_hsl(&self) -> (Float, Float, Float) {
+        let (hue, chroma, max, min) = self.hue_chroma();
+        let lightness = (max + min) / 2.0;
+        let saturation = if lightness > 0.0 && lightness < 1.0 {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+
+        (hue, saturation, lightness)
+    }
+
+    /// Returns the fully saturated color with the given hue in degrees, scaled by the chroma.
+    fn from_hue(hue: Float, chroma: Float) -> Color {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+        match sector as u32 {
+            0 => vec3!(chroma, x, 0),
+            1 => vec3!(x, chroma, 0),
+            2 => vec3!(0, chroma, x),
+            3 => vec3!(0, x, chroma),
+            4 => vec3!(x, 0, chroma),
+            _ => vec3!(chroma, 0, x),
+        }
+    }
+
+    /// Returns the hue in degrees, the chroma, and the largest and smallest channel of the color.
+    fn hue_chroma(&self) -> (Float, Float, Float, Float) {
+        let max = self.x.max(self.y).max(self.z);
+        let min = self.x.min(self.y).min(self.z);
+        let chroma = max - min;
+
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == self.x {
+            60.0 * ((self.y - self.z) / chroma).rem_euclid(6.0)
+        } else if max == self.y {
+            60.0 * ((self.z - self.x) / chroma + 2.0)
+        } else {
+            60.0 * ((self.x - self.y) / chroma + 4.0)
+        };
+
+        (hue, chroma, max, min)
+    }
+}
+
+#[inline]
+/// Converts an sRGB encoded channel in [0, 1] to linear.
+pub fn srgb_to_linear(value: Float) -> Float {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[inline]
+/// Converts a linear channel in [0, 1] to sRGB encoded.
+pub fn linear_to_srgb(value: Float) -> Float {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
 }
 
 /// A point in 3D space, with x, y, and z components.
 /// This is an alias for Vec3.
 pub type Point3 = Vec3;
 
+/// A vector in 3D space, using the floating point type used for rendering.
+pub type Vec3 = GenericVec3<Float>;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
-/// A vector in 3D space, with x, y, and z components.
-pub struct Vec3 {
+/// A vector in 3D space, with x, y, and z components of any scalar type.
+pub struct GenericVec3<T> {
     /// The x component of the vector.
-    pub x: f64,
+    pub x: T,
     /// The y component of the vector.
-    pub y: f64,
+    pub y: T,
     /// The z component of the vector.
-    pub z: f64,
+    pub z: T,
 }
 
-impl Vec3 {
+impl<T: Scalar> GenericVec3<T> {
     /// Constant vector with all components set to 0.
-    pub const ZERO: Vec3 = Vec3 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
+    pub const ZERO: Self = Self {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::ZERO,
     };
 
-    /// Create a new Vec3 that lies inside the unit sphere.
-    pub fn random_in_unit_sphere() -> Vec3 {
-        THREAD_RNG.with(|rng| {
-            let mut rng = rng.borrow_mut();
+    /// Constant vector with all components set to 1.
+    pub const ONE: Self = Self {
+        x: T::ONE,
+        y: T::ONE,
+        z: T::ONE,
+    };
 
-            loop {
-                let p = vec3!(
-                    rng.random_f64() * 2.0 - 1.0,
-                    rng.random_f64() * 2.0 - 1.0,
-                    rng.random_f64() * 2.0 - 1.0
-                );
+    /// Constant unit vector along the x axis.
+    pub const X: Self = Self {
+        x: T::ONE,
+        y: T::ZERO,
+        z: T::ZERO,
+    };
 
-                if p.len_sq() < 1.0 {
-                    return p;
-                }
-            }
-        })
+    /// Constant unit vector along the y axis.
+    pub const Y: Self = Self {
+        x: T::ZERO,
+        y: T::ONE,
+        z: T::ZERO,
+    };
+
+    /// Constant unit vector along the z axis.
+    pub const Z: Self = Self {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::ONE,
+    };
+
+    #[inline]
+    /// Applies a function to every component of the vector.
+    pub fn map<F: Fn(T) -> T>(&self, f: F) -> Self {
+        Self {
+            x: f(self.x),
+            y: f(self.y),
+            z: f(self.z),
+        }
+    }
+
+    #[inline]
+    /// Returns the componentwise minimum of two vectors.
+    pub fn min(&self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        }
+    }
+
+    #[inline]
+    /// Returns the componentwise maximum of two vectors.
+    pub fn max(&self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        }
+    }
+
+    #[inline]
+    /// Returns the absolute value of every component.
+    pub fn abs(&self) -> Self {
+        self.map(T::abs)
+    }
+
+    #[inline]
+    /// Returns the square root of every component.
+    pub fn sqrt(&self) -> Self {
+        self.map(T::sqrt)
+    }
+
+    #[inline]
+    /// Clamps every component between the given bounds.
+    pub fn clamp(&self, min: T, max: T) -> Self {
+        self.map(|value| value.max(min).min(max))
+    }
+
+    #[inline]
+    /// Linearly interpolates between this vector at 0 and the other vector at 1.
+    pub fn lerp(&self, other: Self, t: T) -> Self {
+        *self + (other - *self) * t
     }
 
     #[inline]
     /// Checks if the vector is near zero.
     pub fn near_zero(&self) -> bool {
-        const DELTA: f64 = 1e-8;
-        self.x.abs() < DELTA && self.y.abs() < DELTA && self.z.abs() < DELTA
+        let delta = T::from_f64(1e-8);
+        self.x.abs() < delta && self.y.abs() < delta && self.z.abs() < delta
     }
 
     #[inline]
     /// Calculates the length of the vector.
-    pub fn len(&self) -> f64 {
+    pub fn len(&self) -> T {
         self.len_sq().sqrt()
     }
 
     #[inline]
     /// Calculates the squared length of the vector.
-    pub fn len_sq(&self) -> f64 {
+    pub fn len_sq(&self) -> T {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
 
     #[inline]
     /// Calculates the dot product of two vectors.
-    pub fn dot(&self, other: Vec3) -> f64 {
+    pub fn dot(&self, other: Self) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
     #[inline]
     /// Calculates the cross product of two vectors.
-    pub fn cross(&self, other: Vec3) -> Vec3 {
-        Vec3 {
+    pub fn cross(&self, other: Self) -> Self {
+        Self {
             x: self.y * other.z - self.z * other.y,
             y: self.z * other.x - self.x * other.z,
             z: self.x * other.y - self.y * other.x,
@@ -111,33 +293,54 @@ impl Vec3 {
 
     #[inline]
     /// Returns a new vector with the same direction, but a length of 1.
-    pub fn unit(&self) -> Vec3 {
+    pub fn unit(&self) -> Self {
         *self / self.len()
     }
 
     #[inline]
     /// Reflects the vector across a normal.
-    pub fn reflect(&self, normal: Vec3) -> Vec3 {
-        *self - normal * self.dot(normal) * 2.0
+    pub fn reflect(&self, normal: Self) -> Self {
+        *self - normal * (self.dot(normal) * T::from_f64(2.0))
     }
 
     #[inline]
     /// Refracts the vector through a surface with the given normal and refractive index.
-    pub fn refract(&self, normal: Vec3, etai_over_etat: f64) -> Vec3 {
-        let cos_theta = (-*self).dot(normal).min(1.0);
+    pub fn refract(&self, normal: Self, etai_over_etat: T) -> Self {
+        let cos_theta = (-*self).dot(normal).min(T::ONE);
         let r_out_perp = (*self + normal * cos_theta) * etai_over_etat;
-        let r_out_parallel = normal * -(1.0 - r_out_perp.len_sq()).abs().sqrt();
+        let r_out_parallel = normal * -(T::ONE - r_out_perp.len_sq()).abs().sqrt();
         r_out_perp + r_out_parallel
     }
 }
 
-impl Add for Vec3 {
-    type Output = Vec3;
+impl Vec3 {
+    /// Create a new Vec3 that lies inside the unit sphere.
+    pub fn random_in_unit_sphere() -> Vec3 {
+        THREAD_RNG.with(|rng| {
+            let mut rng = rng.borrow_mut();
+
+            loop {
+                let p = vec3!(
+                    rng.random_float() * 2.0 - 1.0,
+                    rng.random_float() * 2.0 - 1.0,
+                    rng.random_float() * 2.0 - 1.0
+                );
+
+                if p.len_sq() < 1.0 {
+                    return p;
+                }
+            }
+        })
+    }
+}
+
+impl<T: Scalar> Add for GenericVec3<T> {
+    type Output = Self;
 
     #[inline]
     /// Adds two vectors together.
-    fn add(self, other: Vec3) -> Vec3 {
-        Vec3 {
+    fn add(self, other: Self) -> Self {
+        Self {
             x: self.x + other.x,
             y: self.y + other.y,
             z: self.z + other.z,
@@ -145,21 +348,21 @@ impl Add for Vec3 {
     }
 }
 
-impl AddAssign for Vec3 {
+impl<T: Scalar> AddAssign for GenericVec3<T> {
     #[inline]
     /// Adds another vector to this one.
-    fn add_assign(&mut self, other: Vec3) {
+    fn add_assign(&mut self, other: Self) {
         *self = *self + other;
     }
 }
 
-impl Neg for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Neg for GenericVec3<T> {
+    type Output = Self;
 
     #[inline]
     /// Negates the vector.
-    fn neg(self) -> Vec3 {
-        Vec3 {
+    fn neg(self) -> Self {
+        Self {
             x: -self.x,
             y: -self.y,
             z: -self.z,
@@ -167,31 +370,31 @@ impl Neg for Vec3 {
     }
 }
 
-impl Sub for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Sub for GenericVec3<T> {
+    type Output = Self;
 
     #[inline]
     /// Subtracts one vector from another.
-    fn sub(self, other: Vec3) -> Vec3 {
+    fn sub(self, other: Self) -> Self {
         self + -other
     }
 }
 
-impl SubAssign for Vec3 {
+impl<T: Scalar> SubAssign for GenericVec3<T> {
     #[inline]
     /// Subtracts another vector from this one.
-    fn sub_assign(&mut self, other: Vec3) {
+    fn sub_assign(&mut self, other: Self) {
         *self = *self - other;
     }
 }
 
-impl Mul<f64> for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Mul<T> for GenericVec3<T> {
+    type Output = Self;
 
     #[inline]
     /// Multiplies a vector by a scalar.
-    fn mul(self, scalar: f64) -> Vec3 {
-        Vec3 {
+    fn mul(self, scalar: T) -> Self {
+        Self {
             x: self.x * scalar,
             y: self.y * scalar,
             z: self.z * scalar,
@@ -199,23 +402,31 @@ impl Mul<f64> for Vec3 {
     }
 }
 
-impl Mul<Vec3> for f64 {
-    type Output = Vec3;
+macro_rules! impl_scalar_mul {
+    ($($ty:ty),*) => {
+        $(
+            impl Mul<GenericVec3<$ty>> for $ty {
+                type Output = GenericVec3<$ty>;
 
-    #[inline]
-    /// Multiplies a scalar by a vector.
-    fn mul(self, vec: Vec3) -> Vec3 {
-        vec * self
-    }
+                #[inline]
+                /// Multiplies a scalar by a vector.
+                fn mul(self, vec: GenericVec3<$ty>) -> GenericVec3<$ty> {
+                    vec * self
+                }
+            }
+        )*
+    };
 }
 
-impl Mul<Vec3> for Vec3 {
-    type Output = Vec3;
+impl_scalar_mul!(f32, f64);
+
+impl<T: Scalar> Mul for GenericVec3<T> {
+    type Output = Self;
 
     #[inline]
     /// Multiplies two vectors together.
-    fn mul(self, other: Vec3) -> Vec3 {
-        Vec3 {
+    fn mul(self, other: Self) -> Self {
+        Self {
             x: self.x * other.x,
             y: self.y * other.y,
             z: self.z * other.z,
@@ -223,43 +434,43 @@ impl Mul<Vec3> for Vec3 {
     }
 }
 
-impl MulAssign<f64> for Vec3 {
+impl<T: Scalar> MulAssign<T> for GenericVec3<T> {
     #[inline]
     /// Multiplies this vector by a scalar.
-    fn mul_assign(&mut self, scalar: f64) {
+    fn mul_assign(&mut self, scalar: T) {
         *self = *self * scalar;
     }
 }
 
-impl MulAssign<Vec3> for Vec3 {
+impl<T: Scalar> MulAssign for GenericVec3<T> {
     #[inline]
     /// Multiplies this vector by another vector.
-    fn mul_assign(&mut self, other: Vec3) {
+    fn mul_assign(&mut self, other: Self) {
         *self = *self * other;
     }
 }
 
-impl Div<f64> for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Div<T> for GenericVec3<T> {
+    type Output = Self;
 
     #[inline]
     /// Divides a vector by a scalar.
-    fn div(self, scalar: f64) -> Vec3 {
-        let scalar = 1.0 / scalar;
+    fn div(self, scalar: T) -> Self {
+        let scalar = T::ONE / scalar;
         self * scalar
     }
 }
 
-impl DivAssign<f64> for Vec3 {
+impl<T: Scalar> DivAssign<T> for GenericVec3<T> {
     #[inline]
     /// Divides this vector by a scalar.
-    fn div_assign(&mut self, scalar: f64) {
+    fn div_assign(&mut self, scalar: T) {
         *self = *self / scalar;
     }
 }
 
-impl Index<usize> for Vec3 {
-    type Output = f64;
+impl<T> Index<usize> for GenericVec3<T> {
+    type Output = T;
 
     #[inline]
     /// Indexes into the vector.
@@ -0,0 +1,423 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::imgbuf::ImageBuffer;
+
+/// The key appended to the key of a WebSocket handshake before it is hashed into the answer.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The number of connections served at once, further browsers are turned away.
+const MAX_CONNECTIONS: usize = 16;
+
+/// The longest line of a request that is accepted, in bytes.
+const MAX_LINE: usize = 8192;
+
+/// The most header lines of a request that are accepted.
+const MAX_HEADERS: usize = 64;
+
+/// How long a browser may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The opcodes of the WebSocket frames the viewer sends or answers.
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+/// The page served to browsers, which shows every image it receives over the WebSocket.
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>rusty-ray</title>
+<style>
+body { margin: 0; background: #1e1e1e; color: #ccc; font: 14px sans-serif; text-align: center; }
+img { max-width: 100vw; max-height: calc(100vh - 2em); image-rendering: pixelated; }
+</style>
+</head>
+<body>
+<div id="status">connecting...</div>
+<img id="image">
+<script>
+const image = document.getElementById("image");
+const status = document.getElementById("status");
+const socket = new WebSocket("ws://" + location.host + "/live");
+let frames = 0;
+socket.onopen = () => status.textContent = "waiting for the first image...";
+socket.onclose = () => status.textContent += " (disconnected)";
+socket.onmessage = (event) => {
+    const previous = image.src;
+    image.src = URL.createObjectURL(event.data);
+    if (previous) URL.revokeObjectURL(previous);
+    status.textContent = "update " + (++frames);
+};
+</script>
+</body>
+</html>
+"#;
+
+/// A PNG encoded image shared by the connections.
+type Frame = Arc<[u8]>;
+
+#[derive(Debug)]
+/// A message for the writing side of a connection.
+enum Message {
+    /// An image to show.
+    Image(Frame),
+    /// The answer to a ping of the browser, with the payload of the ping.
+    Pong(Vec<u8>),
+    /// The browser closed the connection or went away.
+    Close,
+}
+
+#[derive(Debug, Default)]
+/// The state shared by the viewer and its connections.
+struct Shared {
+    /// The channels of the connected browsers.
+    clients: Mutex<Vec<Sender<Message>>>,
+    /// The last published image, sent to browsers as soon as they connect.
+    latest: Mutex<Option<Frame>>,
+    /// The number of connections being served.
+    connections: AtomicUsize,
+}
+
+#[derive(Debug)]
+/// Serves a web page showing the images of a render as they are published, so renders on a
+/// headless machine can be watched from a browser. The images are pushed over a WebSocket.
+pub struct LiveViewer {
+    /// The address the viewer listens on.
+    addr: SocketAddr,
+    /// The state shared with the connections.
+    shared: Arc<Shared>,
+}
+
+impl LiveViewer {
+    /// Starts serving the viewer on the given address, like `0.0.0.0:8080`,
+    /// from a background thread until the process exits.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<LiveViewer, &'static str> {
+        let listener = TcpListener::bind(addr).map_err(|_| "failed to bind address")?;
+        let addr = listener
+            .local_addr()
+            .map_err(|_| "failed to get bound address")?;
+        let shared = Arc::new(Shared::default());
+
+        let accepting = shared.clone();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                if accepting.connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                    accepting.connections.fetch_sub(1, Ordering::Relaxed);
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    );
+                    continue;
+                }
+
+                let shared = accepting.clone();
+                thread::spawn(move || {
+                    // A browser that goes away only ends its own connection.
+                    let _ = serve(stream, &shared);
+                    shared.connections.fetch_sub(1, Ordering::Relaxed);
+                });
+            }
+        });
+
+        Ok(LiveViewer { addr, shared })
+    }
+
+    /// Returns the address the viewer listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Sends the image to all connected browsers, and to the browsers connecting later.
+    pub fn publish(&self, image: &ImageBuffer) -> Result<(), &'static str> {
+        let mut png = Vec::new();
+        ImageBuffer::with_data(image.width, image.height, image.data.clone()).save_to(&mut png)?;
+        let frame: Frame = png.into();
+
+        *self.shared.latest.lock().unwrap() = Some(frame.clone());
+        self.shared
+            .clients
+            .lock()
+            .unwrap()
+            .retain(|client| client.send(Message::Image(frame.clone())).is_ok());
+
+        Ok(())
+    }
+}
+
+/// Answers a request of a browser, either with the page or by streaming the images over a WebSocket.
+fn serve(stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+
+    let mut request = String::new();
+    read_line(&mut reader, &mut request)?;
+
+    // Only the key of a WebSocket handshake matters in the headers.
+    let mut key = None;
+    for _ in 0..=MAX_HEADERS {
+        let mut line = String::new();
+        if read_line(&mut reader, &mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    match (path, key) {
+        ("/live", Some(key)) => {
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(&key)
+            )?;
+
+            let (sender, receiver) = mpsc::channel();
+            if let Some(frame) = shared.latest.lock().unwrap().clone() {
+                let _ = sender.send(Message::Image(frame));
+            }
+            shared.clients.lock().unwrap().push(sender.clone());
+
+            // The browser may stay quiet for as long as it watches.
+            stream.set_read_timeout(None)?;
+            let reading = thread::spawn(move || read_messages(&mut reader, &sender));
+            let result = write_messages(&mut stream, receiver);
+
+            // Ends the reading side too, if the browser went away without closing.
+            let _ = stream.shutdown(Shutdown::Both);
+            let _ = reading.join();
+
+            result
+        }
+        ("/", _) => write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+            PAGE.len()
+        ),
+        _ => write!(
+            stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+    }
+}
+
+/// Reads a line of a request, failing if it is longer than `MAX_LINE`.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    let read = reader.take(MAX_LINE as u64).read_line(line)?;
+    if read == MAX_LINE && !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "request line is too long",
+        ));
+    }
+
+    Ok(read)
+}
+
+/// Returns the answer to the key of a WebSocket handshake.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{WEBSOCKET_GUID}").as_bytes()))
+}
+
+/// Sends the messages of a connection to the browser, until it closes the connection.
+fn write_messages<W: Write>(writer: &mut W, receiver: Receiver<Message>) -> io::Result<()> {
+    for message in receiver {
+        match message {
+            Message::Image(frame) => write_frame(writer, OPCODE_BINARY, &frame)?,
+            Message::Pong(payload) => write_frame(writer, OPCODE_PONG, &payload)?,
+            Message::Close => return write_frame(writer, OPCODE_CLOSE, &[]),
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the frames sent by the browser, answering its pings, until it closes the connection.
+fn read_messages<R: Read>(reader: &mut R, sender: &Sender<Message>) {
+    loop {
+        match read_frame(reader) {
+            Ok((OPCODE_PING, payload)) => {
+                if sender.send(Message::Pong(payload)).is_err() {
+                    return;
+                }
+            }
+            Ok((OPCODE_CLOSE, _)) | Err(_) => {
+                let _ = sender.send(Message::Close);
+                return;
+            }
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Reads a single frame sent by a browser, returning its opcode and unmasked payload.
+/// The page only sends control frames, so frames longer than those are rejected.
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+
+    let len = (header[1] & 0x7f) as usize;
+    if header[1] & 0x80 == 0 || len > 125 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected WebSocket frame",
+        ));
+    }
+
+    let mut mask = [0; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+
+    Ok((header[0] & 0x0f, payload))
+}
+
+/// Writes the data as a single WebSocket frame, which servers send unmasked.
+fn write_frame<W: Write>(writer: &mut W, opcode: u8, data: &[u8]) -> io::Result<()> {
+    let mut header = vec![0x80 | opcode];
+    match data.len() {
+        len @ 0..=125 => header.push(len as u8),
+        len @ 126..=0xffff => {
+            header.push(126);
+            header.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            header.push(127);
+            header.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+
+    writer.write_all(&header)?;
+    writer.write_all(data)?;
+    writer.flush()
+}
+
+/// Computes the SHA-1 hash of the data, which the WebSocket handshake requires.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+    // The message is padded with a one bit and zeros, followed by its length in bits.
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut hash = [0; 20];
+    for (bytes, value) in hash.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+
+    hash
+}
+
+/// Encodes the data in base64 with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_key_matches_rfc_6455() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn read_frame_unmasks_payload() {
+        let frame = [
+            0x89, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+        let (opcode, payload) = read_frame(&mut &frame[..]).unwrap();
+        assert_eq!(opcode, OPCODE_PING);
+        assert_eq!(payload, b"Hello");
+    }
+
+    #[test]
+    fn read_frame_rejects_unmasked_frames() {
+        let frame = [0x89, 0x05, b'H', b'e', b'l', b'l', b'o'];
+        assert!(read_frame(&mut &frame[..]).is_err());
+    }
+}
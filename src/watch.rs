@@ -0,0 +1,63 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, Default)]
+/// Watches files for changes by polling their modification times.
+pub struct FileWatcher {
+    /// The watched files with their last seen modification time.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FileWatcher {
+    /// Creates a new watcher without any files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts watching the file at the given path.
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref().to_path_buf();
+
+        if self.files.iter().all(|(watched, _)| *watched != path) {
+            let modified = Self::modified(&path);
+            self.files.push((path, modified));
+        }
+    }
+
+    /// Returns the files that changed since the last call.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+
+        for (path, last) in self.files.iter_mut() {
+            let modified = Self::modified(path);
+
+            if modified != *last {
+                *last = modified;
+                changed.push(path.clone());
+            }
+        }
+
+        changed
+    }
+
+    /// Blocks until at least one file changed, checking at the given interval.
+    pub fn wait(&mut self, interval: Duration) -> Vec<PathBuf> {
+        loop {
+            let changed = self.changed();
+            if !changed.is_empty() {
+                return changed;
+            }
+
+            thread::sleep(interval);
+        }
+    }
+
+    /// Returns the modification time of the file, or `None` if it doesn't exist.
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+}